@@ -1,4 +1,8 @@
-use std::{fmt::Display, ops::Deref, path::PathBuf};
+use std::{
+    fmt::Display,
+    ops::Deref,
+    path::{Path, PathBuf},
+};
 
 use arcstr::{ArcStr, Substr};
 use gpui::SharedString;
@@ -6,6 +10,8 @@ use serde::{Deserialize, Serialize};
 use unicase::UniCase;
 use unicode_segmentation::UnicodeSegmentation;
 
+use crate::{cache::CacheSize, url::Url};
+
 /// Case insensitive, efficient representation of an immutable UTF-8 encoded string
 #[derive(Debug, Default, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct AppString(#[serde(with = "unicase_serde::unicase")] UniCase<ArcStr>);
@@ -26,6 +32,185 @@ pub struct ExecutableApp {
     pub(crate) path: PathBuf,
     pub(crate) is_open: bool,
     pub(crate) icon_png_data: Option<Vec<u8>>,
+    /// Created with Safari's "Add to Dock"
+    #[serde(default)]
+    pub(crate) is_web_app: bool,
+    /// App Store category, without the `public.app-category.` prefix
+    /// (for instance: `"developer-tools"`)
+    #[serde(default)]
+    pub(crate) category: Option<String>,
+    /// UTIs of the documents the app declares it can open
+    /// (`LSItemContentTypes` of its `CFBundleDocumentTypes`)
+    #[serde(default)]
+    pub(crate) document_types: Vec<String>,
+    /// Lowercase extensions of the documents the app declares it can open
+    /// (`CFBundleTypeExtensions`), which older apps declare instead of UTIs
+    #[serde(default)]
+    pub(crate) document_extensions: Vec<String>,
+    /// Where the app is stored, which is checked before reading its bundle
+    #[serde(default)]
+    pub(crate) location: AppLocation,
+    /// Version shown to users (`CFBundleShortVersionString`), as in `"2.4.1"`
+    #[serde(default)]
+    pub(crate) version: Option<String>,
+    /// Where the app gets its updates from, if it can be told: the updates
+    /// page of the Mac App Store, or the Sparkle feed the app declares
+    #[serde(default)]
+    pub(crate) updates_url: Option<Url>,
+    /// Processors the executable of the app was built for
+    #[serde(default)]
+    pub(crate) architecture: Option<Architecture>,
+    /// Filled in by the search engine when listing results
+    #[serde(skip)]
+    pub(crate) usage: Option<AppUsage>,
+}
+
+/// Apps that aren't stored locally are indexed by name only: reading the
+/// bundle of an iCloud Drive placeholder downloads it, and reading from a
+/// network volume can take seconds. They are downloaded when opened.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Hash)]
+pub enum AppLocation {
+    #[default]
+    Local,
+    /// In iCloud Drive, but not downloaded (a "dataless" placeholder)
+    ICloud,
+    /// On a volume mounted over the network (SMB, AFP, NFS, WebDAV)
+    NetworkVolume,
+}
+
+impl AppLocation {
+    /// Shown next to results stored elsewhere than on this Mac
+    #[must_use]
+    pub fn badge(self) -> Option<&'static str> {
+        match self {
+            AppLocation::Local => None,
+            AppLocation::ICloud => Some("☁ In iCloud Drive"),
+            AppLocation::NetworkVolume => Some("☁ On a network volume"),
+        }
+    }
+}
+
+/// `cputype` of Intel processors in Mach-O headers
+const CPU_TYPE_X86_64: u32 = 0x0100_0007;
+/// `cputype` of Apple Silicon processors in Mach-O headers
+const CPU_TYPE_ARM64: u32 = 0x0100_000C;
+/// Flag of the `cputype` of 64-bit processors
+const CPU_ARCH_ABI64: u32 = 0x0100_0000;
+
+/// Universal binaries start with a big-endian header listing their slices
+const FAT_MAGIC: u32 = 0xCAFE_BABE;
+const FAT_MAGIC_64: u32 = 0xCAFE_BABF;
+/// Other binaries start with a header in the byte order of their processor,
+/// which is little-endian on Macs
+const MH_MAGIC: u32 = 0xFEED_FACE;
+const MH_MAGIC_64: u32 = 0xFEED_FACF;
+
+/// Bytes read from executables to tell their architecture, which fit the
+/// headers of universal binaries
+pub const MACH_O_HEADER_LEN: u64 = 1024;
+
+/// Only Intel and Apple Silicon slices matter, which are listed first
+const MAX_FAT_ARCHS: usize = 16;
+
+/// Processors an app was built for, read from the Mach-O header of its
+/// executable
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Hash)]
+pub enum Architecture {
+    AppleSilicon,
+    /// Runs with Rosetta on Apple Silicon
+    Intel,
+    /// Runs natively on both, and can still be opened with Rosetta, for
+    /// instance for plugins that were only built for Intel
+    Universal,
+}
+
+impl Architecture {
+    /// Reads the first bytes of an executable, which hold the processor
+    /// types of every slice of universal binaries
+    #[must_use]
+    pub fn from_mach_o_header(header: &[u8]) -> Option<Self> {
+        let cpu_types = mach_o_cpu_types(header)?;
+
+        match (
+            cpu_types.contains(&CPU_TYPE_ARM64),
+            cpu_types.contains(&CPU_TYPE_X86_64),
+        ) {
+            (true, true) => Some(Architecture::Universal),
+            (true, false) => Some(Architecture::AppleSilicon),
+            (false, true) => Some(Architecture::Intel),
+            (false, false) => None,
+        }
+    }
+
+    #[must_use]
+    pub fn display_name(self) -> &'static str {
+        match self {
+            Architecture::AppleSilicon => "Apple Silicon",
+            Architecture::Intel => "Intel",
+            Architecture::Universal => "Universal",
+        }
+    }
+
+    /// Whether the app can be opened with Rosetta, rather than natively
+    #[must_use]
+    pub fn can_use_rosetta(self) -> bool {
+        cfg!(target_arch = "aarch64") && self == Architecture::Universal
+    }
+}
+
+/// Whether an executable was only built for 32-bit processors, which macOS
+/// stopped running in Catalina
+#[must_use]
+pub fn is_32_bit_mach_o(header: &[u8]) -> bool {
+    mach_o_cpu_types(header).is_some_and(|cpu_types| {
+        !cpu_types.is_empty()
+            && cpu_types
+                .iter()
+                .all(|cpu_type| cpu_type & CPU_ARCH_ABI64 == 0)
+    })
+}
+
+/// The `cputype` of every slice of an executable
+fn mach_o_cpu_types(header: &[u8]) -> Option<Vec<u32>> {
+    let read_u32 = |offset: usize, from_bytes: fn([u8; 4]) -> u32| {
+        header
+            .get(offset..offset + 4)
+            .and_then(|bytes| bytes.try_into().ok())
+            .map(from_bytes)
+    };
+
+    let cpu_types = match read_u32(0, u32::from_be_bytes)? {
+        magic @ (FAT_MAGIC | FAT_MAGIC_64) => {
+            // `fat_arch` and `fat_arch_64`, which start with `cputype`
+            let arch_size = if magic == FAT_MAGIC { 20 } else { 32 };
+            let arch_count = usize::try_from(read_u32(4, u32::from_be_bytes)?).ok()?;
+
+            (0..arch_count.min(MAX_FAT_ARCHS))
+                .map(|i| read_u32(8 + i * arch_size, u32::from_be_bytes))
+                .collect::<Option<_>>()?
+        }
+        _ => match read_u32(0, u32::from_le_bytes)? {
+            MH_MAGIC | MH_MAGIC_64 => vec![read_u32(4, u32::from_le_bytes)?],
+            _ => return None,
+        },
+    };
+
+    Some(cpu_types)
+}
+
+/// How often and how recently the user opened an app from Fetch.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Hash)]
+pub struct AppUsage {
+    pub(crate) launch_count: u32,
+    /// In seconds since the UNIX epoch
+    pub(crate) last_opened: u64,
+}
+
+/// An app that is currently running, as reported by the OS.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Hash)]
+pub struct RunningApp {
+    pub(crate) name: AppName,
+    pub(crate) bundle_id: String,
 }
 
 impl Deref for AppSubstr {
@@ -70,6 +255,20 @@ impl AppString {
     pub fn substring(&self, i: usize, len: usize) -> AppSubstr {
         AppSubstr(UniCase::new(self.0.substr(i..i + len)))
     }
+
+    /// Returns what follows `prefix`, if this string starts with `prefix` (ignoring case)
+    #[must_use]
+    pub fn strip_prefix_ignore_case(&self, prefix: &str) -> Option<&str> {
+        let mut chars = self.0.chars();
+        for prefix_char in prefix.chars() {
+            let char = chars.next()?;
+            if !prefix_char.to_lowercase().eq(char.to_lowercase()) {
+                return None;
+            }
+        }
+
+        Some(chars.as_str())
+    }
 }
 
 impl From<SharedString> for AppString {
@@ -104,6 +303,84 @@ impl Deref for AppString {
     }
 }
 
+impl ExecutableApp {
+    /// Category formatted for display (for instance: "Developer Tools")
+    #[must_use]
+    pub fn category_display_name(&self) -> Option<String> {
+        let category = self.category.as_ref()?;
+
+        Some(
+            category
+                .split('-')
+                .map(|word| {
+                    let mut chars = word.chars();
+                    chars.next().map_or_else(String::new, |first| {
+                        first.to_uppercase().collect::<String>() + chars.as_str()
+                    })
+                })
+                .collect::<Vec<_>>()
+                .join(" "),
+        )
+    }
+}
+
+impl ExecutableApp {
+    /// How specifically the app opens a file, given the UTIs the file
+    /// conforms to (most specific first): the position of the most
+    /// specific one the app declares, if any. Apps only declaring the
+    /// extension of the file rank after those declaring one of its UTIs.
+    #[must_use]
+    pub fn document_type_rank(&self, path: &Path, content_types: &[String]) -> Option<usize> {
+        content_types
+            .iter()
+            .position(|content_type| self.document_types.contains(content_type))
+            .or_else(|| {
+                let extension = path.extension()?.to_string_lossy().to_lowercase();
+                self.document_extensions
+                    .contains(&extension)
+                    .then_some(content_types.len())
+            })
+    }
+
+    /// Whether the app declares it can open `kind`, either an extension
+    /// (`pdf`) or a UTI (`com.adobe.pdf`). Extensions also match UTIs
+    /// ending with them, as most apps only declare UTIs.
+    #[must_use]
+    pub fn opens(&self, kind: &str) -> bool {
+        let kind = kind.trim_start_matches('.').to_lowercase();
+        if kind.is_empty() {
+            return false;
+        }
+
+        self.document_extensions.contains(&kind)
+            || self.document_types.iter().any(|content_type| {
+                let content_type = content_type.to_lowercase();
+                content_type == kind || content_type.rsplit('.').next() == Some(kind.as_str())
+            })
+    }
+}
+
+impl CacheSize for ExecutableApp {
+    fn cache_size(&self) -> usize {
+        size_of::<Self>()
+            + self.name.len()
+            + self.path.as_os_str().len()
+            + self.icon_png_data.as_ref().map_or(0, Vec::len)
+            + self.category.as_ref().map_or(0, String::len)
+            + self.version.as_ref().map_or(0, String::len)
+            + self
+                .updates_url
+                .as_ref()
+                .map_or(0, |url| url.to_string().len())
+            + self.document_types.iter().map(String::len).sum::<usize>()
+            + self
+                .document_extensions
+                .iter()
+                .map(String::len)
+                .sum::<usize>()
+    }
+}
+
 impl PartialOrd for ExecutableApp {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
         Some(self.cmp(other))
@@ -115,3 +392,58 @@ impl Ord for ExecutableApp {
         self.path.cmp(&other.path)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A header of `cpu_types`, as `lipo` writes them
+    fn fat_header(cpu_types: &[u32]) -> Vec<u8> {
+        let mut header = FAT_MAGIC.to_be_bytes().to_vec();
+        header.extend(u32::try_from(cpu_types.len()).unwrap().to_be_bytes());
+        for cpu_type in cpu_types {
+            header.extend(cpu_type.to_be_bytes());
+            header.extend([0; 16]);
+        }
+        header
+    }
+
+    #[test]
+    fn test_architecture_from_mach_o_header() {
+        let mut thin_header = MH_MAGIC_64.to_le_bytes().to_vec();
+        thin_header.extend(CPU_TYPE_X86_64.to_le_bytes());
+        assert_eq!(
+            Architecture::from_mach_o_header(&thin_header),
+            Some(Architecture::Intel)
+        );
+
+        assert_eq!(
+            Architecture::from_mach_o_header(&fat_header(&[CPU_TYPE_X86_64, CPU_TYPE_ARM64])),
+            Some(Architecture::Universal)
+        );
+        assert_eq!(
+            Architecture::from_mach_o_header(&fat_header(&[CPU_TYPE_ARM64])),
+            Some(Architecture::AppleSilicon)
+        );
+
+        // A script, or a header cut short
+        assert_eq!(Architecture::from_mach_o_header(b"#!/bin/sh"), None);
+        assert_eq!(
+            Architecture::from_mach_o_header(&fat_header(&[CPU_TYPE_ARM64])[..10]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_is_32_bit_mach_o() {
+        // `CPU_TYPE_I386`
+        let mut thin_header = MH_MAGIC.to_le_bytes().to_vec();
+        thin_header.extend(7_u32.to_le_bytes());
+        assert!(is_32_bit_mach_o(&thin_header));
+        assert_eq!(Architecture::from_mach_o_header(&thin_header), None);
+
+        assert!(is_32_bit_mach_o(&fat_header(&[7])));
+        assert!(!is_32_bit_mach_o(&fat_header(&[7, CPU_TYPE_X86_64])));
+        assert!(!is_32_bit_mach_o(b"#!/bin/sh"));
+    }
+}
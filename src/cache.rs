@@ -0,0 +1,302 @@
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    sync::{
+        Arc, Mutex, Weak,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+
+pub const BYTES_PER_MB: usize = 1024 * 1024;
+
+/// Caches registered with [`LruCache::registered`], listed by [`stats`]
+static REGISTRY: Mutex<Vec<Weak<CacheUsage>>> = Mutex::new(Vec::new());
+
+/// Estimate of how many bytes a cached value takes up in memory
+pub trait CacheSize {
+    fn cache_size(&self) -> usize;
+}
+
+impl<T: CacheSize> CacheSize for Vec<T> {
+    fn cache_size(&self) -> usize {
+        self.iter().map(CacheSize::cache_size).sum()
+    }
+}
+
+/// Current usage of a cache, shared with the registry
+#[derive(Debug)]
+struct CacheUsage {
+    name: &'static str,
+    byte_budget: usize,
+    entries: AtomicUsize,
+    bytes: AtomicUsize,
+}
+
+/// Usage of the caches of the same name, as listed by [`stats`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheStats {
+    pub name: &'static str,
+    pub entries: usize,
+    pub bytes: usize,
+    pub byte_budget: usize,
+}
+
+/// Usage of every registered cache still alive in this process.
+/// Caches of the same name (like the ones of each window) are added up.
+#[must_use]
+pub fn stats() -> Vec<CacheStats> {
+    let mut registry = REGISTRY.lock().expect("no lock poisoning");
+    registry.retain(|usage| usage.strong_count() > 0);
+
+    let mut stats: Vec<CacheStats> = Vec::new();
+    for usage in registry.iter().filter_map(Weak::upgrade) {
+        let entries = usage.entries.load(Ordering::Relaxed);
+        let bytes = usage.bytes.load(Ordering::Relaxed);
+
+        match stats.iter_mut().find(|stats| stats.name == usage.name) {
+            Some(stats) => {
+                stats.entries += entries;
+                stats.bytes += bytes;
+                stats.byte_budget += usage.byte_budget;
+            }
+            None => stats.push(CacheStats {
+                name: usage.name,
+                entries,
+                bytes,
+                byte_budget: usage.byte_budget,
+            }),
+        }
+    }
+
+    stats
+}
+
+#[derive(Debug)]
+struct Entry<V> {
+    value: V,
+    /// Tick at which the value was last used
+    last_used: u64,
+    size: usize,
+}
+
+/// A map holding at most `capacity` entries, which evicts the least
+/// recently used entry when full, or when its entries take up more
+/// than its byte budget.
+///
+/// Eviction is a linear scan, so this is meant for small capacities.
+#[derive(Debug)]
+pub struct LruCache<K, V> {
+    entries: HashMap<K, Entry<V>>,
+    capacity: usize,
+    tick: u64,
+    usage: Arc<CacheUsage>,
+    size_of: fn(&V) -> usize,
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> LruCache<K, V> {
+    /// A cache without a byte budget, which isn't listed by [`stats`]
+    #[must_use]
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: HashMap::with_capacity(capacity),
+            capacity,
+            tick: 0,
+            usage: Arc::new(CacheUsage {
+                name: "",
+                byte_budget: usize::MAX,
+                entries: AtomicUsize::new(0),
+                bytes: AtomicUsize::new(0),
+            }),
+            size_of: |_| 0,
+        }
+    }
+
+    pub fn get(&mut self, key: &K) -> Option<V> {
+        self.tick += 1;
+        let tick = self.tick;
+
+        self.entries.get_mut(key).map(|entry| {
+            entry.last_used = tick;
+            entry.value.clone()
+        })
+    }
+
+    pub fn insert(&mut self, key: K, value: V) {
+        let size = (self.size_of)(&value);
+        if self.capacity == 0 || size > self.usage.byte_budget {
+            return;
+        }
+
+        if let Some(previous) = self.entries.remove(&key) {
+            self.usage.bytes.fetch_sub(previous.size, Ordering::Relaxed);
+        }
+
+        while self.entries.len() >= self.capacity
+            || self.usage.bytes.load(Ordering::Relaxed) + size > self.usage.byte_budget
+        {
+            let Some(lru_key) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone())
+            else {
+                break;
+            };
+
+            if let Some(evicted) = self.entries.remove(&lru_key) {
+                self.usage.bytes.fetch_sub(evicted.size, Ordering::Relaxed);
+            }
+        }
+
+        self.tick += 1;
+        self.entries.insert(
+            key,
+            Entry {
+                value,
+                last_used: self.tick,
+                size,
+            },
+        );
+        self.usage.bytes.fetch_add(size, Ordering::Relaxed);
+        self.usage
+            .entries
+            .store(self.entries.len(), Ordering::Relaxed);
+    }
+
+    #[must_use]
+    pub fn contains(&self, key: &K) -> bool {
+        self.entries.contains_key(key)
+    }
+
+    /// Whether a value of `size` bytes fits without evicting any entry
+    #[must_use]
+    pub fn has_room_for(&self, size: usize) -> bool {
+        self.entries.len() < self.capacity
+            && self.usage.bytes.load(Ordering::Relaxed) + size <= self.usage.byte_budget
+    }
+
+    /// Removes the entries whose key doesn't satisfy `keep`
+    pub fn retain(&mut self, mut keep: impl FnMut(&K) -> bool) {
+        let mut removed_bytes = 0;
+        self.entries.retain(|key, entry| {
+            let keep = keep(key);
+            if !keep {
+                removed_bytes += entry.size;
+            }
+            keep
+        });
+
+        self.usage.bytes.fetch_sub(removed_bytes, Ordering::Relaxed);
+        self.usage
+            .entries
+            .store(self.entries.len(), Ordering::Relaxed);
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.usage.entries.store(0, Ordering::Relaxed);
+        self.usage.bytes.store(0, Ordering::Relaxed);
+    }
+}
+
+impl<K: Hash + Eq + Clone, V: Clone + CacheSize> LruCache<K, V> {
+    /// A cache listed by [`stats`] under `name`, whose entries
+    /// take up at most `byte_budget` bytes
+    #[must_use]
+    pub fn registered(name: &'static str, capacity: usize, byte_budget: usize) -> Self {
+        let usage = Arc::new(CacheUsage {
+            name,
+            byte_budget,
+            entries: AtomicUsize::new(0),
+            bytes: AtomicUsize::new(0),
+        });
+        REGISTRY
+            .lock()
+            .expect("no lock poisoning")
+            .push(Arc::downgrade(&usage));
+
+        Self {
+            entries: HashMap::with_capacity(capacity),
+            capacity,
+            tick: 0,
+            usage,
+            size_of: V::cache_size,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    impl CacheSize for String {
+        fn cache_size(&self) -> usize {
+            self.len()
+        }
+    }
+
+    #[test]
+    fn test_lru_eviction() {
+        let mut cache = LruCache::new(2);
+        cache.insert("a", 1);
+        cache.insert("b", 2);
+
+        // "a" is now more recently used than "b"
+        assert_eq!(cache.get(&"a"), Some(1));
+
+        cache.insert("c", 3);
+        assert_eq!(cache.get(&"b"), None);
+        assert_eq!(cache.get(&"a"), Some(1));
+        assert_eq!(cache.get(&"c"), Some(3));
+
+        cache.insert("c", 4);
+        assert_eq!(cache.get(&"c"), Some(4));
+        assert_eq!(cache.get(&"a"), Some(1));
+    }
+
+    #[test]
+    fn test_byte_budget_eviction() {
+        let mut cache = LruCache::registered("test_byte_budget_eviction", 10, 8);
+        cache.insert("a", String::from("aaaa"));
+        cache.insert("b", String::from("bbbb"));
+        assert_eq!(cache.get(&"a"), Some(String::from("aaaa")));
+
+        // Evicts "b", the least recently used
+        cache.insert("c", String::from("cc"));
+        assert_eq!(cache.get(&"b"), None);
+        assert_eq!(cache.get(&"a"), Some(String::from("aaaa")));
+
+        // Larger than the whole budget
+        cache.insert("d", String::from("ddddddddd"));
+        assert_eq!(cache.get(&"d"), None);
+
+        assert!(cache.has_room_for(2));
+        assert!(!cache.has_room_for(3));
+
+        let stats = stats()
+            .into_iter()
+            .find(|stats| stats.name == "test_byte_budget_eviction");
+        assert_eq!(
+            stats,
+            Some(CacheStats {
+                name: "test_byte_budget_eviction",
+                entries: 2,
+                bytes: 6,
+                byte_budget: 8,
+            })
+        );
+    }
+
+    #[test]
+    fn test_retain() {
+        let mut cache = LruCache::registered("test_retain", 10, 100);
+        cache.insert("a", String::from("aaaa"));
+        cache.insert("b", String::from("bb"));
+
+        cache.retain(|key| *key != "a");
+        assert_eq!(cache.get(&"a"), None);
+        assert_eq!(cache.get(&"b"), Some(String::from("bb")));
+        assert!(cache.has_room_for(98));
+        assert!(!cache.has_room_for(99));
+    }
+}
@@ -1,37 +1,332 @@
-use std::borrow::Cow;
+use std::{borrow::Cow, collections::BTreeMap, path::PathBuf};
 
-use rootcause::{Report, option_ext::OptionExt};
+use rootcause::{Report, option_ext::OptionExt, prelude::ResultExt};
+use serde::{Deserialize, Serialize};
 use trie_rs::map::{Trie, TrieBuilder};
 
 use crate::{
+    errors::{self, ErrorEvent},
+    fs::config::expand_home,
+    launch,
     platform::{ImplPlatform, Platform},
     url::Url,
 };
 
+/// An AppleScript run through `osascript`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AppleScript {
+    /// Source code of the script
+    Source(String),
+    /// Path to a `.scpt` or `.applescript` file
+    File(PathBuf),
+}
+
+/// Quotes and escapes `string` so that it can be put in an AppleScript as
+/// a string literal
+#[must_use]
+pub fn applescript_string(string: &str) -> String {
+    format!("\"{}\"", string.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// What running a command does.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum CommandAction {
+    /// Open a web page, for instance `"https://github.com"`
+    Url(String),
+    /// Run an AppleScript snippet
+    #[serde(rename = "applescript")]
+    AppleScript(String),
+    /// Run a `.scpt` or `.applescript` file
+    #[serde(rename = "applescript_file")]
+    AppleScriptFile(PathBuf),
+}
+
+/// What a script runs with, on top of the environment of Fetch. Apps
+/// opened from the Dock or at login don't get what shell profiles set, such
+/// as `PATH` or `SSH_AUTH_SOCK`, so tools called by a script may need them
+/// set here to behave like they do in a terminal.
+///
+/// ```toml
+/// [[commands]]
+/// keyword = "deploy"
+/// applescript = "do shell script \"make deploy\""
+/// working_dir = "~/code/site"
+/// env = { PATH = "/opt/homebrew/bin:$PATH", SSH_AUTH_SOCK = "~/.ssh/agent.sock" }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, Hash)]
+pub struct ProcessEnvironment {
+    /// Variables to set, in which `$NAME` and `${NAME}` stand for the value
+    /// Fetch has, and a leading `~` for the home folder
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    /// Folder to run in, rather than the one Fetch runs in (`/` when opened
+    /// from Finder)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub working_dir: Option<String>,
+}
+
+impl ProcessEnvironment {
+    /// Sets the variables and folder of `process`
+    pub fn apply(&self, process: &mut std::process::Command) {
+        for (name, value) in &self.env {
+            let value = expand_variables(value, |name| std::env::var(name).ok());
+            process.env(name, expand_home(&value));
+        }
+        if let Some(working_dir) = &self.working_dir {
+            process.current_dir(expand_home(working_dir));
+        }
+    }
+}
+
+/// Replaces `$NAME` and `${NAME}` in `value` with what `lookup` gives for
+/// them, or with nothing if it gives nothing, like shells do. Any other `$`
+/// is kept as is.
+fn expand_variables(value: &str, lookup: impl Fn(&str) -> Option<String>) -> String {
+    let is_name_char = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    let mut expanded = String::with_capacity(value.len());
+    let mut rest = value;
+
+    while let Some(dollar) = rest.find('$') {
+        expanded.push_str(&rest[..dollar]);
+        let after = &rest[dollar + 1..];
+
+        let (name, remaining) = match after.strip_prefix('{') {
+            Some(braced) => match braced.split_once('}') {
+                Some((name, remaining)) if !name.is_empty() && name.chars().all(is_name_char) => {
+                    (name, remaining)
+                }
+                _ => ("", after),
+            },
+            // Names don't start with a digit, so that amounts like `$5` are kept
+            None if after.starts_with(|c: char| c.is_ascii_digit()) => ("", after),
+            None => {
+                let len = after.find(|c| !is_name_char(c)).unwrap_or(after.len());
+                (&after[..len], &after[len..])
+            }
+        };
+
+        if name.is_empty() {
+            expanded.push('$');
+        } else {
+            expanded.push_str(&lookup(name).unwrap_or_default());
+        }
+        rest = remaining;
+    }
+
+    expanded.push_str(rest);
+    expanded
+}
+
+/// A command defined in the configuration file, run by typing its keyword
+/// and pressing Enter.
+///
+/// ```toml
+/// [[commands]]
+/// keyword = "ip"
+/// applescript = "do shell script \"ipconfig getifaddr en0\""
+/// show_output = true
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Hash)]
+pub struct CommandEntry {
+    pub keyword: String,
+    #[serde(flatten)]
+    pub action: CommandAction,
+    /// Display the output of the script as a result, instead of closing the window
+    #[serde(default)]
+    pub show_output: bool,
+    /// Bundle identifier of the browser the URL opens in, instead of the
+    /// `browser` of `[open_behavior]` (for instance: "com.google.Chrome")
+    #[serde(default)]
+    pub browser: Option<String>,
+    /// Environment of the script, see [`ProcessEnvironment`]
+    #[serde(flatten)]
+    pub environment: ProcessEnvironment,
+}
+
+/// A regex defined in the configuration file, suggesting a command
+/// whenever it matches the query. Capture groups (`$0`, `$1`, `${name}`...)
+/// are substituted into the name and the URL or script, as-is.
+///
+/// ```toml
+/// [[matchers]]
+/// pattern = '^[A-Z]+-\d+$'
+/// name = "Open $0 in Jira"
+/// url = "https://jira.example.com/browse/$0"
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MatcherEntry {
+    pub pattern: String,
+    /// Name of the result, defaults to the query
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(flatten)]
+    pub action: CommandAction,
+    #[serde(default)]
+    pub show_output: bool,
+    #[serde(default)]
+    pub browser: Option<String>,
+    #[serde(flatten)]
+    pub environment: ProcessEnvironment,
+}
+
+impl CommandEntry {
+    /// Runs the command, opening URLs in `default_browser` unless it
+    /// picks its own. Returns its output if it should be shown to the user.
+    pub fn run(&self, default_browser: Option<&str>) -> Result<Option<String>, Report> {
+        Command::from_entry(self)
+            .ok_or_report()
+            .attach(format!("Invalid command `{}`", self.keyword))?
+            .run(default_browser)
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Command {
+    Url {
+        url: Url,
+        /// Bundle identifier of the browser, if not the default one
+        browser: Option<String>,
+    },
+    AppleScript {
+        script: AppleScript,
+        show_output: bool,
+        environment: ProcessEnvironment,
+    },
+}
+
+impl Command {
+    fn from_entry(entry: &CommandEntry) -> Option<Self> {
+        let command = match &entry.action {
+            CommandAction::Url(url) => {
+                let Some(domain) = url.strip_prefix("https://") else {
+                    let summary = format!("Command `{}` ignored", entry.keyword);
+                    eprintln!("{summary}: only https URLs are supported");
+                    errors::publish(ErrorEvent {
+                        summary,
+                        details: format!("Only https URLs are supported, `{url}` isn't one"),
+                        remedy: None,
+                    });
+                    return None;
+                };
+                Command::Url {
+                    url: Url::Https(Cow::Owned(domain.to_owned())),
+                    browser: entry.browser.clone(),
+                }
+            }
+            CommandAction::AppleScript(source) => Command::AppleScript {
+                script: AppleScript::Source(source.clone()),
+                show_output: entry.show_output,
+                environment: entry.environment.clone(),
+            },
+            CommandAction::AppleScriptFile(path) => Command::AppleScript {
+                script: AppleScript::File(path.clone()),
+                show_output: entry.show_output,
+                environment: entry.environment.clone(),
+            },
+        };
+
+        Some(command)
+    }
+
+    fn run(&self, default_browser: Option<&str>) -> Result<Option<String>, Report> {
+        match self {
+            Command::Url { url, browser } => {
+                launch::open_in_browser(url, browser.as_deref().or(default_browser))?;
+                Ok(None)
+            }
+            Command::AppleScript {
+                script,
+                show_output,
+                environment,
+            } => {
+                let output = ImplPlatform::run_applescript(script, environment)?;
+                Ok((*show_output).then(|| output.trim_end().to_owned()))
+            }
+        }
+    }
+}
+
 pub struct CommandTrie {
-    inner: Trie<u8, Url>,
+    inner: Trie<u8, Command>,
 }
 
 impl Default for CommandTrie {
     fn default() -> Self {
+        Self::new(&[])
+    }
+}
+
+impl CommandTrie {
+    #[must_use]
+    pub fn new(entries: &[CommandEntry]) -> Self {
         let mut builder = TrieBuilder::new();
 
-        builder.push("hn", Url::Https(Cow::Borrowed("news.ycombinator.com")));
-        builder.push("gh", Url::Https(Cow::Borrowed("github.com")));
+        builder.push(
+            "hn",
+            Command::Url {
+                url: Url::Https(Cow::Borrowed("news.ycombinator.com")),
+                browser: None,
+            },
+        );
+        builder.push(
+            "gh",
+            Command::Url {
+                url: Url::Https(Cow::Borrowed("github.com")),
+                browser: None,
+            },
+        );
+
+        for entry in entries {
+            if let Some(command) = Command::from_entry(entry) {
+                builder.push(&entry.keyword, command);
+            }
+        }
 
         Self {
             inner: builder.build(),
         }
     }
+
+    /// The command matching `command` exactly, ready to be run on another
+    /// thread, see [`CommandEntry::run`]
+    pub fn find(
+        &self,
+        command: &str,
+        default_browser: Option<&str>,
+    ) -> Option<impl FnOnce() -> Result<Option<String>, Report> + Send + 'static> {
+        let command = self.inner.exact_match(command)?.clone();
+        let default_browser = default_browser.map(str::to_owned);
+
+        Some(move || command.run(default_browser.as_deref()))
+    }
 }
 
-impl CommandTrie {
-    pub fn execute(&self, command: &str) -> Result<(), Report> {
-        self.inner
-            .exact_match(command)
-            .and_then(|res| ImplPlatform::open_url(res).ok())
-            .ok_or_report()?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_variables() {
+        let lookup = |name: &str| match name {
+            "PATH" => Some("/usr/bin:/bin".to_owned()),
+            "HOME" => Some("/Users/me".to_owned()),
+            _ => None,
+        };
+
+        assert_eq!(
+            expand_variables("/opt/homebrew/bin:$PATH", lookup),
+            "/opt/homebrew/bin:/usr/bin:/bin"
+        );
+        assert_eq!(
+            expand_variables("${HOME}/.ssh/agent.sock", lookup),
+            "/Users/me/.ssh/agent.sock"
+        );
+        assert_eq!(expand_variables("$HOME_DIR/bin", lookup), "/bin");
+        assert_eq!(expand_variables("no variables", lookup), "no variables");
 
-        Ok(())
+        // Dollars not followed by a name are kept
+        assert_eq!(expand_variables("costs $5 or $", lookup), "costs $5 or $");
+        assert_eq!(expand_variables("${not closed", lookup), "${not closed");
     }
 }
@@ -0,0 +1,583 @@
+//! Searching happens in a separate process, the daemon, which owns the
+//! app index, the persisted data and the providers. The UI process only
+//! renders results, so that indexing never slows down showing the search
+//! window, and a crashing provider only takes down the daemon, which is
+//! then started again.
+//!
+//! The daemon is a child process of the UI, running the same executable
+//! with [`DAEMON_ARG`]. They exchange JSON messages, one per line, over the
+//! standard input and output of the daemon. The daemon exits once its
+//! standard input is closed, which happens when the UI exits.
+//! Errors published in the daemon are forwarded to the UI, to be shown there.
+
+use std::{
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::{
+        Arc, Mutex, MutexGuard,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use rootcause::{Report, option_ext::OptionExt};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{
+    broadcast::{self, error::RecvError},
+    watch::{self, Receiver, Sender},
+};
+
+use crate::{
+    app::{AppString, ExecutableApp},
+    errors::{self, ErrorEvent},
+    extensions::{
+        DeferredReceiver, DeferredSender, DeferredToken, Provider, SearchContext, SearchEngine,
+        SearchResult,
+        cache_stats::CacheStatsProvider,
+        deterministic_search::DeterministicSearchEngine,
+        tasks::{TaskId, TaskProgress, apply_progress},
+    },
+    fs::config::{Configuration, SearchEngineKind, SortMode},
+    url::{INDEX_EVENTS_CAPACITY, IndexEvent, IndexingProgress},
+};
+
+/// Argument the executable is started with to run as the daemon
+pub const DAEMON_ARG: &str = "--daemon";
+
+/// Searches taking longer than this are reported, as a provider is likely
+/// stuck
+const SEARCH_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// How often the daemon checks whether indexing progressed, or errors occurred
+const PROGRESS_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How often [`DaemonSearchEngine::blocking_search`] checks whether results
+/// arrived
+const RESULTS_POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/// Sent by the UI to the daemon
+#[derive(Debug, Serialize, Deserialize)]
+enum Request {
+    Preload(SearchContext),
+    Search {
+        id: usize,
+        query: AppString,
+    },
+    AfterSearch(Option<SearchResult>),
+    SetPrivateMode(bool),
+    ToggleHidden(SearchResult),
+    TogglePinned {
+        query: AppString,
+        result: SearchResult,
+    },
+    SetSortMode(SortMode),
+    RebuildIndex,
+    ForgetApp(PathBuf),
+    CancelTask(TaskId),
+}
+
+/// Sent by the daemon to the UI
+#[derive(Debug, Serialize, Deserialize)]
+enum Response {
+    Results {
+        id: usize,
+        results: Vec<SearchResult>,
+    },
+    IndexingProgress(IndexingProgress),
+    /// See [`SearchEngine::top_apps`]
+    TopApps(Vec<ExecutableApp>),
+    /// See [`SearchEngine::results_changed`]
+    ResultsChanged,
+    /// See [`SearchEngine::index_events`]
+    IndexEvent(IndexEvent),
+    /// See [`SearchEngine::task_progress`]
+    TaskProgress(TaskProgress),
+    Error(ErrorEvent),
+}
+
+type SharedStdout = Arc<Mutex<std::io::Stdout>>;
+
+fn write_message(writer: &mut impl Write, message: &impl Serialize) -> Result<(), Report> {
+    let mut line = serde_json::to_vec(message)?;
+    line.push(b'\n');
+    writer.write_all(&line)?;
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// The engine picked by `search_engine` in the configuration
+fn build_engine(config: Arc<Configuration>) -> Result<Arc<dyn SearchEngine>, Report> {
+    Ok(match config.search_engine {
+        SearchEngineKind::Deterministic => Arc::new(DeterministicSearchEngine::build(config)?),
+        #[cfg(feature = "fuzzy-engine")]
+        SearchEngineKind::FuzzyFirst => Arc::new(
+            crate::extensions::fuzzy_search::FuzzySearchEngine::build(config)?,
+        ),
+        #[cfg(not(feature = "fuzzy-engine"))]
+        SearchEngineKind::FuzzyFirst => {
+            errors::report_error(
+                "The fuzzy-first search engine isn't in this build",
+                rootcause::report!("Fetch was built without the `fuzzy-engine` feature"),
+            );
+            Arc::new(DeterministicSearchEngine::build(config)?)
+        }
+    })
+}
+
+/// Entry point of the daemon, which serves the requests of the UI until it exits
+pub fn run() -> Result<(), Report> {
+    let config = Arc::new(Configuration::read_from_fs()?);
+    let engine = build_engine(config)?;
+    let stdout: SharedStdout = Arc::new(Mutex::new(std::io::stdout()));
+
+    if let Some(progress_rx) = engine.indexing_progress() {
+        let stdout = stdout.clone();
+        std::thread::spawn(move || {
+            forward_changes(progress_rx, &stdout, |progress| {
+                Some(Response::IndexingProgress(progress))
+            });
+        });
+    }
+    if let Some(top_apps_rx) = engine.top_apps() {
+        let stdout = stdout.clone();
+        std::thread::spawn(move || {
+            forward_changes(top_apps_rx, &stdout, |apps| {
+                (!apps.is_empty()).then_some(Response::TopApps(apps))
+            });
+        });
+    }
+    if let Some(results_changed_rx) = engine.results_changed() {
+        let stdout = stdout.clone();
+        std::thread::spawn(move || {
+            forward_changes(results_changed_rx, &stdout, |count| {
+                (count > 0).then_some(Response::ResultsChanged)
+            });
+        });
+    }
+    if let Some(index_events_rx) = engine.index_events() {
+        let stdout = stdout.clone();
+        std::thread::spawn(move || forward_index_events(index_events_rx, &stdout));
+    }
+    if let Some(task_progress_rx) = engine.task_progress() {
+        let stdout = stdout.clone();
+        std::thread::spawn(move || forward_task_progress(task_progress_rx, &stdout));
+    }
+    {
+        let stdout = stdout.clone();
+        std::thread::spawn(move || {
+            forward_changes(errors::subscribe(), &stdout, |error| {
+                error.map(Response::Error)
+            });
+        });
+    }
+
+    for line in std::io::stdin().lock().lines() {
+        let request = match serde_json::from_str::<Request>(&line?) {
+            Ok(request) => request,
+            Err(err) => {
+                eprintln!("Invalid request from the UI: {err}");
+                continue;
+            }
+        };
+
+        match request {
+            // Hiding, pinning, sorting, forgetting and cancelling are
+            // quick, and must happen before the next search
+            Request::ToggleHidden(result) => engine.toggle_hidden(&result),
+            Request::TogglePinned { query, result } => engine.toggle_pinned(&query, &result),
+            Request::SetPrivateMode(private) => engine.set_private_mode(private),
+            Request::SetSortMode(mode) => engine.set_sort_mode(mode),
+            Request::ForgetApp(path) => engine.forget_app(&path),
+            Request::CancelTask(id) => engine.cancel_task(id),
+            // Other requests are served on their own thread, so that a
+            // slow provider only holds up the request that reached it
+            Request::Preload(context) => {
+                let engine = engine.clone();
+                std::thread::spawn(move || engine.preload(context));
+            }
+            Request::Search { id, query } => {
+                let engine = engine.clone();
+                let stdout = stdout.clone();
+                std::thread::spawn(move || {
+                    let results = engine.blocking_search(query);
+                    let mut stdout = stdout.lock().expect("no lock poisoning");
+                    if let Err(report) =
+                        write_message(&mut *stdout, &Response::Results { id, results })
+                    {
+                        eprintln!("{}", report.context("Could not send results to the UI"));
+                    }
+                });
+            }
+            Request::AfterSearch(opened_result) => {
+                let engine = engine.clone();
+                std::thread::spawn(move || engine.after_search(opened_result));
+            }
+            Request::RebuildIndex => {
+                let engine = engine.clone();
+                std::thread::spawn(move || engine.rebuild_index());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Sends every value of `rx` to the UI, as turned into a response by
+/// `response`. The index is rebuilt from time to time, and errors may occur
+/// at any time, so values are forwarded for as long as the daemon runs.
+fn forward_changes<T: Clone>(
+    mut rx: Receiver<T>,
+    stdout: &SharedStdout,
+    response: fn(T) -> Option<Response>,
+) {
+    loop {
+        let value = rx.borrow_and_update().clone();
+        if let Some(response) = response(value) {
+            let mut stdout_guard = stdout.lock().expect("no lock poisoning");
+            if write_message(&mut *stdout_guard, &response).is_err() {
+                return;
+            }
+        }
+
+        loop {
+            match rx.has_changed() {
+                Ok(true) => break,
+                Ok(false) => std::thread::sleep(PROGRESS_POLL_INTERVAL),
+                Err(_) => return,
+            }
+        }
+    }
+}
+
+/// Sends every index event to the UI. Events missed for lagging behind are
+/// replaced by [`IndexEvent::IndexUpdated`], for the UI to refresh everything.
+fn forward_index_events(mut rx: broadcast::Receiver<IndexEvent>, stdout: &SharedStdout) {
+    loop {
+        let event = match rx.blocking_recv() {
+            Ok(event) => event,
+            Err(RecvError::Lagged(_)) => IndexEvent::IndexUpdated,
+            Err(RecvError::Closed) => return,
+        };
+
+        let mut stdout_guard = stdout.lock().expect("no lock poisoning");
+        if write_message(&mut *stdout_guard, &Response::IndexEvent(event)).is_err() {
+            return;
+        }
+    }
+}
+
+/// Sends the progress of every task to the UI. Updates missed for lagging
+/// behind are skipped, as the ones after replace them.
+fn forward_task_progress(mut rx: broadcast::Receiver<TaskProgress>, stdout: &SharedStdout) {
+    loop {
+        let progress = match rx.blocking_recv() {
+            Ok(progress) => progress,
+            Err(RecvError::Lagged(_)) => continue,
+            Err(RecvError::Closed) => return,
+        };
+
+        let mut stdout_guard = stdout.lock().expect("no lock poisoning");
+        if write_message(&mut *stdout_guard, &Response::TaskProgress(progress)).is_err() {
+            return;
+        }
+    }
+}
+
+/// The latest search sent to the daemon. Results of earlier ones are
+/// dropped, as they would replace the ones being waited for.
+#[derive(Debug, Default)]
+struct LatestSearch {
+    id: usize,
+    /// Results of the UI, listed after the ones of the daemon
+    ui_results: Vec<SearchResult>,
+}
+
+/// A running daemon, whose responses are handled on a thread of their own
+#[derive(Debug)]
+struct DaemonProcess {
+    process: Child,
+    stdin: ChildStdin,
+}
+
+impl DaemonProcess {
+    fn spawn(
+        indexing_progress: Arc<Sender<IndexingProgress>>,
+        top_apps: Arc<Sender<Vec<ExecutableApp>>>,
+        results_changed: Arc<Sender<usize>>,
+        index_events: broadcast::Sender<IndexEvent>,
+        deferred_watcher: DeferredSender,
+        latest_search: Arc<Mutex<LatestSearch>>,
+    ) -> Result<Self, Report> {
+        let mut process = Command::new(std::env::current_exe()?)
+            .arg(DAEMON_ARG)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        let stdin = process.stdin.take().ok_or_report()?;
+        let stdout = process.stdout.take().ok_or_report()?;
+
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                match serde_json::from_str(&line) {
+                    Ok(Response::Results { id, mut results }) => {
+                        let mut latest_search = latest_search.lock().expect("no lock poisoning");
+                        if id == latest_search.id {
+                            results.append(&mut latest_search.ui_results);
+                            deferred_watcher.send_replace((id, results));
+                        }
+                    }
+                    Ok(Response::IndexingProgress(progress)) => {
+                        indexing_progress.send_replace(progress);
+                    }
+                    Ok(Response::TopApps(apps)) => {
+                        top_apps.send_replace(apps);
+                    }
+                    Ok(Response::ResultsChanged) => {
+                        results_changed.send_modify(|count| *count += 1);
+                    }
+                    Ok(Response::IndexEvent(event)) => {
+                        let _ = index_events.send(event);
+                    }
+                    Ok(Response::TaskProgress(progress)) => {
+                        deferred_watcher
+                            .send_modify(|(_, results)| apply_progress(results, &progress));
+                    }
+                    Ok(Response::Error(event)) => errors::publish(event),
+                    Err(err) => eprintln!("Invalid response from the daemon: {err}"),
+                }
+            }
+        });
+
+        Ok(Self { process, stdin })
+    }
+
+    fn is_running(&mut self) -> bool {
+        matches!(self.process.try_wait(), Ok(None))
+    }
+}
+
+impl Drop for DaemonProcess {
+    /// Stops a daemon that can't be reached anymore, and reaps it so that
+    /// it doesn't linger as a zombie process
+    fn drop(&mut self) {
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+    }
+}
+
+/// Search engine of the UI, forwarding everything to the daemon.
+/// The daemon is started again whenever it is found to have exited.
+#[derive(Debug)]
+pub struct DaemonSearchEngine {
+    daemon: Mutex<Option<DaemonProcess>>,
+    indexing_progress: Arc<Sender<IndexingProgress>>,
+    top_apps: Arc<Sender<Vec<ExecutableApp>>>,
+    results_changed: Arc<Sender<usize>>,
+    index_events: broadcast::Sender<IndexEvent>,
+    /// See [`DeterministicSearchEngine`], whose deferred searches are
+    /// mirrored. Tokens are also the ids of the searches sent to the daemon.
+    deferred_token: AtomicUsize,
+    deferred_watcher: DeferredSender,
+    latest_search: Arc<Mutex<LatestSearch>>,
+}
+
+impl DaemonSearchEngine {
+    pub fn spawn() -> Result<Self, Report> {
+        let indexing_progress = Arc::new(Sender::new(IndexingProgress::default()));
+        let top_apps = Arc::new(Sender::new(Vec::new()));
+        let results_changed = Arc::new(Sender::new(0));
+        let index_events = broadcast::Sender::new(INDEX_EVENTS_CAPACITY);
+        let (deferred_watcher, _rx) = watch::channel((0, vec![]));
+        let latest_search = Arc::new(Mutex::new(LatestSearch::default()));
+        let daemon = DaemonProcess::spawn(
+            indexing_progress.clone(),
+            top_apps.clone(),
+            results_changed.clone(),
+            index_events.clone(),
+            deferred_watcher.clone(),
+            latest_search.clone(),
+        )?;
+
+        Ok(Self {
+            daemon: Mutex::new(Some(daemon)),
+            indexing_progress,
+            top_apps,
+            results_changed,
+            index_events,
+            // The empty results the watcher starts with aren't the ones of
+            // the first search
+            deferred_token: AtomicUsize::new(1),
+            deferred_watcher,
+            latest_search,
+        })
+    }
+
+    /// The running daemon, started again if it exited
+    fn daemon(&self) -> MutexGuard<'_, Option<DaemonProcess>> {
+        let mut daemon = self.daemon.lock().expect("no lock poisoning");
+
+        if !daemon.as_mut().is_some_and(DaemonProcess::is_running) {
+            eprintln!("Search daemon is not running, starting it again");
+            *daemon = match DaemonProcess::spawn(
+                self.indexing_progress.clone(),
+                self.top_apps.clone(),
+                self.results_changed.clone(),
+                self.index_events.clone(),
+                self.deferred_watcher.clone(),
+                self.latest_search.clone(),
+            ) {
+                Ok(process) => Some(process),
+                Err(report) => {
+                    errors::report_error("Could not start search daemon", report);
+                    None
+                }
+            };
+        }
+
+        daemon
+    }
+
+    /// Sends a request, dropping the daemon if it can't be reached
+    fn send(daemon: &mut Option<DaemonProcess>, request: &Request) {
+        let Some(process) = daemon else {
+            return;
+        };
+
+        if let Err(report) = write_message(&mut process.stdin, request) {
+            eprintln!("{}", report.context("Could not reach search daemon"));
+            *daemon = None;
+        }
+    }
+}
+
+impl SearchEngine for DaemonSearchEngine {
+    fn preload(&self, context: SearchContext) {
+        Self::send(&mut self.daemon(), &Request::Preload(context));
+    }
+
+    fn blocking_search(&self, query: AppString) -> Vec<SearchResult> {
+        let (token, rx) = self.deferred_search(query);
+
+        let deadline = Instant::now() + SEARCH_TIMEOUT;
+        loop {
+            {
+                let (results_token, results) = &*rx.borrow();
+                if *results_token == token {
+                    return results.clone();
+                }
+            }
+            if Instant::now() >= deadline {
+                return Vec::new();
+            }
+            std::thread::sleep(RESULTS_POLL_INTERVAL);
+        }
+    }
+
+    /// Sends the search to the daemon without waiting for it, as the UI
+    /// thread would be blocked meanwhile. Results are sent to the watcher
+    /// by the thread reading the responses of the daemon.
+    fn deferred_search(&self, query: AppString) -> (DeferredToken, DeferredReceiver) {
+        let tx = self.deferred_watcher.clone();
+        let rx = tx.subscribe();
+        let token = self.deferred_token.fetch_add(1, Ordering::Acquire);
+
+        // The caches of the UI are listed along with the ones of the daemon
+        *self.latest_search.lock().expect("no lock poisoning") = LatestSearch {
+            id: token,
+            ui_results: CacheStatsProvider.search(&query),
+        };
+
+        let mut daemon = self.daemon();
+        Self::send(&mut daemon, &Request::Search { id: token, query });
+        if daemon.is_none() {
+            let ui_results = std::mem::take(
+                &mut self
+                    .latest_search
+                    .lock()
+                    .expect("no lock poisoning")
+                    .ui_results,
+            );
+            tx.send_replace((token, ui_results));
+            return (token, rx);
+        }
+        drop(daemon);
+
+        let latest_search = self.latest_search.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(SEARCH_TIMEOUT);
+            let is_latest_search = latest_search.lock().expect("no lock poisoning").id == token;
+            if is_latest_search && tx.borrow().0 != token {
+                eprintln!("Search daemon took too long to answer");
+                errors::publish(ErrorEvent {
+                    summary: String::from("Search timed out"),
+                    details: format!(
+                        "The search daemon didn't answer within {}s",
+                        SEARCH_TIMEOUT.as_secs()
+                    ),
+                    remedy: None,
+                });
+            }
+        });
+
+        (token, rx)
+    }
+
+    fn after_search(&self, opened_result: Option<SearchResult>) {
+        // Unlike the other engines, tokens aren't reset here, so that
+        // results still on their way can't be taken for the ones of the
+        // next search
+        Self::send(&mut self.daemon(), &Request::AfterSearch(opened_result));
+    }
+
+    fn set_private_mode(&self, private: bool) {
+        Self::send(&mut self.daemon(), &Request::SetPrivateMode(private));
+    }
+
+    fn toggle_hidden(&self, result: &SearchResult) {
+        Self::send(&mut self.daemon(), &Request::ToggleHidden(result.clone()));
+    }
+
+    fn toggle_pinned(&self, query: &AppString, result: &SearchResult) {
+        Self::send(
+            &mut self.daemon(),
+            &Request::TogglePinned {
+                query: query.clone(),
+                result: result.clone(),
+            },
+        );
+    }
+
+    fn set_sort_mode(&self, mode: SortMode) {
+        Self::send(&mut self.daemon(), &Request::SetSortMode(mode));
+    }
+
+    fn rebuild_index(&self) {
+        Self::send(&mut self.daemon(), &Request::RebuildIndex);
+    }
+
+    fn forget_app(&self, path: &Path) {
+        Self::send(&mut self.daemon(), &Request::ForgetApp(path.to_path_buf()));
+    }
+
+    fn cancel_task(&self, id: TaskId) {
+        Self::send(&mut self.daemon(), &Request::CancelTask(id));
+    }
+
+    fn indexing_progress(&self) -> Option<Receiver<IndexingProgress>> {
+        Some(self.indexing_progress.subscribe())
+    }
+
+    fn top_apps(&self) -> Option<Receiver<Vec<ExecutableApp>>> {
+        Some(self.top_apps.subscribe())
+    }
+
+    fn results_changed(&self) -> Option<Receiver<usize>> {
+        Some(self.results_changed.subscribe())
+    }
+
+    fn index_events(&self) -> Option<broadcast::Receiver<IndexEvent>> {
+        Some(self.index_events.subscribe())
+    }
+}
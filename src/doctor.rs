@@ -0,0 +1,205 @@
+//! Checks that what Fetch relies on works on this Mac: the hotkey, Spotlight,
+//! the data directory, permissions and icon decoding. Run with
+//! `fetch --doctor`, or by typing `doctor`, the report is meant to be pasted
+//! into an issue.
+
+use std::fmt::Write as _;
+
+use global_hotkey::GlobalHotKeyManager;
+use rootcause::{Report, prelude::ResultExt, report};
+
+use crate::{
+    fs::{config::Configuration, db::FilesystemPersistence},
+    permissions::{Permission, PermissionStatus, required_permissions},
+    platform::{ImplPlatform, Platform},
+    url::{Url, UrlEntry},
+};
+
+/// Flag of the executable, as in `fetch --doctor`
+pub const DOCTOR_ARG: &str = "--doctor";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    /// What was checked (for instance: "Data directory")
+    pub name: String,
+    pub passed: bool,
+    /// What was found, or why the check failed
+    pub details: String,
+}
+
+impl Check {
+    fn from_result(name: impl Into<String>, result: Result<String, Report>) -> Self {
+        let (passed, details) = match result {
+            Ok(details) => (true, details),
+            Err(report) => (false, report.to_string()),
+        };
+
+        Self {
+            name: name.into(),
+            passed,
+            details,
+        }
+    }
+}
+
+/// Runs every check. The hotkey is only registered when `register_hotkey`
+/// is set, since the running app already holds it.
+#[must_use]
+pub fn run_checks(config: &Configuration, register_hotkey: bool) -> Vec<Check> {
+    let mut checks = vec![
+        Check::from_result("Hotkey", check_hotkey(config, register_hotkey)),
+        Check::from_result("Spotlight", check_spotlight()),
+        Check::from_result("Data directory", check_data_dir()),
+    ];
+    checks.extend(check_permissions(config));
+    checks.push(Check::from_result("Icon decoding", check_icon_decoding()));
+
+    checks
+}
+
+fn check_hotkey(config: &Configuration, register: bool) -> Result<String, Report> {
+    let hotkey = config.hotkey_config()?;
+    if !register {
+        return Ok(format!("{} is a valid hotkey", config.open_search_hotkey));
+    }
+
+    let manager = GlobalHotKeyManager::new()?;
+    manager
+        .register(hotkey)
+        .attach("It may be used by another app, or by Fetch if it is running")?;
+    manager.unregister(hotkey)?;
+
+    Ok(format!("{} can be registered", config.open_search_hotkey))
+}
+
+fn check_spotlight() -> Result<String, Report> {
+    match ImplPlatform::count_spotlight_apps()? {
+        0 => Err(report!("Spotlight found no app, it may still be indexing")),
+        count => Ok(format!("{count} apps found")),
+    }
+}
+
+fn check_data_dir() -> Result<String, Report> {
+    // Opened for writing, creating the directory if needed
+    FilesystemPersistence::open()?;
+
+    Ok("The data file can be written".to_owned())
+}
+
+/// One check per permission the enabled features need
+fn check_permissions(config: &Configuration) -> Vec<Check> {
+    let mut permissions: Vec<(Permission, Vec<&str>)> = Vec::new();
+    for required in required_permissions(config) {
+        match permissions
+            .iter_mut()
+            .find(|(permission, _)| *permission == required.permission)
+        {
+            Some((_, features)) => features.push(required.feature),
+            None => permissions.push((required.permission, vec![required.feature])),
+        }
+    }
+
+    if permissions.is_empty() {
+        return vec![Check {
+            name: "Permissions".to_owned(),
+            passed: true,
+            details: "None of the enabled features needs a permission".to_owned(),
+        }];
+    }
+
+    permissions
+        .into_iter()
+        .map(|(permission, features)| {
+            let status = permission.status();
+            Check {
+                name: format!("{} permission", permission.display_name()),
+                passed: status != PermissionStatus::Denied,
+                details: format!("{status}, for {}", features.join(", ")),
+            }
+        })
+        .collect()
+}
+
+/// Reads the icon of an app every Mac has, and decodes it
+fn check_icon_decoding() -> Result<String, Report> {
+    let app_path = ImplPlatform::default_app_paths()
+        .into_iter()
+        .next()
+        .ok_or_else(|| report!("No app to read the icon of"))?;
+    let Some(UrlEntry::App { app }) = ImplPlatform::to_url_entry(&Url::File(app_path.clone()))
+    else {
+        return Err(report!("Could not read {}", app_path.display()));
+    };
+
+    let png = app
+        .icon_png_data
+        .ok_or_else(|| report!("{} has no icon", app.name))?;
+    let icon = image::load_from_memory(&png)?;
+
+    Ok(format!(
+        "The icon of {} is {}×{}",
+        app.name,
+        icon.width(),
+        icon.height()
+    ))
+}
+
+/// A summary line, then one line per check
+#[must_use]
+pub fn render_report(checks: &[Check]) -> String {
+    let passed = checks.iter().filter(|check| check.passed).count();
+    let mut report = format!(
+        "Fetch {} ({}): {passed} of {} checks passed\n",
+        env!("CARGO_PKG_VERSION"),
+        std::env::consts::ARCH,
+        checks.len()
+    );
+
+    for check in checks {
+        let mark = if check.passed { '✓' } else { '✗' };
+        // Errors may span several lines, which are indented under the check
+        let details = check.details.trim_end().replace('\n', "\n    ");
+        let _ = writeln!(report, "{mark} {}: {details}", check.name);
+    }
+
+    report
+}
+
+/// Runs `fetch --doctor`, which prints the report to the standard output
+pub fn run_command() -> Result<(), Report> {
+    let config = Configuration::read_from_fs()?;
+    print!("{}", render_report(&run_checks(&config, true)));
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_report() {
+        let checks = [
+            Check {
+                name: "Spotlight".to_owned(),
+                passed: true,
+                details: "120 apps found".to_owned(),
+            },
+            Check {
+                name: "Hotkey".to_owned(),
+                passed: false,
+                details: "Already registered\nIt may be used by another app\n".to_owned(),
+            },
+        ];
+
+        let report = render_report(&checks);
+        let (summary, checks) = report.split_once('\n').expect("a summary line");
+
+        assert!(summary.ends_with(": 1 of 2 checks passed"));
+        assert_eq!(
+            checks,
+            "✓ Spotlight: 120 apps found\n\
+             ✗ Hotkey: Already registered\n    It may be used by another app\n"
+        );
+    }
+}
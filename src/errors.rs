@@ -0,0 +1,56 @@
+//! Errors the user should know about (a failed launch, a provider that
+//! couldn't refresh, an invalid config entry) are shown in a banner above
+//! the results, rather than only printed to stderr.
+//!
+//! Each process publishes to its own channel. The daemon forwards the
+//! errors it publishes to the UI, which shows the latest one until it is
+//! dismissed.
+
+use std::{fmt::Display, sync::LazyLock};
+
+use rootcause::Report;
+use serde::{Deserialize, Serialize};
+use tokio::sync::watch::{Receiver, Sender};
+
+use crate::launch::Remedy;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ErrorEvent {
+    /// What failed (for instance: "Could not fetch repositories")
+    pub summary: String,
+    /// The whole error, shown on demand
+    pub details: String,
+    /// A fix the user can apply from the banner
+    #[serde(default)]
+    pub remedy: Option<Remedy>,
+}
+
+/// Only the latest error is kept, as a single one is shown at once
+static LATEST_ERROR: LazyLock<Sender<Option<ErrorEvent>>> = LazyLock::new(|| Sender::new(None));
+
+/// Prints `report` to stderr, and shows it in the search window
+pub fn report_error(summary: impl Display, report: Report) {
+    let summary = summary.to_string();
+    let details = report.to_string();
+    eprintln!("{}", report.context(summary.clone()));
+
+    publish(ErrorEvent {
+        summary,
+        details,
+        remedy: None,
+    });
+}
+
+pub fn publish(event: ErrorEvent) {
+    LATEST_ERROR.send_replace(Some(event));
+}
+
+/// Hides the latest error, until another one is published
+pub fn dismiss() {
+    LATEST_ERROR.send_replace(None);
+}
+
+#[must_use]
+pub fn subscribe() -> Receiver<Option<ErrorEvent>> {
+    LATEST_ERROR.subscribe()
+}
@@ -1,9 +1,55 @@
+use std::path::{Path, PathBuf};
+
 use serde::{Deserialize, Serialize};
-use tokio::sync::watch::{self, Receiver, Sender};
+use tokio::sync::{
+    broadcast,
+    watch::{self, Receiver, Sender},
+};
 
+pub mod cache_stats;
+pub mod corrections;
+pub mod dates;
 pub mod deterministic_search;
+pub mod doctor;
+pub mod files;
+#[cfg(feature = "fuzzy-engine")]
+pub mod fuzzy_search;
+pub mod generators;
+pub mod history;
+pub mod internal_commands;
+pub mod keyword_aliases;
+pub mod mail;
+pub mod matchers;
+pub mod menu_items;
+pub mod name_arena;
+pub mod permissions;
+pub mod projects;
+pub mod query_operators;
+pub mod repos;
+pub mod secrets;
+pub mod settings_sections;
+pub mod shortcuts;
+pub mod ssh;
+pub mod tasks;
+pub mod text_transforms;
+pub mod weather;
 
-use crate::app::{AppString, ExecutableApp};
+use crate::{
+    app::{AppString, ExecutableApp, RunningApp},
+    command::{CommandAction, CommandEntry},
+    errors,
+    extensions::{
+        internal_commands::InternalCommand,
+        menu_items::MenuItem,
+        projects::Project,
+        shortcuts::Shortcut,
+        ssh::SshHost,
+        tasks::{TaskId, TaskProgress},
+    },
+    fs::config::SortMode,
+    platform::{ImplPlatform, Platform},
+    url::{IndexEvent, IndexingProgress, Url},
+};
 
 pub type DeferredToken = usize;
 pub type DeferredMessage = (DeferredToken, Vec<SearchResult>);
@@ -13,10 +59,227 @@ pub type DeferredReceiver = Receiver<DeferredMessage>;
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Hash)]
 pub enum SearchResult {
     Executable(ExecutableApp),
+    /// An app moved to the Trash since it was indexed, which
+    /// gets restored when selected
+    TrashedApp {
+        app: ExecutableApp,
+        trash_path: PathBuf,
+    },
+    MenuItem(MenuItem),
+    Shortcut(Shortcut),
+    SshHost(SshHost),
+    Project(Project),
+    Url {
+        name: AppString,
+        url: Url,
+    },
+    /// Text that gets copied to the clipboard when selected
+    Text {
+        text: AppString,
+        label: AppString,
+    },
+    /// A command to run, named after its keyword
+    Command(CommandEntry),
+    /// Files selected in Finder, to open with an app that declares
+    /// it can open them
+    OpenWith {
+        app: ExecutableApp,
+        files: Vec<PathBuf>,
+    },
+    /// A keyword listed by typing `?`, which gets typed into the search
+    /// bar when selected
+    Keyword {
+        keyword: AppString,
+        description: AppString,
+    },
+    /// A query searched before, listed by typing `history`, which gets
+    /// typed into the search bar again when selected
+    PastQuery {
+        query: AppString,
+        /// When it was searched
+        label: AppString,
+    },
+    /// The query with its typos corrected, offered when it finds no app,
+    /// which gets typed into the search bar when selected
+    Correction {
+        query: AppString,
+    },
+    /// Something Fetch does to itself, such as quitting
+    Internal(InternalCommand),
+    /// Work a provider is still doing for the query, which can be
+    /// cancelled but not selected
+    Progress(TaskProgress),
+}
+
+impl SearchResult {
+    /// The name displayed for this result
+    #[must_use]
+    pub fn name(&self) -> AppString {
+        match self {
+            SearchResult::Executable(app)
+            | SearchResult::TrashedApp { app, .. }
+            | SearchResult::OpenWith { app, .. } => app.name.clone(),
+            SearchResult::MenuItem(menu_item) => menu_item.title.clone(),
+            SearchResult::Shortcut(shortcut) => shortcut.name.clone(),
+            SearchResult::SshHost(host) => host.name.clone(),
+            SearchResult::Project(project) => project.name.clone(),
+            SearchResult::Url { name, .. } => name.clone(),
+            SearchResult::Text { text, .. } => text.clone(),
+            SearchResult::Command(command) => command.keyword.as_str().into(),
+            SearchResult::Keyword { keyword, .. } => keyword.clone(),
+            SearchResult::PastQuery { query, .. } | SearchResult::Correction { query } => {
+                query.clone()
+            }
+            SearchResult::Internal(command) => command.name().into(),
+            SearchResult::Progress(progress) => progress.label.clone(),
+        }
+    }
+
+    /// The path or URL this result points to, if any
+    #[must_use]
+    pub fn location(&self) -> Option<String> {
+        match self {
+            SearchResult::Executable(app) | SearchResult::OpenWith { app, .. } => {
+                Some(app.path.to_string_lossy().into_owned())
+            }
+            SearchResult::TrashedApp { trash_path, .. } => {
+                Some(trash_path.to_string_lossy().into_owned())
+            }
+            SearchResult::Project(project) => Some(project.path.to_string_lossy().into_owned()),
+            SearchResult::Url { url, .. } => Some(url.to_string()),
+            SearchResult::Command(command) => match &command.action {
+                CommandAction::Url(url) => Some(url.clone()),
+                CommandAction::AppleScript(_) | CommandAction::AppleScriptFile(_) => None,
+            },
+            SearchResult::MenuItem(_)
+            | SearchResult::Shortcut(_)
+            | SearchResult::SshHost(_)
+            | SearchResult::Text { .. }
+            | SearchResult::Keyword { .. }
+            | SearchResult::PastQuery { .. }
+            | SearchResult::Correction { .. }
+            | SearchResult::Internal(_)
+            | SearchResult::Progress(_) => None,
+        }
+    }
+
+    /// Files this result points to, which can be previewed with Quick Look
+    /// or dragged out of the results
+    #[must_use]
+    pub fn file_paths(&self) -> Vec<PathBuf> {
+        match self {
+            SearchResult::Executable(app) => vec![app.path.clone()],
+            SearchResult::TrashedApp { trash_path, .. } => vec![trash_path.clone()],
+            SearchResult::OpenWith { files, .. } => files.clone(),
+            SearchResult::Project(project) => vec![project.path.clone()],
+            SearchResult::Url {
+                url: Url::File(path),
+                ..
+            } => vec![path.clone()],
+            SearchResult::Url { .. }
+            | SearchResult::MenuItem(_)
+            | SearchResult::Shortcut(_)
+            | SearchResult::SshHost(_)
+            | SearchResult::Text { .. }
+            | SearchResult::Command(_)
+            | SearchResult::Keyword { .. }
+            | SearchResult::PastQuery { .. }
+            | SearchResult::Correction { .. }
+            | SearchResult::Internal(_)
+            | SearchResult::Progress(_) => Vec::new(),
+        }
+    }
+
+    /// Identifies this result whatever its rank, by its kind and what it
+    /// points to (for instance: `app:/Applications/Safari.app`). Rows of
+    /// the results are named after it, so that UI automation finds them
+    /// wherever they are listed.
+    #[must_use]
+    pub fn stable_id(&self) -> String {
+        match self {
+            SearchResult::Executable(app) => format!("app:{}", app.path.display()),
+            SearchResult::TrashedApp { trash_path, .. } => {
+                format!("trashed-app:{}", trash_path.display())
+            }
+            SearchResult::OpenWith { app, .. } => format!("open-with:{}", app.path.display()),
+            SearchResult::MenuItem(item) => {
+                let menu_path: Vec<&str> = item.menu_path.iter().map(|menu| &**menu).collect();
+                format!(
+                    "menu-item:{}:{} > {}",
+                    item.app.bundle_id,
+                    menu_path.join(" > "),
+                    item.title
+                )
+            }
+            SearchResult::Shortcut(shortcut) => format!("shortcut:{}", shortcut.name),
+            SearchResult::SshHost(host) => format!("ssh:{}", host.name),
+            SearchResult::Project(project) => format!("project:{}", project.path.display()),
+            SearchResult::Url { url, .. } => format!("url:{url}"),
+            // Generated text changes on every search, unlike what it is
+            SearchResult::Text { label, .. } => format!("text:{label}"),
+            SearchResult::Command(command) => format!("command:{}", command.keyword),
+            SearchResult::Keyword { keyword, .. } => format!("keyword:{keyword}"),
+            SearchResult::PastQuery { query, .. } => format!("past-query:{query}"),
+            SearchResult::Correction { .. } => "correction".to_owned(),
+            SearchResult::Internal(command) => format!("internal:{}", command.name()),
+            SearchResult::Progress(progress) => format!("task:{}", progress.id),
+        }
+    }
+}
+
+/// State of the system at the moment the search window was opened,
+/// which providers get when preloading.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SearchContext {
+    /// The app that was frontmost before Fetch was summoned.
+    pub frontmost_app: Option<RunningApp>,
+    /// Files and folders selected in Finder, when it was the frontmost app
+    /// and reading its selection is enabled in the configuration.
+    /// Filled in by the search engine, as asking Finder takes a moment.
+    #[serde(default)]
+    pub selected_finder_items: Option<Vec<PathBuf>>,
+}
+
+/// Bundle identifier of Finder, whose selection is read
+const FINDER_BUNDLE_ID: &str = "com.apple.finder";
+
+impl SearchContext {
+    /// Must be called *before* the search window is shown, otherwise
+    /// Fetch itself is the frontmost app.
+    #[must_use]
+    pub fn capture() -> Self {
+        Self {
+            frontmost_app: ImplPlatform::frontmost_app(),
+            selected_finder_items: None,
+        }
+    }
+
+    /// Reads the Finder selection, if Finder was the frontmost app. The
+    /// selection is kept when Fetch comes to the front, so this can be
+    /// done after the search window is shown.
+    pub fn read_finder_selection(&mut self) {
+        let is_finder_frontmost = self
+            .frontmost_app
+            .as_ref()
+            .is_some_and(|app| app.bundle_id == FINDER_BUNDLE_ID);
+        if !is_finder_frontmost {
+            return;
+        }
+
+        self.selected_finder_items = match ImplPlatform::finder_selection() {
+            Ok(items) => Some(items),
+            Err(report) => {
+                // Likely for lack of the Automation permission, which
+                // `permissions` lists
+                errors::report_error("Could not read the Finder selection", report);
+                None
+            }
+        };
+    }
 }
 
 pub trait SearchEngine: Send + Sync + 'static {
-    fn preload(&self);
+    fn preload(&self, context: SearchContext);
     fn blocking_search(&self, query: AppString) -> Vec<SearchResult>;
     fn deferred_search(&self, query: AppString) -> (DeferredToken, DeferredReceiver) {
         let res = self.blocking_search(query);
@@ -27,4 +290,124 @@ pub trait SearchEngine: Send + Sync + 'static {
     /// This function is called after a search: either the user cancelled the search
     /// by pressing Esc, or they succeded a search by selecting an app.
     fn after_search(&self, selected_app: Option<SearchResult>);
+
+    /// Turns private mode on or off. Queries typed while it is on are
+    /// forgotten once it is turned off or the search ends (which turns it
+    /// off), rather than learned from or kept in the history of queries.
+    /// The ones typed before it was turned on are kept.
+    fn set_private_mode(&self, _private: bool) {}
+
+    /// Hides a result from future searches, or shows it again if it was hidden.
+    fn toggle_hidden(&self, _result: &SearchResult) {}
+
+    /// Pins a result to a query, so that it is listed first whenever that
+    /// query is typed, or unpins it if it already was.
+    fn toggle_pinned(&self, _query: &AppString, _result: &SearchResult) {}
+
+    /// Changes the order of results which rank the same, until the next
+    /// time the search window is opened.
+    fn set_sort_mode(&self, _mode: SortMode) {}
+
+    /// Reads every app again, forgetting what was indexed so far, for
+    /// engines that keep an index.
+    fn rebuild_index(&self) {}
+
+    /// Removes an app found to be gone when it was about to be opened,
+    /// rather than waiting for the next update of the index.
+    fn forget_app(&self, _path: &Path) {}
+
+    /// Reports how far along building the index is, for engines that build it
+    /// in the background.
+    fn indexing_progress(&self) -> Option<Receiver<IndexingProgress>> {
+        None
+    }
+
+    /// The apps opened most, listed again whenever the index is built, so
+    /// that their icons can be decoded before they are searched for.
+    fn top_apps(&self) -> Option<Receiver<Vec<ExecutableApp>>> {
+        None
+    }
+
+    /// Counts the times a provider got new results for a query after it was
+    /// searched (such as once a forecast is fetched), so that the current
+    /// query can be searched again.
+    fn results_changed(&self) -> Option<Receiver<usize>> {
+        None
+    }
+
+    /// Apps added to or removed from the index after it was built, so that
+    /// what was derived from them (like results and icons) can be refreshed.
+    fn index_events(&self) -> Option<broadcast::Receiver<IndexEvent>> {
+        None
+    }
+
+    /// Progress of the tasks listed as rows, which is also applied to the
+    /// results of the deferred channel, for engines whose providers run any.
+    fn task_progress(&self) -> Option<broadcast::Receiver<TaskProgress>> {
+        None
+    }
+
+    /// Stops a task listed as a row, which is removed from the results.
+    fn cancel_task(&self, _id: TaskId) {}
+}
+
+/// What to type to get results from a provider, as listed by typing `?`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Keyword {
+    pub keyword: &'static str,
+    pub description: &'static str,
+}
+
+/// A source of search results that aren't indexed apps, queried by
+/// the search engine alongside the app index. Provider results are
+/// listed after app results.
+pub trait Provider: std::fmt::Debug + Send + Sync + 'static {
+    /// Called whenever the search window opens, before any search is made.
+    /// Slow work (such as talking to other processes) belongs here.
+    fn preload(&self, _context: &SearchContext) {}
+
+    fn search(&self, query: &AppString) -> Vec<SearchResult>;
+
+    /// Keywords this provider answers to. Providers searching every query,
+    /// rather than the text after a keyword, don't have any.
+    fn keywords(&self) -> Vec<Keyword> {
+        Vec::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stable_id() {
+        let docs = SearchResult::Url {
+            name: "Docs".into(),
+            url: Url::File("/Users/me/Docs".into()),
+        };
+        assert_eq!(docs.stable_id(), "url:file:///Users/me/Docs");
+
+        // Renaming a result, or generating other text, keeps its id
+        let renamed = SearchResult::Url {
+            name: "Documents".into(),
+            url: Url::File("/Users/me/Docs".into()),
+        };
+        assert_eq!(renamed.stable_id(), docs.stable_id());
+        let uuid = |text: &str| SearchResult::Text {
+            text: text.into(),
+            label: "UUID".into(),
+        };
+        assert_eq!(uuid("a").stable_id(), uuid("b").stable_id());
+
+        // Results of different kinds never share one
+        let keyword = SearchResult::Keyword {
+            keyword: "proj".into(),
+            description: "Projects".into(),
+        };
+        let past_query = SearchResult::PastQuery {
+            query: "proj".into(),
+            label: "Yesterday".into(),
+        };
+        assert_ne!(keyword.stable_id(), past_query.stable_id());
+    }
 }
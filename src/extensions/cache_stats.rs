@@ -0,0 +1,50 @@
+use crate::{
+    app::AppString,
+    cache::{self, BYTES_PER_MB},
+    extensions::{Keyword, Provider, SearchResult},
+};
+
+/// Typing `stats` lists how much memory each cache of this process
+/// uses, against its budget. Selecting a cache copies its usage.
+#[derive(Debug, Default)]
+pub struct CacheStatsProvider;
+
+const KEYWORD: &str = "stats";
+
+#[allow(
+    clippy::cast_precision_loss,
+    reason = "only displayed, with a single decimal"
+)]
+fn megabytes(bytes: usize) -> String {
+    format!("{:.1} MB", bytes as f64 / BYTES_PER_MB as f64)
+}
+
+impl Provider for CacheStatsProvider {
+    fn search(&self, query: &AppString) -> Vec<SearchResult> {
+        if !query.trim().eq_ignore_ascii_case(KEYWORD) {
+            return Vec::new();
+        }
+
+        cache::stats()
+            .into_iter()
+            .map(|stats| SearchResult::Text {
+                text: format!(
+                    "{}: {} of {} · {} entries",
+                    stats.name,
+                    megabytes(stats.bytes),
+                    megabytes(stats.byte_budget),
+                    stats.entries
+                )
+                .into(),
+                label: "Cache usage".into(),
+            })
+            .collect()
+    }
+
+    fn keywords(&self) -> Vec<Keyword> {
+        vec![Keyword {
+            keyword: KEYWORD,
+            description: "Memory used by the caches of Fetch",
+        }]
+    }
+}
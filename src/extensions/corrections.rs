@@ -0,0 +1,133 @@
+//! Spelling corrections of queries which find no app, such as "chrome"
+//! for "chrme", offered in a "Did you mean" row. Words are corrected to
+//! the closest word of the names of the indexed apps.
+
+use std::collections::BTreeSet;
+
+/// Words shorter than this are left alone, as they are close to too many
+const MIN_CORRECTED_LEN: usize = 3;
+
+/// Edits a word of `len` characters may need to be corrected: one for
+/// short words, which a second edit turns into about anything, two for
+/// longer ones
+fn max_edits(len: usize) -> usize {
+    if len < 5 { 1 } else { 2 }
+}
+
+/// Characters to insert, delete or substitute, or pairs of adjacent
+/// characters to swap, to turn `a` into `b` (the optimal string alignment
+/// distance). Swaps count as one edit, as they are the most common typo.
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    // Distances between the prefixes of `a` and those of `b`, for the
+    // current and the two previous prefixes of `a`
+    let mut before_previous: Vec<usize> = Vec::new();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, a_char) in a.iter().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != b_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+
+            let is_swap = i > 0 && j > 0 && *a_char == b[j - 1] && a[i - 1] == *b_char;
+            if is_swap {
+                current[j + 1] = current[j + 1].min(before_previous[j - 1] + 1);
+            }
+        }
+
+        before_previous = std::mem::replace(&mut previous, current);
+    }
+
+    previous[b.len()]
+}
+
+/// The word of `words` closest to `word`, if one is close enough. Ties
+/// go to the word of the closest length, then to the first in order.
+fn closest_word<'a>(word: &str, words: &BTreeSet<&'a str>) -> Option<&'a str> {
+    let chars: Vec<char> = word.chars().collect();
+    let max_edits = max_edits(chars.len());
+
+    words
+        .iter()
+        .filter(|candidate| candidate.chars().count().abs_diff(chars.len()) <= max_edits)
+        .map(|candidate| {
+            let candidate_chars: Vec<char> = candidate.chars().collect();
+            let distance = edit_distance(&chars, &candidate_chars);
+            (
+                distance,
+                candidate_chars.len().abs_diff(chars.len()),
+                *candidate,
+            )
+        })
+        .filter(|(distance, _, _)| *distance <= max_edits)
+        .min()
+        .map(|(_, _, candidate)| candidate)
+}
+
+/// `query` (normalized and lowercase) with each of its words that no word
+/// of `words` contains corrected, or `None` if none needs to be or one
+/// can't be
+#[must_use]
+pub fn correct_query(query: &str, words: &BTreeSet<&str>) -> Option<String> {
+    let mut corrected = Vec::new();
+    let mut was_corrected = false;
+
+    for word in query.split_whitespace() {
+        if words.iter().any(|known| known.contains(word)) {
+            corrected.push(word);
+            continue;
+        }
+        if word.chars().count() < MIN_CORRECTED_LEN {
+            return None;
+        }
+
+        corrected.push(closest_word(word, words)?);
+        was_corrected = true;
+    }
+
+    was_corrected.then(|| corrected.join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn distance(a: &str, b: &str) -> usize {
+        let a: Vec<char> = a.chars().collect();
+        let b: Vec<char> = b.chars().collect();
+        edit_distance(&a, &b)
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(distance("chrome", "chrome"), 0);
+        assert_eq!(distance("chrme", "chrome"), 1);
+        assert_eq!(distance("chorme", "chrome"), 1);
+        assert_eq!(distance("safar", "safari"), 1);
+        assert_eq!(distance("kitten", "sitting"), 3);
+        assert_eq!(distance("", "abc"), 3);
+        assert_eq!(distance("café", "cafe"), 1);
+    }
+
+    #[test]
+    fn test_correct_query() {
+        let words: BTreeSet<&str> = ["google", "chrome", "safari", "slack", "notes"].into();
+
+        assert_eq!(correct_query("chrme", &words).as_deref(), Some("chrome"));
+        assert_eq!(
+            correct_query("gogle chorme", &words).as_deref(),
+            Some("google chrome")
+        );
+        // Words found as they are typed are kept
+        assert_eq!(
+            correct_query("google chrme", &words).as_deref(),
+            Some("google chrome")
+        );
+        assert_eq!(correct_query("chro", &words), None);
+
+        // Too far from any word, or too short to tell
+        assert_eq!(correct_query("xylophone", &words), None);
+        assert_eq!(correct_query("chrme zq", &words), None);
+        assert_eq!(correct_query("slak", &words).as_deref(), Some("slack"));
+    }
+}
@@ -0,0 +1,357 @@
+use std::sync::Mutex;
+
+use crate::{
+    app::AppString,
+    extensions::{Keyword, Provider, SearchContext, SearchResult},
+    fs::config::Configuration,
+    locale::{Date, Locale},
+    platform::{ImplPlatform, Platform},
+};
+
+/// Typing `date <expression>` computes a date or a duration, shown inline
+/// and copied when selected:
+/// - `date today + 45 days`, `date 2025-03-01 - 2 weeks`
+/// - `date next friday`, `date last monday`
+/// - `date weeks between 2024-01-01 and now`
+///
+/// Dates are read and written in the locale of the user. Workdays skip
+/// weekends, but not public holidays, which differ from place to place.
+#[derive(Debug)]
+pub struct DateProvider {
+    locale: Locale,
+    /// The current date when the search window was opened
+    today: Mutex<Option<Date>>,
+}
+
+const KEYWORD: &str = "date";
+
+/// Offsets past this are more likely typos than dates anyone needs
+const MAX_COUNT: i64 = 100_000;
+
+const WEEKDAYS: [&str; 7] = [
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+    "Sunday",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Unit {
+    Day,
+    /// Monday to Friday
+    Workday,
+    Week,
+    Month,
+    Year,
+}
+
+impl Unit {
+    /// Reads singular and plural units, as in `day` or `days`
+    fn parse(word: &str) -> Option<Self> {
+        let word = word.to_lowercase();
+        match word.strip_suffix('s').unwrap_or(&word) {
+            "day" => Some(Self::Day),
+            "workday" | "weekday" | "business day" => Some(Self::Workday),
+            "week" => Some(Self::Week),
+            "month" => Some(Self::Month),
+            "year" => Some(Self::Year),
+            _ => None,
+        }
+    }
+
+    fn name(self, count: i64) -> String {
+        let name = match self {
+            Self::Day => "day",
+            Self::Workday => "workday",
+            Self::Week => "week",
+            Self::Month => "month",
+            Self::Year => "year",
+        };
+
+        if count.abs() == 1 {
+            format!("{count} {name}")
+        } else {
+            format!("{count} {name}s")
+        }
+    }
+}
+
+impl DateProvider {
+    #[must_use]
+    pub fn new(config: &Configuration) -> Self {
+        Self {
+            locale: Locale::current(config),
+            today: Mutex::new(None),
+        }
+    }
+}
+
+impl Provider for DateProvider {
+    fn preload(&self, _context: &SearchContext) {
+        *self.today.lock().expect("no lock poisoning") = ImplPlatform::today();
+    }
+
+    fn search(&self, query: &AppString) -> Vec<SearchResult> {
+        let Some(expression) = query
+            .strip_prefix_ignore_case(KEYWORD)
+            .and_then(|rest| rest.strip_prefix(' '))
+        else {
+            return Vec::new();
+        };
+        let Some(today) = *self.today.lock().expect("no lock poisoning") else {
+            return Vec::new();
+        };
+
+        evaluate(expression, today, &self.locale)
+            .map(|(text, label)| SearchResult::Text {
+                text: text.into(),
+                label: label.into(),
+            })
+            .into_iter()
+            .collect()
+    }
+
+    fn keywords(&self) -> Vec<Keyword> {
+        vec![Keyword {
+            keyword: KEYWORD,
+            description: "Compute dates and durations, as in date today + 45 days",
+        }]
+    }
+}
+
+/// The text to copy and its label, if the expression can be computed
+fn evaluate(expression: &str, today: Date, locale: &Locale) -> Option<(String, String)> {
+    let expression = expression.trim().to_lowercase();
+
+    if let Some((unit, dates)) = expression.split_once("between ") {
+        let unit = match unit.trim() {
+            "" => Unit::Day,
+            unit => Unit::parse(unit)?,
+        };
+        let (start, end) = dates.split_once(" and ")?;
+        let start = parse_base(start, today, locale)?;
+        let end = parse_base(end, today, locale)?;
+
+        let label = format!(
+            "From {} to {}",
+            locale.format_date(start),
+            locale.format_date(end)
+        );
+        return Some((duration_between(start, end, unit), label));
+    }
+
+    let date = if let Some(weekday) = expression.strip_prefix("next ") {
+        let days = (7 + i64::from(parse_weekday(weekday)?) - i64::from(today.weekday())) % 7;
+        today.add_days(if days == 0 { 7 } else { days })
+    } else if let Some(weekday) = expression.strip_prefix("last ") {
+        let days = (7 + i64::from(today.weekday()) - i64::from(parse_weekday(weekday)?)) % 7;
+        today.add_days(if days == 0 { -7 } else { -days })
+    } else {
+        parse_offsets(&expression, today, locale)?
+    };
+
+    let weekday = WEEKDAYS[usize::try_from(date.weekday()).ok()?];
+    Some((locale.format_date(date), weekday.to_owned()))
+}
+
+/// Reads `today`, `now`, `tomorrow`, `yesterday` or a date
+fn parse_base(text: &str, today: Date, locale: &Locale) -> Option<Date> {
+    match text.trim() {
+        "today" | "now" => Some(today),
+        "tomorrow" => Some(today.add_days(1)),
+        "yesterday" => Some(today.add_days(-1)),
+        date => locale.parse_date(date),
+    }
+}
+
+/// Reads full and abbreviated weekdays, as in `friday` or `fri`
+fn parse_weekday(text: &str) -> Option<u32> {
+    let text = text.trim();
+    if text.len() < 3 {
+        return None;
+    }
+
+    WEEKDAYS
+        .iter()
+        .position(|weekday| weekday.to_lowercase().starts_with(text))
+        .and_then(|weekday| u32::try_from(weekday).ok())
+}
+
+/// Reads a date followed by any number of offsets, as in
+/// `today + 1 month - 2 days`
+fn parse_offsets(expression: &str, today: Date, locale: &Locale) -> Option<Date> {
+    // Dates may contain dashes, so only a sign after a space (or a `+`)
+    // starts the offsets
+    let offsets_start = expression.find(['+', ' ']).unwrap_or(expression.len());
+    let (base, mut rest) = expression.split_at(offsets_start);
+    let mut date = parse_base(base, today, locale)?;
+
+    loop {
+        rest = rest.trim_start();
+        if rest.is_empty() {
+            return Some(date);
+        }
+
+        let sign = match rest.chars().next()? {
+            '+' => 1,
+            '-' | '−' => -1,
+            _ => return None,
+        };
+        rest = rest[rest.chars().next()?.len_utf8()..].trim_start();
+
+        let count_end = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let count = rest[..count_end]
+            .parse::<i64>()
+            .ok()
+            .filter(|count| *count <= MAX_COUNT)?;
+        rest = rest[count_end..].trim_start();
+
+        let unit_end = rest.find(['+', '-', '−']).unwrap_or(rest.len());
+        let unit = Unit::parse(rest[..unit_end].trim())?;
+        rest = &rest[unit_end..];
+
+        date = add(date, sign * count, unit);
+    }
+}
+
+fn add(date: Date, count: i64, unit: Unit) -> Date {
+    match unit {
+        Unit::Day => date.add_days(count),
+        Unit::Workday => {
+            let step = count.signum();
+            let mut date = date;
+            let mut remaining = count.abs();
+            while remaining > 0 {
+                date = date.add_days(step);
+                if date.weekday() < 5 {
+                    remaining -= 1;
+                }
+            }
+            date
+        }
+        Unit::Week => date.add_days(count * 7),
+        Unit::Month => date.add_months(count),
+        Unit::Year => date.add_months(count * 12),
+    }
+}
+
+/// Whole units from `start` to `end`, negative if `end` comes first.
+/// Days left over are written out, as in `14 weeks and 2 days`.
+fn duration_between(start: Date, end: Date, unit: Unit) -> String {
+    let (sign, start, end) = if end < start {
+        (-1, end, start)
+    } else {
+        (1, start, end)
+    };
+    let days = end.days_since_epoch() - start.days_since_epoch();
+
+    let (count, rest) = match unit {
+        Unit::Day => (days, 0),
+        Unit::Workday => {
+            let workdays = (0..days)
+                .filter(|day| start.add_days(*day).weekday() < 5)
+                .count();
+            (i64::try_from(workdays).unwrap_or_default(), 0)
+        }
+        Unit::Week => (days / 7, days % 7),
+        Unit::Month | Unit::Year => {
+            let mut months = (i64::from(end.year) - i64::from(start.year)) * 12
+                + i64::from(end.month)
+                - i64::from(start.month);
+            if end.day < start.day {
+                months -= 1;
+            }
+            let months = if unit == Unit::Year {
+                months - months % 12
+            } else {
+                months
+            };
+            let rest = end.days_since_epoch() - start.add_months(months).days_since_epoch();
+
+            (
+                if unit == Unit::Year {
+                    months / 12
+                } else {
+                    months
+                },
+                rest,
+            )
+        }
+    };
+
+    let duration = unit.name(sign * count);
+    if rest == 0 {
+        duration
+    } else {
+        format!("{duration} and {}", Unit::Day.name(rest))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> Date {
+        Date::new(year, month, day).unwrap()
+    }
+
+    fn evaluate_text(expression: &str) -> Option<String> {
+        // A Wednesday
+        let today = date(2025, 4, 2);
+        evaluate(expression, today, &Locale::from_identifier("en_US")).map(|(text, _)| text)
+    }
+
+    #[test]
+    fn test_offsets() {
+        assert_eq!(
+            evaluate_text("today + 45 days").as_deref(),
+            Some("05/17/2025")
+        );
+        assert_eq!(
+            evaluate_text("2025-01-31 + 1 month - 1 day").as_deref(),
+            Some("02/27/2025")
+        );
+        assert_eq!(
+            evaluate_text("tomorrow+2 weeks").as_deref(),
+            Some("04/17/2025")
+        );
+        assert_eq!(
+            evaluate_text("today + 3 workdays").as_deref(),
+            Some("04/07/2025")
+        );
+        assert_eq!(evaluate_text("today + 45 parsecs"), None);
+    }
+
+    #[test]
+    fn test_weekdays() {
+        assert_eq!(evaluate_text("next friday").as_deref(), Some("04/04/2025"));
+        assert_eq!(evaluate_text("next wed").as_deref(), Some("04/09/2025"));
+        assert_eq!(evaluate_text("last monday").as_deref(), Some("03/31/2025"));
+        assert_eq!(evaluate_text("next fr"), None);
+    }
+
+    #[test]
+    fn test_durations() {
+        assert_eq!(
+            evaluate_text("weeks between 2024-01-01 and now").as_deref(),
+            Some("65 weeks and 2 days")
+        );
+        assert_eq!(
+            evaluate_text("between 2025-04-12 and today").as_deref(),
+            Some("-10 days")
+        );
+        assert_eq!(
+            evaluate_text("months between 2025-01-31 and 2025-03-30").as_deref(),
+            Some("1 month and 30 days")
+        );
+        assert_eq!(
+            evaluate_text("workdays between today and 2025-04-09").as_deref(),
+            Some("5 workdays")
+        );
+    }
+}
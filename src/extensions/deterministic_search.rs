@@ -3,38 +3,161 @@
 #![cfg_attr(not(debug_assertions), allow(unused_imports))]
 
 use std::{
+    cmp::Reverse,
+    collections::{BTreeSet, VecDeque},
     fmt::Debug,
+    path::{Path, PathBuf},
     sync::{
         Arc, Mutex,
-        atomic::{AtomicUsize, Ordering},
+        atomic::{AtomicBool, AtomicUsize, Ordering},
     },
+    time::{Instant, SystemTime, UNIX_EPOCH},
 };
 
 use rayon::{
-    iter::{IntoParallelIterator, ParallelIterator},
+    iter::{IntoParallelIterator, IntoParallelRefIterator, ParallelIterator},
     slice::ParallelSliceMut,
 };
 use rootcause::Report;
-use scc::{Guard, HashMap, hash_map::OccupiedEntry};
-use tokio::sync::watch::channel;
+use scc::{Guard, HashMap, HashSet};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{
+    broadcast,
+    watch::{Receiver, Sender, channel},
+};
 use unicode_segmentation::UnicodeSegmentation;
 
 use crate::{
-    app::{AppName, AppString, AppSubstr, ExecutableApp},
-    extensions::{DeferredReceiver, DeferredSender, DeferredToken, SearchEngine, SearchResult},
+    app::{AppName, AppString, AppSubstr, AppUsage, ExecutableApp},
+    cache::{BYTES_PER_MB, LruCache},
+    command::CommandAction,
+    extensions::{
+        DeferredReceiver, DeferredSender, DeferredToken, Keyword, Provider, SearchContext,
+        SearchEngine, SearchResult,
+        cache_stats::CacheStatsProvider,
+        corrections::correct_query,
+        dates::DateProvider,
+        doctor::DoctorProvider,
+        files::FileProvider,
+        generators::GeneratorProvider,
+        history::{HistoryProvider, QueryHistory},
+        internal_commands::InternalCommandProvider,
+        keyword_aliases::KeywordAliases,
+        mail::MailProvider,
+        matchers::MatcherProvider,
+        menu_items::MenuItemProvider,
+        permissions::PermissionsProvider,
+        projects::ProjectProvider,
+        query_operators::QueryOperators,
+        repos::RepoProvider,
+        settings_sections::SettingsSectionProvider,
+        shortcuts::ShortcutsProvider,
+        ssh::SshProvider,
+        tasks::{TaskId, TaskProgress, Tasks},
+        text_transforms::TextTransformProvider,
+        weather::WeatherProvider,
+    },
     fs::{
-        config::Configuration,
-        db::{AppPersistence, FilesystemPersistence},
+        config::{Configuration, SortMode},
+        db::{AppPersistence, FilesystemPersistence, RenamedKey, VersionedKey},
+        stats::{self, SEARCH_LATENCIES_KEY, SearchLatency},
     },
-    url::{UrlEntry, UrlIndex},
+    normalize::normalize,
+    platform::{ImplPlatform, Platform},
+    scheduler::BackgroundScheduler,
+    url::{INDEX_EVENTS_CAPACITY, IndexEvent, IndexingProgress, Url, UrlEntry, UrlIndex},
+};
+
+#[cfg(test)]
+mod fixtures;
+
+/// Key of the data file holding the apps learned for each query
+const LEARNED_APPS_KEY: VersionedKey = VersionedKey {
+    name: "learned_apps",
+    renamed_from: Some(RenamedKey {
+        name: "learned_substring_index",
+        migration: migrate_learned_substring_index,
+    }),
+    migrations: &[],
 };
 
+/// Key of the data file holding how often and when each app was opened
+const APP_USAGE_KEY: VersionedKey = VersionedKey {
+    name: "app_usage",
+    renamed_from: None,
+    migrations: &[],
+};
+
+/// Restricts the search to a category, as in `category:games`
+const CATEGORY_FILTER_PREFIX: &str = "category:";
+
+/// Lists the apps that can open a kind of file, as in `opens:pdf`
+const OPENS_FILTER_PREFIX: &str = "opens:";
+
+/// Lists the apps the user hid, so that they can be unhidden
+const HIDDEN_FILTER_PREFIX: &str = "hidden:";
+
+/// Lists the apps that can open the files selected in Finder, as in `with preview`
+const OPEN_WITH_KEYWORD: &str = "with";
+
+/// Lists every keyword, of the search engine, of providers and of
+/// commands, along with their aliases
+const KEYWORDS_QUERY: &str = "?";
+
+/// Keywords of the search engine itself, listed along with those of providers
+const ENGINE_KEYWORDS: [Keyword; 4] = [
+    Keyword {
+        keyword: CATEGORY_FILTER_PREFIX,
+        description: "Apps of a category, as in category:games",
+    },
+    Keyword {
+        keyword: OPENS_FILTER_PREFIX,
+        description: "Apps that can open a kind of file, as in opens:pdf",
+    },
+    Keyword {
+        keyword: HIDDEN_FILTER_PREFIX,
+        description: "Apps you hid, to show them again",
+    },
+    Keyword {
+        keyword: OPEN_WITH_KEYWORD,
+        description: "Apps that can open the files selected in Finder",
+    },
+];
+
+/// Queries shorter than this don't match path aliases, which would list
+/// every app of a folder after a couple of keystrokes
+const MIN_PATH_ALIAS_QUERY_LEN: usize = 3;
+
+/// How many queries to keep the results of
+const RESULTS_CACHE_CAPACITY: usize = 128;
+
+/// Queries this short have their results ranked ahead of time
+const MAX_PRECOMPUTED_QUERY_LEN: usize = 2;
+
+/// Apps matching a query from which they are sorted in parallel
+const MIN_PARALLEL_SORT_LEN: usize = 2_000;
+
+/// How many of the apps opened most have their icons decoded ahead of time
+const TOP_APPS_LEN: usize = 30;
+
+/// How much of its weight a learned app keeps when another app
+/// is opened from the same query
+const LEARNED_WEIGHT_DECAY: f32 = 0.7;
+
+/// Learned apps lighter than this are forgotten
+const MIN_LEARNED_WEIGHT: f32 = 0.05;
+
+/// A file selected in Finder, along with the UTIs it
+/// conforms to (most specific first)
+type SelectedFile = (PathBuf, Vec<String>);
+
 /// This simple search engine works by caching
 /// every substring of every app into a hash table,
 /// resulting in effectively O(1) lookup for any search.
 ///
 /// Search results are then sorted by several factors:
-/// - Alphabetical order
+/// - Alphabetical order, or how recently or often apps were opened
+///   (see [`SortMode`])
 /// - How close the substring is to the app name, which we call
 ///   "beginning distance". For instance, looking for "code" might
 ///   result in "Visual Studio Code" or "Xcode", but the user likely
@@ -42,13 +165,77 @@ use crate::{
 /// - Learned searches. If a user previously searched for "Foo", then
 ///   typing "F" or "Fo" will result in "Foo" appearing first, even
 ///   though another app named "Font" would have otherwise been.
+///   Several apps can be learned for the same query, in which case
+///   the one most likely to be picked appears first.
 #[derive(Debug, Clone)]
 pub struct DeterministicSearchEngine {
     db: Arc<Mutex<FilesystemPersistence>>,
     config: Arc<Configuration>,
-    url_index: UrlIndex,
-    learned_substring_index: Arc<HashMap<AppString, ExecutableApp>>,
+    url_index: Arc<UrlIndex>,
+    indexing_progress: Sender<IndexingProgress>,
+    /// Set once the index is first built, before which it isn't updated
+    index_built: Arc<AtomicBool>,
+    /// See [`SearchEngine::top_apps`]
+    top_apps: Sender<Vec<ExecutableApp>>,
+    /// See [`SearchEngine::results_changed`], shared with the providers
+    /// fetching results in the background
+    results_changed: Arc<Sender<usize>>,
+    /// See [`SearchEngine::index_events`], published once the caches of the
+    /// engine are refreshed, so that subscribers never search stale ones
+    index_events: broadcast::Sender<IndexEvent>,
+    /// Work providers do in the background, listed as progress rows
+    tasks: Arc<Tasks>,
+    learned_substring_index: Arc<HashMap<AppString, LearnedApps>>,
     substring_index: Arc<HashMap<AppString, Vec<AppName>>>,
+    /// Apps by every pair of graphemes of their normalized name. Queries of
+    /// two graphemes or more are matched against the apps listed under
+    /// their first two, rather than against every app.
+    two_letter_candidates: Arc<HashMap<AppString, Vec<Url>>>,
+    /// Apps by the prefixes of the words of their [`path_aliases`], which
+    /// match after every app whose name matches
+    path_alias_index: Arc<HashMap<AppString, Vec<AppName>>>,
+    providers: Arc<Vec<Box<dyn Provider>>>,
+    /// Listed before any other result, see [`MatcherProvider`]
+    matchers: Arc<MatcherProvider>,
+    /// Words of the user's language typed instead of provider keywords
+    keyword_aliases: Arc<KeywordAliases>,
+    /// Paths of the running apps, refreshed whenever the search bar is opened
+    open_apps: Arc<HashSet<PathBuf>>,
+    /// Mount points of the external volumes apps were indexed on, which
+    /// were unmounted since. Their apps are hidden until the volume is back.
+    unmounted_volumes: Arc<HashSet<PathBuf>>,
+    /// Paths of the apps the user chose to hide from results
+    hidden_apps: Arc<HashSet<PathBuf>>,
+    /// Path of the app the user pinned to each query, listed first
+    /// whenever that query is typed whatever was learned
+    pinned_apps: Arc<HashMap<AppString, PathBuf>>,
+    /// How often and how recently each app was opened, by path
+    app_usage: Arc<HashMap<PathBuf, AppUsage>>,
+    /// Order of apps which rank the same, reset to the one of the
+    /// configuration whenever the search bar is opened
+    sort_mode: Arc<Mutex<SortMode>>,
+    /// When each SSH host was last connected to, shared with [`SshProvider`]
+    ssh_last_used: Arc<HashMap<AppString, u64>>,
+    /// How often and how recently each project was opened, by path, shared
+    /// with [`ProjectProvider`]
+    project_usage: Arc<HashMap<PathBuf, AppUsage>>,
+    /// Defers refreshes on battery, shared with the providers refreshing lists
+    scheduler: Arc<BackgroundScheduler>,
+    /// Ranked apps of recent queries. Cleared whenever the
+    /// index or the learned substrings change.
+    results_cache: Arc<Mutex<LruCache<AppString, Vec<ExecutableApp>>>>,
+    /// Ranked apps of every query of up to [`MAX_PRECOMPUTED_QUERY_LEN`]
+    /// graphemes, ranked in the background whenever `results_cache` is
+    /// cleared, so that the first keystrokes don't wait for ranking. Every
+    /// matching app is kept, as all of them are listed.
+    precomputed_results: Arc<HashMap<AppString, Vec<ExecutableApp>>>,
+    /// Incremented whenever ranked apps get stale, so that precomputed
+    /// results ranked in the meantime are thrown away
+    ranking_generation: Arc<AtomicUsize>,
+    /// How long the latest searches took, saved along with what was learned
+    search_latencies: Arc<Mutex<VecDeque<SearchLatency>>>,
+    /// Files selected in Finder when the search bar was opened
+    finder_selection: Arc<Mutex<Vec<SelectedFile>>>,
 
     /// Keeps track of the latest search query.
     /// The higher that number is, the more recent
@@ -63,148 +250,1279 @@ pub struct DeterministicSearchEngine {
     /// Every query the user has entered when searching
     /// for an app. For instance, if the user launches Fetch, and opens
     /// Firefox by having search "Fire", then the vector will contain the
-    /// following: `["F", "Fi", "Fir", "Fire"]`
+    /// following: `["F", "Fi", "Fir", "Fire"]`. Queries for other results,
+    /// as in `text hello`, aren't kept.
     query_history: scc::Stack<AppString>,
+    /// While private mode is on, how many queries of `query_history` were
+    /// typed before it was turned on
+    private_from: Arc<Mutex<Option<usize>>>,
+    /// Queries of past searches, shared with [`HistoryProvider`]
+    past_queries: Arc<Mutex<QueryHistory>>,
 }
 
 impl SearchEngine for DeterministicSearchEngine {
     fn blocking_search(&self, query: AppString) -> Vec<SearchResult> {
-        self.query_history.push(query.clone());
+        self.search_with(&query, Self::ranked_apps)
+    }
+
+    fn deferred_search(&self, query: AppString) -> (DeferredToken, DeferredReceiver) {
+        self.deferred_search_with(query, |query| self.blocking_search(query))
+    }
+
+    fn after_search(&self, opened_app: Option<SearchResult>) {
+        let mut queries: Vec<AppString> = {
+            // Each search bar starts out of private mode
+            let private_from = self.private_from.lock().expect("no lock poisoning").take();
+            if let Some(kept) = private_from {
+                self.forget_queries_after(kept);
+            }
+
+            let guard = Guard::new();
+            self.query_history.pop_all().iter(&guard).cloned().collect()
+        };
+        // Popped latest first
+        queries.reverse();
+
+        let today = ImplPlatform::today().filter(|_| self.config.query_history.enabled);
+        if let Some(today) = today {
+            let mut past_queries = self.past_queries.lock().expect("no lock poisoning");
+            past_queries.record(today, &queries, self.config.query_history.retention_days);
+            if opened_app.is_some() {
+                // Along with the ones typed before the window was last closed
+                queries = past_queries.take_in_flight();
+            }
+
+            self.db
+                .lock()
+                .expect("no lock poisoning")
+                .save_data("past_queries", &*past_queries)
+                .expect("json map is expected to function");
+        }
+
+        match opened_app {
+            Some(SearchResult::Executable(app)) => {
+                {
+                    let mut entry = self.app_usage.entry_sync(app.path.clone()).or_default();
+                    let usage = entry.get_mut();
+                    usage.launch_count += 1;
+                    usage.last_opened = unix_now();
+                }
+
+                for query in queries {
+                    self.learned_substring_index
+                        .entry_sync(AppString::from(normalize(&query)))
+                        .or_default()
+                        .get_mut()
+                        .learn(&app.name);
+                }
+
+                self.db
+                    .lock()
+                    .expect("no lock poisoning")
+                    .save_data(LEARNED_APPS_KEY.name, self.learned_substring_index.clone())
+                    .expect("json map is expected to function");
+                self.db
+                    .lock()
+                    .expect("no lock poisoning")
+                    .save_data(APP_USAGE_KEY.name, self.app_usage.clone())
+                    .expect("json map is expected to function");
+
+                self.invalidate_results();
+            }
+            Some(SearchResult::SshHost(host)) => {
+                let _ = self.ssh_last_used.upsert_sync(host.name, unix_now());
+
+                self.db
+                    .lock()
+                    .expect("no lock poisoning")
+                    .save_data("ssh_last_used", self.ssh_last_used.clone())
+                    .expect("json map is expected to function");
+            }
+            Some(SearchResult::Project(project)) => {
+                {
+                    let mut entry = self.project_usage.entry_sync(project.path).or_default();
+                    let usage = entry.get_mut();
+                    usage.launch_count += 1;
+                    usage.last_opened = unix_now();
+                }
+
+                self.db
+                    .lock()
+                    .expect("no lock poisoning")
+                    .save_data("project_usage", self.project_usage.clone())
+                    .expect("json map is expected to function");
+            }
+            _ => {}
+        }
+
+        let search_latencies = self
+            .search_latencies
+            .lock()
+            .expect("no lock poisoning")
+            .clone();
+        self.db
+            .lock()
+            .expect("no lock poisoning")
+            .save_data(SEARCH_LATENCIES_KEY, search_latencies)
+            .expect("json map is expected to function");
+
+        self.deferred_token.store(0, Ordering::Release);
+
+        self.index_apps();
+    }
+
+    fn set_private_mode(&self, private: bool) {
+        let mut private_from = self.private_from.lock().expect("no lock poisoning");
+        match (private, *private_from) {
+            (true, None) => *private_from = Some(self.query_history.len()),
+            (false, Some(kept)) => {
+                self.forget_queries_after(kept);
+                *private_from = None;
+            }
+            _ => {}
+        }
+    }
+
+    fn toggle_hidden(&self, result: &SearchResult) {
+        let SearchResult::Executable(app) = result else {
+            return;
+        };
+
+        if self.hidden_apps.remove_sync(&app.path).is_none() {
+            let _ = self.hidden_apps.insert_sync(app.path.clone());
+        }
+
+        self.db
+            .lock()
+            .expect("no lock poisoning")
+            .save_data("hidden_apps", self.hidden_apps.clone())
+            .expect("json map is expected to function");
+
+        self.invalidate_results();
+    }
+
+    fn toggle_pinned(&self, query: &AppString, result: &SearchResult) {
+        let SearchResult::Executable(app) = result else {
+            return;
+        };
+        // Kept by normalized query, which is what apps are ranked by
+        let query = AppString::from(normalize(&QueryOperators::parse(query).apps_query));
+        if query.is_empty() {
+            return;
+        }
+
+        let was_pinned = self
+            .pinned_apps
+            .remove_if_sync(&query, |path| *path == app.path)
+            .is_some();
+        if !was_pinned {
+            // Replaces the app pinned to the query before, if any
+            let _ = self.pinned_apps.upsert_sync(query, app.path.clone());
+        }
+
+        self.db
+            .lock()
+            .expect("no lock poisoning")
+            .save_data("pinned_apps", self.pinned_apps.clone())
+            .expect("json map is expected to function");
+
+        self.invalidate_results();
+    }
+
+    fn set_sort_mode(&self, mode: SortMode) {
+        let mut sort_mode = self.sort_mode.lock().expect("no lock poisoning");
+        if *sort_mode != mode {
+            *sort_mode = mode;
+            self.invalidate_results();
+        }
+    }
+
+    fn rebuild_index(&self) {
+        self.url_index.clear();
+        self.substring_index.clear_sync();
+        self.two_letter_candidates.clear_sync();
+        self.path_alias_index.clear_sync();
+        self.invalidate_results();
+
+        self.build_index();
+    }
+
+    fn forget_app(&self, path: &Path) {
+        if self.url_index.remove(path) {
+            self.invalidate_results();
+            self.refresh_top_apps();
+            self.publish_index_events(vec![
+                IndexEvent::AppRemoved(path.to_path_buf()),
+                IndexEvent::IndexUpdated,
+            ]);
+        }
+    }
+
+    fn indexing_progress(&self) -> Option<Receiver<IndexingProgress>> {
+        Some(self.indexing_progress.subscribe())
+    }
+
+    fn top_apps(&self) -> Option<Receiver<Vec<ExecutableApp>>> {
+        Some(self.top_apps.subscribe())
+    }
+
+    fn results_changed(&self) -> Option<Receiver<usize>> {
+        Some(self.results_changed.subscribe())
+    }
+
+    fn index_events(&self) -> Option<broadcast::Receiver<IndexEvent>> {
+        Some(self.index_events.subscribe())
+    }
+
+    fn task_progress(&self) -> Option<broadcast::Receiver<TaskProgress>> {
+        Some(self.tasks.subscribe())
+    }
+
+    fn cancel_task(&self, id: TaskId) {
+        self.tasks.cancel(id);
+    }
+
+    fn preload(&self, mut context: SearchContext) {
+        self.scheduler.begin_batch();
+        self.set_sort_mode(self.config.sort_mode);
+
+        if self.config.finder_selection.enabled {
+            context.read_finder_selection();
+        }
+        let open_apps_changed = self.refresh_open_apps();
+        if self.refresh_unmounted_volumes() || open_apps_changed {
+            self.invalidate_results();
+        }
+        *self.finder_selection.lock().expect("no lock poisoning") = context
+            .selected_finder_items
+            .iter()
+            .flatten()
+            .map(|path| (path.clone(), ImplPlatform::content_types(path)))
+            .collect();
+
+        self.providers
+            .par_iter()
+            .for_each(|provider| provider.preload(&context));
+
+        // While saving power, the index is only updated along with the rest
+        // of a batch. Apps are all read by the build in the meantime.
+        if self.index_built.load(Ordering::Acquire) && self.scheduler.is_due(Some(Instant::now())) {
+            let events = self.url_index.update(&self.config);
+            if !events.is_empty() {
+                // So that installed apps can be searched right away
+                self.index_apps();
+                self.invalidate_results();
+                self.refresh_top_apps();
+                self.publish_index_events(events);
+            }
+        }
+    }
+}
+
+/// Apps opened after typing a query, weighted by how likely each is
+/// to be the one the user is looking for. Opening an app from the query
+/// decays the weight of the others, so that alternating between a few
+/// apps keeps all of them near the top.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LearnedApps(Vec<LearnedApp>);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct LearnedApp {
+    name: AppName,
+    weight: f32,
+}
+
+impl LearnedApps {
+    fn learn(&mut self, opened_app: &AppName) {
+        for learned_app in &mut self.0 {
+            learned_app.weight *= LEARNED_WEIGHT_DECAY;
+        }
+
+        match self
+            .0
+            .iter_mut()
+            .find(|learned_app| learned_app.name == *opened_app)
+        {
+            Some(learned_app) => learned_app.weight += 1.0,
+            None => self.0.push(LearnedApp {
+                name: opened_app.clone(),
+                weight: 1.0,
+            }),
+        }
+
+        self.0
+            .retain(|learned_app| learned_app.weight >= MIN_LEARNED_WEIGHT);
+        self.0.sort_by(|a, b| b.weight.total_cmp(&a.weight));
+    }
+
+    /// Names of the learned apps, most likely first
+    fn ranked_names(&self) -> Vec<AppName> {
+        self.0
+            .iter()
+            .map(|learned_app| learned_app.name.clone())
+            .collect()
+    }
+}
+
+/// Learned apps used to be stored as a single app per query
+fn migrate_learned_substring_index(value: serde_json::Value) -> Result<serde_json::Value, Report> {
+    let learned_substring_index: std::collections::HashMap<AppString, ExecutableApp> =
+        serde_json::from_value(value)?;
+
+    let learned_apps: std::collections::HashMap<AppString, LearnedApps> = learned_substring_index
+        .into_iter()
+        .map(|(query, app)| {
+            let mut apps = LearnedApps::default();
+            apps.learn(&app.name);
+            (query, apps)
+        })
+        .collect();
+
+    Ok(serde_json::to_value(learned_apps)?)
+}
+
+/// Seconds since the UNIX epoch
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+/// The providers enabled in the configuration
+fn build_providers(
+    config: &Arc<Configuration>,
+    scheduler: &Arc<BackgroundScheduler>,
+    ssh_last_used: &Arc<HashMap<AppString, u64>>,
+    project_usage: &Arc<HashMap<PathBuf, AppUsage>>,
+    past_queries: &Arc<Mutex<QueryHistory>>,
+    results_changed: &Arc<Sender<usize>>,
+    tasks: &Arc<Tasks>,
+) -> Vec<Box<dyn Provider>> {
+    let mut providers: Vec<Box<dyn Provider>> = vec![
+        Box::new(CacheStatsProvider),
+        Box::new(PermissionsProvider::new(config)),
+        Box::new(DoctorProvider::new(config.clone())),
+        Box::new(InternalCommandProvider),
+    ];
+    if config.search_menu_items {
+        providers.push(Box::new(MenuItemProvider::default()));
+    }
+    if config.shortcuts.enabled {
+        providers.push(Box::new(ShortcutsProvider::new(scheduler.clone())));
+    }
+    if config.settings_sections.enabled {
+        providers.push(Box::new(SettingsSectionProvider::new(
+            &config.settings_sections,
+        )));
+    }
+    if config.text_transforms.enabled {
+        providers.push(Box::new(TextTransformProvider::default()));
+    }
+    if config.generators.enabled {
+        providers.push(Box::new(GeneratorProvider::new(config.generators.clone())));
+    }
+    if config.dates.enabled {
+        providers.push(Box::new(DateProvider::new(config)));
+    }
+    if config.mail.enabled {
+        providers.push(Box::new(MailProvider::new(config.mail.clone())));
+    }
+    if config.files.enabled {
+        providers.push(Box::new(FileProvider::new(
+            config.files.clone(),
+            tasks.clone(),
+        )));
+    }
+    let repos_config = RepoProvider::with_stored_tokens(config.repos.clone());
+    if repos_config.github_token.is_some() || repos_config.gitlab_token.is_some() {
+        providers.push(Box::new(RepoProvider::new(repos_config, scheduler.clone())));
+    }
+    if !config.projects.roots.is_empty() {
+        providers.push(Box::new(ProjectProvider::new(
+            config.projects.clone(),
+            scheduler.clone(),
+            project_usage.clone(),
+        )));
+    }
+    if config.ssh.enabled {
+        providers.push(Box::new(SshProvider::new(ssh_last_used.clone())));
+    }
+    if config.query_history.enabled {
+        providers.push(Box::new(HistoryProvider::new(past_queries.clone())));
+    }
+    if config.weather.enabled {
+        providers.push(Box::new(WeatherProvider::new(
+            config.weather.clone(),
+            results_changed.clone(),
+        )));
+    }
+
+    providers
+}
+
+impl DeterministicSearchEngine {
+    /// Forgets the latest queries of `query_history`, down to the first
+    /// `kept` ones
+    fn forget_queries_after(&self, kept: usize) {
+        for _ in kept..self.query_history.len() {
+            let _ = self.query_history.pop();
+        }
+    }
+
+    /// Searches like [`SearchEngine::blocking_search`], with the apps
+    /// matching a query ranked by `rank_apps`. Other engines rank apps
+    /// their own way with it, and leave everything else to this one.
+    ///
+    /// How long the search took is recorded here, rather than by deferred
+    /// searches, as the daemon makes blocking searches only.
+    pub(crate) fn search_with(
+        &self,
+        query: &AppString,
+        rank_apps: impl FnOnce(&Self, &AppString) -> Vec<ExecutableApp>,
+    ) -> Vec<SearchResult> {
+        let search_start = Instant::now();
+        let results = self.collect_results(query, rank_apps);
+        stats::record_search(
+            &mut self.search_latencies.lock().expect("no lock poisoning"),
+            SearchLatency {
+                timestamp: unix_now(),
+                query_length: query.grapheme_len(),
+                duration_us: u64::try_from(search_start.elapsed().as_micros()).unwrap_or(u64::MAX),
+                results: results.len(),
+            },
+        );
+
+        results
+    }
+
+    fn collect_results(
+        &self,
+        query: &AppString,
+        rank_apps: impl FnOnce(&Self, &AppString) -> Vec<ExecutableApp>,
+    ) -> Vec<SearchResult> {
+        let operators = QueryOperators::parse(query);
+        let is_keyword_query = self.is_keyword_query(&operators.providers_query);
+
+        if query.trim() == KEYWORDS_QUERY {
+            return self.search_keywords();
+        }
+
+        if let Some(filter) = query.strip_prefix_ignore_case(HIDDEN_FILTER_PREFIX) {
+            return self
+                .search_hidden(filter.trim())
+                .into_par_iter()
+                .map(|app| self.app_result(app))
+                .collect();
+        }
+
+        if let Some(filter) = query.strip_prefix_ignore_case(CATEGORY_FILTER_PREFIX) {
+            let (category, rest) = filter.split_once(' ').unwrap_or((filter, ""));
+
+            return self
+                .search_category(category, &rest.into())
+                .into_par_iter()
+                .map(|app| self.app_result(app))
+                .collect();
+        }
+
+        if let Some(filter) = query.strip_prefix_ignore_case(OPENS_FILTER_PREFIX) {
+            let (kind, rest) = filter.split_once(' ').unwrap_or((filter, ""));
+
+            return self
+                .search_opens(kind, &rest.into())
+                .into_par_iter()
+                .map(|app| self.app_result(app))
+                .collect();
+        }
+
+        if let Some(results) = self.search_open_with(query) {
+            return results;
+        }
+
+        let query = operators.apps_query;
+        let apps = rank_apps(self, &query);
+        let app_results = apps.into_iter().map(|app| self.app_result(app));
+
+        let mut results = if operators.apps_only {
+            app_results.collect()
+        } else {
+            let providers_query = operators.providers_query;
+            let mut results = self.matchers.search(&providers_query);
+            results.extend(app_results);
+            // Matchers read queries as typed, as their patterns are the user's
+            let providers_query = self.keyword_aliases.resolve(&providers_query);
+            results.extend(
+                self.providers
+                    .iter()
+                    .flat_map(|provider| provider.search(&providers_query)),
+            );
+            results
+        };
+
+        if operators.exact {
+            let normalized_query = normalize(&query);
+            results.retain(|result| normalize(&result.name()) == normalized_query);
+        }
+
+        // Providers finding something means the query wasn't for an app
+        let only_apps = results
+            .iter()
+            .all(|result| matches!(result, SearchResult::Executable(_)));
+        // Only queries for apps are learned from and kept, rather than what
+        // providers were given, as in `mail …`. Operators aren't, so that
+        // `fire!` teaches the same as `fire`.
+        let found_apps = results
+            .iter()
+            .any(|result| matches!(result, SearchResult::Executable(_)));
+        if !is_keyword_query && (found_apps || only_apps) {
+            self.query_history.push(query.clone());
+        }
+
+        if only_apps && let Some(correction) = self.correction(&query) {
+            // After the apps found another way, which Enter still opens
+            results.push(SearchResult::Correction { query: correction });
+        }
+
+        results
+    }
+
+    /// Whether `query` is text given to a provider or command after its
+    /// keyword, as in `text hello` (or an alias of it)
+    fn is_keyword_query(&self, query: &AppString) -> bool {
+        let query = self.keyword_aliases.resolve(query);
+        let Some((word, _)) = query.split_once(' ') else {
+            return false;
+        };
+        let word = word.to_lowercase();
+
+        self.providers
+            .iter()
+            .flat_map(|provider| provider.keywords())
+            .any(|keyword| keyword.keyword == word)
+            || self
+                .config
+                .commands
+                .iter()
+                .any(|command| command.keyword.to_lowercase() == word)
+    }
+
+    /// `query` with its typos corrected, when it matches no app by name
+    /// but the corrected query does. Other engines may still find apps
+    /// for `query` their own way, like fuzzy matching does.
+    fn correction(&self, query: &AppString) -> Option<AppString> {
+        if query.trim().is_empty() || !self.ranked_apps(query).is_empty() {
+            return None;
+        }
+
+        let names: Vec<String> = {
+            let guard = Guard::new();
+            self.visible_apps(&guard)
+                .map(|app| normalize(&app.name).to_lowercase())
+                .collect()
+        };
+        let words: BTreeSet<&str> = names
+            .iter()
+            .flat_map(|name| name.split_whitespace())
+            .collect();
+
+        let correction = AppString::from(correct_query(&normalize(query).to_lowercase(), &words)?);
+        (!self.ranked_apps(&correction).is_empty()).then_some(correction)
+    }
+
+    /// Searches like [`SearchEngine::deferred_search`], with `search`
+    pub(crate) fn deferred_search_with(
+        &self,
+        query: AppString,
+        search: impl FnOnce(AppString) -> Vec<SearchResult>,
+    ) -> (DeferredToken, DeferredReceiver) {
+        let tx = self.deferred_watcher.clone();
+        let rx = tx.subscribe();
+        let token = self.deferred_token.fetch_add(1, Ordering::Acquire);
+        let res = search(query);
+        tx.send_replace((token, res));
+        (token, rx)
+    }
+
+    /// Apps matching `query`, best first, from the ranked apps of recent
+    /// queries when there are some. They are kept by normalized query, as
+    /// ranking only depends on it, so that `cafe` and `Café` share them.
+    pub(crate) fn ranked_apps(&self, query: &AppString) -> Vec<ExecutableApp> {
+        let query = &AppString::from(normalize(query));
+        let precomputed_apps = (query.grapheme_len() <= MAX_PRECOMPUTED_QUERY_LEN)
+            .then(|| {
+                self.precomputed_results
+                    .read_sync(query, |_, apps| apps.clone())
+            })
+            .flatten();
+        let cached_apps = precomputed_apps.or_else(|| {
+            self.results_cache
+                .lock()
+                .expect("no lock poisoning")
+                .get(query)
+        });
+
+        cached_apps.unwrap_or_else(|| {
+            let apps = self.rank_apps(query);
+            self.results_cache
+                .lock()
+                .expect("no lock poisoning")
+                .insert(query.clone(), apps.clone());
+            apps
+        })
+    }
+
+    /// Lists the app pinned to `query` before `apps`, if one is
+    pub(crate) fn with_pinned_app(
+        &self,
+        query: &AppString,
+        apps: Vec<ExecutableApp>,
+    ) -> Vec<ExecutableApp> {
+        let guard = Guard::new();
+        let pinned_app = self
+            .pinned_apps
+            .read_sync(query, |_, path| {
+                self.visible_apps(&guard)
+                    .find(|app| app.path == *path)
+                    .cloned()
+            })
+            .flatten();
+
+        match pinned_app {
+            Some(pinned_app) => pin_first(apps, pinned_app, |a, b| a.path == b.path),
+            None => apps,
+        }
+    }
+
+    /// Every app shown in results, for engines matching them their own way
+    #[cfg(feature = "fuzzy-engine")]
+    pub(crate) fn visible_app_list(&self) -> Vec<ExecutableApp> {
+        let guard = Guard::new();
+        self.visible_apps(&guard).cloned().collect()
+    }
+
+    pub fn build(config: Arc<Configuration>) -> Result<Self, Report> {
+        let mut engine = Self::new(config, FilesystemPersistence::open()?, UrlIndex::default());
+        engine.providers = Arc::new(build_providers(
+            &engine.config,
+            &engine.scheduler,
+            &engine.ssh_last_used,
+            &engine.project_usage,
+            &engine.past_queries,
+            &engine.results_changed,
+            &engine.tasks,
+        ));
+
+        // Index in the background, so that the search bar can be
+        // opened (and show progress) while apps are being read
+        let indexer = engine.clone();
+        rayon::spawn(move || indexer.build_index());
+
+        Ok(engine)
+    }
+
+    /// An engine reading what was learned from `db`, and the apps of
+    /// `url_index` once its index is built. Providers are left to `build`.
+    fn new(config: Arc<Configuration>, mut db: FilesystemPersistence, url_index: UrlIndex) -> Self {
+        let substring_index = Arc::new(scc::HashMap::new());
+
+        let learned_substring_index =
+            Arc::new(db.load_versioned(&LEARNED_APPS_KEY).unwrap_or_default());
+        let hidden_apps = Arc::new(db.get_data("hidden_apps").unwrap_or_default());
+        // Pins made or imported before they were kept by normalized query
+        // are found again
+        let pinned_apps: Arc<HashMap<AppString, PathBuf>> = Arc::new(HashMap::new());
+        let stored_pins: std::collections::HashMap<AppString, PathBuf> =
+            db.get_data("pinned_apps").unwrap_or_default();
+        for (query, path) in stored_pins {
+            let _ = pinned_apps.upsert_sync(AppString::from(normalize(&query)), path);
+        }
+        let ssh_last_used: Arc<HashMap<AppString, u64>> =
+            Arc::new(db.get_data("ssh_last_used").unwrap_or_default());
+        let project_usage: Arc<HashMap<PathBuf, AppUsage>> =
+            Arc::new(db.get_data("project_usage").unwrap_or_default());
+        let past_queries = Arc::new(Mutex::new(db.get_data("past_queries").unwrap_or_default()));
+        let app_usage = Arc::new(db.load_versioned(&APP_USAGE_KEY).unwrap_or_default());
+        let search_latencies = Arc::new(Mutex::new(
+            db.get_data(SEARCH_LATENCIES_KEY).unwrap_or_default(),
+        ));
+
+        let scheduler = Arc::new(BackgroundScheduler::new(config.power));
+        let results_changed = Arc::new(Sender::new(0));
+        let (tx, _rx) = channel((0, vec![]));
+        let tasks = Arc::new(Tasks::new(tx.clone(), results_changed.clone()));
+
+        let matchers = Arc::new(MatcherProvider::new(&config.matchers));
+        let keyword_aliases = Arc::new(KeywordAliases::new(&config));
+        let results_cache = Arc::new(Mutex::new(LruCache::registered(
+            "Search results",
+            RESULTS_CACHE_CAPACITY,
+            config.cache_budgets.search_results_mb * BYTES_PER_MB,
+        )));
+
+        let sort_mode = config.sort_mode;
+        Self {
+            db: Arc::new(Mutex::new(db)),
+            config,
+            url_index: Arc::new(url_index),
+            indexing_progress: Sender::new(IndexingProgress::default()),
+            index_built: Arc::new(AtomicBool::new(false)),
+            top_apps: Sender::new(Vec::new()),
+            results_changed,
+            index_events: broadcast::Sender::new(INDEX_EVENTS_CAPACITY),
+            tasks,
+            learned_substring_index,
+            substring_index,
+            two_letter_candidates: Arc::new(HashMap::new()),
+            path_alias_index: Arc::new(HashMap::new()),
+            providers: Arc::new(Vec::new()),
+            matchers,
+            keyword_aliases,
+            open_apps: Arc::new(HashSet::new()),
+            unmounted_volumes: Arc::new(HashSet::new()),
+            hidden_apps,
+            pinned_apps,
+            app_usage,
+            sort_mode: Arc::new(Mutex::new(sort_mode)),
+            ssh_last_used,
+            project_usage,
+            scheduler,
+            results_cache,
+            precomputed_results: Arc::new(HashMap::new()),
+            ranking_generation: Arc::new(AtomicUsize::new(0)),
+            search_latencies,
+            finder_selection: Arc::new(Mutex::new(Vec::new())),
+            deferred_token: Arc::new(AtomicUsize::new(0)),
+            deferred_watcher: tx,
+            query_history: scc::Stack::new(),
+            private_from: Arc::new(Mutex::new(None)),
+            past_queries,
+        }
+    }
+
+    /// Reads every app into the index, then what is derived from the apps
+    fn build_index(&self) {
+        self.url_index.build(&self.config, &self.indexing_progress);
+        self.index_apps();
+        self.invalidate_results();
+
+        if self.url_index.fill_missing_icons(&self.config) {
+            self.invalidate_results();
+        }
+        self.refresh_top_apps();
+        self.index_built.store(true, Ordering::Release);
+        self.publish_index_events(vec![IndexEvent::IndexUpdated]);
+    }
+
+    fn publish_index_events(&self, events: Vec<IndexEvent>) {
+        for event in events {
+            // Fails when nothing is subscribed, which is fine
+            let _ = self.index_events.send(event);
+        }
+    }
+
+    /// Forgets ranked apps, which are stale once the index, what was
+    /// learned or how apps are ranked changes, then ranks the shortest
+    /// queries again in the background
+    fn invalidate_results(&self) {
+        {
+            let mut results_cache = self.results_cache.lock().expect("no lock poisoning");
+            self.ranking_generation.fetch_add(1, Ordering::AcqRel);
+            results_cache.clear();
+            self.precomputed_results.clear_sync();
+        }
+
+        let engine = self.clone();
+        rayon::spawn(move || engine.precompute_results());
+    }
+
+    /// Ranks every query of up to [`MAX_PRECOMPUTED_QUERY_LEN`] graphemes
+    /// matching an app
+    fn precompute_results(&self) {
+        let generation = self.ranking_generation.load(Ordering::Acquire);
+
+        let mut queries = Vec::new();
+        self.substring_index.iter_sync(|substring, _| {
+            let len = substring.grapheme_len();
+            if (1..=MAX_PRECOMPUTED_QUERY_LEN).contains(&len) {
+                queries.push(substring.clone());
+            }
+            true
+        });
+
+        let ranked: Vec<(AppString, Vec<ExecutableApp>)> = queries
+            .into_par_iter()
+            .map(|query| {
+                let apps = self.rank_apps(&query);
+                (query, apps)
+            })
+            .collect();
+
+        // Held so that results can't be invalidated while being stored
+        let _results_cache = self.results_cache.lock().expect("no lock poisoning");
+        if self.ranking_generation.load(Ordering::Acquire) != generation {
+            return;
+        }
+        for (query, apps) in ranked {
+            let _ = self.precomputed_results.upsert_sync(query, apps);
+        }
+    }
+
+    fn app_result(&self, mut app: ExecutableApp) -> SearchResult {
+        app.usage = self.app_usage.read_sync(&app.path, |_, usage| *usage);
+        app.is_open = self.is_open(&app);
+
+        // The index is only refreshed after a search
+        if !app.path.exists()
+            && let Some(trash_path) = ImplPlatform::trash_location(&app.path)
+        {
+            return SearchResult::TrashedApp { app, trash_path };
+        }
+
+        SearchResult::Executable(app)
+    }
+
+    fn indexed_apps<'a>(&'a self, guard: &'a Guard) -> impl Iterator<Item = &'a ExecutableApp> {
+        self.url_index.iter(guard).filter_map(|(_, url)| {
+            if let UrlEntry::App { app } = url {
+                Some(app)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Whether the app is running, as of the last time the search bar was opened
+    fn is_open(&self, app: &ExecutableApp) -> bool {
+        self.open_apps.contains_sync(&app.path)
+    }
+
+    /// Returns `true` if apps were launched or quit since the last refresh
+    fn refresh_open_apps(&self) -> bool {
+        let open_binaries = ImplPlatform::list_open_binaries();
+
+        let mut changed = false;
+        self.open_apps.retain_sync(|path| {
+            let keep = open_binaries.contains(path);
+            changed |= !keep;
+            keep
+        });
+        for path in open_binaries {
+            changed |= self.open_apps.insert_sync(path).is_ok();
+        }
+
+        changed
+    }
+
+    /// Returns `true` if volumes apps were indexed on were mounted or
+    /// unmounted since the last refresh
+    fn refresh_unmounted_volumes(&self) -> bool {
+        let guard = Guard::new();
+        let mut volumes: Vec<PathBuf> = self
+            .indexed_apps(&guard)
+            .filter_map(|app| ImplPlatform::external_volume(&app.path))
+            .collect();
+        volumes.sort_unstable();
+        volumes.dedup();
+
+        let mut changed = false;
+        for volume in volumes {
+            changed |= if volume.exists() {
+                self.unmounted_volumes.remove_sync(&volume).is_some()
+            } else {
+                self.unmounted_volumes.insert_sync(volume).is_ok()
+            };
+        }
+
+        changed
+    }
+
+    /// Lists the apps opened most again, with the icons the index holds
+    fn refresh_top_apps(&self) {
+        let guard = Guard::new();
+        let mut apps: Vec<ExecutableApp> = self
+            .visible_apps(&guard)
+            .filter(|app| app.icon_png_data.is_some() && self.app_usage.contains_sync(&app.path))
+            .cloned()
+            .collect();
+        sort_by_mode(&mut apps, SortMode::Frequent, |app| {
+            let usage = self
+                .app_usage
+                .read_sync(&app.path, |_, usage| *usage)
+                .unwrap_or_default();
+            (usage, app.name.clone())
+        });
+        apps.truncate(TOP_APPS_LEN);
+
+        self.top_apps.send_replace(apps);
+    }
+
+    /// Every indexed app, except the ones the user hid and the ones on
+    /// volumes that were unmounted
+    fn visible_apps<'a>(&'a self, guard: &'a Guard) -> impl Iterator<Item = &'a ExecutableApp> {
+        self.indexed_apps(guard).filter(|app| self.is_visible(app))
+    }
+
+    fn is_visible(&self, app: &ExecutableApp) -> bool {
+        !self.hidden_apps.contains_sync(&app.path)
+            && ImplPlatform::external_volume(&app.path)
+                .is_none_or(|volume| !self.unmounted_volumes.contains_sync(&volume))
+    }
+
+    /// Visible apps whose normalized name contains `normalized_query`,
+    /// found among the apps listed under its first two graphemes
+    fn matching_apps<'a>(
+        &'a self,
+        normalized_query: &AppString,
+        guard: &'a Guard,
+    ) -> Vec<&'a ExecutableApp> {
+        // Looked up once rather than for every app, as the first keystrokes
+        // match most apps
+        let matching_names = || matching_names(&self.substring_index, normalized_query);
+
+        let first_two: String = normalized_query.graphemes(true).take(2).collect();
+        if first_two.graphemes(true).count() < 2 {
+            let matching_names = matching_names();
+            return self
+                .visible_apps(guard)
+                .filter(|app| matching_names.contains(&app.name))
+                .collect();
+        }
+
+        let candidates: Vec<&ExecutableApp> = self
+            .two_letter_candidates
+            .read_sync(&AppString::from(first_two.as_str()), |_, urls| {
+                urls.iter()
+                    .filter_map(|url| match self.url_index.get(url, guard) {
+                        Some(UrlEntry::App { app }) => Some(app),
+                        _ => None,
+                    })
+                    .filter(|app| self.is_visible(app))
+                    .collect()
+            })
+            .unwrap_or_default();
+        // Every candidate contains a query of two graphemes
+        if first_two.len() == normalized_query.len() {
+            return candidates;
+        }
+
+        let matching_names = matching_names();
+        candidates
+            .into_iter()
+            .filter(|app| matching_names.contains(&app.name))
+            .collect()
+    }
+
+    /// Lists the apps the user hid whose name contains `query`
+    fn search_hidden(&self, query: &str) -> Vec<ExecutableApp> {
+        let query = normalize(query).to_lowercase();
+        let guard = Guard::new();
+
+        let mut apps: Vec<ExecutableApp> = self
+            .indexed_apps(&guard)
+            .filter(|app| self.hidden_apps.contains_sync(&app.path))
+            .filter(|app| normalize(&app.name).to_lowercase().contains(&query))
+            .cloned()
+            .collect();
+
+        apps.par_sort_by_cached_key(|app| app.name.clone());
+
+        apps
+    }
+
+    /// Lists the apps of a category (whose name starts with `category`) matching `query`.
+    /// If `query` is empty, every app of the category is listed, grouped by category.
+    fn search_category(&self, category: &str, query: &AppString) -> Vec<ExecutableApp> {
+        let category = category.to_lowercase();
+        let is_in_category = |app: &ExecutableApp| {
+            app.category
+                .as_ref()
+                .is_some_and(|app_category| app_category.starts_with(&category))
+        };
+
+        if !query.is_empty() {
+            return self
+                .rank_apps(query)
+                .into_iter()
+                .filter(is_in_category)
+                .collect();
+        }
+
+        let guard = Guard::new();
+        let mut apps: Vec<ExecutableApp> = self
+            .visible_apps(&guard)
+            .filter(|app| is_in_category(app))
+            .cloned()
+            .collect();
+
+        self.sort_by_mode(&mut apps);
+        apps.par_sort_by_cached_key(|app| app.category.clone());
+
+        apps
+    }
+
+    /// Lists the apps that can open `kind` (see [`ExecutableApp::opens`])
+    /// matching `query`, or every one of them if `query` is empty
+    fn search_opens(&self, kind: &str, query: &AppString) -> Vec<ExecutableApp> {
+        if !query.is_empty() {
+            return self
+                .rank_apps(query)
+                .into_iter()
+                .filter(|app| app.opens(kind))
+                .collect();
+        }
 
         let guard = Guard::new();
-
-        let mut filtered_apps: Vec<ExecutableApp> = self
-            .url_index
-            .iter(&guard)
-            .filter_map(|(_, url)| {
-                if let UrlEntry::App { app } = url {
-                    Some(app)
-                } else {
-                    None
-                }
-            })
-            .filter(|app| self.is_query_substring_of_app_name(&query, &app.name))
+        let mut apps: Vec<ExecutableApp> = self
+            .visible_apps(&guard)
+            .filter(|app| app.opens(kind))
             .cloned()
             .collect();
 
-        filtered_apps.par_sort_by_cached_key(|app| app.name.clone());
+        self.sort_by_mode(&mut apps);
 
-        filtered_apps.par_sort_by_cached_key(|app| {
-            if query == app.name {
-                (0, 0)
-            } else {
-                let (dist_name, dist_substring) =
-                    beginning_distance(&query.substring(0, query.len()), &app.name);
+        apps
+    }
 
-                (
-                    dist_name.overflowing_neg().0,
-                    dist_substring.overflowing_neg().0,
-                )
-            }
-        });
+    /// Lists the keywords of the search engine, of the enabled providers and
+    /// of the commands of the configuration, so that the list can't get out
+    /// of date
+    fn search_keywords(&self) -> Vec<SearchResult> {
+        let keywords = ENGINE_KEYWORDS
+            .into_iter()
+            .chain(
+                self.providers
+                    .iter()
+                    .flat_map(|provider| provider.keywords()),
+            )
+            .map(|keyword| SearchResult::Keyword {
+                keyword: keyword.keyword.into(),
+                description: keyword.description.into(),
+            });
+        let commands = self
+            .config
+            .commands
+            .iter()
+            .map(|command| SearchResult::Keyword {
+                keyword: command.keyword.as_str().into(),
+                description: match &command.action {
+                    CommandAction::Url(url) => url.as_str().into(),
+                    CommandAction::AppleScript(_) | CommandAction::AppleScriptFile(_) => {
+                        "Run script".into()
+                    }
+                },
+            });
+        let aliases = self
+            .keyword_aliases
+            .iter()
+            .map(|(alias, keyword)| SearchResult::Keyword {
+                keyword: alias.into(),
+                description: format!("Same as {keyword}").into(),
+            });
 
-        filtered_apps.par_sort_by_key(|app| {
-            i32::from(self.learned_substring_index.get_sync(&query).is_none_or(
-                |s: OccupiedEntry<'_, AppString, ExecutableApp, _>| s.get().name != app.name,
-            ))
-        });
+        keywords.chain(commands).chain(aliases).collect()
+    }
+
+    /// Lists the apps matching `query` that can open every file selected in
+    /// Finder, if `query` starts with [`OPEN_WITH_KEYWORD`] and files are
+    /// selected. Apps declaring the most specific type come first.
+    fn search_open_with(&self, query: &AppString) -> Option<Vec<SearchResult>> {
+        let query = match query.strip_prefix_ignore_case(OPEN_WITH_KEYWORD)? {
+            "" => "",
+            rest => rest.strip_prefix(' ')?,
+        };
+        let query = normalize(query).to_lowercase();
 
-        if self.config.prioritize_open_apps {
-            filtered_apps.par_sort_by_key(|app| !app.is_open);
+        let selection = self.finder_selection.lock().expect("no lock poisoning");
+        if selection.is_empty() {
+            return None;
         }
 
-        filtered_apps
-            .into_par_iter()
-            .map(SearchResult::Executable)
-            .collect()
-    }
+        let guard = Guard::new();
+        let mut apps: Vec<(usize, ExecutableApp)> = self
+            .visible_apps(&guard)
+            .filter(|app| normalize(&app.name).to_lowercase().contains(&query))
+            .filter_map(|app| {
+                let rank = selection
+                    .iter()
+                    .map(|(path, content_types)| app.document_type_rank(path, content_types))
+                    .try_fold(0, |rank, file_rank| Some(rank.max(file_rank?)))?;
+                Some((rank, app.clone()))
+            })
+            .collect();
+        apps.par_sort_by_cached_key(|(rank, app)| (*rank, app.name.clone()));
 
-    fn deferred_search(&self, query: AppString) -> (DeferredToken, DeferredReceiver) {
-        let tx = self.deferred_watcher.clone();
-        let rx = tx.subscribe();
-        let token = self.deferred_token.fetch_add(1, Ordering::Acquire);
-        let res = self.blocking_search(query);
-        tx.send_replace((token, res));
-        (token, rx)
+        let files: Vec<PathBuf> = selection.iter().map(|(path, _)| path.clone()).collect();
+        Some(
+            apps.into_iter()
+                .map(|(_, app)| SearchResult::OpenWith {
+                    app,
+                    files: files.clone(),
+                })
+                .collect(),
+        )
     }
 
-    fn after_search(&self, opened_app: Option<SearchResult>) {
-        let query_history = self.query_history.pop_all();
-
-        if let Some(SearchResult::Executable(app)) = opened_app {
-            {
-                let guard = Guard::new();
-                query_history.iter(&guard).for_each(|query| {
-                    let _ = self
-                        .learned_substring_index
-                        .upsert_sync(query.clone(), app.clone());
-                });
+    /// Lists the apps matching `query`, best matches first
+    fn rank_apps(&self, query: &AppString) -> Vec<ExecutableApp> {
+        let guard = Guard::new();
+
+        if query.is_empty() {
+            if !self.config.open_apps.group {
+                return Vec::new();
             }
 
-            self.db
-                .lock()
-                .expect("no lock poisoning")
-                .save_data(
-                    "learned_substring_index",
-                    self.learned_substring_index.clone(),
-                )
-                .expect("json map is expected to function");
-        }
+            let mut open_apps: Vec<ExecutableApp> = self
+                .visible_apps(&guard)
+                .filter(|app| self.is_open(app))
+                .cloned()
+                .collect();
+            self.sort_by_mode(&mut open_apps);
 
-        self.deferred_token.store(0, Ordering::Release);
+            return open_apps;
+        }
 
-        self.index_apps();
-    }
+        let normalized_query = AppString::from(normalize(query));
 
-    fn preload(&self) {
-        self.url_index.update(&self.config);
-    }
-}
+        // On fresh installs there is nothing learned nor used to look up
+        let learned_apps = if self.learned_substring_index.is_empty() {
+            Vec::new()
+        } else {
+            self.learned_substring_index
+                .read_sync(&normalized_query, |_, learned_apps| {
+                    learned_apps.ranked_names()
+                })
+                .unwrap_or_default()
+        };
+        let mode = *self.sort_mode.lock().expect("no lock poisoning");
+        let reads_usage = mode != SortMode::Alphabetical && !self.app_usage.is_empty();
+        let open_apps_first = (self.config.prioritize_open_apps
+            && self.config.open_apps.boost.is_none())
+            || (self.config.open_apps.group && normalized_query.grapheme_len() <= 1);
 
-impl DeterministicSearchEngine {
-    pub fn build(config: Arc<Configuration>) -> Result<Self, Report> {
-        let db = FilesystemPersistence::open()?;
-        let app_index = UrlIndex::build(&config);
-        let substring_index = Arc::new(scc::HashMap::new());
+        let mut scored_apps: Vec<(AppScore, ExecutableApp)> = self
+            .matching_apps(&normalized_query, &guard)
+            .into_iter()
+            .map(|app| {
+                let usage = if reads_usage {
+                    self.app_usage
+                        .read_sync(&app.path, |_, usage| *usage)
+                        .unwrap_or_default()
+                } else {
+                    AppUsage::default()
+                };
+                let learned_position = if learned_apps.is_empty() {
+                    usize::MAX
+                } else {
+                    learned_apps
+                        .iter()
+                        .position(|name| *name == app.name)
+                        .unwrap_or(usize::MAX)
+                };
+                let score = AppScore {
+                    is_closed: open_apps_first && !self.is_open(app),
+                    learned_position,
+                    name_rank: name_rank(&normalized_query, &app.name),
+                    usage: Reverse(usage_key(usage, mode)),
+                    name: app.name.clone(),
+                    path: app.path.clone(),
+                };
+                (score, app.clone())
+            })
+            .collect();
+        sort_scored(&mut scored_apps);
+        let mut filtered_apps: Vec<ExecutableApp> =
+            scored_apps.into_iter().map(|(_, app)| app).collect();
 
-        let learned_substring_index =
-            Arc::new(db.get_data("learned_substring_index").unwrap_or_default());
+        // Moves apps by a number of places, which a score can't express
+        if self.config.prioritize_open_apps
+            && let Some(boost) = self.config.open_apps.boost
+        {
+            // Ties go to the running app, which was boosted past the other
+            filtered_apps = boost_open_apps(filtered_apps, boost, |app| self.is_open(app));
+        }
 
-        let (tx, _rx) = channel((0, vec![]));
-        let engine = Self {
-            db: Arc::new(Mutex::new(db)),
-            config,
-            url_index: app_index,
-            learned_substring_index,
-            substring_index,
-            deferred_token: Arc::new(AtomicUsize::new(0)),
-            deferred_watcher: tx,
-            query_history: scc::Stack::new(),
-        };
+        filtered_apps.extend(self.path_alias_matches(&normalized_query, &guard));
 
-        engine.index_apps();
+        self.with_pinned_app(query, filtered_apps)
+    }
 
-        Ok(engine)
+    /// Sorts apps in the order of the current [`SortMode`]
+    fn sort_by_mode(&self, apps: &mut [ExecutableApp]) {
+        let mode = *self.sort_mode.lock().expect("no lock poisoning");
+        sort_by_mode(apps, mode, |app| {
+            let usage = self
+                .app_usage
+                .read_sync(&app.path, |_, usage| *usage)
+                .unwrap_or_default();
+            (usage, app.name.clone())
+        });
     }
 
     #[inline]
     fn index_apps(&self) {
         let guard = Guard::new();
-        self.url_index.iter(&guard).for_each(|(_, url_entry)| {
+        self.url_index.iter(&guard).for_each(|(url, url_entry)| {
             if let UrlEntry::App { app } = url_entry {
-                for n in 0..=app.name.grapheme_len() {
-                    let substrings = substrings(&app.name, n);
-                    for substr in substrings {
-                        self.substring_index
-                            .entry_sync(substr.into())
-                            .or_default()
-                            .push(app.name.clone());
+                for substr in indexed_substrings(&app.name) {
+                    self.substring_index
+                        .entry_sync(substr.into())
+                        .or_default()
+                        .push(app.name.clone());
+                }
+
+                for pair in substrings(&normalize(&app.name), 2) {
+                    let mut urls = self
+                        .two_letter_candidates
+                        .entry_sync(pair.into())
+                        .or_default();
+                    if !urls.contains(url) {
+                        urls.push(url.clone());
+                    }
+                }
+
+                for alias in path_aliases(&app.path) {
+                    for prefix in word_prefixes(&normalize(&alias), MIN_PATH_ALIAS_QUERY_LEN) {
+                        let mut names =
+                            self.path_alias_index.entry_sync(prefix.into()).or_default();
+                        if !names.contains(&app.name) {
+                            names.push(app.name.clone());
+                        }
                     }
                 }
             }
         });
     }
 
+    /// Apps stored in a folder whose name starts like `normalized_query`
+    /// (as in `utilities` or `jetbrains`), which the name of the app
+    /// doesn't match
+    fn path_alias_matches(
+        &self,
+        normalized_query: &AppString,
+        guard: &Guard,
+    ) -> Vec<ExecutableApp> {
+        if normalized_query.grapheme_len() < MIN_PATH_ALIAS_QUERY_LEN {
+            return Vec::new();
+        }
+        let Some(names) = self
+            .path_alias_index
+            .read_sync(normalized_query, |_, names| names.clone())
+        else {
+            return Vec::new();
+        };
+
+        let mut apps: Vec<ExecutableApp> = self
+            .visible_apps(guard)
+            .filter(|app| names.contains(&app.name))
+            .filter(|app| !self.is_query_substring_of_app_name(normalized_query, &app.name))
+            .cloned()
+            .collect();
+        self.sort_by_mode(&mut apps);
+
+        apps
+    }
+
     #[inline]
     fn is_query_substring_of_app_name(&self, query: &AppString, app_name: &AppName) -> bool {
         let Some(res) = self.substring_index.get_sync(query) else {
@@ -215,6 +1533,88 @@ impl DeterministicSearchEngine {
     }
 }
 
+/// Moves each app for which `is_open` is `true` up to `boost` places, keeping
+/// the order of the others
+fn boost_open_apps<T>(items: Vec<T>, boost: usize, is_open: impl Fn(&T) -> bool) -> Vec<T> {
+    let mut ranked: Vec<(usize, bool, T)> = items
+        .into_iter()
+        .enumerate()
+        .map(|(position, item)| {
+            let is_open = is_open(&item);
+            let position = if is_open {
+                position.saturating_sub(boost)
+            } else {
+                position
+            };
+            (position, !is_open, item)
+        })
+        .collect();
+    ranked.sort_by_key(|(position, is_closed, _)| (*position, *is_closed));
+
+    ranked.into_iter().map(|(_, _, item)| item).collect()
+}
+
+/// Moves `pinned` to the start of `items`, adding it if no item `is_same`
+fn pin_first<T>(mut items: Vec<T>, pinned: T, is_same: impl Fn(&T, &T) -> bool) -> Vec<T> {
+    items.retain(|item| !is_same(item, &pinned));
+    items.insert(0, pinned);
+
+    items
+}
+
+/// Where an app ranks for a query, lowest first. Fields are compared in
+/// order, from the strongest signal to the weakest, so that the first one
+/// telling two apps apart decides. Every app gets a different score, which
+/// makes the order of results the same from one search to the next.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct AppScore {
+    /// Only set when open apps are grouped or prioritized
+    is_closed: bool,
+    /// Among the apps learned for the query
+    learned_position: usize,
+    /// See [`name_rank`]
+    name_rank: (usize, usize),
+    /// See [`usage_key`]
+    usage: Reverse<(u64, u64)>,
+    name: AppName,
+    /// Tells apart apps of the same name
+    path: PathBuf,
+}
+
+/// Orders apps from the best score to the worst. As no two apps score the
+/// same, an unstable sort gives the same order as a stable one.
+fn sort_scored<T: Send>(scored: &mut [(AppScore, T)]) {
+    // Handing a sort to the thread pool costs more than sorting the few
+    // apps most queries match on the calling thread
+    if scored.len() < MIN_PARALLEL_SORT_LEN {
+        scored.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+    } else {
+        scored.par_sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+    }
+}
+
+/// What apps are sorted by in `mode`, highest first
+fn usage_key(usage: AppUsage, mode: SortMode) -> (u64, u64) {
+    match mode {
+        SortMode::Alphabetical => (0, 0),
+        SortMode::Recent => (usage.last_opened, 0),
+        SortMode::Frequent => (u64::from(usage.launch_count), usage.last_opened),
+    }
+}
+
+/// Sorts items by their usage in the order of `mode`, then by name
+fn sort_by_mode<T: Send, N: Ord + Send>(
+    items: &mut [T],
+    mode: SortMode,
+    usage_and_name: impl Fn(&T) -> (AppUsage, N) + Sync,
+) {
+    items.par_sort_by_cached_key(|item| {
+        let (usage, name) = usage_and_name(item);
+
+        (Reverse(usage_key(usage, mode)), name)
+    });
+}
+
 #[inline]
 #[must_use]
 pub fn substrings(string: &str, n: usize) -> Vec<String> {
@@ -224,7 +1624,7 @@ pub fn substrings(string: &str, n: usize) -> Vec<String> {
     }
 
     let mut vec = vec![];
-    for i in 0..=(string.len() - n) {
+    for i in 0..=(graphemes.len() - n) {
         // TODO: Slow, can probably use pointers + graphemes here to get valid UTF-8 memory range
         let substr_vec = graphemes.get(i..i + n).expect("within range").to_vec();
 
@@ -237,16 +1637,107 @@ pub fn substrings(string: &str, n: usize) -> Vec<String> {
     vec
 }
 
+/// Names of the apps whose normalized name contains `normalized_query`, as
+/// listed by `substring_index`
+fn matching_names(
+    substring_index: &HashMap<AppString, Vec<AppName>>,
+    normalized_query: &AppString,
+) -> std::collections::HashSet<AppName> {
+    substring_index
+        .read_sync(normalized_query, |_, names| names.iter().cloned().collect())
+        .unwrap_or_default()
+}
+
+/// Keys of the substring index an app is listed under: every substring of
+/// its normalized name, as queries are normalized too (see `rank_apps`)
+fn indexed_substrings(name: &str) -> Vec<String> {
+    let normalized_name = normalize(name);
+    let len = normalized_name.graphemes(true).count();
+
+    (0..=len)
+        .flat_map(|n| substrings(&normalized_name, n))
+        .collect()
+}
+
+/// Where an app matching `normalized_query` ranks by its name alone, lowest
+/// first. Usage, learned picks and open apps reorder them afterwards.
+fn name_rank(normalized_query: &AppString, name: &AppName) -> (usize, usize) {
+    let normalized_name = AppString::from(normalize(name));
+    if *normalized_query == normalized_name {
+        return (0, 0);
+    }
+
+    let (dist_name, dist_substring) = beginning_distance(
+        &normalized_query.substring(0, normalized_query.len()),
+        &normalized_name,
+    );
+
+    (
+        dist_name.overflowing_neg().0,
+        dist_substring.overflowing_neg().0,
+    )
+}
+
+/// Folders an app is stored in below the last `Applications` folder of its
+/// path, as in `Utilities` for `/System/Applications/Utilities/Terminal.app`.
+/// Apps inside other apps are helpers, which don't get any.
+fn path_aliases(path: &Path) -> Vec<String> {
+    let folders: Vec<&str> = path
+        .parent()
+        .into_iter()
+        .flat_map(Path::components)
+        .filter_map(|component| component.as_os_str().to_str())
+        .collect();
+    let Some(applications_idx) = folders.iter().rposition(|folder| *folder == "Applications")
+    else {
+        return Vec::new();
+    };
+
+    let aliases = &folders[applications_idx + 1..];
+    if aliases.iter().any(|folder| {
+        Path::new(folder)
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("app"))
+    }) {
+        return Vec::new();
+    }
+
+    aliases.iter().map(|folder| (*folder).to_owned()).collect()
+}
+
+/// Prefixes of at least `min_len` graphemes of each word of `text`
+fn word_prefixes(text: &str, min_len: usize) -> Vec<String> {
+    text.split_whitespace()
+        .flat_map(|word| {
+            let graphemes: Vec<&str> = word.graphemes(true).collect();
+            (min_len..=graphemes.len()).map(move |len| graphemes[..len].concat())
+        })
+        .collect()
+}
+
 /// Substring distance from a space and/or beginning of app name
 /// Users are expected to search starting from the beginning of app name
 /// (For instance: "Ad" or "Ph" for "Adobe Photoshop")
+///
+/// Words are told apart by Unicode word boundaries rather than spaces, as
+/// Chinese, Japanese and Thai names have none: each ideograph is a word,
+/// and so is each run of katakana. Thai words can't be told apart without
+/// a dictionary, so each Thai character is one. A match may go on into the
+/// following words.
 #[inline]
 fn beginning_distance(substr: &AppSubstr, name: &AppString) -> (usize, usize) {
-    for (i, word) in name.split_ascii_whitespace().enumerate() {
-        let word_appstr = AppString::from(word);
-        for j in 0..word_appstr.len().saturating_sub(substr.len()) {
-            let name_substr = word_appstr.substring(j, substr.len());
-            if substr == &name_substr {
+    for (i, (word_start, word)) in name.unicode_word_indices().enumerate() {
+        for (j, _) in word.char_indices() {
+            let start = word_start + j;
+            let end = start + substr.len();
+            // Byte offsets, which fall inside characters of non-ASCII names
+            if end > name.len() {
+                break;
+            }
+            if !name.is_char_boundary(end) {
+                continue;
+            }
+            if *substr == name.substring(start, substr.len()) {
                 return (i, j);
             }
         }
@@ -257,7 +1748,207 @@ fn beginning_distance(substr: &AppSubstr, name: &AppString) -> (usize, usize) {
 
 #[cfg(test)]
 mod tests {
+    use std::time::Duration;
+
+    use rootcause::report;
+
     use super::*;
+    use crate::{app::AppLocation, platform::SystemApps};
+
+    /// The substring index of the apps of the corpus
+    fn fixtures_index() -> std::collections::HashMap<AppString, Vec<AppName>> {
+        let mut index = std::collections::HashMap::<AppString, Vec<AppName>>::new();
+        for name in fixtures::APP_NAMES {
+            for substr in indexed_substrings(name) {
+                index
+                    .entry(substr.into())
+                    .or_default()
+                    .push(AppName::from(*name));
+            }
+        }
+
+        index
+    }
+
+    /// Apps of the given names, installed in `/Applications`
+    #[derive(Debug)]
+    struct FakeApps(Vec<String>);
+
+    impl FakeApps {
+        fn path(name: &str) -> PathBuf {
+            PathBuf::from(format!("/Applications/{name}.app"))
+        }
+    }
+
+    impl SystemApps for FakeApps {
+        fn list_app_paths(&self, _config: &Configuration, _quick: bool) -> HashSet<PathBuf> {
+            let paths = HashSet::new();
+            for name in &self.0 {
+                let _ = paths.insert_sync(Self::path(name));
+            }
+
+            paths
+        }
+
+        fn read_entry(&self, url: &Url) -> Option<UrlEntry> {
+            let Url::File(path) = url else {
+                return None;
+            };
+            let name = self.0.iter().find(|name| Self::path(name) == *path)?;
+            let app = ExecutableApp {
+                name: name.as_str().into(),
+                path: path.clone(),
+                is_open: false,
+                icon_png_data: None,
+                is_web_app: false,
+                category: None,
+                document_types: Vec::new(),
+                document_extensions: Vec::new(),
+                location: AppLocation::Local,
+                version: None,
+                updates_url: None,
+                architecture: None,
+                usage: None,
+            };
+
+            Some(UrlEntry::App { app })
+        }
+
+        fn decode_icon(&self, _app: &Path) -> Result<Vec<u8>, Report> {
+            Err(report!("No icons"))
+        }
+    }
+
+    /// An engine with the apps of `names` indexed, which keeps what it
+    /// learns in a data directory of its own, unique to the test
+    fn test_engine(test: &str, names: &[impl AsRef<str>]) -> DeterministicSearchEngine {
+        let data_dir =
+            std::env::temp_dir().join(format!("fetch-search-test-{test}-{}", std::process::id()));
+        std::fs::create_dir_all(&data_dir).unwrap();
+        let mut config = Configuration::with_app_paths(Vec::new(), Vec::new());
+        config.locale = Some("en_US".to_owned());
+        let names = names.iter().map(|name| name.as_ref().to_owned()).collect();
+
+        let engine = DeterministicSearchEngine::new(
+            Arc::new(config),
+            FilesystemPersistence::in_dir(data_dir),
+            UrlIndex::new(FakeApps(names)),
+        );
+        engine.build_index();
+
+        engine
+    }
+
+    /// Names of the apps `engine` ranks for `query`, best first
+    fn rank_names(engine: &DeterministicSearchEngine, query: &str) -> Vec<AppName> {
+        engine
+            .rank_apps(&query.into())
+            .into_iter()
+            .map(|app| app.name)
+            .collect()
+    }
+
+    #[test]
+    fn test_ranking_corpus() {
+        let engine = test_engine("ranking-corpus", fixtures::APP_NAMES);
+
+        for (query, expected) in fixtures::RANKING_CASES {
+            let ranked = rank_names(&engine, query);
+            let top: Vec<&str> = ranked
+                .iter()
+                .take(expected.len())
+                .map(|name| &**name)
+                .collect();
+            assert_eq!(top, *expected, "ranking of {query:?}");
+        }
+    }
+
+    #[test]
+    fn test_ranking_corpus_exact_names() {
+        let engine = test_engine("ranking-corpus-exact-names", fixtures::APP_NAMES);
+
+        for name in fixtures::APP_NAMES {
+            let ranked = rank_names(&engine, name);
+            assert_eq!(ranked.first().map(|name| &**name), Some(*name));
+        }
+    }
+
+    #[test]
+    fn test_boost_open_apps() {
+        let apps = vec!["a", "b", "c", "D", "e", "F"];
+        let is_open = |app: &&str| app.chars().all(char::is_uppercase);
+
+        assert_eq!(
+            boost_open_apps(apps.clone(), 2, is_open),
+            vec!["a", "D", "b", "c", "F", "e"]
+        );
+        assert_eq!(boost_open_apps(apps.clone(), 0, is_open), apps.clone());
+        assert_eq!(
+            boost_open_apps(apps, usize::MAX, is_open),
+            vec!["D", "F", "a", "b", "c", "e"]
+        );
+    }
+
+    #[test]
+    fn test_pin_first() {
+        let same = |a: &&str, b: &&str| a == b;
+
+        assert_eq!(
+            pin_first(vec!["a", "b", "c"], "c", same),
+            vec!["c", "a", "b"]
+        );
+        // Pinned apps are listed even if their name doesn't match
+        assert_eq!(pin_first(vec!["a", "b"], "m", same), vec!["m", "a", "b"]);
+        assert_eq!(pin_first(Vec::new(), "m", same), vec!["m"]);
+    }
+
+    #[test]
+    fn test_sort_by_mode() {
+        let usage = |launch_count, last_opened| AppUsage {
+            launch_count,
+            last_opened,
+        };
+        let mut apps = vec![
+            ("Safari", usage(3, 100)),
+            ("Calendar", usage(0, 0)),
+            ("Notes", usage(8, 50)),
+            ("Mail", usage(3, 200)),
+        ];
+        let names = |apps: &[(&'static str, AppUsage)]| -> Vec<&'static str> {
+            apps.iter().map(|(name, _)| *name).collect()
+        };
+
+        sort_by_mode(&mut apps, SortMode::Alphabetical, |(name, usage)| {
+            (*usage, *name)
+        });
+        assert_eq!(names(&apps), ["Calendar", "Mail", "Notes", "Safari"]);
+        sort_by_mode(&mut apps, SortMode::Recent, |(name, usage)| (*usage, *name));
+        assert_eq!(names(&apps), ["Mail", "Safari", "Notes", "Calendar"]);
+        sort_by_mode(&mut apps, SortMode::Frequent, |(name, usage)| {
+            (*usage, *name)
+        });
+        assert_eq!(names(&apps), ["Notes", "Mail", "Safari", "Calendar"]);
+    }
+
+    #[test]
+    fn test_app_score() {
+        let score = |is_closed, learned_position, name: &str, path: &str| AppScore {
+            is_closed,
+            learned_position,
+            name_rank: (0, 0),
+            usage: Reverse((0, 0)),
+            name: AppName::from(name),
+            path: PathBuf::from(path),
+        };
+        let safari = score(false, usize::MAX, "Safari", "/Applications/Safari.app");
+
+        // Running apps come first, then learned ones, then by name
+        assert!(score(true, 0, "Safari", "/Applications/Safari.app") > safari);
+        assert!(score(false, 0, "Visual Studio", "/Applications/VS.app") < safari);
+        assert!(score(false, usize::MAX, "Visual Studio", "/Applications/VS.app") > safari);
+        // Apps of the same name still rank in a set order
+        assert!(safari < score(false, usize::MAX, "Safari", "/Volumes/Safari.app"));
+    }
 
     #[test]
     fn test_substrings() {
@@ -273,6 +1964,45 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_path_aliases() {
+        assert_eq!(
+            path_aliases(Path::new("/System/Applications/Utilities/Terminal.app")),
+            vec!["Utilities"]
+        );
+        assert_eq!(
+            path_aliases(Path::new(
+                "/Users/me/Applications/JetBrains Toolbox/IntelliJ IDEA.app"
+            )),
+            vec!["JetBrains Toolbox"]
+        );
+        assert_eq!(
+            path_aliases(Path::new("/Applications/Safari.app")),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            path_aliases(Path::new(
+                "/Applications/Xcode.app/Contents/Developer/Applications/Simulator.app"
+            )),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            path_aliases(Path::new(
+                "/Applications/Tools.app/Contents/MacOS/Helper.app"
+            )),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn test_word_prefixes() {
+        assert_eq!(
+            word_prefixes("JetBrains Toolbox", 7),
+            vec!["JetBrai", "JetBrain", "JetBrains", "Toolbox"]
+        );
+        assert_eq!(word_prefixes("Go", 3), Vec::<String>::new());
+    }
+
     #[test]
     fn test_substring_beginning_distance() {
         let test_app_name: AppString = "Adobe Photoshop".into();
@@ -283,5 +2013,233 @@ mod tests {
         assert_eq!(beginning_distance(&"dob".into(), &test_app_name), (0, 1));
         assert_eq!(beginning_distance(&"hot".into(), &test_app_name), (1, 1));
         assert_eq!(beginning_distance(&"oto".into(), &test_app_name), (1, 2));
+        assert_eq!(beginning_distance(&"obe".into(), &test_app_name), (0, 2));
+    }
+
+    #[test]
+    fn test_beginning_distance_without_spaces() {
+        // Each ideograph is a word
+        assert_eq!(beginning_distance(&"音乐".into(), &"QQ音乐".into()), (1, 0));
+        assert_eq!(
+            beginning_distance(&"乐".into(), &"网易云音乐".into()),
+            (4, 0)
+        );
+        assert_eq!(beginning_distance(&"微信".into(), &"微信".into()), (0, 0));
+        assert_eq!(beginning_distance(&"信".into(), &"微信".into()), (1, 0));
+
+        // Runs of katakana are words, unlike hiragana and kanji
+        let name: AppString = "ファイナルカット プロ".into();
+        assert_eq!(
+            beginning_distance(&"カット".into(), &name),
+            (0, "ファイナル".len())
+        );
+        assert_eq!(beginning_distance(&"プロ".into(), &name), (1, 0));
+        assert_eq!(
+            beginning_distance(&"かな".into(), &"ことえりかな".into()),
+            (4, 0)
+        );
+
+        assert_eq!(beginning_distance(&"แมน".into(), &"ไลน์ แมน".into()), (3, 0));
+
+        // Matches across words, as spaces are kept in queries
+        let name: AppString = "Adobe Photoshop".into();
+        assert_eq!(beginning_distance(&"adobe ph".into(), &name), (0, 0));
+        assert_eq!(beginning_distance(&"be ph".into(), &name), (0, 3));
+    }
+
+    #[test]
+    fn test_learned_apps_alternating() {
+        let firefox: AppName = "Firefox".into();
+        let finder: AppName = "Finder".into();
+        let mut learned_apps = LearnedApps::default();
+
+        learned_apps.learn(&firefox);
+        learned_apps.learn(&firefox);
+        learned_apps.learn(&finder);
+        assert_eq!(
+            learned_apps.ranked_names(),
+            vec![firefox.clone(), finder.clone()]
+        );
+
+        learned_apps.learn(&finder);
+        assert_eq!(
+            learned_apps.ranked_names(),
+            vec![finder.clone(), firefox.clone()]
+        );
+    }
+
+    #[test]
+    fn test_learned_apps_forgotten() {
+        let firefox: AppName = "Firefox".into();
+        let finder: AppName = "Finder".into();
+        let mut learned_apps = LearnedApps::default();
+
+        learned_apps.learn(&firefox);
+        for _ in 0..10 {
+            learned_apps.learn(&finder);
+        }
+        assert_eq!(learned_apps.ranked_names(), vec![finder]);
+    }
+
+    #[test]
+    fn test_private_mode() {
+        let engine = test_engine("private-mode", &["Safari", "Xcode"]);
+        let history = |engine: &DeterministicSearchEngine| {
+            let guard = Guard::new();
+            let mut queries: Vec<String> = engine
+                .query_history
+                .iter(&guard)
+                .map(ToString::to_string)
+                .collect();
+            queries.reverse();
+            queries
+        };
+
+        engine.blocking_search("sa".into());
+        engine.set_private_mode(true);
+        engine.blocking_search("xc".into());
+        engine.set_private_mode(false);
+        engine.blocking_search("saf".into());
+        assert_eq!(history(&engine), ["sa", "saf"]);
+
+        // Ending the search forgets private queries too
+        engine.set_private_mode(true);
+        engine.blocking_search("xco".into());
+        assert_eq!(history(&engine), ["sa", "saf", "xco"]);
+        engine.after_search(None);
+        assert!(history(&engine).is_empty());
+        engine.blocking_search("xc".into());
+        assert_eq!(history(&engine), ["xc"]);
+    }
+
+    /// Run with `cargo test --release -- --ignored --nocapture bench_`
+    #[test]
+    #[ignore = "benchmark"]
+    fn bench_two_letter_queries() {
+        const ROUNDS: u32 = 100;
+        const WORDS: [&str; 24] = [
+            "Adobe", "Photo", "Code", "Studio", "Music", "Mail", "Notes", "Safari", "Pro",
+            "Editor", "Player", "Manager", "Viewer", "Calendar", "Desktop", "Cloud", "Drive",
+            "Chat", "Terminal", "Preview", "Utility", "Office", "Draw", "Sync",
+        ];
+
+        // As many apps as a well stocked Mac, most sharing two letters
+        let names: Vec<String> = WORDS
+            .iter()
+            .flat_map(|first| WORDS.iter().map(move |second| format!("{first} {second}")))
+            .collect();
+        let engine = test_engine("bench-two-letter", &names);
+        let mut queries = Vec::new();
+        engine.two_letter_candidates.iter_sync(|query, _| {
+            queries.push(query.clone());
+            true
+        });
+
+        // Ranking still running in the background is thrown away
+        engine.ranking_generation.fetch_add(1, Ordering::AcqRel);
+        let mut slowest_ranked = Duration::ZERO;
+        let mut ranked = Vec::new();
+        for query in &queries {
+            let mut results = Vec::new();
+            let start = Instant::now();
+            for _ in 0..ROUNDS {
+                // As before the shortest queries are ranked ahead of time
+                engine.precomputed_results.clear_sync();
+                engine.results_cache.lock().unwrap().clear();
+                results = engine.blocking_search(query.clone());
+            }
+            slowest_ranked = slowest_ranked.max(start.elapsed() / ROUNDS);
+            ranked.push(results);
+        }
+
+        engine.precompute_results();
+        let mut slowest_precomputed = Duration::ZERO;
+        for (query, ranked) in queries.iter().zip(&ranked) {
+            let mut results = Vec::new();
+            let start = Instant::now();
+            for _ in 0..ROUNDS {
+                results = engine.blocking_search(query.clone());
+            }
+            slowest_precomputed = slowest_precomputed.max(start.elapsed() / ROUNDS);
+            assert_eq!(&results, ranked, "{query}");
+        }
+
+        println!(
+            "{} apps, {} two-letter queries: slowest search ranking apps {slowest_ranked:.2?}, \
+             with apps ranked ahead of time {slowest_precomputed:.2?}",
+            names.len(),
+            queries.len()
+        );
+        assert!(slowest_ranked < Duration::from_millis(1));
+        assert!(slowest_precomputed < Duration::from_millis(1));
+    }
+
+    /// Run with `cargo test --release -- --ignored --nocapture bench_`
+    #[test]
+    #[ignore = "benchmark"]
+    fn bench_fresh_install_ranking() {
+        const ROUNDS: u32 = 1_000;
+
+        let index = fixtures_index();
+        // Nothing learned nor opened yet
+        let learned_substring_index: HashMap<AppString, LearnedApps> = HashMap::new();
+        let app_usage: HashMap<PathBuf, AppUsage> = HashMap::new();
+        let score = |query: &AppString, name: &AppName, learned_position, usage| AppScore {
+            is_closed: false,
+            learned_position,
+            name_rank: name_rank(query, name),
+            usage: Reverse(usage_key(usage, SortMode::Frequent)),
+            name: name.clone(),
+            path: PathBuf::from(&**name),
+        };
+
+        for query in ["a", "ad", "code", "photo"] {
+            let query = AppString::from(normalize(query));
+            let names = index.get(&query).cloned().unwrap_or_default();
+
+            let start = Instant::now();
+            let mut looked_up = Vec::new();
+            for _ in 0..ROUNDS {
+                let learned_apps = learned_substring_index
+                    .read_sync(&query, |_, learned_apps| learned_apps.ranked_names())
+                    .unwrap_or_default();
+                looked_up = names
+                    .iter()
+                    .map(|name| {
+                        let usage = app_usage
+                            .read_sync(Path::new(&**name), |_, usage| *usage)
+                            .unwrap_or_default();
+                        let learned_position = learned_apps
+                            .iter()
+                            .position(|learned| learned == name)
+                            .unwrap_or(usize::MAX);
+                        (score(&query, name, learned_position, usage), name.clone())
+                    })
+                    .collect();
+                looked_up.par_sort_by(|(a, _), (b, _)| a.cmp(b));
+            }
+            let before = start.elapsed() / ROUNDS;
+
+            let start = Instant::now();
+            let mut fast_path = Vec::new();
+            for _ in 0..ROUNDS {
+                fast_path = names
+                    .iter()
+                    .map(|name| {
+                        let fresh_score = score(&query, name, usize::MAX, AppUsage::default());
+                        (fresh_score, name.clone())
+                    })
+                    .collect();
+                sort_scored(&mut fast_path);
+            }
+            let after = start.elapsed() / ROUNDS;
+
+            assert_eq!(fast_path, looked_up);
+            println!(
+                "{query:>8}: {:>4} matches, looking up learned apps and usage {before:>10.2?}, \
+                 skipping them {after:>10.2?}",
+                names.len()
+            );
+        }
     }
 }
@@ -0,0 +1,396 @@
+//! Real-world app names, and the apps canonical queries are expected to
+//! rank first among them. Changes to ranking are checked against these, so
+//! that improving one query doesn't quietly make another one worse.
+
+/// Names as they are read from bundles, which come with version suffixes
+/// (`Adobe Photoshop 2024`), channels (`Xcode-beta`), dots left from the
+/// bundle name (`zoom.us`), accents, full-width and non-Latin scripts
+pub const APP_NAMES: &[&str] = &[
+    "1Password",
+    "1Password 7",
+    "Activity Monitor",
+    "Adobe Acrobat Reader",
+    "Adobe After Effects 2024",
+    "Adobe Bridge 2024",
+    "Adobe Illustrator 2024",
+    "Adobe InDesign 2024",
+    "Adobe Lightroom Classic",
+    "Adobe Photoshop 2024",
+    "Adobe Premiere Pro 2024",
+    "AirDrop",
+    "Alfred 5",
+    "Android Studio",
+    "App Store",
+    "AppCleaner",
+    "Arc",
+    "Audio MIDI Setup",
+    "Automator",
+    "Bartender 4",
+    "BBEdit",
+    "Bitwarden",
+    "Blender",
+    "Bluetooth File Exchange",
+    "Books",
+    "Boot Camp Assistant",
+    "Brave Browser",
+    "Calculator",
+    "Calendar",
+    "Camo",
+    "Chess",
+    "Citrix Workspace",
+    "CleanMyMac X",
+    "Clock",
+    "ColorSync Utility",
+    "Console",
+    "Contacts",
+    "CotEditor",
+    "Cyberduck",
+    "DaVinci Resolve",
+    "DBeaver",
+    "Dictionary",
+    "Discord",
+    "Disk Utility",
+    "Docker",
+    "Dropbox",
+    "Element",
+    "Evernote",
+    "FaceTime",
+    "Fantastical",
+    "Figma",
+    "Final Cut Pro",
+    "Find My",
+    "Finder",
+    "Firefox",
+    "Firefox Developer Edition",
+    "Firefox Nightly",
+    "Font Book",
+    "Fork",
+    "Freeform",
+    "GarageBand",
+    "GitHub Desktop",
+    "GoLand",
+    "Google Chrome",
+    "Google Chrome Canary",
+    "Google Drive",
+    "Grapher",
+    "HandBrake",
+    "Home",
+    "iMovie",
+    "Image Capture",
+    "IINA",
+    "Insomnia",
+    "IntelliJ IDEA",
+    "IntelliJ IDEA CE",
+    "iPhone Mirroring",
+    "iStat Menus",
+    "iTerm",
+    "Karabiner-Elements",
+    "Keychain Access",
+    "Keynote",
+    "Keka",
+    "Kindle",
+    "Launchpad",
+    "Linear",
+    "Logic Pro",
+    "Mail",
+    "Maps",
+    "Messages",
+    "Microsoft Edge",
+    "Microsoft Excel",
+    "Microsoft OneNote",
+    "Microsoft Outlook",
+    "Microsoft PowerPoint",
+    "Microsoft Teams",
+    "Microsoft Word",
+    "Migration Assistant",
+    "Mission Control",
+    "Music",
+    "Notes",
+    "Notion",
+    "Numbers",
+    "NetNewsWire",
+    "Obsidian",
+    "OBS",
+    "OmniFocus 3",
+    "Pages",
+    "Parallels Desktop",
+    "Passwords",
+    "Photo Booth",
+    "Photos",
+    "Pixelmator Pro",
+    "Podcasts",
+    "Postman",
+    "Preview",
+    "Python Launcher",
+    "PyCharm",
+    "PyCharm CE",
+    "QuickTime Player",
+    "Raycast",
+    "Rectangle",
+    "Reminders",
+    "Remote Desktop Connection",
+    "Rider",
+    "RubyMine",
+    "Safari",
+    "Safari Technology Preview",
+    "Screen Sharing",
+    "Screenshot",
+    "Script Editor",
+    "Shortcuts",
+    "Signal",
+    "Simulator",
+    "Sketch",
+    "Slack",
+    "Spotify",
+    "Steam",
+    "Stickies",
+    "Stocks",
+    "Sublime Merge",
+    "Sublime Text",
+    "System Information",
+    "System Settings",
+    "Tailscale",
+    "TablePlus",
+    "Telegram",
+    "Terminal",
+    "TextEdit",
+    "The Unarchiver",
+    "Things3",
+    "Time Machine",
+    "Todoist",
+    "Tor Browser",
+    "Tower",
+    "Transmission",
+    "Transmit 5",
+    "TV",
+    "UTM",
+    "VirtualBox",
+    "Visual Studio Code",
+    "Visual Studio Code - Insiders",
+    "VLC",
+    "VoiceOver Utility",
+    "Voice Memos",
+    "Warp",
+    "Weather",
+    "WebStorm",
+    "WhatsApp",
+    "Wireshark",
+    "Xcode",
+    "Xcode-beta",
+    "Zed",
+    "Zed Preview",
+    "zoom.us",
+    "draw.io",
+    "Affinity Designer 2",
+    "Affinity Photo 2",
+    "Affinity Publisher 2",
+    "Capture One 23",
+    "Pixelmator Pro 3",
+    "Ableton Live 12 Suite",
+    "Native Access",
+    "Steinberg Download Assistant",
+    "CCleaner",
+    "Cinema 4D 2024",
+    "Unity Hub",
+    "Unreal Engine",
+    "Epic Games Launcher",
+    "Battle.net",
+    "Minecraft",
+    "League of Legends",
+    "Microsoft Remote Desktop",
+    "Microsoft To Do",
+    "Jump Desktop",
+    "Royal TSX",
+    "Charles",
+    "Proxyman",
+    "Paw",
+    "RapidAPI",
+    "Kaleidoscope 3",
+    "Beyond Compare",
+    "Sourcetree",
+    "GitKraken",
+    "Nova",
+    "Emacs",
+    "MacVim",
+    "Neovide",
+    "Alacritty",
+    "kitty",
+    "WezTerm",
+    "Hyper",
+    "Ghostty",
+    "Dash",
+    "DevUtils",
+    "Hex Fiend",
+    "Cursor",
+    "Windsurf",
+    "ChatGPT",
+    "Claude",
+    "Perplexity",
+    "Ollama",
+    "LM Studio",
+    "Café",
+    "Crème Brûlée",
+    "Ångström",
+    "Pokémon TCG Live",
+    "Übersicht",
+    "Résumé Builder",
+    "Señor Taco",
+    "Mañana",
+    "Ｍｅｎｕ",
+    "Québec Météo",
+    "微信",
+    "企业微信",
+    "QQ",
+    "QQ音乐",
+    "网易云音乐",
+    "钉钉",
+    "飞书",
+    "百度网盘",
+    "腾讯会议",
+    "WPS Office",
+    "有道翻译",
+    "LINE",
+    "カレンダー",
+    "メモ",
+    "写真",
+    "辞書",
+    "ことえり",
+    "KakaoTalk",
+    "카카오톡",
+    "네이버 웨일",
+    "Яндекс Браузер",
+    "Telegram Desktop",
+    "Steuererklärung 2024",
+    "ELSTER",
+    "WISO Steuer 2024",
+    "MoneyMoney",
+    "Banking 4",
+    "Outbank",
+    "Teams (work or school)",
+    "Microsoft Teams classic",
+    "Adobe Creative Cloud",
+    "Creative Cloud Uninstaller",
+    "Google Docs",
+    "Google Sheets",
+    "Google Slides",
+    "YouTube",
+    "Gmail",
+    "Twitter",
+    "Mastodon",
+    "Ivory",
+    "Reeder",
+    "Reeder 5",
+    "Readwise Reader",
+    "Day One",
+    "Bear",
+    "Craft",
+    "Drafts",
+    "Ulysses",
+    "iA Writer",
+    "Scrivener 3",
+    "Typora",
+    "MarkText",
+    "Logseq",
+    "Anki",
+    "Zotero",
+    "Skim",
+    "PDF Expert",
+    "Acrobat Distiller",
+    "Calibre",
+    "Spark",
+    "Airmail 5",
+    "Mimestream",
+    "Superhuman",
+    "Cardhop",
+    "BusyCal",
+    "Amie",
+    "Cron",
+    "Notion Calendar",
+    "Microsoft Word 2019",
+    "Microsoft Excel 2019",
+    "Office 365",
+];
+
+/// Queries, with the apps expected first for them, in order
+pub const RANKING_CASES: &[(&str, &[&str])] = &[
+    ("saf", &["Safari", "Safari Technology Preview"]),
+    (
+        "fire",
+        &["Firefox", "Firefox Developer Edition", "Firefox Nightly"],
+    ),
+    (
+        "fox",
+        &["Firefox", "Firefox Developer Edition", "Firefox Nightly"],
+    ),
+    ("chrome", &["Google Chrome", "Google Chrome Canary"]),
+    (
+        "goo",
+        &[
+            "Google Chrome",
+            "Google Chrome Canary",
+            "Google Docs",
+            "Google Drive",
+        ],
+    ),
+    (
+        "code",
+        &[
+            "Xcode",
+            "Xcode-beta",
+            "Visual Studio Code",
+            "Visual Studio Code - Insiders",
+        ],
+    ),
+    ("xco", &["Xcode", "Xcode-beta"]),
+    ("term", &["Terminal", "WezTerm", "iTerm"]),
+    ("iterm", &["iTerm"]),
+    ("photoshop", &["Adobe Photoshop 2024"]),
+    ("pho", &["Photo Booth", "Photos"]),
+    ("excel", &["Microsoft Excel", "Microsoft Excel 2019"]),
+    (
+        "teams",
+        &[
+            "Teams (work or school)",
+            "Microsoft Teams",
+            "Microsoft Teams classic",
+        ],
+    ),
+    ("1pa", &["1Password", "1Password 7"]),
+    ("pass", &["Passwords", "1Password", "1Password 7"]),
+    ("sys", &["System Information", "System Settings"]),
+    ("calc", &["Calculator"]),
+    ("cal", &["Calculator", "Calendar", "Calibre"]),
+    ("find", &["Find My", "Finder"]),
+    ("spot", &["Spotify"]),
+    ("mail", &["Mail", "Airmail 5", "Gmail"]),
+    (
+        "note",
+        &["Notes", "Evernote", "Keynote", "Microsoft OneNote"],
+    ),
+    ("zoom", &["zoom.us"]),
+    ("draw", &["draw.io"]),
+    ("battle", &["Battle.net"]),
+    ("intel", &["IntelliJ IDEA", "IntelliJ IDEA CE"]),
+    ("idea", &["IntelliJ IDEA", "IntelliJ IDEA CE"]),
+    ("pych", &["PyCharm", "PyCharm CE"]),
+    ("zed", &["Zed", "Zed Preview"]),
+    ("zed p", &["Zed Preview"]),
+    ("arc", &["Arc", "The Unarchiver"]),
+    ("key", &["Keychain Access", "Keynote"]),
+    ("pre", &["Preview", "Safari Technology Preview"]),
+    ("cafe", &["Café"]),
+    ("café", &["Café"]),
+    ("creme", &["Crème Brûlée"]),
+    ("angstrom", &["Ångström"]),
+    ("pokemon", &["Pokémon TCG Live"]),
+    ("ubersicht", &["Übersicht"]),
+    ("menu", &["Ｍｅｎｕ", "iStat Menus"]),
+    ("ｍｅｎｕ", &["Ｍｅｎｕ", "iStat Menus"]),
+    ("steuererklarung", &["Steuererklärung 2024"]),
+    ("微信", &["微信", "企业微信"]),
+    ("音乐", &["网易云音乐", "QQ音乐"]),
+    ("qq", &["QQ", "QQ音乐"]),
+    ("カレンダー", &["カレンダー"]),
+    ("카카오", &["카카오톡"]),
+    ("яндекс", &["Яндекс Браузер"]),
+];
@@ -0,0 +1,53 @@
+use std::sync::Arc;
+
+use crate::{
+    app::AppString,
+    doctor::{render_report, run_checks},
+    extensions::{Keyword, Provider, SearchResult},
+    fs::config::Configuration,
+};
+
+/// Typing `doctor` checks that what Fetch relies on works, see
+/// [`crate::doctor`]. Selecting the first result copies the whole report.
+#[derive(Debug)]
+pub struct DoctorProvider {
+    config: Arc<Configuration>,
+}
+
+const KEYWORD: &str = "doctor";
+
+impl DoctorProvider {
+    #[must_use]
+    pub fn new(config: Arc<Configuration>) -> Self {
+        Self { config }
+    }
+}
+
+impl Provider for DoctorProvider {
+    fn search(&self, query: &AppString) -> Vec<SearchResult> {
+        if !query.trim().eq_ignore_ascii_case(KEYWORD) {
+            return Vec::new();
+        }
+
+        // The hotkey is held by the UI, which is running
+        let checks = run_checks(&self.config, false);
+        let report = SearchResult::Text {
+            text: render_report(&checks).into(),
+            label: "Copy the report, to paste it into an issue".into(),
+        };
+
+        std::iter::once(report)
+            .chain(checks.into_iter().map(|check| SearchResult::Text {
+                text: format!("{} {}", if check.passed { '✓' } else { '✗' }, check.details).into(),
+                label: check.name.into(),
+            }))
+            .collect()
+    }
+
+    fn keywords(&self) -> Vec<Keyword> {
+        vec![Keyword {
+            keyword: KEYWORD,
+            description: "Check that Fetch works on this Mac",
+        }]
+    }
+}
@@ -0,0 +1,356 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use crate::{
+    app::AppString,
+    extensions::{
+        Keyword, Provider, SearchContext, SearchResult,
+        tasks::{TaskId, Tasks},
+    },
+    fs::config::{FileRankingConfig, FilesConfig},
+    locale::Date,
+    platform::{ImplPlatform, Platform},
+    url::Url,
+};
+
+/// Typing `file <name>` lists the files in the home folder whose name
+/// contains `<name>`, as found by Spotlight.
+///
+/// Spotlight's own order says nothing of what the user is after, so files
+/// are ranked by how well their name matches, and by how recently and how
+/// often they were opened (`kMDItemLastUsedDate` and `kMDItemUseCount`),
+/// with the weights of `ranking` in the configuration.
+///
+/// Files are looked for in the background once typing paused, like
+/// forecasts are, and kept until the search window opens again. Until
+/// Spotlight answers, which takes a while in large folders, a row tells
+/// that files are being searched, and cancelling it lists none.
+#[derive(Debug)]
+pub struct FileProvider {
+    state: Arc<FileState>,
+}
+
+#[derive(Debug)]
+struct FileState {
+    config: FilesConfig,
+    /// Files found for the latest names, by lowercase name
+    found: Mutex<HashMap<String, Vec<FileMetadata>>>,
+    /// Name the latest search was requested for, until it is done
+    pending: Mutex<Option<String>>,
+    /// Name Spotlight is being asked for, along with the task of its row
+    searching: Mutex<Option<(String, TaskId)>>,
+    tasks: Arc<Tasks>,
+}
+
+/// A file found by Spotlight, with what it knows of its use
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileMetadata {
+    pub path: PathBuf,
+    /// Day the file was last opened, if it ever was
+    pub last_used: Option<Date>,
+    /// How many times the file was opened
+    pub use_count: u32,
+}
+
+const KEYWORD: &str = "file";
+
+/// Names are only searched once typing paused for this long
+const SEARCH_DELAY: Duration = Duration::from_millis(300);
+
+/// Files ranked out of those Spotlight finds first, as ranking asks
+/// Spotlight for the metadata of each one
+const MAX_CANDIDATES: usize = 200;
+
+/// Searches of this many names are kept
+const MAX_CACHED_NAMES: usize = 16;
+
+/// Use count at which the frequency score reaches one half
+const HALF_FREQUENCY_COUNT: f64 = 10.0;
+
+/// How well `file_name` matches `query` (both lowercase), from 0 to 1
+fn name_score(file_name: &str, query: &str) -> f64 {
+    let stem = Path::new(file_name)
+        .file_stem()
+        .map_or(file_name, |stem| stem.to_str().unwrap_or(file_name));
+
+    if stem == query || file_name == query {
+        1.0
+    } else if file_name.starts_with(query) {
+        0.75
+    } else if file_name.match_indices(query).any(|(idx, _)| {
+        !file_name[..idx]
+            .chars()
+            .next_back()
+            .is_some_and(char::is_alphanumeric)
+    }) {
+        0.5
+    } else if file_name.contains(query) {
+        0.25
+    } else {
+        0.0
+    }
+}
+
+/// 1 for a file opened today, halving every `half_life_days`, and 0 for a
+/// file never opened
+fn recency_score(last_used: Option<Date>, today: Date, half_life_days: f64) -> f64 {
+    let Some(last_used) = last_used else {
+        return 0.0;
+    };
+    #[expect(
+        clippy::cast_precision_loss,
+        reason = "Ages in days are far below 2^52"
+    )]
+    let age_days = (today.days_since_epoch() - last_used.days_since_epoch()).max(0) as f64;
+
+    0.5_f64.powf(age_days / half_life_days.max(f64::MIN_POSITIVE))
+}
+
+/// From 0 for a file never opened, towards 1 for files opened often
+fn frequency_score(use_count: u32) -> f64 {
+    let use_count = f64::from(use_count);
+    use_count / (use_count + HALF_FREQUENCY_COUNT)
+}
+
+impl FileMetadata {
+    fn score(&self, query: &str, today: Option<Date>, weights: &FileRankingConfig) -> f64 {
+        let file_name = self
+            .path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        let recency = today.map_or(0.0, |today| {
+            recency_score(self.last_used, today, weights.recency_half_life_days)
+        });
+
+        weights.name * name_score(&file_name, query)
+            + weights.recency * recency
+            + weights.frequency * frequency_score(self.use_count)
+    }
+}
+
+/// Orders `files` from the best match of `query` (lowercase) to the worst
+fn rank_files(
+    files: Vec<FileMetadata>,
+    query: &str,
+    today: Option<Date>,
+    weights: &FileRankingConfig,
+) -> Vec<FileMetadata> {
+    let mut scored: Vec<_> = files
+        .into_iter()
+        .map(|file| (file.score(query, today, weights), file))
+        .collect();
+    scored.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+
+    scored.into_iter().map(|(_, file)| file).collect()
+}
+
+impl FileState {
+    /// Searches `name` in the background, unless another name is
+    /// requested before [`SEARCH_DELAY`]
+    fn request(self: &Arc<Self>, name: String) {
+        {
+            let mut pending = self.pending.lock().expect("no lock poisoning");
+            if pending.as_ref() == Some(&name) {
+                return;
+            }
+            *pending = Some(name.clone());
+        }
+
+        let state = self.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(SEARCH_DELAY);
+            if state.pending.lock().expect("no lock poisoning").as_ref() != Some(&name) {
+                return;
+            }
+
+            let task = state.tasks.start(format!("Searching files named {name}"));
+            *state.searching.lock().expect("no lock poisoning") = Some((name.clone(), task.id()));
+
+            let files = match ImplPlatform::find_files(&name, MAX_CANDIDATES) {
+                // Kept empty, so that the name isn't searched again
+                _ if task.is_cancelled() => Vec::new(),
+                Ok(files) => {
+                    let today = ImplPlatform::today();
+                    let mut files = rank_files(files, &name, today, &state.config.ranking);
+                    files.truncate(state.config.max_results);
+                    files
+                }
+                Err(report) => {
+                    // Kept to themselves, as names are searched while being typed
+                    eprintln!(
+                        "{}",
+                        report.context(format!("Could not search files named {name}"))
+                    );
+                    Vec::new()
+                }
+            };
+            {
+                let mut found = state.found.lock().expect("no lock poisoning");
+                if found.len() >= MAX_CACHED_NAMES {
+                    found.clear();
+                }
+                found.insert(name.clone(), files);
+            }
+
+            let mut pending = state.pending.lock().expect("no lock poisoning");
+            if pending.as_ref() == Some(&name) {
+                *pending = None;
+            }
+            drop(pending);
+            let mut searching = state.searching.lock().expect("no lock poisoning");
+            if searching
+                .as_ref()
+                .is_some_and(|(searched, _)| *searched == name)
+            {
+                *searching = None;
+            }
+            drop(searching);
+            // Searches again, listing the files found instead of the row
+            drop(task);
+        });
+    }
+}
+
+impl FileProvider {
+    #[must_use]
+    pub fn new(config: FilesConfig, tasks: Arc<Tasks>) -> Self {
+        Self {
+            state: Arc::new(FileState {
+                config,
+                found: Mutex::new(HashMap::new()),
+                pending: Mutex::new(None),
+                searching: Mutex::new(None),
+                tasks,
+            }),
+        }
+    }
+}
+
+impl Provider for FileProvider {
+    fn preload(&self, _context: &SearchContext) {
+        // Files were likely opened, moved or created since
+        self.state.found.lock().expect("no lock poisoning").clear();
+    }
+
+    fn search(&self, query: &AppString) -> Vec<SearchResult> {
+        let Some(name) = query
+            .strip_prefix_ignore_case(KEYWORD)
+            .and_then(|rest| rest.strip_prefix(' '))
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+        else {
+            return Vec::new();
+        };
+        let name = name.to_lowercase();
+
+        let found = self
+            .state
+            .found
+            .lock()
+            .expect("no lock poisoning")
+            .get(&name)
+            .cloned();
+        let Some(files) = found else {
+            self.state.request(name.clone());
+            let searching = self.state.searching.lock().expect("no lock poisoning");
+            return searching
+                .as_ref()
+                .filter(|(searched, _)| *searched == name)
+                .and_then(|(_, id)| self.state.tasks.progress(*id))
+                .map(SearchResult::Progress)
+                .into_iter()
+                .collect();
+        };
+
+        files
+            .into_iter()
+            .map(|file| SearchResult::Url {
+                name: file
+                    .path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default()
+                    .into(),
+                url: Url::File(file.path),
+            })
+            .collect()
+    }
+
+    fn keywords(&self) -> Vec<Keyword> {
+        vec![Keyword {
+            keyword: KEYWORD,
+            description: "Search files by name, the ones opened often first",
+        }]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(path: &str, last_used: Option<Date>, use_count: u32) -> FileMetadata {
+        FileMetadata {
+            path: PathBuf::from(path),
+            last_used,
+            use_count,
+        }
+    }
+
+    #[test]
+    fn test_name_score() {
+        assert!((name_score("report.pdf", "report") - 1.0).abs() < f64::EPSILON);
+        assert!((name_score("report 2024.pdf", "report") - 0.75).abs() < f64::EPSILON);
+        assert!((name_score("q3 report.pdf", "report") - 0.5).abs() < f64::EPSILON);
+        assert!((name_score("myreport.pdf", "report") - 0.25).abs() < f64::EPSILON);
+        assert!(name_score("notes.txt", "report").abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_usage_scores() {
+        let today = Date::new(2025, 6, 30).unwrap();
+
+        assert!((recency_score(Some(today), today, 30.0) - 1.0).abs() < f64::EPSILON);
+        let month_ago = today.add_days(-30);
+        assert!((recency_score(Some(month_ago), today, 30.0) - 0.5).abs() < 1e-9);
+        assert!(recency_score(None, today, 30.0).abs() < f64::EPSILON);
+
+        assert!(frequency_score(0).abs() < f64::EPSILON);
+        assert!((frequency_score(10) - 0.5).abs() < f64::EPSILON);
+        assert!(frequency_score(100) > frequency_score(10));
+    }
+
+    #[test]
+    fn test_rank_files() {
+        let today = Date::new(2025, 6, 30).unwrap();
+        let weights = FileRankingConfig::default();
+        let files = rank_files(
+            vec![
+                file("/Users/me/Old/report.pdf", None, 0),
+                file("/Users/me/q3 report.key", Some(today), 40),
+                file("/Users/me/myreport.txt", Some(today.add_days(-400)), 1),
+            ],
+            "report",
+            Some(today),
+            &weights,
+        );
+        let names: Vec<_> = files
+            .iter()
+            .map(|file| file.path.file_name().unwrap().to_str().unwrap())
+            .collect();
+        // The file opened every day beats the better named one never opened
+        assert_eq!(names, ["q3 report.key", "report.pdf", "myreport.txt"]);
+
+        let by_name_only = FileRankingConfig {
+            recency: 0.0,
+            frequency: 0.0,
+            ..weights
+        };
+        let files = rank_files(files, "report", Some(today), &by_name_only);
+        assert_eq!(files[0].path, Path::new("/Users/me/Old/report.pdf"));
+    }
+}
@@ -0,0 +1,305 @@
+use std::{
+    cmp::Reverse,
+    collections::HashMap,
+    hash::Hash,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use nucleo_matcher::{
+    Matcher, Utf32Str,
+    pattern::{AtomKind, CaseMatching, Normalization, Pattern},
+};
+use rootcause::Report;
+use tokio::sync::{broadcast, watch::Receiver};
+
+use crate::{
+    app::{AppString, ExecutableApp},
+    extensions::{
+        DeferredReceiver, DeferredToken, SearchContext, SearchEngine, SearchResult,
+        deterministic_search::DeterministicSearchEngine,
+        name_arena::NameArena,
+        tasks::{TaskId, TaskProgress},
+    },
+    fs::config::{Configuration, SortMode},
+    url::{IndexEvent, IndexingProgress},
+};
+
+/// An experimental engine, picked with `search_engine = "fuzzy_first"` in
+/// builds with the `fuzzy-engine` feature. Apps are ranked by how well
+/// their name fuzzy-matches the query first, so that "vsc" finds "Visual
+/// Studio Code", and by the order of [`DeterministicSearchEngine`] when
+/// they match as well.
+///
+/// Everything else (providers, learning, filters) is left to the
+/// deterministic engine it wraps.
+#[derive(Debug, Clone)]
+pub struct FuzzySearchEngine {
+    inner: DeterministicSearchEngine,
+    /// Reused between searches, as it holds the buffers of matching
+    matcher: Arc<Mutex<Matcher>>,
+}
+
+/// Apps listed at most, as fuzzy matches get loose past the first ones
+const MAX_APPS: usize = 50;
+
+/// Keeps the `candidates` whose name `pattern` could match, found with a
+/// scan of a [`NameArena`] rather than by scoring each of them. Only ASCII
+/// names are left out, as matching folds the case and diacritics of the
+/// others, and only by ASCII queries, whose words are then matched as typed.
+fn prefilter<T>(pattern: &Pattern, candidates: Vec<T>, name: impl Fn(&T) -> &str) -> Vec<T> {
+    let mut words = Vec::with_capacity(pattern.atoms.len());
+    for atom in &pattern.atoms {
+        if let Utf32Str::Ascii(word) = atom.needle_text()
+            && let Ok(word) = str::from_utf8(word)
+        {
+            words.push(word);
+        } else {
+            return candidates;
+        }
+    }
+
+    let arena = NameArena::new(
+        candidates
+            .iter()
+            .map(|item| name(item).to_ascii_lowercase()),
+    );
+    // Words are matched in any order, so names must contain each of them
+    let mut matched_words = vec![0; arena.len()];
+    for word in &words {
+        for idx in arena.candidates(word) {
+            matched_words[idx] += 1;
+        }
+    }
+
+    candidates
+        .into_iter()
+        .zip(matched_words)
+        .filter(|(item, matched)| *matched == words.len() || !name(item).is_ascii())
+        .map(|(item, _)| item)
+        .collect()
+}
+
+/// Orders `candidates` by how well their name fuzzy-matches `pattern`, the
+/// ones `ranked` lists first when they match as well. The apps of `ranked`
+/// which don't match (found by a path alias, or learned) follow.
+fn fuzzy_first<T, K: Hash + Eq>(
+    pattern: &Pattern,
+    matcher: &mut Matcher,
+    candidates: Vec<T>,
+    ranked: Vec<T>,
+    name: impl Fn(&T) -> &str,
+    id: impl Fn(&T) -> K,
+) -> Vec<T> {
+    let positions: HashMap<K, usize> = ranked
+        .iter()
+        .enumerate()
+        .map(|(position, item)| (id(item), position))
+        .collect();
+
+    let mut buf = Vec::new();
+    let mut scored: Vec<_> = candidates
+        .into_iter()
+        .filter_map(|item| {
+            let score = pattern.score(Utf32Str::new(name(&item), &mut buf), matcher)?;
+            let position = positions.get(&id(&item)).copied().unwrap_or(usize::MAX);
+            Some((Reverse(score), position, item))
+        })
+        .collect();
+    scored.sort_by_key(|(score, position, _)| (*score, *position));
+    scored.truncate(MAX_APPS);
+
+    let listed: Vec<K> = scored.iter().map(|(_, _, item)| id(item)).collect();
+    let unmatched = ranked
+        .into_iter()
+        .filter(|item| !listed.contains(&id(item)));
+
+    scored
+        .into_iter()
+        .map(|(_, _, item)| item)
+        .chain(unmatched)
+        .collect()
+}
+
+impl FuzzySearchEngine {
+    pub fn build(config: Arc<Configuration>) -> Result<Self, Report> {
+        Ok(Self {
+            inner: DeterministicSearchEngine::build(config)?,
+            matcher: Arc::new(Mutex::new(Matcher::new(nucleo_matcher::Config::DEFAULT))),
+        })
+    }
+
+    fn rank_apps(
+        &self,
+        engine: &DeterministicSearchEngine,
+        query: &AppString,
+    ) -> Vec<ExecutableApp> {
+        let ranked = engine.ranked_apps(query);
+        // Running apps, listed when nothing is typed
+        if query.is_empty() {
+            return ranked;
+        }
+
+        let pattern = Pattern::new(
+            query,
+            CaseMatching::Ignore,
+            Normalization::Smart,
+            AtomKind::Fuzzy,
+        );
+        let apps = fuzzy_first(
+            &pattern,
+            &mut self.matcher.lock().expect("no lock poisoning"),
+            prefilter(&pattern, engine.visible_app_list(), |app| &app.name),
+            ranked,
+            |app| &app.name,
+            |app| app.path.clone(),
+        );
+        // Pins override fuzzy matching as they override learning
+        engine.with_pinned_app(query, apps)
+    }
+}
+
+impl SearchEngine for FuzzySearchEngine {
+    fn blocking_search(&self, query: AppString) -> Vec<SearchResult> {
+        self.inner
+            .search_with(&query, |engine, query| self.rank_apps(engine, query))
+    }
+
+    fn deferred_search(&self, query: AppString) -> (DeferredToken, DeferredReceiver) {
+        self.inner
+            .deferred_search_with(query, |query| self.blocking_search(query))
+    }
+
+    fn preload(&self, context: SearchContext) {
+        self.inner.preload(context);
+    }
+
+    fn after_search(&self, selected_app: Option<SearchResult>) {
+        self.inner.after_search(selected_app);
+    }
+
+    fn set_private_mode(&self, private: bool) {
+        self.inner.set_private_mode(private);
+    }
+
+    fn toggle_hidden(&self, result: &SearchResult) {
+        self.inner.toggle_hidden(result);
+    }
+
+    fn toggle_pinned(&self, query: &AppString, result: &SearchResult) {
+        self.inner.toggle_pinned(query, result);
+    }
+
+    fn set_sort_mode(&self, mode: SortMode) {
+        self.inner.set_sort_mode(mode);
+    }
+
+    fn rebuild_index(&self) {
+        self.inner.rebuild_index();
+    }
+
+    fn forget_app(&self, path: &Path) {
+        self.inner.forget_app(path);
+    }
+
+    fn indexing_progress(&self) -> Option<Receiver<IndexingProgress>> {
+        self.inner.indexing_progress()
+    }
+
+    fn top_apps(&self) -> Option<Receiver<Vec<ExecutableApp>>> {
+        self.inner.top_apps()
+    }
+
+    fn results_changed(&self) -> Option<Receiver<usize>> {
+        self.inner.results_changed()
+    }
+
+    fn index_events(&self) -> Option<broadcast::Receiver<IndexEvent>> {
+        self.inner.index_events()
+    }
+
+    fn task_progress(&self) -> Option<broadcast::Receiver<TaskProgress>> {
+        self.inner.task_progress()
+    }
+
+    fn cancel_task(&self, id: TaskId) {
+        self.inner.cancel_task(id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pattern(query: &str) -> Pattern {
+        Pattern::new(
+            query,
+            CaseMatching::Ignore,
+            Normalization::Smart,
+            AtomKind::Fuzzy,
+        )
+    }
+
+    fn rank(
+        query: &str,
+        candidates: &[&'static str],
+        ranked: &[&'static str],
+    ) -> Vec<&'static str> {
+        fuzzy_first(
+            &pattern(query),
+            &mut Matcher::new(nucleo_matcher::Config::DEFAULT),
+            candidates.to_vec(),
+            ranked.to_vec(),
+            |name| name,
+            |name| *name,
+        )
+    }
+
+    #[test]
+    fn test_fuzzy_first() {
+        let apps = ["Visual Studio Code", "Safari", "VLC"];
+        // Only found by fuzzy matching
+        assert_eq!(rank("vsc", &apps, &[]), ["Visual Studio Code"]);
+        // Learned apps whose name doesn't match are kept, after the others
+        assert_eq!(rank("saf", &apps, &["VLC", "Safari"]), ["Safari", "VLC"]);
+
+        // Apps matching as well keep the order of the deterministic engine
+        let twins = ["Notes A", "Notes B"];
+        assert_eq!(
+            rank("notes", &twins, &["Notes B", "Notes A"]),
+            ["Notes B", "Notes A"]
+        );
+        assert_eq!(
+            rank("notes", &twins, &["Notes A", "Notes B"]),
+            ["Notes A", "Notes B"]
+        );
+    }
+
+    #[test]
+    fn test_prefilter() {
+        let apps = [
+            "Visual Studio Code",
+            "Safari",
+            "VLC",
+            "Café Menu",
+            "System Settings",
+        ];
+        let prefiltered = |query| prefilter(&pattern(query), apps.to_vec(), |name| name);
+
+        assert_eq!(prefiltered("vsc"), ["Visual Studio Code", "Café Menu"]);
+        // Words match in any order
+        assert_eq!(prefiltered("code vis"), ["Visual Studio Code", "Café Menu"]);
+        assert_eq!(prefiltered("SET"), ["Café Menu", "System Settings"]);
+        // Matching folds the case of other letters, which only scoring does
+        assert_eq!(prefiltered("É"), apps);
+
+        // Never leaves out an app scoring finds
+        for query in ["vsc", "saf", "cafe", "code vis", "ss", "xyz", "v\\ s", "é"] {
+            assert_eq!(
+                rank(query, &prefiltered(query), &[]),
+                rank(query, &apps, &[]),
+                "{query}"
+            );
+        }
+    }
+}
@@ -0,0 +1,164 @@
+use std::{fmt::Write, fs::File, io::Read};
+
+use crate::{
+    app::AppString,
+    extensions::{Keyword, Provider, SearchResult},
+    fs::config::GeneratorsConfig,
+};
+
+/// Generates values that are shown inline and copied when selected:
+/// - `uuid`: a random (v4) UUID
+/// - `password [length]`: a random password
+/// - `lorem [count][p|w]`: placeholder text, in paragraphs (default) or words
+#[derive(Debug)]
+pub struct GeneratorProvider {
+    config: GeneratorsConfig,
+}
+
+const LOREM_PARAGRAPHS: [&str; 3] = [
+    "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor \
+     incididunt ut labore et dolore magna aliqua. Ut enim ad minim veniam, quis nostrud \
+     exercitation ullamco laboris nisi ut aliquip ex ea commodo consequat.",
+    "Duis aute irure dolor in reprehenderit in voluptate velit esse cillum dolore eu fugiat \
+     nulla pariatur. Excepteur sint occaecat cupidatat non proident, sunt in culpa qui \
+     officia deserunt mollit anim id est laborum.",
+    "Sed ut perspiciatis unde omnis iste natus error sit voluptatem accusantium doloremque \
+     laudantium, totam rem aperiam, eaque ipsa quae ab illo inventore veritatis et quasi \
+     architecto beatae vitae dicta sunt explicabo.",
+];
+
+/// Generated values can't be pasted anywhere useful past this size
+const MAX_COUNT: usize = 1000;
+
+impl GeneratorProvider {
+    #[must_use]
+    pub fn new(config: GeneratorsConfig) -> Self {
+        Self { config }
+    }
+
+    fn password(&self, length: usize) -> Option<String> {
+        let charset: Vec<char> = self.config.password_charset.chars().collect();
+        // Each char is picked with a single random byte
+        if charset.is_empty() || charset.len() > 256 {
+            return None;
+        }
+
+        let mut password = String::with_capacity(length);
+        while password.chars().count() < length {
+            for byte in random_bytes(length)? {
+                // Skip bytes past the largest multiple of the charset length,
+                // so that every char is equally likely to be picked
+                if usize::from(byte) < 256 - 256 % charset.len() {
+                    password.push(charset[usize::from(byte) % charset.len()]);
+                }
+            }
+        }
+
+        Some(password.chars().take(length).collect())
+    }
+
+    fn lorem(&self, args: &str) -> String {
+        let (count, unit) = match args.find(|c: char| !c.is_ascii_digit()) {
+            Some(idx) => args.split_at(idx),
+            None => (args, "p"),
+        };
+        let count = count
+            .parse::<usize>()
+            .unwrap_or(self.config.lorem_paragraphs)
+            .min(MAX_COUNT);
+
+        let paragraphs = LOREM_PARAGRAPHS.iter().cycle();
+        if unit == "w" {
+            paragraphs
+                .flat_map(|paragraph| paragraph.split_whitespace())
+                .take(count)
+                .collect::<Vec<_>>()
+                .join(" ")
+        } else {
+            paragraphs
+                .take(count)
+                .copied()
+                .collect::<Vec<_>>()
+                .join("\n\n")
+        }
+    }
+}
+
+impl Provider for GeneratorProvider {
+    fn search(&self, query: &AppString) -> Vec<SearchResult> {
+        let query: &str = query;
+        let (keyword, args) = query.split_once(' ').unwrap_or((query, ""));
+        let args = args.trim();
+
+        let generated = match keyword.to_lowercase().as_str() {
+            "uuid" if args.is_empty() => uuid_v4().map(|uuid| (uuid, "UUID")),
+            "password" => {
+                let length = args
+                    .parse::<usize>()
+                    .unwrap_or(self.config.password_length)
+                    .min(MAX_COUNT);
+                self.password(length).map(|password| (password, "Password"))
+            }
+            "lorem" => Some((self.lorem(args), "Lorem ipsum")),
+            _ => None,
+        };
+
+        generated
+            .filter(|(text, _)| !text.is_empty())
+            .map(|(text, label)| SearchResult::Text {
+                text: text.into(),
+                label: label.into(),
+            })
+            .into_iter()
+            .collect()
+    }
+
+    fn keywords(&self) -> Vec<Keyword> {
+        vec![
+            Keyword {
+                keyword: "uuid",
+                description: "Generate a UUID",
+            },
+            Keyword {
+                keyword: "password",
+                description: "Generate a password, as in password 24",
+            },
+            Keyword {
+                keyword: "lorem",
+                description: "Generate placeholder text, as in lorem 3",
+            },
+        ]
+    }
+}
+
+/// Reads from the OS's cryptographically secure RNG
+fn random_bytes(len: usize) -> Option<Vec<u8>> {
+    let mut bytes = vec![0; len];
+
+    File::open("/dev/urandom")
+        .and_then(|mut urandom| urandom.read_exact(&mut bytes))
+        .inspect_err(|err| eprintln!("Could not generate random bytes: {err}"))
+        .ok()?;
+
+    Some(bytes)
+}
+
+fn uuid_v4() -> Option<String> {
+    let mut bytes = random_bytes(16)?;
+    bytes[6] = (bytes[6] & 0x0F) | 0x40; // Version 4
+    bytes[8] = (bytes[8] & 0x3F) | 0x80; // RFC 4122 variant
+
+    let hex = bytes.iter().fold(String::new(), |mut hex, byte| {
+        let _ = write!(hex, "{byte:02x}");
+        hex
+    });
+
+    Some(format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    ))
+}
@@ -0,0 +1,228 @@
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app::AppString,
+    extensions::{Keyword, Provider, SearchResult},
+    locale::Date,
+    platform::{ImplPlatform, Platform},
+};
+
+/// Queries typed in the search window, kept across its openings so that
+/// closing it doesn't lose them.
+///
+/// Queries typed since a result was last opened are learned for the next
+/// app opened that day, even if the window was closed in between. Those of
+/// the last `retention_days` can be typed again from `history`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QueryHistory {
+    /// Queries of each day (as in "2025-06-30"), oldest first
+    days: BTreeMap<String, Vec<AppString>>,
+    /// Day of `in_flight`, which is dropped once the day is over
+    in_flight_day: String,
+    /// Queries typed since a result was last opened
+    in_flight: Vec<AppString>,
+}
+
+/// Queries kept per day, which is more than are typed on most days
+const MAX_QUERIES_PER_DAY: usize = 500;
+
+/// Past queries listed at most
+const MAX_RECALLED: usize = 20;
+
+const KEYWORD: &str = "history";
+
+/// Appends `queries` to `list`, moving the ones already in it to its end
+fn push_queries(list: &mut Vec<AppString>, queries: &[AppString]) {
+    for query in queries.iter().filter(|query| !query.trim().is_empty()) {
+        list.retain(|listed| listed != query);
+        list.push(query.clone());
+    }
+
+    let excess = list.len().saturating_sub(MAX_QUERIES_PER_DAY);
+    list.drain(..excess);
+}
+
+impl QueryHistory {
+    /// Adds the queries of a search (oldest first), and forgets the days
+    /// older than `retention_days`
+    pub fn record(&mut self, today: Date, queries: &[AppString], retention_days: u32) {
+        let day = today.to_string();
+
+        push_queries(self.days.entry(day.clone()).or_default(), queries);
+        if self.in_flight_day != day {
+            self.in_flight.clear();
+            self.in_flight_day.clone_from(&day);
+        }
+        push_queries(&mut self.in_flight, queries);
+
+        let expired = today.add_days(-i64::from(retention_days)).to_string();
+        // ISO 8601 dates sort like the days they are
+        self.days.retain(|day, _| *day > expired);
+    }
+
+    /// Queries to learn for the result just opened, which are forgotten
+    /// for the next one
+    pub fn take_in_flight(&mut self) -> Vec<AppString> {
+        std::mem::take(&mut self.in_flight)
+    }
+
+    /// Past queries containing `filter`, latest first, along with their
+    /// day. Queries typed on the way to the next one (like "fir" before
+    /// "fire") are left out.
+    #[must_use]
+    pub fn recall(&self, filter: &str) -> Vec<(AppString, &str)> {
+        let filter = filter.to_lowercase();
+        let mut recalled: Vec<(AppString, &str)> = Vec::new();
+
+        for (day, queries) in self.days.iter().rev() {
+            let typed_on_the_way = |idx: usize| {
+                queries.get(idx + 1).is_some_and(|next| {
+                    next.to_lowercase()
+                        .starts_with(&queries[idx].to_lowercase())
+                })
+            };
+
+            for (idx, query) in queries.iter().enumerate().rev() {
+                if recalled.len() == MAX_RECALLED {
+                    return recalled;
+                }
+
+                let is_recalled = !typed_on_the_way(idx)
+                    && query.strip_prefix_ignore_case(KEYWORD).is_none()
+                    && query.to_lowercase().contains(&filter)
+                    && !recalled.iter().any(|(listed, _)| listed == query);
+                if is_recalled {
+                    recalled.push((query.trim().into(), day));
+                }
+            }
+        }
+
+        recalled
+    }
+}
+
+/// Typing `history` lists the queries typed lately, which are typed into
+/// the search bar again when selected. Text typed after `history` filters
+/// them.
+#[derive(Debug)]
+pub struct HistoryProvider {
+    /// Shared with the search engine, which records queries
+    history: Arc<Mutex<QueryHistory>>,
+}
+
+impl HistoryProvider {
+    #[must_use]
+    pub fn new(history: Arc<Mutex<QueryHistory>>) -> Self {
+        Self { history }
+    }
+}
+
+impl Provider for HistoryProvider {
+    fn search(&self, query: &AppString) -> Vec<SearchResult> {
+        let Some(rest) = query.strip_prefix_ignore_case(KEYWORD) else {
+            return Vec::new();
+        };
+        let filter = match rest.strip_prefix(' ') {
+            Some(filter) => filter.trim(),
+            None if rest.is_empty() => "",
+            // Query only starts with "history", like "historyfile"
+            None => return Vec::new(),
+        };
+
+        let today = ImplPlatform::today().map(|today| today.to_string());
+        let history = self.history.lock().expect("no lock poisoning");
+
+        history
+            .recall(filter)
+            .into_iter()
+            .map(|(query, day)| SearchResult::PastQuery {
+                query,
+                label: if today.as_deref() == Some(day) {
+                    "Searched today".into()
+                } else {
+                    format!("Searched on {day}").into()
+                },
+            })
+            .collect()
+    }
+
+    fn keywords(&self) -> Vec<Keyword> {
+        vec![Keyword {
+            keyword: KEYWORD,
+            description: "Search again for what you searched lately",
+        }]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn queries(queries: &[&str]) -> Vec<AppString> {
+        queries
+            .iter()
+            .map(|query| AppString::from(*query))
+            .collect()
+    }
+
+    fn recalled(history: &QueryHistory, filter: &str) -> Vec<String> {
+        history
+            .recall(filter)
+            .into_iter()
+            .map(|(query, _)| query.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_recall() {
+        let monday = Date::new(2025, 6, 30).unwrap();
+        let tuesday = monday.add_days(1);
+        let mut history = QueryHistory::default();
+
+        history.record(monday, &queries(&["f", "fi", "fir", "fire"]), 30);
+        history.record(monday, &queries(&["sa", "saf"]), 30);
+        history.record(tuesday, &queries(&["no", "not", "history f"]), 30);
+
+        assert_eq!(recalled(&history, ""), ["not", "saf", "fire"]);
+        assert_eq!(recalled(&history, "FI"), ["fire"]);
+
+        // Searching again moves a query to the end
+        history.record(tuesday, &queries(&["fire"]), 30);
+        assert_eq!(recalled(&history, "")[0], "fire");
+    }
+
+    #[test]
+    fn test_in_flight() {
+        let monday = Date::new(2025, 6, 30).unwrap();
+        let mut history = QueryHistory::default();
+
+        // Typed before the window was closed, then after it was opened again
+        history.record(monday, &queries(&["sla"]), 30);
+        history.record(monday, &queries(&["sl", "sla", "slack"]), 30);
+        assert_eq!(history.take_in_flight(), queries(&["sl", "sla", "slack"]));
+        assert!(history.take_in_flight().is_empty());
+
+        // Queries of another day aren't learned
+        history.record(monday, &queries(&["ma"]), 30);
+        history.record(monday.add_days(1), &queries(&["mail"]), 30);
+        assert_eq!(history.take_in_flight(), queries(&["mail"]));
+    }
+
+    #[test]
+    fn test_retention() {
+        let day = Date::new(2025, 6, 30).unwrap();
+        let mut history = QueryHistory::default();
+
+        history.record(day, &queries(&["old"]), 7);
+        history.record(day.add_days(6), &queries(&["recent"]), 7);
+        assert_eq!(recalled(&history, ""), ["recent", "old"]);
+        history.record(day.add_days(7), &queries(&["new"]), 7);
+        assert_eq!(recalled(&history, ""), ["new", "recent"]);
+    }
+}
@@ -0,0 +1,114 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app::AppString,
+    extensions::{Provider, SearchResult},
+};
+
+/// Something Fetch does to itself, listed as a result when its name is
+/// typed, rather than only being available from the status menu.
+///
+/// Commands are run by the search window, as most of them act on the
+/// window or on the search engine rather than on the app index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum InternalCommand {
+    Quit,
+    Restart,
+    /// Restarts Fetch, once the configuration file was checked to be valid,
+    /// as every part of Fetch reads it when started
+    ReloadConfig,
+    /// Reads every app again, rather than only those added or removed
+    RebuildIndex,
+}
+
+/// Queries shorter than this don't list commands, as "re" would list
+/// most of them along with apps
+const MIN_QUERY_LEN: usize = 3;
+
+impl InternalCommand {
+    /// Every command, in the order they are listed
+    pub const ALL: [Self; 4] = [
+        Self::Quit,
+        Self::Restart,
+        Self::ReloadConfig,
+        Self::RebuildIndex,
+    ];
+
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Quit => "Quit Fetch",
+            Self::Restart => "Restart Fetch",
+            Self::ReloadConfig => "Reload Config",
+            Self::RebuildIndex => "Rebuild Index",
+        }
+    }
+
+    #[must_use]
+    pub const fn description(self) -> &'static str {
+        match self {
+            Self::Quit => "Stop Fetch until it is opened again",
+            Self::Restart => "Quit Fetch, then open it again",
+            Self::ReloadConfig => "Restart Fetch with the configuration file as it is now",
+            Self::RebuildIndex => "Look for installed apps again, from scratch",
+        }
+    }
+
+    /// Whether the name of this command, or its end from one of its words
+    /// on, starts with `query`, as in "quit", "quit f" or "index"
+    fn matches(self, query: &str) -> bool {
+        let query = query.trim().to_lowercase();
+        if query.chars().count() < MIN_QUERY_LEN {
+            return false;
+        }
+
+        let name = self.name().to_lowercase();
+        std::iter::once(0)
+            .chain(name.match_indices(' ').map(|(idx, _)| idx + 1))
+            .any(|word_start| name[word_start..].starts_with(&query))
+    }
+}
+
+/// Lists the [`InternalCommand`]s whose name starts like the query
+#[derive(Debug, Default)]
+pub struct InternalCommandProvider;
+
+impl Provider for InternalCommandProvider {
+    fn search(&self, query: &AppString) -> Vec<SearchResult> {
+        InternalCommand::ALL
+            .into_iter()
+            .filter(|command| command.matches(query))
+            .map(SearchResult::Internal)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matching(query: &str) -> Vec<InternalCommand> {
+        InternalCommand::ALL
+            .into_iter()
+            .filter(|command| command.matches(query))
+            .collect()
+    }
+
+    #[test]
+    fn test_matches() {
+        assert_eq!(matching("quit"), [InternalCommand::Quit]);
+        assert_eq!(matching("Quit F"), [InternalCommand::Quit]);
+        assert_eq!(matching("reload config"), [InternalCommand::ReloadConfig]);
+        assert_eq!(matching("index"), [InternalCommand::RebuildIndex]);
+        assert_eq!(
+            matching("fetch"),
+            [InternalCommand::Quit, InternalCommand::Restart]
+        );
+
+        // Too short, or not from the start of a word
+        assert!(matching("re").is_empty());
+        assert!(matching("etch").is_empty());
+        assert!(matching("quitter").is_empty());
+    }
+}
@@ -0,0 +1,96 @@
+//! Other words typed instead of the keyword of a provider, such as `datei`
+//! for `file` in German, as in `datei report`. The language of the locale
+//! gives some (see [`crate::locale::keyword_aliases`]), and those of the
+//! configuration are added to them, replacing any typed the same.
+
+use std::collections::BTreeMap;
+
+use crate::{
+    app::AppString,
+    fs::config::Configuration,
+    locale::{current_identifier, keyword_aliases},
+};
+
+/// Rewrites queries starting with an alias to start with its keyword,
+/// before providers read them
+#[derive(Debug, Default)]
+pub struct KeywordAliases {
+    /// Keywords by lowercase alias
+    keywords: BTreeMap<String, String>,
+}
+
+impl KeywordAliases {
+    #[must_use]
+    pub fn new(config: &Configuration) -> Self {
+        Self::from_tables(
+            keyword_aliases(&current_identifier(config)),
+            &config.keyword_aliases,
+        )
+    }
+
+    fn from_tables(locale: &[(&str, &str)], configured: &BTreeMap<String, String>) -> Self {
+        let keywords = locale
+            .iter()
+            .map(|(alias, keyword)| ((*alias).to_owned(), (*keyword).to_owned()))
+            .chain(configured.clone())
+            .map(|(alias, keyword)| (alias.trim().to_lowercase(), keyword.trim().to_owned()))
+            .filter(|(alias, keyword)| {
+                !alias.is_empty() && !alias.contains(' ') && !keyword.is_empty()
+            })
+            .collect();
+
+        Self { keywords }
+    }
+
+    /// `query` with its first word replaced by the keyword it is an alias
+    /// of, or `query` as it is if that word isn't an alias
+    #[must_use]
+    pub fn resolve(&self, query: &AppString) -> AppString {
+        let (word, rest) = match query.split_once(' ') {
+            Some((word, rest)) => (word, Some(rest)),
+            None => (&**query, None),
+        };
+
+        match (self.keywords.get(&word.to_lowercase()), rest) {
+            (Some(keyword), Some(rest)) => format!("{keyword} {rest}").into(),
+            (Some(keyword), None) => keyword.as_str().into(),
+            (None, _) => query.clone(),
+        }
+    }
+
+    /// Every alias along with its keyword, listed by typing `?`
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.keywords
+            .iter()
+            .map(|(alias, keyword)| (alias.as_str(), keyword.as_str()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolve(aliases: &KeywordAliases, query: &str) -> String {
+        aliases.resolve(&query.into()).to_string()
+    }
+
+    #[test]
+    fn test_resolve() {
+        let configured = BTreeMap::from([
+            ("f".to_owned(), "file".to_owned()),
+            ("Datum".to_owned(), "history".to_owned()),
+        ]);
+        let aliases =
+            KeywordAliases::from_tables(&[("datei", "file"), ("datum", "date")], &configured);
+
+        assert_eq!(resolve(&aliases, "datei report"), "file report");
+        assert_eq!(resolve(&aliases, "Datei"), "file");
+        assert_eq!(resolve(&aliases, "f report.pdf"), "file report.pdf");
+        // The configuration replaces the aliases of the locale
+        assert_eq!(resolve(&aliases, "datum morgen"), "history morgen");
+
+        // Only the first word is an alias
+        assert_eq!(resolve(&aliases, "dateien"), "dateien");
+        assert_eq!(resolve(&aliases, "open datei"), "open datei");
+    }
+}
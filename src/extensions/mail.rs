@@ -0,0 +1,240 @@
+use std::fmt::Write;
+
+use crate::{
+    app::AppString,
+    extensions::{Keyword, Provider, SearchResult, text_transforms::url_encode},
+    fs::config::{MailConfig, MailTemplate},
+    url::Url,
+};
+
+/// Typing `mail <address> <subject>` composes a message to `<address>` in
+/// the default mail app. Several addresses can be separated by commas.
+///
+/// Templates of the configuration fill in messages written often: typing
+/// `mail standup` composes the `standup` template, and an address or a
+/// subject typed after its name replaces the one of the template.
+#[derive(Debug)]
+pub struct MailProvider {
+    config: MailConfig,
+}
+
+const KEYWORD: &str = "mail";
+
+/// A message to compose
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+struct Draft {
+    to: Vec<String>,
+    cc: Vec<String>,
+    subject: String,
+    body: String,
+}
+
+impl Draft {
+    fn from_template(template: &MailTemplate) -> Self {
+        Self {
+            to: template.to.clone(),
+            cc: template.cc.clone(),
+            subject: template.subject.clone(),
+            body: template.body.clone(),
+        }
+    }
+
+    /// Name of the result composing it
+    fn label(&self) -> String {
+        let mut label = if self.to.is_empty() {
+            String::from("New message")
+        } else {
+            format!("New message to {}", self.to.join(", "))
+        };
+        if !self.subject.is_empty() {
+            let _ = write!(label, ": {}", self.subject);
+        }
+
+        label
+    }
+
+    /// `mailto:` URL of the draft, as in RFC 6068
+    fn mailto_url(&self) -> String {
+        let mut url = format!("mailto:{}", encode_addresses(&self.to));
+
+        let fields = [
+            ("cc", encode_addresses(&self.cc)),
+            ("subject", url_encode(&self.subject)),
+            ("body", url_encode(&self.body)),
+        ];
+        let mut separator = '?';
+        for (name, value) in fields {
+            if !value.is_empty() {
+                let _ = write!(url, "{separator}{name}={value}");
+                separator = '&';
+            }
+        }
+
+        url
+    }
+}
+
+/// Percent-encodes addresses, except for their `@`
+fn encode_addresses(addresses: &[String]) -> String {
+    addresses
+        .iter()
+        .map(|address| url_encode(address).replace("%40", "@"))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// The addresses in `word`, if it is one or more addresses separated by commas
+fn parse_addresses(word: &str) -> Option<Vec<String>> {
+    word.split(',')
+        .map(|address| {
+            let (user, domain) = address.split_once('@')?;
+            (!user.is_empty() && !domain.is_empty()).then(|| address.to_owned())
+        })
+        .collect()
+}
+
+impl MailProvider {
+    #[must_use]
+    pub fn new(config: MailConfig) -> Self {
+        Self { config }
+    }
+
+    /// Drafts for what was typed after `mail `
+    fn drafts(&self, input: &str) -> Vec<Draft> {
+        let (first, rest) = input.split_once(' ').unwrap_or((input, ""));
+        let rest = rest.trim();
+
+        if let Some(to) = parse_addresses(first) {
+            return vec![Draft {
+                to,
+                subject: rest.to_owned(),
+                ..Draft::default()
+            }];
+        }
+
+        self.config
+            .templates
+            .iter()
+            .filter(|template| {
+                // Templates are listed while their name is typed
+                if input.contains(' ') {
+                    template.name.eq_ignore_ascii_case(first)
+                } else {
+                    template
+                        .name
+                        .to_lowercase()
+                        .starts_with(&first.to_lowercase())
+                }
+            })
+            .map(|template| {
+                let mut draft = Draft::from_template(template);
+                let (address, subject) = rest.split_once(' ').unwrap_or((rest, ""));
+                let subject = match parse_addresses(address) {
+                    Some(to) => {
+                        draft.to = to;
+                        subject.trim()
+                    }
+                    None => rest,
+                };
+                if !subject.is_empty() {
+                    subject.clone_into(&mut draft.subject);
+                }
+
+                draft
+            })
+            .collect()
+    }
+}
+
+impl Provider for MailProvider {
+    fn search(&self, query: &AppString) -> Vec<SearchResult> {
+        let Some(input) = query
+            .strip_prefix_ignore_case(KEYWORD)
+            .and_then(|rest| rest.strip_prefix(' '))
+        else {
+            return Vec::new();
+        };
+
+        self.drafts(input.trim_start())
+            .into_iter()
+            .map(|draft| SearchResult::Url {
+                name: draft.label().into(),
+                url: Url::Other(draft.mailto_url().into()),
+            })
+            .collect()
+    }
+
+    fn keywords(&self) -> Vec<Keyword> {
+        vec![Keyword {
+            keyword: KEYWORD,
+            description: "Compose an email in your mail app",
+        }]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn provider() -> MailProvider {
+        MailProvider::new(MailConfig {
+            enabled: true,
+            templates: vec![MailTemplate {
+                name: String::from("standup"),
+                to: vec![String::from("team@example.com")],
+                cc: vec![String::from("lead@example.com")],
+                subject: String::from("Standup"),
+                body: String::from("Yesterday:\nToday:"),
+            }],
+        })
+    }
+
+    #[test]
+    fn test_mailto_url() {
+        let draft = Draft {
+            to: vec![
+                String::from("bob@example.com"),
+                String::from("eve+fetch@example.com"),
+            ],
+            cc: Vec::new(),
+            subject: String::from("Lunch & coffee?"),
+            body: String::from("At noon\nSee you"),
+        };
+
+        assert_eq!(
+            draft.mailto_url(),
+            "mailto:bob@example.com,eve%2Bfetch@example.com\
+             ?subject=Lunch%20%26%20coffee%3F&body=At%20noon%0ASee%20you"
+        );
+        assert_eq!(Draft::default().mailto_url(), "mailto:");
+    }
+
+    #[test]
+    fn test_drafts() {
+        let provider = provider();
+
+        assert_eq!(
+            provider.drafts("bob@example.com subject here"),
+            [Draft {
+                to: vec![String::from("bob@example.com")],
+                subject: String::from("subject here"),
+                ..Draft::default()
+            }]
+        );
+        assert_eq!(provider.drafts("a@b.c,d@e.f")[0].to, ["a@b.c", "d@e.f"]);
+
+        let standup = Draft::from_template(&provider.config.templates[0]);
+        assert_eq!(provider.drafts("stand"), std::slice::from_ref(&standup));
+        assert_eq!(
+            provider.drafts("standup Monday notes")[0].subject,
+            "Monday notes"
+        );
+        let draft = &provider.drafts("standup bob@example.com Late")[0];
+        assert_eq!(draft.to, ["bob@example.com"]);
+        assert_eq!(draft.subject, "Late");
+        assert_eq!(draft.cc, standup.cc);
+
+        assert!(provider.drafts("stand notes").is_empty());
+        assert!(provider.drafts("@example.com").is_empty());
+    }
+}
@@ -0,0 +1,159 @@
+use regex::{Captures, Regex};
+
+use crate::{
+    app::AppString,
+    command::{CommandAction, CommandEntry, MatcherEntry, applescript_string},
+    errors::{self, ErrorEvent},
+    extensions::{Provider, SearchResult, text_transforms::url_encode},
+};
+
+/// Suggests the commands of the [`MatcherEntry`]s whose pattern matches
+/// the query.
+///
+/// Matchers take precedence over every other result, since they are
+/// written for queries that are known to have a specific meaning (like
+/// ticket numbers). They don't replace app results though, so a pattern
+/// that is too broad won't prevent launching apps.
+#[derive(Debug, Default)]
+pub struct MatcherProvider {
+    matchers: Vec<(Regex, MatcherEntry)>,
+}
+
+impl MatcherProvider {
+    #[must_use]
+    pub fn new(entries: &[MatcherEntry]) -> Self {
+        let matchers = entries
+            .iter()
+            .filter_map(|entry| match Regex::new(&entry.pattern) {
+                Ok(regex) => Some((regex, entry.clone())),
+                Err(err) => {
+                    eprintln!("Matcher `{}` ignored: {err}", entry.pattern);
+                    errors::publish(ErrorEvent {
+                        summary: format!("Matcher `{}` ignored", entry.pattern),
+                        details: err.to_string(),
+                        remedy: None,
+                    });
+                    None
+                }
+            })
+            .collect();
+
+        Self { matchers }
+    }
+}
+
+impl Provider for MatcherProvider {
+    fn search(&self, query: &AppString) -> Vec<SearchResult> {
+        let query: &str = query;
+
+        self.matchers
+            .iter()
+            .filter_map(|(regex, entry)| {
+                let captures = regex.captures(query)?;
+
+                let action = match &entry.action {
+                    CommandAction::Url(url) => {
+                        CommandAction::Url(expand(&captures, url, url_encode))
+                    }
+                    CommandAction::AppleScript(source) => {
+                        CommandAction::AppleScript(expand(&captures, source, applescript_string))
+                    }
+                    CommandAction::AppleScriptFile(path) => {
+                        CommandAction::AppleScriptFile(path.clone())
+                    }
+                };
+
+                Some(SearchResult::Command(CommandEntry {
+                    keyword: entry.name.as_ref().map_or_else(
+                        || query.to_string(),
+                        |name| expand(&captures, name, str::to_owned),
+                    ),
+                    action,
+                    show_output: entry.show_output,
+                    browser: entry.browser.clone(),
+                    environment: entry.environment.clone(),
+                }))
+            })
+            .collect()
+    }
+}
+
+/// Replaces `$1`, `$name`, `${1}` and `${name}` in `template` with the
+/// groups of `captures` passed through `encode`, or with nothing for groups
+/// that didn't match, like [`Captures::expand`] does. `$$` stands for `$`.
+///
+/// What is typed is encoded so that it can't change the meaning of the URL
+/// or script it is put in, such as with `&` in a URL or `"` in a script.
+fn expand(captures: &Captures<'_>, template: &str, encode: impl Fn(&str) -> String) -> String {
+    let is_name_char = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    let mut expanded = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(dollar) = rest.find('$') {
+        expanded.push_str(&rest[..dollar]);
+        let after = &rest[dollar + 1..];
+
+        if let Some(remaining) = after.strip_prefix('$') {
+            expanded.push('$');
+            rest = remaining;
+            continue;
+        }
+
+        let (name, remaining) = if let Some(braced) = after.strip_prefix('{') {
+            match braced.split_once('}') {
+                Some((name, remaining)) if !name.is_empty() && name.chars().all(is_name_char) => {
+                    (name, remaining)
+                }
+                _ => ("", after),
+            }
+        } else {
+            let len = after.find(|c| !is_name_char(c)).unwrap_or(after.len());
+            (&after[..len], &after[len..])
+        };
+
+        if name.is_empty() {
+            expanded.push('$');
+        } else {
+            let group = match name.parse::<usize>() {
+                Ok(index) => captures.get(index),
+                Err(_) => captures.name(name),
+            };
+            if let Some(group) = group {
+                expanded.push_str(&encode(group.as_str()));
+            }
+        }
+        rest = remaining;
+    }
+    expanded.push_str(rest);
+
+    expanded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand_with(pattern: &str, query: &str, template: &str) -> [String; 2] {
+        let regex = Regex::new(pattern).unwrap();
+        let captures = regex.captures(query).unwrap();
+
+        [
+            expand(&captures, template, url_encode),
+            expand(&captures, template, applescript_string),
+        ]
+    }
+
+    #[test]
+    fn test_expand() {
+        let [url, script] = expand_with(r"^g (.+)$", "g a&b=\"c\"", "https://x.com/?q=$1");
+        assert_eq!(url, "https://x.com/?q=a%26b%3D%22c%22");
+        assert_eq!(script, "https://x.com/?q=\"a&b=\\\"c\\\"\"");
+
+        let [url, _] = expand_with(r"^(?<id>[A-Z]+-\d+)$", "AB-12", "${id}/$0/$id-x/$$5/$");
+        assert_eq!(url, "AB-12/AB-12/AB-12-x/$5/$");
+
+        // Groups that didn't match are left out
+        let [url, _] = expand_with(r"^a(b)?$", "a", "[$1]");
+        assert_eq!(url, "[]");
+    }
+}
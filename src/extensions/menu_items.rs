@@ -0,0 +1,95 @@
+use std::{borrow::Cow, sync::Mutex};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app::{AppString, RunningApp},
+    errors,
+    extensions::{Provider, SearchContext, SearchResult},
+    platform::{ImplPlatform, Platform},
+    url::Url,
+};
+
+/// An item from the menu bar of an app, such as "File › Export › PDF".
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Hash)]
+pub struct MenuItem {
+    /// The app owning the menu bar
+    pub(crate) app: RunningApp,
+    /// Titles of the menus leading to the item, starting
+    /// from the menu bar (for instance: `["File", "Export"]`)
+    pub(crate) menu_path: Vec<AppString>,
+    pub(crate) title: AppString,
+}
+
+impl MenuItem {
+    /// "File › Export", displayed alongside the item title
+    #[must_use]
+    pub fn breadcrumb(&self) -> String {
+        self.menu_path
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(" › ")
+    }
+}
+
+/// Lists the menu items of the frontmost app (the one that was
+/// active before Fetch was opened), so that deep menu commands can
+/// be searched for and executed.
+///
+/// Reading menus of another app requires the Accessibility permission.
+/// When it isn't granted, the provider suggests opening the relevant
+/// System Settings pane instead.
+#[derive(Debug, Default)]
+pub struct MenuItemProvider {
+    items: Mutex<Vec<MenuItem>>,
+    has_permission: Mutex<bool>,
+}
+
+/// Queries shorter than this would match most menu items
+const MIN_QUERY_LEN: usize = 2;
+
+impl Provider for MenuItemProvider {
+    fn preload(&self, context: &SearchContext) {
+        let has_permission = ImplPlatform::has_accessibility_permission();
+        *self.has_permission.lock().expect("no lock poisoning") = has_permission;
+
+        let items = match &context.frontmost_app {
+            Some(app) if has_permission => {
+                ImplPlatform::list_menu_items(app).unwrap_or_else(|report| {
+                    errors::report_error("Could not list menu items", report);
+                    Vec::new()
+                })
+            }
+            _ => Vec::new(),
+        };
+
+        *self.items.lock().expect("no lock poisoning") = items;
+    }
+
+    fn search(&self, query: &AppString) -> Vec<SearchResult> {
+        if query.grapheme_len() < MIN_QUERY_LEN {
+            return Vec::new();
+        }
+
+        if !*self.has_permission.lock().expect("no lock poisoning") {
+            return vec![SearchResult::Url {
+                name: "Allow Fetch to search menu items…".into(),
+                url: Url::SystemSettings(Cow::Borrowed(
+                    "com.apple.preference.security?Privacy_Accessibility",
+                )),
+            }];
+        }
+
+        let query = query.to_lowercase();
+
+        self.items
+            .lock()
+            .expect("no lock poisoning")
+            .iter()
+            .filter(|item| item.title.to_lowercase().contains(&query))
+            .cloned()
+            .map(SearchResult::MenuItem)
+            .collect()
+    }
+}
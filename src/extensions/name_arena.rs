@@ -0,0 +1,88 @@
+//! Prefiltering of the names a query fuzzy-matches, shared by the
+//! providers and engines scoring many of them.
+
+/// Names laid end to end in a single buffer. Finding the names a query could
+/// match then takes one scan of the buffer with `memchr`, which picks the
+/// widest SIMD instructions of the CPU at runtime (NEON on Apple Silicon,
+/// AVX2 or SSE2 on Intel), instead of a scan of each name.
+#[derive(Debug, Default)]
+pub struct NameArena {
+    buffer: String,
+    /// Where each name ends in `buffer`
+    ends: Vec<usize>,
+}
+
+impl NameArena {
+    pub fn new(names: impl IntoIterator<Item = String>) -> Self {
+        let mut arena = Self::default();
+        for name in names {
+            arena.buffer.push_str(&name);
+            arena.ends.push(arena.buffer.len());
+        }
+
+        arena
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.ends.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.ends.is_empty()
+    }
+
+    fn start(&self, idx: usize) -> usize {
+        idx.checked_sub(1).map_or(0, |prev| self.ends[prev])
+    }
+
+    #[must_use]
+    pub fn name(&self, idx: usize) -> &str {
+        &self.buffer[self.start(idx)..self.ends[idx]]
+    }
+
+    /// Indices of the names containing the bytes of `query` in order, which
+    /// includes every name a fuzzy match of `query` could find. Cheap enough
+    /// to run over every name, unlike scoring.
+    #[must_use]
+    pub fn candidates(&self, query: &str) -> Vec<usize> {
+        let Some((&first, rest)) = query.as_bytes().split_first() else {
+            return (0..self.ends.len()).collect();
+        };
+
+        let buffer = self.buffer.as_bytes();
+        let mut candidates = Vec::new();
+        let mut from = 0;
+        let mut idx = 0;
+        while let Some(offset) = memchr::memchr(first, &buffer[from..]) {
+            let pos = from + offset;
+            // Cheaper than a binary search, names are short
+            while self.ends[idx] <= pos {
+                idx += 1;
+            }
+            // Matching from the first occurrence in a name is enough, later
+            // ones have less of the name left to match the rest in
+            from = self.ends[idx];
+
+            if contains_in_order(&buffer[pos + 1..from], rest) {
+                candidates.push(idx);
+            }
+        }
+
+        candidates
+    }
+}
+
+/// Whether `haystack` contains every byte of `needle`, in order
+fn contains_in_order(haystack: &[u8], needle: &[u8]) -> bool {
+    let mut rest = haystack;
+    for &byte in needle {
+        let Some(idx) = memchr::memchr(byte, rest) else {
+            return false;
+        };
+        rest = &rest[idx + 1..];
+    }
+
+    true
+}
@@ -0,0 +1,61 @@
+use crate::{
+    app::AppString,
+    extensions::{Keyword, Provider, SearchResult},
+    fs::config::Configuration,
+    permissions::{RequiredPermission, required_permissions},
+};
+
+/// Typing `permissions` lists the permissions the enabled features need,
+/// and whether they were granted. Selecting one opens its pane in System
+/// Settings.
+#[derive(Debug)]
+pub struct PermissionsProvider {
+    required: Vec<RequiredPermission>,
+}
+
+const KEYWORD: &str = "permissions";
+
+impl PermissionsProvider {
+    #[must_use]
+    pub fn new(config: &Configuration) -> Self {
+        Self {
+            required: required_permissions(config),
+        }
+    }
+}
+
+impl Provider for PermissionsProvider {
+    fn search(&self, query: &AppString) -> Vec<SearchResult> {
+        if !query.trim().eq_ignore_ascii_case(KEYWORD) {
+            return Vec::new();
+        }
+
+        if self.required.is_empty() {
+            return vec![SearchResult::Text {
+                text: "No permission needed".into(),
+                label: "None of the enabled features needs a permission".into(),
+            }];
+        }
+
+        self.required
+            .iter()
+            .map(|required| SearchResult::Url {
+                name: format!(
+                    "{}: {} · {}",
+                    required.permission.display_name(),
+                    required.permission.status(),
+                    required.feature
+                )
+                .into(),
+                url: required.permission.settings_url(),
+            })
+            .collect()
+    }
+
+    fn keywords(&self) -> Vec<Keyword> {
+        vec![Keyword {
+            keyword: KEYWORD,
+            description: "Permissions the enabled features need",
+        }]
+    }
+}
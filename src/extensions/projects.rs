@@ -0,0 +1,250 @@
+use std::{
+    cmp::Reverse,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime},
+};
+
+use scc::HashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app::{AppString, AppUsage},
+    extensions::{Keyword, Provider, SearchContext, SearchResult},
+    fs::config::{ProjectTarget, ProjectsConfig, expand_home},
+    scheduler::BackgroundScheduler,
+};
+
+/// A git repository found in one of the project roots, along with
+/// the app to open it with.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Hash)]
+pub struct Project {
+    pub(crate) name: AppString,
+    pub(crate) path: PathBuf,
+    pub(crate) target: ProjectTarget,
+}
+
+/// Typing `proj <name>` lists the git repositories in the configured
+/// roots matching `<name>`, the ones opened often and recently from Fetch
+/// first, then the most recently modified. They open with
+/// the configured app, unless the query ends with ` with`, in which case
+/// the best match is listed once for every app it can be opened with.
+#[derive(Debug)]
+pub struct ProjectProvider {
+    config: ProjectsConfig,
+    /// Paths of the repositories, with when they were last modified
+    projects: Mutex<Vec<(PathBuf, SystemTime)>>,
+    last_refresh: Mutex<Option<Instant>>,
+    scheduler: Arc<BackgroundScheduler>,
+    /// How often and how recently each project was opened, by path
+    usage: Arc<HashMap<PathBuf, AppUsage>>,
+}
+
+const KEYWORD: &str = "proj";
+
+/// Suffix of the query listing every app a project can be opened with
+const TARGETS_SUFFIX: &str = " with";
+
+/// Scanning roots walks a lot of directories, so it is only
+/// done once in a while
+const REFRESH_INTERVAL: Duration = Duration::from_mins(5);
+
+/// Repositories are rarely nested deeper than `~/dev/owner/repo`
+const MAX_DEPTH: usize = 3;
+
+const MAX_RESULTS: usize = 8;
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+impl ProjectProvider {
+    #[must_use]
+    pub fn new(
+        config: ProjectsConfig,
+        scheduler: Arc<BackgroundScheduler>,
+        usage: Arc<HashMap<PathBuf, AppUsage>>,
+    ) -> Self {
+        Self {
+            config,
+            projects: Mutex::new(Vec::new()),
+            last_refresh: Mutex::new(None),
+            scheduler,
+            usage,
+        }
+    }
+}
+
+/// Higher is better. Every open counts, opens of the last few days more
+/// than older ones, so that a project worked on a lot last year doesn't
+/// outrank the one of this week.
+fn frecency(usage: AppUsage, now: u64) -> u64 {
+    let age_days = now.saturating_sub(usage.last_opened) / SECONDS_PER_DAY;
+    let weight = match age_days {
+        0..4 => 100,
+        4..14 => 70,
+        14..31 => 50,
+        31..90 => 30,
+        _ => 10,
+    };
+
+    u64::from(usage.launch_count) * weight
+}
+
+/// Whether `query` ends with [`TARGETS_SUFFIX`], whatever its case
+fn strip_targets_suffix(query: &str) -> Option<&str> {
+    let split = query.len().checked_sub(TARGETS_SUFFIX.len())?;
+    let (name_query, suffix) = (query.get(..split)?, query.get(split..)?);
+
+    suffix
+        .eq_ignore_ascii_case(TARGETS_SUFFIX)
+        .then_some(name_query)
+}
+
+/// Finds git repositories in `dir`, without looking inside them
+fn find_repos(dir: &Path, depth: usize, repos: &mut Vec<(PathBuf, SystemTime)>) {
+    let git_dir = dir.join(".git");
+    if git_dir.exists() {
+        // The index is written to by most git commands, which
+        // makes it a better sign of activity than the directory
+        let modified = std::fs::metadata(git_dir.join("index"))
+            .or_else(|_| std::fs::metadata(&git_dir))
+            .and_then(|metadata| metadata.modified())
+            .unwrap_or(SystemTime::UNIX_EPOCH);
+        repos.push((dir.to_path_buf(), modified));
+        return;
+    }
+
+    if depth == 0 {
+        return;
+    }
+
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let is_hidden = entry.file_name().to_string_lossy().starts_with('.');
+        let is_dir = entry.file_type().is_ok_and(|file_type| file_type.is_dir());
+
+        if is_dir && !is_hidden && entry.file_name() != "node_modules" {
+            find_repos(&entry.path(), depth - 1, repos);
+        }
+    }
+}
+
+impl Provider for ProjectProvider {
+    fn preload(&self, _context: &SearchContext) {
+        let mut last_refresh = self.last_refresh.lock().expect("no lock poisoning");
+        if !self
+            .scheduler
+            .is_due(last_refresh.map(|instant| instant + REFRESH_INTERVAL))
+        {
+            return;
+        }
+
+        let mut projects = Vec::new();
+        for root in &self.config.roots {
+            find_repos(&expand_home(root), MAX_DEPTH, &mut projects);
+        }
+        projects.sort_by_key(|(_, modified)| Reverse(*modified));
+
+        *self.projects.lock().expect("no lock poisoning") = projects;
+        *last_refresh = Some(Instant::now());
+    }
+
+    fn search(&self, query: &AppString) -> Vec<SearchResult> {
+        let Some(rest) = query
+            .strip_prefix_ignore_case(KEYWORD)
+            .and_then(|rest| rest.strip_prefix(' '))
+        else {
+            return Vec::new();
+        };
+
+        let (name_query, pick_target) = match strip_targets_suffix(rest) {
+            Some(name_query) => (name_query.trim(), true),
+            None => (rest.trim(), false),
+        };
+        let name_query = name_query.to_lowercase();
+
+        let projects = self.projects.lock().expect("no lock poisoning");
+        // Already sorted by most recently modified, which the stable sort
+        // keeps for projects of the same frecency
+        let mut matches: Vec<(AppString, &PathBuf)> = projects
+            .iter()
+            .filter_map(|(path, _)| {
+                let name = path.file_name()?.to_string_lossy();
+                name.to_lowercase()
+                    .contains(&name_query)
+                    .then(|| (AppString::from(&*name), path))
+            })
+            .collect();
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+        matches.sort_by_cached_key(|(_, path)| {
+            Reverse(
+                self.usage
+                    .read_sync(*path, |_, usage| frecency(*usage, now))
+                    .unwrap_or_default(),
+            )
+        });
+        let mut matches = matches.into_iter();
+
+        let project = |(name, path): (AppString, &PathBuf), target| {
+            SearchResult::Project(Project {
+                name,
+                path: path.clone(),
+                target,
+            })
+        };
+
+        if pick_target {
+            let Some(best_match) = matches.next() else {
+                return Vec::new();
+            };
+
+            ProjectTarget::ALL
+                .iter()
+                .map(|target| project(best_match.clone(), *target))
+                .collect()
+        } else {
+            matches
+                .take(MAX_RESULTS)
+                .map(|best_match| project(best_match, self.config.open_with))
+                .collect()
+        }
+    }
+
+    fn keywords(&self) -> Vec<Keyword> {
+        vec![Keyword {
+            keyword: KEYWORD,
+            description: "Open the git repositories in your project folders",
+        }]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_targets_suffix() {
+        assert_eq!(strip_targets_suffix("fetch with"), Some("fetch"));
+        assert_eq!(strip_targets_suffix("fetch WITH"), Some("fetch"));
+        assert_eq!(strip_targets_suffix("fetch"), None);
+        assert_eq!(strip_targets_suffix("éwith"), None);
+    }
+
+    #[test]
+    fn test_frecency() {
+        let now = 100 * SECONDS_PER_DAY;
+        let usage = |launch_count, days_ago| AppUsage {
+            launch_count,
+            last_opened: now - days_ago * SECONDS_PER_DAY,
+        };
+
+        // Opened a few times this week beats opened a lot months ago
+        assert!(frecency(usage(3, 1), now) > frecency(usage(20, 95), now));
+        assert!(frecency(usage(2, 1), now) > frecency(usage(1, 1), now));
+        assert_eq!(frecency(AppUsage::default(), now), 0);
+    }
+}
@@ -0,0 +1,98 @@
+use crate::app::AppString;
+
+/// Leading `!`: only results named exactly like the query
+const EXACT_OPERATOR: char = '!';
+/// Leading `^`: only apps, without the results of providers
+const APPS_ONLY_OPERATOR: char = '^';
+/// Trailing `!`: opens the top app right away
+const OPEN_OPERATOR: char = '!';
+
+/// Operators typed around a query, as in `!code`, `^term` or `firefox!`.
+/// Leading operators can be combined, as in `^!notes!`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QueryOperators {
+    /// The query without any operator, which apps are ranked against
+    pub apps_query: AppString,
+    /// The query without its leading operators. Providers read the text
+    /// after their keyword as is, so it may end with a `!`.
+    pub providers_query: AppString,
+    pub exact: bool,
+    pub apps_only: bool,
+    pub open_top_app: bool,
+}
+
+impl QueryOperators {
+    #[must_use]
+    pub fn parse(query: &str) -> Self {
+        let mut operators = Self::default();
+
+        let mut rest = query;
+        loop {
+            if let Some(stripped) = rest.strip_prefix(EXACT_OPERATOR) {
+                operators.exact = true;
+                rest = stripped;
+            } else if let Some(stripped) = rest.strip_prefix(APPS_ONLY_OPERATOR) {
+                operators.apps_only = true;
+                rest = stripped;
+            } else {
+                break;
+            }
+        }
+        if operators.exact || operators.apps_only {
+            rest = rest.trim_start();
+        }
+        operators.providers_query = rest.into();
+
+        let mut apps_query = rest;
+        if let Some(stripped) = rest.trim_end().strip_suffix(OPEN_OPERATOR)
+            && !stripped.trim().is_empty()
+        {
+            operators.open_top_app = true;
+            apps_query = stripped.trim_end();
+        }
+        operators.apps_query = apps_query.into();
+
+        operators
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_no_operators() {
+        let operators = QueryOperators::parse("visual studio");
+        assert_eq!(operators.apps_query, "visual studio".into());
+        assert_eq!(operators.providers_query, "visual studio".into());
+        assert!(!operators.exact && !operators.apps_only && !operators.open_top_app);
+    }
+
+    #[test]
+    fn test_leading_operators() {
+        let operators = QueryOperators::parse("!code");
+        assert_eq!(operators.apps_query, "code".into());
+        assert!(operators.exact && !operators.apps_only);
+
+        let operators = QueryOperators::parse("^!term");
+        assert_eq!(operators.apps_query, "term".into());
+        assert!(operators.exact && operators.apps_only);
+    }
+
+    #[test]
+    fn test_open_operator() {
+        let operators = QueryOperators::parse("firefox!");
+        assert_eq!(operators.apps_query, "firefox".into());
+        assert_eq!(operators.providers_query, "firefox!".into());
+        assert!(operators.open_top_app && !operators.exact);
+
+        let operators = QueryOperators::parse("^safari !");
+        assert_eq!(operators.apps_query, "safari".into());
+        assert!(operators.open_top_app && operators.apps_only);
+
+        // A lone `!` is the exact operator, without anything to open
+        let operators = QueryOperators::parse("!");
+        assert_eq!(operators.apps_query, "".into());
+        assert!(operators.exact && !operators.open_top_app);
+    }
+}
@@ -0,0 +1,435 @@
+use std::{
+    borrow::Cow,
+    sync::{Arc, Mutex, Weak},
+    time::{Duration, Instant},
+};
+
+use rootcause::Report;
+use serde::{Deserialize, de::DeserializeOwned};
+
+use crate::{
+    app::AppString,
+    errors,
+    extensions::{Keyword, Provider, SearchResult, name_arena::NameArena, secrets::Secrets},
+    fs::config::ReposConfig,
+    platform::{ImplPlatform, Platform},
+    scheduler::BackgroundScheduler,
+    url::Url,
+};
+
+/// A repository the user has access to, on GitHub or GitLab.
+#[derive(Debug, Clone)]
+struct Repo {
+    /// "owner/name"
+    full_name: String,
+    /// Web page of the repository, without the scheme
+    web_url: String,
+    host: RepoHost,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RepoHost {
+    GitHub,
+    GitLab,
+}
+
+impl RepoHost {
+    /// Path of `page` relative to the web page of a repository
+    fn page_path(self, page: RepoPage) -> &'static str {
+        match (self, page) {
+            (_, RepoPage::Home) => "",
+            (RepoHost::GitHub, RepoPage::Issues) => "/issues",
+            (RepoHost::GitHub, RepoPage::PullRequests) => "/pulls",
+            (RepoHost::GitLab, RepoPage::Issues) => "/-/issues",
+            (RepoHost::GitLab, RepoPage::PullRequests) => "/-/merge_requests",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RepoPage {
+    Home,
+    Issues,
+    PullRequests,
+}
+
+impl RepoPage {
+    fn from_keyword(keyword: &str) -> Option<Self> {
+        match keyword.to_lowercase().as_str() {
+            "issues" => Some(RepoPage::Issues),
+            "prs" | "pulls" | "mrs" => Some(RepoPage::PullRequests),
+            _ => None,
+        }
+    }
+
+    fn label(self) -> Option<&'static str> {
+        match self {
+            RepoPage::Home => None,
+            RepoPage::Issues => Some("Issues"),
+            RepoPage::PullRequests => Some("Pull requests"),
+        }
+    }
+}
+
+/// The repositories last fetched, with their names ready to be matched
+#[derive(Debug, Default)]
+struct RepoList {
+    repos: Vec<Repo>,
+    /// Lowercased names of `repos`, in the same order
+    names: NameArena,
+}
+
+impl RepoList {
+    fn new(repos: Vec<Repo>) -> Self {
+        let names = NameArena::new(repos.iter().map(|repo| repo.full_name.to_lowercase()));
+        Self { repos, names }
+    }
+}
+
+#[derive(Deserialize)]
+struct GitHubRepo {
+    full_name: String,
+    html_url: String,
+}
+
+#[derive(Deserialize)]
+struct GitLabProject {
+    path_with_namespace: String,
+    web_url: String,
+}
+
+/// Typing `repo <name>` lists the user's repositories matching `<name>`,
+/// and opens the selected one in the browser. Adding `issues` or `prs`
+/// after the name opens that page of the repository instead.
+///
+/// Repositories are fetched with the tokens of the configuration, or the
+/// stored ones (see [`RepoProvider::with_stored_tokens`]), and kept in memory until the
+/// next refresh. Refreshes run on a thread of their own, as fetching every
+/// page can take seconds and would delay the other providers.
+#[derive(Debug)]
+pub struct RepoProvider {
+    repos: Arc<Mutex<RepoList>>,
+}
+
+const KEYWORD: &str = "repo";
+
+/// Extension the tokens are stored as secrets of, as in
+/// `fetch secret set repos github_token`
+const SECRETS_EXTENSION: &str = "repos";
+
+/// Repositories are fetched by pages of this size
+const PAGE_SIZE: usize = 100;
+
+/// Avoids hitting rate limits for users with a lot of repositories
+const MAX_PAGES: usize = 10;
+
+/// Delay before trying again after a failed refresh
+const FAILURE_BACKOFF: Duration = Duration::from_mins(5);
+
+/// How often the refresh thread checks whether a refresh is due
+const REFRESH_CHECK_INTERVAL: Duration = Duration::from_mins(1);
+
+const MAX_RESULTS: usize = 8;
+
+impl RepoProvider {
+    #[must_use]
+    pub fn new(config: ReposConfig, scheduler: Arc<BackgroundScheduler>) -> Self {
+        let repos = Arc::new(Mutex::new(RepoList::default()));
+
+        let weak_repos = Arc::downgrade(&repos);
+        std::thread::spawn(move || refresh_repos(&config, &scheduler, &weak_repos));
+
+        Self { repos }
+    }
+
+    /// The configuration, with the tokens it doesn't set read from the
+    /// `github_token` and `gitlab_token` secrets
+    #[must_use]
+    pub fn with_stored_tokens(mut config: ReposConfig) -> ReposConfig {
+        let secrets = Secrets::new(SECRETS_EXTENSION);
+        config.github_token = config.github_token.or_else(|| secrets.get("github_token"));
+        config.gitlab_token = config.gitlab_token.or_else(|| secrets.get("gitlab_token"));
+
+        config
+    }
+}
+
+/// Refreshes `repos` whenever the scheduler lets it, until the provider
+/// owning them is dropped
+fn refresh_repos(
+    config: &ReposConfig,
+    scheduler: &BackgroundScheduler,
+    repos: &Weak<Mutex<RepoList>>,
+) {
+    let mut next_refresh = None;
+    // Failures are reported once until a refresh succeeds again, rather
+    // than at every retry while offline
+    let mut is_failing = false;
+
+    while repos.strong_count() > 0 {
+        if scheduler.is_due(next_refresh) {
+            let fetched = fetch_repos(config);
+            let Some(repos) = repos.upgrade() else {
+                return;
+            };
+
+            match fetched {
+                Ok(fetched) => {
+                    *repos.lock().expect("no lock poisoning") = RepoList::new(fetched);
+                    is_failing = false;
+                    next_refresh =
+                        Some(Instant::now() + Duration::from_mins(config.refresh_interval_mins));
+                }
+                Err(report) if is_failing => {
+                    eprintln!("{}", report.context("Could still not fetch repositories"));
+                    next_refresh = Some(Instant::now() + FAILURE_BACKOFF);
+                }
+                Err(report) => {
+                    errors::report_error("Could not fetch repositories", report);
+                    is_failing = true;
+                    next_refresh = Some(Instant::now() + FAILURE_BACKOFF);
+                }
+            }
+        }
+
+        std::thread::sleep(REFRESH_CHECK_INTERVAL);
+    }
+}
+
+fn fetch_repos(config: &ReposConfig) -> Result<Vec<Repo>, Report> {
+    let mut repos = Vec::new();
+
+    if let Some(token) = &config.github_token {
+        let authorization = format!("Bearer {token}");
+        let headers = [
+            ("Accept", "application/vnd.github+json"),
+            ("Authorization", authorization.as_str()),
+        ];
+
+        let github_repos: Vec<GitHubRepo> =
+            fetch_pages("https://api.github.com/user/repos?sort=pushed", &headers)?;
+        repos.extend(github_repos.into_iter().filter_map(|repo| {
+            Some(Repo {
+                web_url: repo.html_url.strip_prefix("https://")?.to_owned(),
+                full_name: repo.full_name,
+                host: RepoHost::GitHub,
+            })
+        }));
+    }
+
+    if let Some(token) = &config.gitlab_token {
+        let url = format!(
+            "{}/api/v4/projects?membership=true&order_by=last_activity_at",
+            config.gitlab_url.trim_end_matches('/')
+        );
+        let headers = [("PRIVATE-TOKEN", token.as_str())];
+
+        let gitlab_projects: Vec<GitLabProject> = fetch_pages(&url, &headers)?;
+        repos.extend(gitlab_projects.into_iter().filter_map(|project| {
+            Some(Repo {
+                web_url: project.web_url.strip_prefix("https://")?.to_owned(),
+                full_name: project.path_with_namespace,
+                host: RepoHost::GitLab,
+            })
+        }));
+    }
+
+    Ok(repos)
+}
+
+/// Fetches every page of a paginated API endpoint
+fn fetch_pages<T: DeserializeOwned>(url: &str, headers: &[(&str, &str)]) -> Result<Vec<T>, Report> {
+    let mut items = Vec::new();
+
+    for page in 1..=MAX_PAGES {
+        let body =
+            ImplPlatform::http_get(&format!("{url}&per_page={PAGE_SIZE}&page={page}"), headers)?;
+        let page_items: Vec<T> = serde_json::from_slice(&body)?;
+        let is_last_page = page_items.len() < PAGE_SIZE;
+
+        items.extend(page_items);
+
+        if is_last_page {
+            break;
+        }
+    }
+
+    Ok(items)
+}
+
+/// Lower is better. Names containing the query rank first, then names
+/// containing every char of the query, in order.
+fn fuzzy_score(name: &str, query: &str) -> Option<usize> {
+    if let Some(idx) = name.find(query) {
+        return Some(idx);
+    }
+
+    let mut name_chars = name.char_indices();
+    let mut first_match = None;
+    let mut last_match = 0;
+    for query_char in query.chars() {
+        let (idx, _) = name_chars.find(|(_, c)| *c == query_char)?;
+        first_match.get_or_insert(idx);
+        last_match = idx;
+    }
+
+    // Spread out matches are less likely to be what the user meant
+    Some(name.len() + last_match - first_match.unwrap_or_default())
+}
+
+impl Provider for RepoProvider {
+    fn search(&self, query: &AppString) -> Vec<SearchResult> {
+        let Some(rest) = query
+            .strip_prefix_ignore_case(KEYWORD)
+            .and_then(|rest| rest.strip_prefix(' '))
+        else {
+            return Vec::new();
+        };
+
+        // "repo fetch issues" -> issues of the "fetch" repo
+        let (name_query, page) = match rest.trim().rsplit_once(' ') {
+            Some((name_query, keyword)) => match RepoPage::from_keyword(keyword) {
+                Some(page) => (name_query, page),
+                None => (rest.trim(), RepoPage::Home),
+            },
+            None => (rest.trim(), RepoPage::Home),
+        };
+        let name_query = name_query.to_lowercase();
+
+        let list = self.repos.lock().expect("no lock poisoning");
+        let mut matches: Vec<(usize, &Repo)> = list
+            .names
+            .candidates(&name_query)
+            .into_iter()
+            .filter_map(|idx| {
+                let score = fuzzy_score(list.names.name(idx), &name_query)?;
+                Some((score, &list.repos[idx]))
+            })
+            .collect();
+        matches.sort_by_key(|(score, repo)| (*score, repo.full_name.len()));
+
+        matches
+            .into_iter()
+            .take(MAX_RESULTS)
+            .map(|(_, repo)| SearchResult::Url {
+                name: match page.label() {
+                    Some(label) => format!("{} › {label}", repo.full_name).into(),
+                    None => repo.full_name.as_str().into(),
+                },
+                url: Url::Https(Cow::Owned(format!(
+                    "{}{}",
+                    repo.web_url,
+                    repo.host.page_path(page)
+                ))),
+            })
+            .collect()
+    }
+
+    fn keywords(&self) -> Vec<Keyword> {
+        vec![Keyword {
+            keyword: KEYWORD,
+            description: "Jump to your GitHub and GitLab repositories",
+        }]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use super::*;
+
+    /// Names like `owner-12/service-345`, spread over a few owners
+    fn generated_names(count: usize) -> Vec<String> {
+        const WORDS: [&str; 8] = [
+            "api", "web", "infra", "docs", "mobile", "billing", "search", "auth",
+        ];
+
+        (0..count)
+            .map(|i| {
+                format!(
+                    "{}-{}/{}-{}",
+                    WORDS[i % 3],
+                    i % 97,
+                    WORDS[(i / 7) % WORDS.len()],
+                    i
+                )
+            })
+            .collect()
+    }
+
+    fn scored(
+        names: &NameArena,
+        candidates: impl Iterator<Item = usize>,
+        query: &str,
+    ) -> Vec<usize> {
+        candidates
+            .filter(|idx| fuzzy_score(names.name(*idx), query).is_some())
+            .collect()
+    }
+
+    #[test]
+    fn test_name_arena() {
+        let names = [
+            "hackerbirds/fetch",
+            "",
+            "rust-lang/rust",
+            "zed-industries/zed",
+            "ünïcödé/ñame",
+        ];
+        let arena = NameArena::new(names.iter().map(|name| (*name).to_owned()));
+
+        for (idx, name) in names.iter().enumerate() {
+            assert_eq!(arena.name(idx), *name);
+        }
+        assert_eq!(arena.candidates("fetch"), [0]);
+        assert_eq!(arena.candidates("rust"), [2]);
+        assert_eq!(arena.candidates("zd"), [3]);
+        assert_eq!(arena.candidates("ñ"), [4]);
+        assert_eq!(arena.candidates("r/"), [0, 2, 3]);
+        assert_eq!(arena.candidates(""), [0, 1, 2, 3, 4]);
+        assert!(arena.candidates("xyz").is_empty());
+
+        // Never misses a name the precise scoring matches
+        for query in ["fetch", "h/f", "ee", "tsr", "dé", "é/ñ", "s/"] {
+            assert_eq!(
+                scored(&arena, arena.candidates(query).into_iter(), query),
+                scored(&arena, 0..names.len(), query),
+                "{query}"
+            );
+        }
+    }
+
+    /// Run with `cargo test --release -- --ignored --nocapture bench_`
+    #[test]
+    #[ignore = "benchmark"]
+    fn bench_candidates() {
+        const ROUNDS: u32 = 20;
+
+        let arena = NameArena::new(generated_names(200_000));
+        let queries = ["billing-9", "srch", "mobile-42/auth", "xq", "a"];
+
+        for query in queries {
+            let start = Instant::now();
+            let mut expected = Vec::new();
+            for _ in 0..ROUNDS {
+                expected = scored(&arena, 0..arena.len(), query);
+            }
+            let scan = start.elapsed() / ROUNDS;
+
+            let start = Instant::now();
+            let mut prefiltered = Vec::new();
+            for _ in 0..ROUNDS {
+                prefiltered = scored(&arena, arena.candidates(query).into_iter(), query);
+            }
+            let prefilter = start.elapsed() / ROUNDS;
+
+            assert_eq!(prefiltered, expected);
+            println!(
+                "{query:>16}: {:>7} matches, scoring every name {scan:>10.2?}, \
+                 prefiltered {prefilter:>10.2?}",
+                expected.len()
+            );
+        }
+    }
+}
@@ -0,0 +1,226 @@
+//! Credentials of extensions, such as API keys and access tokens, kept out
+//! of the configuration and the data file. On macOS they are items of the
+//! login keychain, elsewhere they are stored in a file only the user can
+//! read.
+//!
+//! Each extension has its own namespace, so that two of them can store a
+//! secret of the same name: the `api_key` of `weather` is the keychain item
+//! of service `org.birds.fetch.weather` and account `api_key`.
+
+use rootcause::{Report, report};
+
+#[cfg(any(not(target_os = "macos"), test))]
+use std::{collections::BTreeMap, path::PathBuf};
+
+#[cfg(target_os = "macos")]
+use crate::platform::{ImplPlatform, Platform};
+
+/// Subcommand of the executable, as in `fetch secret set weather api_key`
+pub const SECRET_COMMAND: &str = "secret";
+
+/// Prefix of the keychain services secrets are stored under
+const SERVICE_PREFIX: &str = "org.birds.fetch";
+
+/// Secrets of one extension
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Secrets {
+    extension: String,
+}
+
+impl Secrets {
+    #[must_use]
+    pub fn new(extension: &str) -> Self {
+        Self {
+            extension: extension.to_owned(),
+        }
+    }
+
+    fn service(&self) -> String {
+        format!("{SERVICE_PREFIX}.{}", self.extension)
+    }
+
+    /// The secret called `name`, if it was stored
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<String> {
+        #[cfg(target_os = "macos")]
+        {
+            ImplPlatform::keychain_password(&self.service(), name)
+        }
+
+        #[cfg(not(target_os = "macos"))]
+        {
+            SecretsFile::open().ok()?.get(&self.service(), name)
+        }
+    }
+
+    /// Stores a secret, replacing the one of the same name
+    pub fn set(&self, name: &str, value: &str) -> Result<(), Report> {
+        #[cfg(target_os = "macos")]
+        {
+            ImplPlatform::set_keychain_password(&self.service(), name, value)
+        }
+
+        #[cfg(not(target_os = "macos"))]
+        {
+            let mut file = SecretsFile::open()?;
+            file.set(&self.service(), name, value);
+            file.save()
+        }
+    }
+
+    pub fn delete(&self, name: &str) -> Result<(), Report> {
+        #[cfg(target_os = "macos")]
+        {
+            ImplPlatform::delete_keychain_password(&self.service(), name)
+        }
+
+        #[cfg(not(target_os = "macos"))]
+        {
+            let mut file = SecretsFile::open()?;
+            if !file.delete(&self.service(), name) {
+                return Err(report!("No secret {name} for {}", self.extension));
+            }
+            file.save()
+        }
+    }
+}
+
+/// Secrets stored in `secrets.json` of the data directory, where there is
+/// no keychain. Readable by the user only.
+#[cfg(any(not(target_os = "macos"), test))]
+#[derive(Debug)]
+struct SecretsFile {
+    path: PathBuf,
+    /// By service, then by name
+    secrets: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+#[cfg(any(not(target_os = "macos"), test))]
+impl SecretsFile {
+    #[cfg(not(target_os = "macos"))]
+    fn open() -> Result<Self, Report> {
+        let mut path = dirs::data_local_dir()
+            .ok_or_else(|| report!("No data local directory found (are you on a supported OS?)"))?;
+        path.push("Fetch");
+        path.push("secrets.json");
+
+        Self::open_at(path)
+    }
+
+    fn open_at(path: PathBuf) -> Result<Self, Report> {
+        let secrets = match std::fs::read(&path) {
+            Ok(content) => serde_json::from_slice(&content)?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(err) => return Err(report!(err).attach(path.display().to_string()).into()),
+        };
+
+        Ok(Self { path, secrets })
+    }
+
+    fn get(&self, service: &str, name: &str) -> Option<String> {
+        self.secrets.get(service)?.get(name).cloned()
+    }
+
+    fn set(&mut self, service: &str, name: &str, value: &str) {
+        self.secrets
+            .entry(service.to_owned())
+            .or_default()
+            .insert(name.to_owned(), value.to_owned());
+    }
+
+    /// Returns `false` if there was no such secret
+    fn delete(&mut self, service: &str, name: &str) -> bool {
+        let Some(service_secrets) = self.secrets.get_mut(service) else {
+            return false;
+        };
+        let deleted = service_secrets.remove(name).is_some();
+        if service_secrets.is_empty() {
+            self.secrets.remove(service);
+        }
+
+        deleted
+    }
+
+    fn save(&self) -> Result<(), Report> {
+        use std::{fs::OpenOptions, io::Write, os::unix::fs::OpenOptionsExt};
+
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        // Created readable by the user only, rather than restricted after
+        // the secrets were written
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(&self.path)?;
+        file.write_all(&serde_json::to_vec_pretty(&self.secrets)?)?;
+
+        Ok(())
+    }
+}
+
+/// Runs `fetch secret set <extension> <name>`, reading the secret from
+/// stdin so that it doesn't end up in the shell history, or
+/// `fetch secret delete <extension> <name>`
+pub fn run_command(args: &[String]) -> Result<(), Report> {
+    const USAGE: &str = "Usage: fetch secret <set|delete> <extension> <name>";
+
+    let [action, extension, name] = args else {
+        return Err(report!(USAGE).into());
+    };
+    let secrets = Secrets::new(extension);
+
+    match action.as_str() {
+        "set" => {
+            let mut value = String::new();
+            std::io::stdin().read_line(&mut value)?;
+            let value = value.trim_end_matches(['\r', '\n']);
+            if value.is_empty() {
+                return Err(report!("No secret was given on stdin"));
+            }
+
+            secrets.set(name, value)
+        }
+        "delete" => secrets.delete(name),
+        _ => Err(report!(USAGE).into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::fs::PermissionsExt;
+
+    use super::*;
+
+    #[test]
+    fn test_secrets_file() {
+        let dir = std::env::temp_dir().join("fetch-test-secrets");
+        let _ = std::fs::remove_dir_all(&dir);
+        let path = dir.join("secrets.json");
+
+        let mut file = SecretsFile::open_at(path.clone()).unwrap();
+        file.set("org.birds.fetch.weather", "api_key", "abc");
+        file.set("org.birds.fetch.repos", "api_key", "def");
+        file.save().unwrap();
+
+        let mut file = SecretsFile::open_at(path.clone()).unwrap();
+        assert_eq!(
+            file.get("org.birds.fetch.weather", "api_key").as_deref(),
+            Some("abc")
+        );
+        assert_eq!(
+            file.get("org.birds.fetch.repos", "api_key").as_deref(),
+            Some("def")
+        );
+        assert!(file.delete("org.birds.fetch.weather", "api_key"));
+        assert!(!file.delete("org.birds.fetch.weather", "api_key"));
+        assert_eq!(file.get("org.birds.fetch.weather", "api_key"), None);
+
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
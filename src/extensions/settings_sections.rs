@@ -0,0 +1,49 @@
+use crate::{
+    app::AppString,
+    extensions::{Provider, SearchResult},
+    fs::config::SettingsSectionsConfig,
+    normalize::normalize,
+    settings_anchors::{SettingsSection, sections},
+};
+
+/// Queries shorter than this would match most sections
+const MIN_QUERY_LEN: usize = 3;
+
+/// Lists the sections of System Settings panes matching the query, by
+/// name or alias, so that "night shift" opens Displays right where it is.
+/// See [`crate::settings_anchors`].
+#[derive(Debug)]
+pub struct SettingsSectionProvider {
+    sections: Vec<SettingsSection>,
+}
+
+impl SettingsSectionProvider {
+    #[must_use]
+    pub fn new(config: &SettingsSectionsConfig) -> Self {
+        Self {
+            sections: sections(&config.overrides),
+        }
+    }
+}
+
+impl Provider for SettingsSectionProvider {
+    fn search(&self, query: &AppString) -> Vec<SearchResult> {
+        if query.grapheme_len() < MIN_QUERY_LEN {
+            return Vec::new();
+        }
+
+        let query = normalize(query).to_lowercase();
+        let matches = |text: &str| normalize(text).to_lowercase().contains(&query);
+
+        self.sections
+            .iter()
+            .filter(|section| {
+                matches(&section.name) || section.aliases.iter().any(|alias| matches(alias))
+            })
+            .map(|section| SearchResult::Url {
+                name: section.breadcrumb().into(),
+                url: section.url(),
+            })
+            .collect()
+    }
+}
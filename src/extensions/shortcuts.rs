@@ -0,0 +1,101 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app::AppString,
+    errors,
+    extensions::{Provider, SearchContext, SearchResult},
+    platform::{ImplPlatform, Platform},
+    scheduler::BackgroundScheduler,
+};
+
+/// A shortcut from the Shortcuts app, optionally with text to pass as its input.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Hash)]
+pub struct Shortcut {
+    pub(crate) name: AppString,
+    /// Whatever the user typed after the shortcut name
+    pub(crate) input: Option<AppString>,
+}
+
+/// Lists the user's shortcuts from the Shortcuts app. Typing text after
+/// the full name of a shortcut passes that text as the shortcut's input.
+#[derive(Debug)]
+pub struct ShortcutsProvider {
+    shortcuts: Mutex<Vec<AppString>>,
+    last_refresh: Mutex<Option<Instant>>,
+    scheduler: Arc<BackgroundScheduler>,
+}
+
+/// Listing shortcuts spawns a process, so the list is only
+/// refreshed once in a while
+const REFRESH_INTERVAL: Duration = Duration::from_mins(5);
+
+impl ShortcutsProvider {
+    #[must_use]
+    pub fn new(scheduler: Arc<BackgroundScheduler>) -> Self {
+        Self {
+            shortcuts: Mutex::new(Vec::new()),
+            last_refresh: Mutex::new(None),
+            scheduler,
+        }
+    }
+}
+
+impl Provider for ShortcutsProvider {
+    fn preload(&self, _context: &SearchContext) {
+        let mut last_refresh = self.last_refresh.lock().expect("no lock poisoning");
+        if !self
+            .scheduler
+            .is_due(last_refresh.map(|instant| instant + REFRESH_INTERVAL))
+        {
+            return;
+        }
+
+        match ImplPlatform::list_shortcuts() {
+            Ok(shortcuts) => {
+                *self.shortcuts.lock().expect("no lock poisoning") = shortcuts;
+                *last_refresh = Some(Instant::now());
+            }
+            Err(report) => errors::report_error("Could not list shortcuts", report),
+        }
+    }
+
+    fn search(&self, query: &AppString) -> Vec<SearchResult> {
+        if query.is_empty() {
+            return Vec::new();
+        }
+
+        let lowercase_query = query.to_lowercase();
+
+        self.shortcuts
+            .lock()
+            .expect("no lock poisoning")
+            .iter()
+            .filter_map(|name| {
+                let lowercase_name = name.to_lowercase();
+
+                if lowercase_name.contains(&lowercase_query) {
+                    return Some(Shortcut {
+                        name: name.clone(),
+                        input: None,
+                    });
+                }
+
+                // "Translate hello world" -> run "Translate" with "hello world"
+                query
+                    .strip_prefix_ignore_case(name)
+                    .and_then(|rest| rest.strip_prefix(' '))
+                    .filter(|rest| !rest.is_empty())
+                    .map(|rest| Shortcut {
+                        name: name.clone(),
+                        input: Some(rest.into()),
+                    })
+            })
+            .map(SearchResult::Shortcut)
+            .collect()
+    }
+}
@@ -0,0 +1,221 @@
+use std::{
+    cmp::Reverse,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use scc::HashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app::AppString,
+    extensions::{Keyword, Provider, SearchContext, SearchResult},
+};
+
+/// A host to connect to with `ssh`, either an alias from `~/.ssh/config`
+/// or a hostname. Hosts on another port than the default one are named like
+/// in `~/.ssh/known_hosts`, as in `[example.com]:2222`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Hash)]
+pub struct SshHost {
+    pub(crate) name: AppString,
+}
+
+impl SshHost {
+    /// Arguments to pass to `ssh` to connect to the host
+    #[must_use]
+    pub fn ssh_args(&self) -> Vec<String> {
+        let (host, port) = split_port(&self.name);
+
+        let mut args = Vec::new();
+        if let Some(port) = port {
+            args.extend(["-p".to_owned(), port.to_string()]);
+        }
+        // Options end here, whatever the host looks like
+        args.extend(["--".to_owned(), host.to_owned()]);
+
+        args
+    }
+}
+
+/// Typing `ssh <host>` lists the hosts of `~/.ssh/config` and
+/// `~/.ssh/known_hosts` matching `<host>`, most recently used first.
+/// Selecting one opens a terminal connected to it.
+#[derive(Debug)]
+pub struct SshProvider {
+    hosts: Mutex<Vec<AppString>>,
+    /// When each host was last connected to (in seconds since the UNIX epoch)
+    last_used: Arc<HashMap<AppString, u64>>,
+}
+
+const KEYWORD: &str = "ssh";
+
+impl SshProvider {
+    #[must_use]
+    pub fn new(last_used: Arc<HashMap<AppString, u64>>) -> Self {
+        Self {
+            hosts: Mutex::new(Vec::new()),
+            last_used,
+        }
+    }
+}
+
+/// `[example.com]:2222` -> `example.com` and `2222`
+fn split_port(host: &str) -> (&str, Option<u16>) {
+    host.strip_prefix('[')
+        .and_then(|rest| rest.split_once("]:"))
+        .and_then(|(host, port)| Some((host, Some(port.parse().ok()?))))
+        .unwrap_or((host, None))
+}
+
+/// Hosts are passed to a shell, so anything unusual is left out, as are
+/// hosts `ssh` would take for options
+fn is_valid_host(host: &str) -> bool {
+    let (host, _) = split_port(host);
+
+    !host.is_empty()
+        && !host.starts_with('-')
+        && host
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_' | '@' | ':'))
+}
+
+/// Aliases of the `Host` entries, except patterns like `*.example.com`
+fn parse_ssh_config(config: &str) -> impl Iterator<Item = &str> {
+    config
+        .lines()
+        .filter_map(|line| {
+            let (keyword, value) = line.trim().split_once(char::is_whitespace)?;
+            keyword.eq_ignore_ascii_case("host").then_some(value)
+        })
+        .flat_map(str::split_whitespace)
+        .filter(|host| is_valid_host(host))
+}
+
+/// Hostnames of the entries, except hashed ones
+fn parse_known_hosts(known_hosts: &str) -> impl Iterator<Item = &str> {
+    known_hosts
+        .lines()
+        .filter(|line| !line.starts_with('#') && !line.starts_with('@'))
+        .filter_map(|line| line.split_whitespace().next())
+        .flat_map(|hosts| hosts.split(','))
+        .filter(|host| is_valid_host(host))
+}
+
+fn read_hosts(ssh_dir: &Path) -> Vec<AppString> {
+    let config = std::fs::read_to_string(ssh_dir.join("config")).unwrap_or_default();
+    let known_hosts = std::fs::read_to_string(ssh_dir.join("known_hosts")).unwrap_or_default();
+
+    let mut hosts: Vec<AppString> = parse_ssh_config(&config)
+        .chain(parse_known_hosts(&known_hosts))
+        .map(Into::into)
+        .collect();
+    hosts.sort();
+    hosts.dedup();
+
+    hosts
+}
+
+impl Provider for SshProvider {
+    fn preload(&self, _context: &SearchContext) {
+        let Some(home_dir) = dirs::home_dir() else {
+            return;
+        };
+
+        *self.hosts.lock().expect("no lock poisoning") = read_hosts(&home_dir.join(".ssh"));
+    }
+
+    fn search(&self, query: &AppString) -> Vec<SearchResult> {
+        let Some(rest) = query.strip_prefix_ignore_case(KEYWORD) else {
+            return Vec::new();
+        };
+        let host_query = match rest.strip_prefix(' ') {
+            Some(host_query) => host_query.trim(),
+            None if rest.is_empty() => "",
+            // Query only starts with "ssh", like "sshfs"
+            None => return Vec::new(),
+        };
+        let lowercase_query = host_query.to_lowercase();
+
+        let mut hosts: Vec<AppString> = self
+            .hosts
+            .lock()
+            .expect("no lock poisoning")
+            .iter()
+            .filter(|host| host.to_lowercase().contains(&lowercase_query))
+            .cloned()
+            .collect();
+
+        // Connecting to a host that isn't listed anywhere yet
+        if is_valid_host(host_query)
+            && !hosts
+                .iter()
+                .any(|host| host.eq_ignore_ascii_case(host_query))
+        {
+            hosts.push(host_query.into());
+        }
+
+        hosts.sort_by_key(|host| {
+            Reverse(
+                self.last_used
+                    .read_sync(host, |_, last_used| *last_used)
+                    .unwrap_or_default(),
+            )
+        });
+
+        hosts
+            .into_iter()
+            .map(|name| SearchResult::SshHost(SshHost { name }))
+            .collect()
+    }
+
+    fn keywords(&self) -> Vec<Keyword> {
+        vec![Keyword {
+            keyword: KEYWORD,
+            description: "Connect to the hosts of your SSH configuration",
+        }]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ssh_config() {
+        let config = "Host dev staging\n  HostName 10.0.0.1\n\nHost *.internal\nhost pi\n";
+        let hosts: Vec<&str> = parse_ssh_config(config).collect();
+
+        assert_eq!(hosts, ["dev", "staging", "pi"]);
+    }
+
+    #[test]
+    fn test_parse_known_hosts() {
+        let known_hosts = "github.com,140.82.121.4 ssh-ed25519 AAAA\n\
+                           [example.com]:2222 ssh-rsa AAAA\n\
+                           |1|hashed= ssh-rsa AAAA\n";
+        let hosts: Vec<&str> = parse_known_hosts(known_hosts).collect();
+
+        assert_eq!(hosts, ["github.com", "140.82.121.4", "[example.com]:2222"]);
+    }
+
+    #[test]
+    fn test_ssh_args() {
+        let host = |name: &str| SshHost { name: name.into() };
+
+        assert_eq!(host("dev").ssh_args(), ["--", "dev"]);
+        assert_eq!(
+            host("[example.com]:2222").ssh_args(),
+            ["-p", "2222", "--", "example.com"]
+        );
+    }
+
+    #[test]
+    fn test_is_valid_host() {
+        assert!(is_valid_host("me@example.com"));
+        assert!(is_valid_host("[example.com]:2222"));
+        assert!(!is_valid_host("-oProxyCommand=x"));
+        assert!(!is_valid_host("[-oProxyCommand=x]:22"));
+        assert!(!is_valid_host("[example.com]:port"));
+        assert!(!is_valid_host("dev; rm"));
+    }
+}
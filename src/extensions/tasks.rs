@@ -0,0 +1,237 @@
+//! Work providers do in the background for a query, such as asking
+//! Spotlight for files, listed as a progress row until it is done.
+//!
+//! Providers list the row of their running task among their results. Rows
+//! appear and go away by searching again whenever a task starts or ends,
+//! and are updated in place in between: progress is sent over the deferred
+//! channel of the engine, and forwarded to the UI by the daemon. Cancelling
+//! a row flags its task, which gives up at its next step.
+
+use std::{
+    collections::BTreeMap,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+    },
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::{broadcast, watch::Sender};
+
+use crate::{
+    app::AppString,
+    extensions::{DeferredSender, SearchResult},
+};
+
+pub type TaskId = usize;
+
+/// Progress updates kept for receivers lagging behind, which only miss
+/// percentages that were replaced since
+pub const TASK_PROGRESS_CAPACITY: usize = 64;
+
+/// How far along a task is, as shown in its row
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Hash)]
+pub struct TaskProgress {
+    pub id: TaskId,
+    /// What the task does (for instance: "Searching files named report")
+    pub label: AppString,
+    /// From 0 to 100, or `None` when it can't be told, which shows a spinner
+    pub percent: Option<u8>,
+}
+
+#[derive(Debug)]
+struct RunningTask {
+    progress: TaskProgress,
+    cancelled: Arc<AtomicBool>,
+}
+
+/// The tasks running in the engine, shared with its providers
+#[derive(Debug)]
+pub struct Tasks {
+    next_id: AtomicUsize,
+    running: Mutex<BTreeMap<TaskId, RunningTask>>,
+    /// Results of the latest search, whose progress rows are updated in place
+    deferred_watcher: DeferredSender,
+    /// See [`SearchEngine::task_progress`](crate::extensions::SearchEngine::task_progress)
+    progress: broadcast::Sender<TaskProgress>,
+    /// Bumped when a task starts or ends, for its row to be listed or removed
+    results_changed: Arc<Sender<usize>>,
+}
+
+/// A running task, which ends once dropped. Dropping it after storing what
+/// it found lists that instead of its row.
+#[derive(Debug)]
+pub struct Task {
+    tasks: Arc<Tasks>,
+    id: TaskId,
+    cancelled: Arc<AtomicBool>,
+}
+
+/// Replaces the row of the task `progress` is of, if it's in `results`
+pub fn apply_progress(results: &mut [SearchResult], progress: &TaskProgress) {
+    for result in results {
+        if let SearchResult::Progress(row) = result
+            && row.id == progress.id
+        {
+            *row = progress.clone();
+        }
+    }
+}
+
+impl Tasks {
+    #[must_use]
+    pub fn new(deferred_watcher: DeferredSender, results_changed: Arc<Sender<usize>>) -> Self {
+        Self {
+            next_id: AtomicUsize::new(0),
+            running: Mutex::new(BTreeMap::new()),
+            deferred_watcher,
+            progress: broadcast::Sender::new(TASK_PROGRESS_CAPACITY),
+            results_changed,
+        }
+    }
+
+    pub fn start(self: &Arc<Self>, label: impl Into<AppString>) -> Task {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let progress = TaskProgress {
+            id,
+            label: label.into(),
+            percent: None,
+        };
+
+        self.running.lock().expect("no lock poisoning").insert(
+            id,
+            RunningTask {
+                progress,
+                cancelled: cancelled.clone(),
+            },
+        );
+        self.results_changed.send_modify(|count| *count += 1);
+
+        Task {
+            tasks: self.clone(),
+            id,
+            cancelled,
+        }
+    }
+
+    /// The row of a task, until it ends or is cancelled
+    #[must_use]
+    pub fn progress(&self, id: TaskId) -> Option<TaskProgress> {
+        self.running
+            .lock()
+            .expect("no lock poisoning")
+            .get(&id)
+            .map(|task| task.progress.clone())
+    }
+
+    /// Removes the row of a task right away, and tells the task to stop
+    pub fn cancel(&self, id: TaskId) {
+        let task = self.running.lock().expect("no lock poisoning").remove(&id);
+
+        if let Some(task) = task {
+            task.cancelled.store(true, Ordering::Release);
+            self.results_changed.send_modify(|count| *count += 1);
+        }
+    }
+
+    #[must_use]
+    pub fn subscribe(&self) -> broadcast::Receiver<TaskProgress> {
+        self.progress.subscribe()
+    }
+
+    fn publish(&self, progress: TaskProgress) {
+        self.deferred_watcher
+            .send_modify(|(_, results)| apply_progress(results, &progress));
+        // Only the daemon listens
+        let _ = self.progress.send(progress);
+    }
+}
+
+impl Task {
+    #[must_use]
+    pub fn id(&self) -> TaskId {
+        self.id
+    }
+
+    pub fn set_percent(&self, percent: u8) {
+        let progress = {
+            let mut running = self.tasks.running.lock().expect("no lock poisoning");
+            let Some(task) = running.get_mut(&self.id) else {
+                return;
+            };
+            task.progress.percent = Some(percent.min(100));
+            task.progress.clone()
+        };
+
+        self.tasks.publish(progress);
+    }
+
+    /// Whether the row was cancelled, in which case the task should give
+    /// up and keep nothing of what it found
+    #[must_use]
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Acquire)
+    }
+}
+
+impl Drop for Task {
+    fn drop(&mut self) {
+        let removed = self
+            .tasks
+            .running
+            .lock()
+            .expect("no lock poisoning")
+            .remove(&self.id);
+
+        // Cancelled tasks were already removed
+        if removed.is_some() {
+            self.tasks.results_changed.send_modify(|count| *count += 1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::watch::channel;
+
+    use super::*;
+
+    #[test]
+    fn test_task_lifecycle() {
+        let (deferred_watcher, deferred_rx) = channel((0, Vec::new()));
+        let results_changed = Arc::new(Sender::new(0));
+        let tasks = Arc::new(Tasks::new(
+            deferred_watcher.clone(),
+            results_changed.clone(),
+        ));
+
+        let task = tasks.start("Searching files");
+        assert_eq!(*results_changed.borrow(), 1);
+        let row = tasks.progress(task.id()).unwrap();
+        assert_eq!(row.percent, None);
+
+        // The row of the latest results is updated in place
+        deferred_watcher.send_replace((3, vec![SearchResult::Progress(row)]));
+        task.set_percent(40);
+        assert_eq!(
+            deferred_rx.borrow().1,
+            [SearchResult::Progress(TaskProgress {
+                id: task.id(),
+                label: "Searching files".into(),
+                percent: Some(40),
+            })]
+        );
+
+        tasks.cancel(task.id());
+        assert!(task.is_cancelled());
+        assert_eq!(tasks.progress(task.id()), None);
+        assert_eq!(*results_changed.borrow(), 2);
+
+        // Dropping a cancelled task doesn't search again
+        drop(task);
+        assert_eq!(*results_changed.borrow(), 2);
+        drop(tasks.start("Searching files"));
+        assert_eq!(*results_changed.borrow(), 4);
+    }
+}
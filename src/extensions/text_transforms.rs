@@ -0,0 +1,201 @@
+use std::{fmt::Write, sync::Mutex};
+
+use crate::{
+    app::AppString,
+    extensions::{Keyword, Provider, SearchContext, SearchResult},
+    platform::{ImplPlatform, Platform},
+};
+
+/// Typing `text <something>` transforms `<something>`, and typing `text` alone
+/// transforms the contents of the clipboard. Every transform is listed as
+/// a result, whose output is copied when selected.
+#[derive(Debug, Default)]
+pub struct TextTransformProvider {
+    /// Clipboard contents when the search window was opened
+    clipboard: Mutex<Option<String>>,
+}
+
+const KEYWORD: &str = "text";
+
+type Transform = fn(&str) -> Option<String>;
+
+const TRANSFORMS: &[(&str, Transform)] = &[
+    ("Uppercase", |text| Some(text.to_uppercase())),
+    ("Lowercase", |text| Some(text.to_lowercase())),
+    ("Slug", |text| Some(slugify(text))),
+    ("Base64 encoded", |text| {
+        Some(base64_encode(text.as_bytes()))
+    }),
+    ("Base64 decoded", |text| {
+        base64_decode(text).and_then(|bytes| String::from_utf8(bytes).ok())
+    }),
+    ("URL encoded", |text| Some(url_encode(text))),
+    ("URL decoded", url_decode),
+    ("Pretty-printed JSON", |text| {
+        let json: serde_json::Value = serde_json::from_str(text).ok()?;
+        serde_json::to_string_pretty(&json).ok()
+    }),
+];
+
+impl Provider for TextTransformProvider {
+    fn preload(&self, _context: &SearchContext) {
+        *self.clipboard.lock().expect("no lock poisoning") = ImplPlatform::read_clipboard();
+    }
+
+    fn search(&self, query: &AppString) -> Vec<SearchResult> {
+        let Some(rest) = query.strip_prefix_ignore_case(KEYWORD) else {
+            return Vec::new();
+        };
+
+        let input = if rest.is_empty() {
+            let clipboard = self.clipboard.lock().expect("no lock poisoning");
+            match clipboard.as_ref() {
+                Some(clipboard) if !clipboard.is_empty() => clipboard.clone(),
+                _ => return Vec::new(),
+            }
+        } else if let Some(text) = rest.strip_prefix(' ') {
+            text.to_owned()
+        } else {
+            // Query only starts with "text", like "textedit"
+            return Vec::new();
+        };
+
+        TRANSFORMS
+            .iter()
+            .filter_map(|(label, transform)| {
+                let output = transform(&input).filter(|output| *output != input)?;
+
+                Some(SearchResult::Text {
+                    text: output.into(),
+                    label: (*label).into(),
+                })
+            })
+            .collect()
+    }
+
+    fn keywords(&self) -> Vec<Keyword> {
+        vec![Keyword {
+            keyword: KEYWORD,
+            description: "Transform the text typed after it, or the clipboard",
+        }]
+    }
+}
+
+/// "Hello, World!" -> "hello-world"
+fn slugify(text: &str) -> String {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn base64_encode(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, byte)| n | (u32::from(*byte) << (16 - 8 * i)));
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                let sextet = (n >> (18 - 6 * i)) & 0b11_1111;
+                encoded.push(char::from(BASE64_ALPHABET[sextet as usize]));
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+
+    encoded
+}
+
+fn base64_decode(text: &str) -> Option<Vec<u8>> {
+    let text = text.trim().trim_end_matches('=');
+    let mut bytes = Vec::with_capacity(text.len() * 3 / 4);
+    let mut n = 0u32;
+    let mut bits = 0;
+
+    for c in text.bytes() {
+        let sextet = BASE64_ALPHABET.iter().position(|b| *b == c)?;
+        n = (n << 6) | u32::try_from(sextet).ok()?;
+        bits += 6;
+
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push(u8::try_from((n >> bits) & 0xFF).ok()?);
+        }
+    }
+
+    Some(bytes)
+}
+
+/// Percent-encodes everything but unreserved characters (RFC 3986)
+pub(crate) fn url_encode(text: &str) -> String {
+    let mut encoded = String::with_capacity(text.len());
+
+    for byte in text.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'.' | b'~') {
+            encoded.push(char::from(byte));
+        } else {
+            let _ = write!(encoded, "%{byte:02X}");
+        }
+    }
+
+    encoded
+}
+
+fn url_decode(text: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut chars = text.bytes();
+
+    while let Some(byte) = chars.next() {
+        match byte {
+            b'%' => {
+                let hex = [chars.next()?, chars.next()?];
+                bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+            }
+            b'+' => bytes.push(b' '),
+            _ => bytes.push(byte),
+        }
+    }
+
+    String::from_utf8(bytes).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slugify() {
+        assert_eq!(slugify("Hello, World!"), "hello-world");
+        assert_eq!(slugify("  Fetch  app launcher "), "fetch-app-launcher");
+    }
+
+    #[test]
+    fn test_base64() {
+        assert_eq!(base64_encode(b"f"), "Zg==");
+        assert_eq!(base64_encode(b"fo"), "Zm8=");
+        assert_eq!(base64_encode(b"foo"), "Zm9v");
+        assert_eq!(base64_encode(b"foobar"), "Zm9vYmFy");
+
+        assert_eq!(base64_decode("Zg==").as_deref(), Some(&b"f"[..]));
+        assert_eq!(base64_decode("Zm8=").as_deref(), Some(&b"fo"[..]));
+        assert_eq!(base64_decode("Zm9vYmFy").as_deref(), Some(&b"foobar"[..]));
+        assert_eq!(base64_decode("not base64!"), None);
+    }
+
+    #[test]
+    fn test_url_encoding() {
+        assert_eq!(url_encode("a b&c=é"), "a%20b%26c%3D%C3%A9");
+        assert_eq!(url_decode("a%20b%26c%3D%C3%A9").as_deref(), Some("a b&c=é"));
+        assert_eq!(url_decode("a+b").as_deref(), Some("a b"));
+        assert_eq!(url_decode("%4"), None);
+    }
+}
@@ -0,0 +1,418 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, OnceLock},
+    time::{Duration, Instant},
+};
+
+use rootcause::{Report, report};
+use serde::Deserialize;
+use tokio::sync::watch::Sender;
+
+use crate::{
+    app::AppString,
+    extensions::{Keyword, Provider, SearchResult, secrets::Secrets, text_transforms::url_encode},
+    fs::config::{TemperatureUnit, WeatherConfig},
+    locale::Date,
+    platform::{ImplPlatform, Platform},
+};
+
+/// Typing `weather <place>` shows the current conditions of the place, and
+/// a glance at the next 3 days.
+///
+/// Forecasts are fetched in the background, once typing paused, and the
+/// query is searched again when they arrive. They are kept for
+/// `cache_mins`, and shown for longer (with their age) while they can't be
+/// fetched again, such as when offline.
+#[derive(Debug)]
+pub struct WeatherProvider {
+    state: Arc<WeatherState>,
+}
+
+#[derive(Debug)]
+struct WeatherState {
+    config: WeatherConfig,
+    /// Read from the configuration or the keychain on the first fetch
+    api_key: OnceLock<Option<String>>,
+    /// By lowercase place
+    forecasts: Mutex<HashMap<String, CachedForecast>>,
+    /// Place the latest fetch was requested for, until it is done
+    pending: Mutex<Option<String>>,
+    results_changed: Arc<Sender<usize>>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct CachedForecast {
+    forecast: Option<(Forecast, Instant)>,
+    /// Why the latest fetch failed, if it did
+    error: Option<(String, Instant)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Forecast {
+    /// "Tokyo, Japan"
+    place: String,
+    temperature: f64,
+    condition: String,
+    days: Vec<DayForecast>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct DayForecast {
+    date: Option<Date>,
+    high: f64,
+    low: f64,
+    condition: String,
+}
+
+const KEYWORD: &str = "weather";
+
+/// Name of the secret the API key is read from, when not configured
+const API_KEY_SECRET: &str = "api_key";
+
+/// Places are only fetched once typing paused for this long, rather than
+/// for every prefix of their name
+const FETCH_DELAY: Duration = Duration::from_millis(400);
+
+/// Delay before trying again after a failed fetch
+const FAILURE_BACKOFF: Duration = Duration::from_mins(1);
+
+/// Forecasts of this many places are kept
+const MAX_CACHED_PLACES: usize = 32;
+
+const WEEKDAYS: [&str; 7] = [
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+    "Sunday",
+];
+
+#[derive(Deserialize)]
+struct ForecastResponse {
+    location: LocationResponse,
+    current: CurrentResponse,
+    forecast: DaysResponse,
+}
+
+#[derive(Deserialize)]
+struct LocationResponse {
+    name: String,
+    country: String,
+}
+
+#[derive(Deserialize)]
+struct CurrentResponse {
+    temp_c: f64,
+    temp_f: f64,
+    condition: ConditionResponse,
+}
+
+#[derive(Deserialize)]
+struct ConditionResponse {
+    text: String,
+}
+
+#[derive(Deserialize)]
+struct DaysResponse {
+    forecastday: Vec<ForecastDayResponse>,
+}
+
+#[derive(Deserialize)]
+struct ForecastDayResponse {
+    date: String,
+    day: DayResponse,
+}
+
+#[derive(Deserialize)]
+struct DayResponse {
+    maxtemp_c: f64,
+    maxtemp_f: f64,
+    mintemp_c: f64,
+    mintemp_f: f64,
+    condition: ConditionResponse,
+}
+
+impl Forecast {
+    fn from_response(response: ForecastResponse, units: TemperatureUnit) -> Self {
+        let pick = |celsius: f64, fahrenheit: f64| match units {
+            TemperatureUnit::Celsius => celsius,
+            TemperatureUnit::Fahrenheit => fahrenheit,
+        };
+
+        Self {
+            place: format!("{}, {}", response.location.name, response.location.country),
+            temperature: pick(response.current.temp_c, response.current.temp_f),
+            condition: response.current.condition.text,
+            days: response
+                .forecast
+                .forecastday
+                .into_iter()
+                .map(|day| DayForecast {
+                    date: parse_date(&day.date),
+                    high: pick(day.day.maxtemp_c, day.day.maxtemp_f),
+                    low: pick(day.day.mintemp_c, day.day.mintemp_f),
+                    condition: day.day.condition.text,
+                })
+                .collect(),
+        }
+    }
+
+    /// The current conditions, then one row per day. `age` is set when the
+    /// forecast is older than it should be.
+    fn results(&self, units: TemperatureUnit, age: Option<Duration>) -> Vec<SearchResult> {
+        let unit = match units {
+            TemperatureUnit::Celsius => "°C",
+            TemperatureUnit::Fahrenheit => "°F",
+        };
+        let age = age.map(|age| format!(", from {}", format_age(age)));
+        let age = age.as_deref().unwrap_or_default();
+
+        let mut results = vec![SearchResult::Text {
+            text: format!("{:.0}{unit}, {}", self.temperature, self.condition).into(),
+            label: format!("Now in {}{age}", self.place).into(),
+        }];
+        results.extend(self.days.iter().enumerate().map(|(idx, day)| {
+            let day_name = if idx == 0 {
+                "Today"
+            } else {
+                day.date
+                    .and_then(|date| WEEKDAYS.get(usize::try_from(date.weekday()).ok()?))
+                    .copied()
+                    .unwrap_or("Later")
+            };
+            SearchResult::Text {
+                text: format!("{:.0}° / {:.0}{unit}, {}", day.high, day.low, day.condition).into(),
+                label: format!("{day_name} in {}{age}", self.place).into(),
+            }
+        }));
+
+        results
+    }
+}
+
+/// Reads dates written as `2025-04-03`
+fn parse_date(text: &str) -> Option<Date> {
+    let mut parts = text.splitn(3, '-');
+    let year = parts.next()?.parse().ok()?;
+    let month = parts.next()?.parse().ok()?;
+    let day = parts.next()?.parse().ok()?;
+
+    Date::new(year, month, day)
+}
+
+fn format_age(age: Duration) -> String {
+    let minutes = age.as_secs() / 60;
+    if minutes < 60 {
+        format!("{minutes} min ago")
+    } else {
+        format!("{} h ago", minutes / 60)
+    }
+}
+
+impl WeatherState {
+    fn api_key(&self) -> Option<&str> {
+        self.api_key
+            .get_or_init(|| {
+                self.config
+                    .api_key
+                    .clone()
+                    .or_else(|| Secrets::new(KEYWORD).get(API_KEY_SECRET))
+            })
+            .as_deref()
+    }
+
+    fn fetch(&self, place: &str) -> Result<Forecast, Report> {
+        let api_key = self
+            .api_key()
+            .ok_or_else(|| report!("No API key, add one to the configuration or the secrets"))?;
+        let url = self
+            .config
+            .endpoint
+            .replace("{key}", &url_encode(api_key))
+            .replace("{location}", &url_encode(place));
+
+        let body = ImplPlatform::http_get(&url, &[])?;
+        let response: ForecastResponse = serde_json::from_slice(&body)?;
+
+        Ok(Forecast::from_response(response, self.config.units))
+    }
+
+    /// Fetches the forecast of `place` in the background, unless another
+    /// place is requested before [`FETCH_DELAY`]
+    fn request(self: &Arc<Self>, place: String) {
+        {
+            let mut pending = self.pending.lock().expect("no lock poisoning");
+            if pending.as_ref() == Some(&place) {
+                return;
+            }
+            *pending = Some(place.clone());
+        }
+
+        let state = self.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(FETCH_DELAY);
+            if state.pending.lock().expect("no lock poisoning").as_ref() != Some(&place) {
+                return;
+            }
+
+            let result = state.fetch(&place);
+            {
+                let mut forecasts = state.forecasts.lock().expect("no lock poisoning");
+                if !forecasts.contains_key(&place) && forecasts.len() >= MAX_CACHED_PLACES {
+                    // Simpler than tracking which place was looked up last,
+                    // for a limit few reach
+                    forecasts.clear();
+                }
+                let cached = forecasts.entry(place.clone()).or_default();
+                match result {
+                    Ok(forecast) => {
+                        *cached = CachedForecast {
+                            forecast: Some((forecast, Instant::now())),
+                            error: None,
+                        }
+                    }
+                    Err(report) => {
+                        // Kept to themselves, as places are looked up while
+                        // being typed, and being offline is expected
+                        let reason = report.to_string();
+                        let reason = reason.lines().next().unwrap_or_default().to_owned();
+                        cached.error = Some((reason, Instant::now()));
+                        eprintln!(
+                            "{}",
+                            report.context(format!("Could not fetch the weather in {place}"))
+                        );
+                    }
+                }
+            }
+
+            let mut pending = state.pending.lock().expect("no lock poisoning");
+            if pending.as_ref() == Some(&place) {
+                *pending = None;
+            }
+            drop(pending);
+            state.results_changed.send_modify(|count| *count += 1);
+        });
+    }
+}
+
+impl WeatherProvider {
+    #[must_use]
+    pub fn new(config: WeatherConfig, results_changed: Arc<Sender<usize>>) -> Self {
+        Self {
+            state: Arc::new(WeatherState {
+                config,
+                api_key: OnceLock::new(),
+                forecasts: Mutex::new(HashMap::new()),
+                pending: Mutex::new(None),
+                results_changed,
+            }),
+        }
+    }
+}
+
+impl Provider for WeatherProvider {
+    fn search(&self, query: &AppString) -> Vec<SearchResult> {
+        let Some(rest) = query.strip_prefix_ignore_case(KEYWORD) else {
+            return Vec::new();
+        };
+        let place = match rest.strip_prefix(' ').map(str::trim) {
+            Some(place) if !place.is_empty() => place.to_owned(),
+            _ if rest.trim().is_empty() => match &self.state.config.default_location {
+                Some(place) => place.clone(),
+                None => return Vec::new(),
+            },
+            _ => return Vec::new(),
+        };
+        let key = place.to_lowercase();
+
+        let cached = self
+            .state
+            .forecasts
+            .lock()
+            .expect("no lock poisoning")
+            .get(&key)
+            .cloned()
+            .unwrap_or_default();
+        let max_age = Duration::from_mins(self.state.config.cache_mins);
+        let is_fresh = cached
+            .forecast
+            .as_ref()
+            .is_some_and(|(_, fetched_at)| fetched_at.elapsed() < max_age);
+        let failed_recently = cached
+            .error
+            .as_ref()
+            .is_some_and(|(_, failed_at)| failed_at.elapsed() < FAILURE_BACKOFF);
+        if !is_fresh && !failed_recently {
+            self.state.request(key);
+        }
+
+        let units = self.state.config.units;
+        match (cached.forecast, cached.error) {
+            (Some((forecast, fetched_at)), _) => {
+                let age = (!is_fresh).then(|| fetched_at.elapsed());
+                forecast.results(units, age)
+            }
+            (None, Some((reason, _))) => vec![SearchResult::Text {
+                text: format!("No weather for {place}").into(),
+                label: reason.into(),
+            }],
+            (None, None) => vec![SearchResult::Text {
+                text: format!("Getting the weather in {place}…").into(),
+                label: "Weather".into(),
+            }],
+        }
+    }
+
+    fn keywords(&self) -> Vec<Keyword> {
+        vec![Keyword {
+            keyword: KEYWORD,
+            description: "Show the weather of a place",
+        }]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RESPONSE: &str = r#"{
+        "location": {"name": "Tokyo", "country": "Japan"},
+        "current": {"temp_c": 18.4, "temp_f": 65.1, "condition": {"text": "Partly cloudy"}},
+        "forecast": {"forecastday": [
+            {"date": "2025-04-03", "day": {"maxtemp_c": 21.0, "maxtemp_f": 69.8,
+                "mintemp_c": 12.6, "mintemp_f": 54.7, "condition": {"text": "Sunny"}}},
+            {"date": "2025-04-04", "day": {"maxtemp_c": 17.2, "maxtemp_f": 63.0,
+                "mintemp_c": 11.0, "mintemp_f": 51.8, "condition": {"text": "Light rain"}}}
+        ]}
+    }"#;
+
+    #[test]
+    fn test_forecast_results() {
+        let response: ForecastResponse = serde_json::from_str(RESPONSE).unwrap();
+        let forecast = Forecast::from_response(response, TemperatureUnit::Celsius);
+
+        let rows: Vec<(String, String)> = forecast
+            .results(TemperatureUnit::Celsius, Some(Duration::from_hours(2)))
+            .iter()
+            .map(|result| match result {
+                SearchResult::Text { text, label } => (text.to_string(), label.to_string()),
+                _ => unreachable!(),
+            })
+            .collect();
+
+        assert_eq!(
+            rows,
+            [
+                ("18°C, Partly cloudy", "Now in Tokyo, Japan, from 2 h ago"),
+                ("21° / 13°C, Sunny", "Today in Tokyo, Japan, from 2 h ago"),
+                (
+                    "17° / 11°C, Light rain",
+                    "Friday in Tokyo, Japan, from 2 h ago"
+                ),
+            ]
+            .map(|(text, label)| (text.to_owned(), label.to_owned()))
+        );
+    }
+}
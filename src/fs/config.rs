@@ -1,4 +1,5 @@
 use std::{
+    collections::BTreeMap,
     fs::File,
     io::{ErrorKind, Read, Write},
     path::{Path, PathBuf},
@@ -10,7 +11,10 @@ use gpui::Keystroke;
 use rootcause::{Report, prelude::ResultExt, report};
 use serde::{Deserialize, Serialize};
 
-use crate::platform::{ImplPlatform, Platform};
+use crate::{
+    command::{CommandEntry, MatcherEntry},
+    platform::{ImplPlatform, Platform},
+};
 
 const DEFAULT_HOTKEY: &str = "alt-space";
 const CONFIG_FILE_NAME: &str = "config.toml";
@@ -19,10 +23,756 @@ const CONFIG_FILE_NAME: &str = "config.toml";
 #[serde(default)]
 pub struct Configuration {
     pub open_search_hotkey: HotkeyString,
+    /// What pressing the hotkey twice in a row does
+    pub hotkey_double_press: DoublePressConfig,
+    /// When pressing the hotkey does nothing
+    pub hotkey_exclusions: HotkeyExclusionsConfig,
     pub launch_on_boot: bool,
     pub prioritize_open_apps: bool,
+    /// How running apps are listed
+    pub open_apps: OpenAppsConfig,
+    /// Search the menu items of the frontmost app (requires the Accessibility permission)
+    pub search_menu_items: bool,
+    /// Let providers know which files are selected in Finder, when it is
+    /// the frontmost app (requires the Automation permission for Finder)
+    pub finder_selection: FinderSelectionConfig,
+    /// Search the shortcuts created in the Shortcuts app
+    pub shortcuts: ShortcutsConfig,
+    /// Open sections of System Settings panes directly, such as Night Shift
+    pub settings_sections: SettingsSectionsConfig,
+    /// Transform text typed after `text`, or the clipboard contents
+    pub text_transforms: TextTransformsConfig,
+    /// Generate UUIDs, passwords and placeholder text
+    pub generators: GeneratorsConfig,
+    /// Compute dates and durations typed after `date`
+    pub dates: DatesConfig,
+    /// Compose emails by typing `mail`
+    pub mail: MailConfig,
+    /// Search files by typing `file`
+    pub files: FilesConfig,
+    /// Jump to GitHub and GitLab repositories by typing `repo`
+    pub repos: ReposConfig,
+    /// Show the weather of a place by typing `weather`
+    pub weather: WeatherConfig,
+    /// List SSH hosts by typing `ssh`
+    pub ssh: SshConfig,
+    /// Terminal app in which SSH connections and projects are opened
+    pub terminal: Terminal,
+    /// What opening a folder, a file or a web link does
+    pub open_behavior: OpenBehaviorConfig,
+    /// Open git repositories by typing `proj`
+    pub projects: ProjectsConfig,
+    pub layout_mode: LayoutMode,
+    pub window: WindowConfig,
+    pub sort_mode: SortMode,
+    /// How apps are ranked, read when Fetch starts
+    pub search_engine: SearchEngineKind,
+    /// Queries kept after the search window closes
+    pub query_history: QueryHistoryConfig,
+    /// Locale numbers and dates are read in (for instance: `"de_DE"`),
+    /// instead of the one of macOS. Its language gives provider keywords
+    /// aliases, such as `datei` for `file` in German.
+    pub locale: Option<String>,
+    /// Other words for the keywords of providers, by word, as in
+    /// `f = "file"`. They replace the aliases of the locale.
+    pub keyword_aliases: BTreeMap<String, String>,
+    pub theme: ThemeConfig,
+    pub search_debounce: DebounceConfig,
+    /// Memory the caches may use, type `stats` to see how much they use
+    pub cache_budgets: CacheBudgetsConfig,
+    /// How background work behaves on battery or in Low Power Mode
+    pub power: PowerConfig,
+    /// Looking for new versions of Fetch, see [`crate::updater`]
+    pub updates: UpdatesConfig,
+    /// How many apps are read at once when building the index
+    pub indexing_threads: usize,
     pub applications: Vec<String>,
     pub application_dirs: Vec<String>,
+    /// Apps installed on external drives
+    pub external_volumes: ExternalVolumesConfig,
+    pub commands: Vec<CommandEntry>,
+    pub matchers: Vec<MatcherEntry>,
+    /// Abbreviations expanded in the search bar, see [`crate::gui::text_macros`]
+    pub text_macros: Vec<TextMacroEntry>,
+}
+
+/// How much room the search window takes up on screen
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LayoutMode {
+    /// Only the input is shown until there are results to display
+    Compact,
+    /// The results area is always shown, even when empty
+    #[default]
+    Expanded,
+}
+
+/// Shortcuts of the Shortcuts app, run by typing their name
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShortcutsConfig {
+    pub enabled: bool,
+}
+
+impl Default for ShortcutsConfig {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+/// Transformations of text typed after `text`, such as `text upper`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TextTransformsConfig {
+    pub enabled: bool,
+}
+
+impl Default for TextTransformsConfig {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+/// Dates and durations typed after `date`, such as `date today + 45 days`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DatesConfig {
+    pub enabled: bool,
+}
+
+impl Default for DatesConfig {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+/// Hosts of `~/.ssh/config` and `~/.ssh/known_hosts`, listed by typing `ssh`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SshConfig {
+    pub enabled: bool,
+}
+
+impl Default for SshConfig {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+/// The search engine ranking apps. Engines other than the deterministic
+/// one are experimental, and only in builds with their feature.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchEngineKind {
+    /// See [`crate::extensions::deterministic_search::DeterministicSearchEngine`]
+    #[default]
+    Deterministic,
+    /// Fuzzy matches first, in builds with the `fuzzy-engine` feature
+    FuzzyFirst,
+}
+
+/// Order of results which rank the same, and of running apps when
+/// nothing is typed. `Cmd+S` cycles through modes until the window closes.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortMode {
+    #[default]
+    Alphabetical,
+    /// Most recently opened from Fetch first
+    Recent,
+    /// Most often opened from Fetch first
+    Frequent,
+}
+
+impl SortMode {
+    /// The mode `Cmd+S` switches to
+    #[must_use]
+    pub fn next(self) -> Self {
+        match self {
+            SortMode::Alphabetical => SortMode::Recent,
+            SortMode::Recent => SortMode::Frequent,
+            SortMode::Frequent => SortMode::Alphabetical,
+        }
+    }
+
+    #[must_use]
+    pub fn display_name(self) -> &'static str {
+        match self {
+            SortMode::Alphabetical => "alphabetically",
+            SortMode::Recent => "by most recently used",
+            SortMode::Frequent => "by most frequently used",
+        }
+    }
+}
+
+/// Appearance of the search window, which follows the light or dark
+/// appearance of macOS
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ThemeConfig {
+    pub icon_style: IconStyle,
+}
+
+/// How icons of results are drawn
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IconStyle {
+    /// App icons in their own colors, provider icons in grey
+    #[default]
+    Color,
+    /// Every icon tinted with the text color of the theme, so that they
+    /// are light in dark mode and dark in light mode
+    Monochrome,
+}
+
+/// Running apps, which are usually what the user is looking for
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OpenAppsConfig {
+    /// How many places a running app moves up in the results, when
+    /// `prioritize_open_apps` is set. Without a limit, running apps come first.
+    pub boost: Option<usize>,
+    /// List running apps in their own section when the query is empty or
+    /// a single letter, since switching to one is the most common search
+    pub group: bool,
+}
+
+/// Files selected in Finder, which providers such as `with` act on
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FinderSelectionConfig {
+    pub enabled: bool,
+}
+
+/// A terminal app Fetch can run commands in
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Terminal {
+    /// Terminal.app
+    #[default]
+    Terminal,
+    Iterm,
+    Kitty,
+}
+
+/// What opening a result does, by the type of what it points to. Apps
+/// always launch. Holding `Option` while opening a result does the other
+/// thing for its type, see [`crate::launch::OpenAction`].
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OpenBehaviorConfig {
+    pub folders: FolderBehavior,
+    pub files: FileBehavior,
+    /// Bundle identifier of the browser web links open in (for instance:
+    /// "org.mozilla.firefox"), instead of the default browser. Commands
+    /// can pick their own.
+    pub browser: Option<String>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FolderBehavior {
+    /// Opened in a Finder window
+    #[default]
+    Finder,
+    /// Opened in a new window of the terminal app of the configuration
+    Terminal,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileBehavior {
+    /// Opened with the default app for its type
+    #[default]
+    Open,
+    /// Shown in Finder, selected in its folder
+    Reveal,
+}
+
+/// Where `proj` looks for git repositories, and what opens them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProjectsConfig {
+    /// Directories containing git repositories, up to a few levels deep
+    pub roots: Vec<String>,
+    pub open_with: ProjectTarget,
+}
+
+impl Default for ProjectsConfig {
+    fn default() -> Self {
+        Self {
+            roots: vec![
+                String::from("~/dev"),
+                String::from("~/Developer"),
+                String::from("~/Projects"),
+            ],
+            open_with: ProjectTarget::default(),
+        }
+    }
+}
+
+/// An app a project can be opened with
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProjectTarget {
+    #[default]
+    VsCode,
+    Zed,
+    Intellij,
+    /// The terminal app of the configuration
+    Terminal,
+    Finder,
+}
+
+impl ProjectTarget {
+    pub const ALL: [ProjectTarget; 5] = [
+        ProjectTarget::VsCode,
+        ProjectTarget::Zed,
+        ProjectTarget::Intellij,
+        ProjectTarget::Terminal,
+        ProjectTarget::Finder,
+    ];
+
+    #[must_use]
+    pub fn display_name(self) -> &'static str {
+        match self {
+            ProjectTarget::VsCode => "VS Code",
+            ProjectTarget::Zed => "Zed",
+            ProjectTarget::Intellij => "IntelliJ IDEA",
+            ProjectTarget::Terminal => "Terminal",
+            ProjectTarget::Finder => "Finder",
+        }
+    }
+}
+
+/// Sections of System Settings panes, see [`crate::settings_anchors`]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SettingsSectionsConfig {
+    pub enabled: bool,
+    /// Replace the built-in sections of the same name, or add sections
+    pub overrides: Vec<SettingsSectionEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SettingsSectionEntry {
+    /// As shown in System Settings, as in "Night Shift"
+    pub name: String,
+    /// Name of the pane the section is in, as in "Displays"
+    #[serde(default)]
+    pub pane_name: String,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    /// Identifier of the pane, as in `com.apple.preference.displays`.
+    /// Without one, the built-in section of the same name is removed.
+    pub pane: Option<String>,
+    /// Identifier of the section in the pane, as in `displaysNightShiftTab`
+    pub anchor: Option<String>,
+}
+
+/// ```toml
+/// [[text_macros]]
+/// abbreviation = "@@gh"
+/// expansion = "repo "
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TextMacroEntry {
+    pub abbreviation: String,
+    /// Replaces the abbreviation, with the cursor placed at `{cursor}`,
+    /// or at the end if there is none
+    pub expansion: String,
+}
+
+/// Pressing the hotkey twice in a row opens the search window in an
+/// alternate mode, with `query` already typed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DoublePressConfig {
+    /// Typed into the search window on a double press, such as `proj ` to
+    /// list projects. Without it, every press opens a search window.
+    pub query: Option<String>,
+    /// Longest time between the two presses (in milliseconds)
+    pub interval_ms: u64,
+}
+
+impl Default for DoublePressConfig {
+    fn default() -> Self {
+        Self {
+            query: None,
+            interval_ms: 300,
+        }
+    }
+}
+
+/// Keeps the hotkey from opening the search window over apps it would get
+/// in the way of, such as games or screen-sharing sessions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HotkeyExclusionsConfig {
+    /// Bundle identifiers of the apps in front of which the hotkey does
+    /// nothing (for instance: "com.apple.ScreenSharing")
+    pub bundle_ids: Vec<String>,
+    /// Do nothing while the frontmost app covers a whole display, as
+    /// full-screen games and presentations do
+    pub presentation_mode: bool,
+}
+
+impl Default for HotkeyExclusionsConfig {
+    fn default() -> Self {
+        Self {
+            bundle_ids: Vec::new(),
+            presentation_mode: true,
+        }
+    }
+}
+
+/// Tuning of the delay between a keypress and the search it triggers
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DebounceConfig {
+    /// Queries with fewer characters than this are always debounced
+    pub short_query_len: usize,
+    /// Searches faster than this (in milliseconds) aren't debounced
+    pub fast_search_ms: u64,
+    /// Upper bound of the debounce delay (in milliseconds)
+    pub max_delay_ms: u64,
+}
+
+impl Default for DebounceConfig {
+    fn default() -> Self {
+        Self {
+            short_query_len: 3,
+            fast_search_ms: 5,
+            max_delay_ms: 50,
+        }
+    }
+}
+
+/// Memory (in megabytes) each cache may use before evicting its least
+/// recently used entries
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CacheBudgetsConfig {
+    /// Ranked apps of recent queries, with their icons
+    pub search_results_mb: usize,
+    /// Rendered rows of the search window, with their icons
+    pub result_rows_mb: usize,
+    /// Decoded icons of apps, shared by every search window. The icons of
+    /// the apps opened most are decoded ahead of time, within this budget.
+    pub app_icons_mb: usize,
+}
+
+impl Default for CacheBudgetsConfig {
+    fn default() -> Self {
+        Self {
+            search_results_mb: 32,
+            result_rows_mb: 64,
+            app_icons_mb: 32,
+        }
+    }
+}
+
+/// Size and position of the search window
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WindowConfig {
+    /// In points
+    pub width: u32,
+    /// Rows of results shown at once, which sets the height of the window
+    pub max_visible_rows: usize,
+    /// Where the window sits in the height left free on the display, from
+    /// 0.0 (at the top) to 1.0 (at the bottom). Spotlight sits at about a
+    /// third.
+    pub vertical_position: f32,
+    /// Show the window where it was last dragged to on each display,
+    /// rather than at `vertical_position`
+    pub remember_position: bool,
+    /// Show the window over full-screen apps, on the Space they're on,
+    /// rather than switching back to the desktop
+    pub show_over_full_screen_apps: bool,
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        Self {
+            width: 520,
+            max_visible_rows: 4,
+            vertical_position: 0.5,
+            remember_position: true,
+            show_over_full_screen_apps: true,
+        }
+    }
+}
+
+/// Refreshing the app index and the lists of providers is deferred on
+/// battery or in Low Power Mode, and done in batches
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PowerConfig {
+    pub save_power_on_battery: bool,
+    /// How often (in minutes) background work runs while saving power
+    pub battery_batch_interval_mins: u64,
+}
+
+impl Default for PowerConfig {
+    fn default() -> Self {
+        Self {
+            save_power_on_battery: true,
+            battery_batch_interval_mins: 30,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UpdatesConfig {
+    pub enabled: bool,
+    pub channel: UpdateChannel,
+    /// GitHub API endpoint listing the releases
+    pub feed_url: String,
+    /// How often (in hours) new versions are looked for
+    pub check_interval_hours: u64,
+}
+
+impl Default for UpdatesConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            channel: UpdateChannel::default(),
+            feed_url: String::from("https://api.github.com/repos/hackerbirds/fetch/releases"),
+            check_interval_hours: 24,
+        }
+    }
+}
+
+/// Which releases are installed
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UpdateChannel {
+    #[default]
+    Stable,
+    /// Pre-releases too, to try new features before they are released
+    Beta,
+}
+
+/// Defaults of the `uuid`, `password` and `lorem` generators
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GeneratorsConfig {
+    pub enabled: bool,
+    /// Length of `password` when none is given
+    pub password_length: usize,
+    /// Characters a password is made of
+    pub password_charset: String,
+    /// Number of paragraphs of `lorem` when none is given
+    pub lorem_paragraphs: usize,
+}
+
+impl Default for GeneratorsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            password_length: 20,
+            password_charset: String::from(
+                "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789!@#$%^&*-_=+",
+            ),
+            lorem_paragraphs: 1,
+        }
+    }
+}
+
+/// Access to the repositories listed by `repo`. Repositories of a
+/// host are only listed when a token is set for it, here or as a secret.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReposConfig {
+    /// Personal access token, with read access to repositories
+    pub github_token: Option<String>,
+    /// Personal access token, with the `read_api` scope
+    pub gitlab_token: Option<String>,
+    /// Base URL of the GitLab instance
+    pub gitlab_url: String,
+    /// How often (in minutes) the list of repositories is fetched again
+    pub refresh_interval_mins: u64,
+}
+
+impl Default for ReposConfig {
+    fn default() -> Self {
+        Self {
+            github_token: None,
+            gitlab_token: None,
+            gitlab_url: String::from("https://gitlab.com"),
+            refresh_interval_mins: 60,
+        }
+    }
+}
+
+/// Messages composed by `mail`, see [`crate::extensions::mail`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MailConfig {
+    pub enabled: bool,
+    pub templates: Vec<MailTemplate>,
+}
+
+impl Default for MailConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            templates: Vec::new(),
+        }
+    }
+}
+
+/// A message written often, composed by typing `mail` and its name
+///
+/// ```toml
+/// [[mail.templates]]
+/// name = "standup"
+/// to = ["team@example.com"]
+/// subject = "Standup"
+/// body = "Yesterday:\n\nToday:\n"
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MailTemplate {
+    pub name: String,
+    #[serde(default)]
+    pub to: Vec<String>,
+    #[serde(default)]
+    pub cc: Vec<String>,
+    #[serde(default)]
+    pub subject: String,
+    #[serde(default)]
+    pub body: String,
+}
+
+/// Files found by `file`, see [`crate::extensions::files`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FilesConfig {
+    pub enabled: bool,
+    pub max_results: usize,
+    pub ranking: FileRankingConfig,
+}
+
+impl Default for FilesConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_results: 10,
+            ranking: FileRankingConfig::default(),
+        }
+    }
+}
+
+/// Weights of what files are ranked by, whose scores (each from 0 to 1)
+/// are added up. A weight of 0 ignores its score.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FileRankingConfig {
+    /// How well the name matches: exactly, at its start, at the start of
+    /// a word, or anywhere
+    pub name: f64,
+    /// How recently the file was opened
+    pub recency: f64,
+    /// How often the file was opened
+    pub frequency: f64,
+    /// Days after which the recency score of a file halves
+    pub recency_half_life_days: f64,
+}
+
+impl Default for FileRankingConfig {
+    fn default() -> Self {
+        Self {
+            name: 1.0,
+            recency: 0.5,
+            frequency: 0.3,
+            recency_half_life_days: 30.0,
+        }
+    }
+}
+
+/// Queries kept across openings of the search window, see
+/// [`crate::extensions::history`]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QueryHistoryConfig {
+    /// When disabled, queries are forgotten as soon as the window closes
+    /// (and nothing is listed by `history`), as they were before
+    pub enabled: bool,
+    /// Days after which queries are forgotten
+    pub retention_days: u32,
+}
+
+impl Default for QueryHistoryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            retention_days: 30,
+        }
+    }
+}
+
+/// Forecasts shown by `weather`, see [`crate::extensions::weather`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WeatherConfig {
+    pub enabled: bool,
+    /// URL of the forecasts, in which `{key}` and `{location}` are replaced.
+    /// Responses must be in the format of weatherapi.com.
+    pub endpoint: String,
+    /// Read from the `api_key` secret of `weather` when not set
+    pub api_key: Option<String>,
+    pub units: TemperatureUnit,
+    /// Shown when `weather` is typed alone
+    pub default_location: Option<String>,
+    /// How long (in minutes) forecasts are shown before being fetched again
+    pub cache_mins: u64,
+}
+
+impl Default for WeatherConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint: String::from(
+                "https://api.weatherapi.com/v1/forecast.json?key={key}&q={location}&days=3",
+            ),
+            api_key: None,
+            units: TemperatureUnit::default(),
+            default_location: None,
+            cache_mins: 30,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TemperatureUnit {
+    #[default]
+    Celsius,
+    Fahrenheit,
+}
+
+/// Apps in the `Applications` folder of volumes mounted in `/Volumes`.
+/// The apps of a volume are hidden while it isn't mounted.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExternalVolumesConfig {
+    /// Index the apps of every mounted volume
+    pub enabled: bool,
+    /// Overrides `enabled` for some volumes, by name: `{ Backup = false }`
+    pub volumes: BTreeMap<String, bool>,
+}
+
+impl ExternalVolumesConfig {
+    /// Whether the apps of the volume mounted at `/Volumes/<volume_name>` are indexed
+    #[must_use]
+    pub fn indexes(&self, volume_name: &str) -> bool {
+        self.volumes
+            .get(volume_name)
+            .copied()
+            .unwrap_or(self.enabled)
+    }
 }
 
 /// Format is "[Modifiers]-Key"
@@ -35,24 +785,69 @@ pub type HotkeyString = String;
 
 impl Default for Configuration {
     fn default() -> Self {
-        Self {
-            open_search_hotkey: DEFAULT_HOTKEY.to_string(),
-            launch_on_boot: true,
-            prioritize_open_apps: true,
-            // TODO: Replace `String` types in `Configuration` with `PathBuf`
-            applications: ImplPlatform::default_app_paths()
+        // TODO: Replace `String` types in `Configuration` with `PathBuf`
+        Self::with_app_paths(
+            ImplPlatform::default_app_paths()
                 .iter()
                 .map(|app| (*app).to_string_lossy().to_string())
                 .collect(),
-            application_dirs: ImplPlatform::default_app_dirs()
+            ImplPlatform::default_app_dirs()
                 .iter()
                 .map(|app_dir| (*app_dir).to_string_lossy().to_string())
                 .collect(),
-        }
+        )
     }
 }
 
 impl Configuration {
+    /// The default configuration, with the given apps and folders of apps
+    /// instead of the ones of the platform
+    #[must_use]
+    pub fn with_app_paths(applications: Vec<String>, application_dirs: Vec<String>) -> Self {
+        Self {
+            open_search_hotkey: DEFAULT_HOTKEY.to_string(),
+            hotkey_double_press: DoublePressConfig::default(),
+            hotkey_exclusions: HotkeyExclusionsConfig::default(),
+            launch_on_boot: true,
+            prioritize_open_apps: true,
+            open_apps: OpenAppsConfig::default(),
+            search_menu_items: false,
+            finder_selection: FinderSelectionConfig::default(),
+            shortcuts: ShortcutsConfig::default(),
+            settings_sections: SettingsSectionsConfig::default(),
+            text_transforms: TextTransformsConfig::default(),
+            generators: GeneratorsConfig::default(),
+            dates: DatesConfig::default(),
+            mail: MailConfig::default(),
+            files: FilesConfig::default(),
+            repos: ReposConfig::default(),
+            weather: WeatherConfig::default(),
+            ssh: SshConfig::default(),
+            terminal: Terminal::default(),
+            open_behavior: OpenBehaviorConfig::default(),
+            projects: ProjectsConfig::default(),
+            layout_mode: LayoutMode::default(),
+            window: WindowConfig::default(),
+            sort_mode: SortMode::default(),
+            search_engine: SearchEngineKind::default(),
+            query_history: QueryHistoryConfig::default(),
+            locale: None,
+            keyword_aliases: BTreeMap::new(),
+            theme: ThemeConfig::default(),
+            search_debounce: DebounceConfig::default(),
+            cache_budgets: CacheBudgetsConfig::default(),
+            power: PowerConfig::default(),
+            updates: UpdatesConfig::default(),
+            indexing_threads: 4,
+            applications,
+            application_dirs,
+            external_volumes: ExternalVolumesConfig::default(),
+            commands: Vec::new(),
+            matchers: Vec::new(),
+            text_macros: Vec::new(),
+        }
+    }
+
     pub fn read_from_fs() -> Result<Configuration, Report> {
         let config_path = config_file_path()?;
         let mut config_file = File::options()
@@ -74,7 +869,7 @@ impl Configuration {
         }
     }
 
-    fn write_to_fs(&self, path: &Path) -> Result<(), Report> {
+    pub fn write_to_fs(&self, path: &Path) -> Result<(), Report> {
         let serialized = toml::to_string_pretty(self)?;
 
         let mut config_file = File::options()
@@ -136,6 +931,11 @@ impl Configuration {
 }
 
 pub fn config_file_path() -> Result<PathBuf, Report> {
+    Ok(data_dir_path()?.join(CONFIG_FILE_NAME))
+}
+
+/// Directory where Fetch keeps its files, created if needed
+pub fn data_dir_path() -> Result<PathBuf, Report> {
     let mut fetch_app_dir = dirs::data_local_dir()
         .ok_or_else(|| report!("No data local directory found (are you on a supported OS?)"))?;
 
@@ -152,7 +952,17 @@ pub fn config_file_path() -> Result<PathBuf, Report> {
         }
     }
 
-    fetch_app_dir.push(CONFIG_FILE_NAME);
-
     Ok(fetch_app_dir)
 }
+
+/// Replaces a leading `~` with the home directory, as paths in the
+/// configuration file are written like they would be in a shell.
+#[must_use]
+pub fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix('~'), dirs::home_dir()) {
+        (Some(rest), Some(home_dir)) if rest.is_empty() || rest.starts_with('/') => {
+            home_dir.join(rest.trim_start_matches('/'))
+        }
+        _ => PathBuf::from(path),
+    }
+}
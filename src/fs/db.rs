@@ -1,17 +1,83 @@
 //! Not really a "database", naive use of filesystem is good enough
 //! for our use case
 
-use std::{fs::File, io::ErrorKind, os::unix::fs::FileExt};
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::ErrorKind,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 use rootcause::{Report, report};
 use serde::{Serialize, de::DeserializeOwned};
-use serde_json::json;
+use serde_json::{Map, Value, json};
 
 pub trait AppPersistence {
     fn get_data<T: DeserializeOwned>(&self, json_key: &str) -> Result<T, Report>;
     fn save_data<T: Serialize>(&mut self, json_key: &str, obj: T) -> Result<(), Report>;
 }
 
+/// Key of the data file holding the version of each [`VersionedKey`]
+const VERSIONS_KEY: &str = "versions";
+
+/// Folder of the data directory that values which can't be read are
+/// moved to
+const BACKUPS_DIR: &str = "backups";
+
+const DATA_FILE_NAME: &str = "data.json";
+
+/// File locked while the data file is being saved. The data file itself is
+/// replaced on every save, so a lock on it would be lost along with it.
+const LOCK_FILE_NAME: &str = "data.lock";
+
+/// Turns a value of a version of a key into one of the next version
+pub type Migration = fn(Value) -> Result<Value, Report>;
+
+/// A key of the data file whose value changes shape along with Fetch.
+/// Values stored before the key was versioned are of version 1, and each
+/// migration upgrades a value by one version, so the current version is
+/// one more than the number of migrations.
+#[derive(Debug, Clone, Copy)]
+pub struct VersionedKey {
+    pub name: &'static str,
+    /// Key the value was stored under before, read when nothing is stored
+    /// under `name`, then removed
+    pub renamed_from: Option<RenamedKey>,
+    pub migrations: &'static [Migration],
+}
+
+/// Former name of a [`VersionedKey`]
+#[derive(Debug, Clone, Copy)]
+pub struct RenamedKey {
+    pub name: &'static str,
+    /// Turns a value stored under the former name into version 1 of the key
+    pub migration: Migration,
+}
+
+impl VersionedKey {
+    #[must_use]
+    pub const fn version(&self) -> usize {
+        self.migrations.len() + 1
+    }
+
+    /// Upgrades `value`, stored with `stored_version` of this key, to the
+    /// current version
+    fn migrate(&self, mut value: Value, stored_version: usize) -> Result<Value, Report> {
+        if stored_version > self.version() {
+            return Err(report!(
+                "Stored by a newer version of Fetch, as version {stored_version}"
+            ));
+        }
+
+        for migration in &self.migrations[stored_version.max(1) - 1..] {
+            value = migration(value)?;
+        }
+
+        Ok(value)
+    }
+}
+
 /// Very naive way of storing data on the filesystem, with JSON files.
 /// Two assumptions about our use case to justify this choice:
 ///
@@ -21,9 +87,15 @@ pub trait AppPersistence {
 /// 2) Storing can be "slow", since indexing happens after search,
 ///    where the user doesn't use the app, so doing things this way is not
 ///    affecting performance
+///
+/// Both the daemon and the UI save to the data file. Saves are made under
+/// a lock, and write a new file that then replaces the data file, so that
+/// no process ever reads it half-written and saves over the others' keys.
 #[derive(Debug)]
 pub struct FilesystemPersistence {
-    data_file: File,
+    data_file_path: PathBuf,
+    /// Folder of the data file
+    data_dir: PathBuf,
 }
 
 impl FilesystemPersistence {
@@ -44,27 +116,168 @@ impl FilesystemPersistence {
             }
         }
 
-        let data_file_path = {
-            let mut path = fetch_app_dir.clone();
-            path.push("data.json");
+        Ok(Self::in_dir(fetch_app_dir))
+    }
+
+    pub(crate) fn in_dir(data_dir: PathBuf) -> Self {
+        Self {
+            data_file_path: data_dir.join(DATA_FILE_NAME),
+            data_dir,
+        }
+    }
+
+    /// Reads the value of `key`, migrating it from the version it was
+    /// stored with, or `None` if there is none.
+    ///
+    /// A value that can't be read, such as after its shape changed without
+    /// a migration, is moved to a backup file and reset, rather than being
+    /// overwritten once new data is saved. Other keys are left as they are.
+    ///
+    /// Records the current version of `key`, as what is saved from then on
+    /// is of that version. Keys must thus be loaded before they are saved.
+    pub fn load_versioned<T: DeserializeOwned>(&mut self, key: &VersionedKey) -> Option<T> {
+        let mut versions: HashMap<String, usize> = self.get_data(VERSIONS_KEY).unwrap_or_default();
+        let mut stored_version = versions.get(key.name).copied().unwrap_or(1);
+        let mut stored_value: Option<Value> = self.get_data(key.name).ok().flatten();
+
+        let renamed_from = key.renamed_from.filter(|_| stored_value.is_none());
+        if let Some(renamed) = renamed_from
+            && let Some(value) = self.get_data::<Option<Value>>(renamed.name).ok().flatten()
+        {
+            match (renamed.migration)(value) {
+                Ok(value) => {
+                    stored_value = Some(value);
+                    stored_version = 1;
+                }
+                Err(report) => eprintln!(
+                    "{}",
+                    report.context(format!("Could not read the stored {}", renamed.name))
+                ),
+            }
+        }
+
+        let loaded = stored_value.and_then(|value| {
+            let read = key
+                .migrate(value.clone(), stored_version)
+                .and_then(|migrated| {
+                    let data = serde_json::from_value::<T>(migrated.clone())?;
+                    if stored_version != key.version() || renamed_from.is_some() {
+                        self.save_data(key.name, migrated)?;
+                    }
+
+                    Ok(data)
+                });
+
+            match read {
+                Ok(data) => Some(data),
+                Err(report) => {
+                    self.reset_unreadable(key, &value, report);
+                    None
+                }
+            }
+        });
+
+        // Removed once moved, so that it isn't read again after the key
+        // is reset
+        if let Some(renamed) = renamed_from
+            && loaded.is_some()
+            && let Err(report) = self.remove_data(renamed.name)
+        {
+            eprintln!(
+                "{}",
+                report.context(format!("Could not remove the stored {}", renamed.name))
+            );
+        }
+
+        if stored_version != key.version() {
+            versions.insert(key.name.to_owned(), key.version());
+            if let Err(report) = self.save_data(VERSIONS_KEY, versions) {
+                eprintln!("{}", report.context("Could not record data versions"));
+            }
+        }
+
+        loaded
+    }
+
+    /// Moves the unreadable `value` of `key` to the backups folder, then
+    /// removes it from the data file. It is kept if it can't be backed up.
+    fn reset_unreadable(&mut self, key: &VersionedKey, value: &Value, cause: Report) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+        let backup_path = self
+            .data_dir
+            .join(BACKUPS_DIR)
+            .join(format!("{}-{timestamp}.json", key.name));
+
+        let reset = (|| -> Result<(), Report> {
+            std::fs::create_dir_all(self.data_dir.join(BACKUPS_DIR))?;
+            std::fs::write(&backup_path, serde_json::to_vec_pretty(value)?)?;
+            self.save_data(key.name, Value::Null)
+        })();
+
+        match reset {
+            Ok(()) => eprintln!(
+                "{}",
+                cause.context(format!(
+                    "Could not read the stored {}, which was reset and backed up to {}",
+                    key.name,
+                    backup_path.display()
+                ))
+            ),
+            Err(report) => eprintln!(
+                "{}",
+                report.context(format!("Could not back up the stored {}", key.name))
+            ),
+        }
+    }
+
+    /// Removes `json_key` from the data file
+    pub fn remove_data(&mut self, json_key: &str) -> Result<(), Report> {
+        self.modify(|map| {
+            map.remove(json_key);
+        })
+    }
 
-            path
+    /// Changes the data file with `change`, under the lock
+    fn modify(&mut self, change: impl FnOnce(&mut Map<String, Value>)) -> Result<(), Report> {
+        // Released once dropped, at the end of the save
+        let lock_file = File::create(self.data_dir.join(LOCK_FILE_NAME))?;
+        lock_file.lock()?;
+
+        // Read again under the lock, as other processes may have saved
+        // since. A data file that can't be read is left as it is, rather
+        // than replaced by the only key being saved.
+        let mut generic_json = self.read_all()?;
+        let Some(map) = generic_json.as_object_mut() else {
+            return Err(report!("No JSON map to read from"));
         };
+        change(map);
+
+        let temp_path = self
+            .data_dir
+            .join(format!("{DATA_FILE_NAME}.{}.tmp", std::process::id()));
+        std::fs::write(&temp_path, serde_json::to_vec(&generic_json)?)?;
+        std::fs::rename(&temp_path, &self.data_file_path)?;
 
-        let data_file = File::options()
-            .read(true)
-            .write(true)
-            .create(true)
-            .truncate(false)
-            .open(data_file_path)?;
+        Ok(())
+    }
 
-        Ok(Self { data_file })
+    /// Everything stored, which is nothing until the first save
+    fn read_all(&self) -> Result<Value, Report> {
+        match std::fs::read(&self.data_file_path) {
+            Ok(contents) if contents.is_empty() => Ok(json!({})),
+            Ok(contents) => Ok(serde_json::from_slice(&contents)?),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(json!({})),
+            Err(err) => Err(err.into()),
+        }
     }
 }
 
 impl AppPersistence for FilesystemPersistence {
     fn get_data<T: DeserializeOwned>(&self, json_key: &str) -> Result<T, Report> {
-        let generic_json: serde_json::Value = serde_json::from_reader(&self.data_file)?;
+        let generic_json = self.read_all()?;
 
         Ok(serde_json::from_value::<T>(
             generic_json.get(json_key).unwrap_or_default().clone(),
@@ -72,20 +285,117 @@ impl AppPersistence for FilesystemPersistence {
     }
 
     fn save_data<T: Serialize>(&mut self, json_key: &str, obj: T) -> Result<(), Report> {
-        let mut generic_json: serde_json::Value =
-            serde_json::from_reader(&self.data_file).unwrap_or(json!({}));
+        let value = serde_json::to_value(obj)?;
+
+        self.modify(|map| {
+            map.insert(json_key.to_string(), value);
+        })
+    }
+}
 
-        if let Some(map) = generic_json.as_object_mut() {
-            let json_value = serde_json::to_value(obj)?;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-            map.insert(json_key.to_string(), json_value);
+    #[allow(clippy::unnecessary_wraps, reason = "Migrations can fail")]
+    fn wrap_in_list(value: Value) -> Result<Value, Report> {
+        Ok(Value::Array(vec![value]))
+    }
 
-            self.data_file
-                .write_all_at(serde_json::to_vec(&generic_json)?.as_ref(), 0)?;
+    fn count_items(value: Value) -> Result<Value, Report> {
+        let Value::Array(items) = value else {
+            return Err(report!("Not a list"));
+        };
 
-            Ok(())
-        } else {
-            Err(report!("No JSON map to read from"))
-        }
+        Ok(json!({ "count": items.len(), "items": items }))
+    }
+
+    const KEY: VersionedKey = VersionedKey {
+        name: "test",
+        renamed_from: None,
+        migrations: &[wrap_in_list, count_items],
+    };
+
+    /// A temporary data directory, unique to the test
+    fn test_data_dir(test: &str) -> PathBuf {
+        let data_dir =
+            std::env::temp_dir().join(format!("fetch-db-test-{test}-{}", std::process::id()));
+        std::fs::create_dir_all(&data_dir).unwrap();
+
+        data_dir
+    }
+
+    #[test]
+    fn test_save_data() {
+        let data_dir = test_data_dir("save");
+
+        // As the daemon and the UI do, each with its own handle
+        let mut daemon = FilesystemPersistence::in_dir(data_dir.clone());
+        let mut ui = FilesystemPersistence::in_dir(data_dir.clone());
+        assert_eq!(daemon.get_data::<Option<u32>>("a").unwrap(), None);
+
+        daemon.save_data("a", 1).unwrap();
+        ui.save_data("b", 2).unwrap();
+        daemon.save_data("a", 3).unwrap();
+
+        assert_eq!(ui.get_data::<u32>("a").unwrap(), 3);
+        assert_eq!(daemon.get_data::<u32>("b").unwrap(), 2);
+
+        // An unreadable data file isn't replaced by a single key
+        std::fs::write(data_dir.join(DATA_FILE_NAME), "{\"a\": 1").unwrap();
+        assert!(ui.save_data("b", 4).is_err());
+
+        std::fs::remove_dir_all(data_dir).unwrap();
+    }
+
+    #[test]
+    fn test_migrate() {
+        assert_eq!(KEY.version(), 3);
+
+        assert_eq!(
+            KEY.migrate(json!("a"), 1).unwrap(),
+            json!({ "items": ["a"], "count": 1 })
+        );
+        assert_eq!(
+            KEY.migrate(json!(["a", "b"]), 2).unwrap(),
+            json!({ "items": ["a", "b"], "count": 2 })
+        );
+        assert_eq!(
+            KEY.migrate(json!({ "count": 0 }), 3).unwrap(),
+            json!({ "count": 0 })
+        );
+
+        // A failing migration, or a value saved by a newer version
+        assert!(KEY.migrate(json!("a"), 2).is_err());
+        assert!(KEY.migrate(json!("a"), 4).is_err());
+    }
+
+    #[test]
+    fn test_load_renamed() {
+        const RENAMED_KEY: VersionedKey = VersionedKey {
+            name: "renamed",
+            renamed_from: Some(RenamedKey {
+                name: "test",
+                migration: wrap_in_list,
+            }),
+            migrations: &[count_items],
+        };
+
+        let data_dir = test_data_dir("renamed");
+        let mut db = FilesystemPersistence::in_dir(data_dir.clone());
+        db.save_data("test", "a").unwrap();
+
+        assert_eq!(
+            db.load_versioned::<Value>(&RENAMED_KEY),
+            Some(json!({ "items": ["a"], "count": 1 }))
+        );
+        assert!(db.read_all().unwrap().get("test").is_none());
+        // Read under its new name from then on
+        assert_eq!(
+            db.load_versioned::<Value>(&RENAMED_KEY),
+            Some(json!({ "items": ["a"], "count": 1 }))
+        );
+
+        std::fs::remove_dir_all(data_dir).unwrap();
     }
 }
@@ -1,2 +1,4 @@
 pub mod config;
 pub mod db;
+pub mod portable;
+pub mod stats;
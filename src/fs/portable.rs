@@ -0,0 +1,192 @@
+//! Everything needed to set Fetch up on another machine, in a single TOML
+//! file which can be kept along with dotfiles: the configuration (commands
+//! and matchers included), the hidden apps and the pinned ones. What Fetch learned from
+//! searches is only included when asked for, as it is specific to a machine.
+//! Tokens and API keys are left out, as the file is meant to be shared, and
+//! can be stored as secrets in the keychain of each machine instead.
+
+use std::{
+    collections::BTreeMap,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use rootcause::{Report, report};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    fs::{
+        config::{Configuration, config_file_path},
+        db::{AppPersistence, FilesystemPersistence},
+    },
+    instance,
+};
+
+/// Subcommand of the executable, as in `fetch config export`
+pub const CONFIG_COMMAND: &str = "config";
+
+/// Asks `fetch config export` to include what Fetch learned from searches
+const WITH_HISTORY_FLAG: &str = "--with-history";
+
+/// Name of the file exported from the search window
+pub const EXPORT_FILE_NAME: &str = "fetch-config.toml";
+
+/// Keys of the data file holding what Fetch learned from searches
+const HISTORY_KEYS: &[&str] = &[
+    "learned_apps",
+    "app_usage",
+    "ssh_last_used",
+    "project_usage",
+    "past_queries",
+];
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PortableConfig {
+    /// Paths of the apps hidden from results
+    #[serde(default)]
+    pub hidden_apps: Vec<PathBuf>,
+    /// Paths of the apps pinned to a query, by query
+    #[serde(default)]
+    pub pinned_apps: BTreeMap<String, PathBuf>,
+    pub config: Configuration,
+    /// Data learned from searches, by key of the data file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub history: Option<toml::Table>,
+}
+
+impl PortableConfig {
+    /// Reads the configuration and data of this machine
+    pub fn collect(with_history: bool) -> Result<Self, Report> {
+        let db = FilesystemPersistence::open()?;
+
+        let history = with_history.then(|| {
+            HISTORY_KEYS
+                .iter()
+                .filter_map(|key| {
+                    let data: toml::Value = db.get_data(key).ok()?;
+                    Some(((*key).to_owned(), data))
+                })
+                .collect()
+        });
+
+        let mut config = Configuration::read_from_fs()?;
+        let left_out = strip_secrets(&mut config);
+        if !left_out.is_empty() {
+            eprintln!(
+                "Left out of the export: {}. Store them as secrets on the other machine, with `Fetch secret set <extension> <name>`",
+                left_out.join(", ")
+            );
+        }
+
+        Ok(Self {
+            hidden_apps: db.get_data("hidden_apps").unwrap_or_default(),
+            pinned_apps: db.get_data("pinned_apps").unwrap_or_default(),
+            config,
+            history,
+        })
+    }
+
+    pub fn to_toml(&self) -> Result<String, Report> {
+        Ok(toml::to_string_pretty(self)?)
+    }
+
+    pub fn read(path: &Path) -> Result<Self, Report> {
+        let contents = std::fs::read_to_string(path)?;
+
+        Ok(toml::from_str(&contents)?)
+    }
+
+    /// Replaces the configuration and data of this machine. History is
+    /// only replaced if the file contains some, and the tokens and API keys
+    /// of this machine are kept unless the file sets others.
+    pub fn apply(&self) -> Result<(), Report> {
+        let mut config = self.config.clone();
+        // A configuration which can't be read has no secrets left to keep
+        if let Ok(mut local_config) = Configuration::read_from_fs() {
+            for ((value, _), (local_value, _)) in secrets(&mut config)
+                .into_iter()
+                .zip(secrets(&mut local_config))
+            {
+                if value.is_none() {
+                    *value = local_value.take();
+                }
+            }
+        }
+        config.write_to_fs(&config_file_path()?)?;
+
+        let mut db = FilesystemPersistence::open()?;
+        db.save_data("hidden_apps", &self.hidden_apps)?;
+        db.save_data("pinned_apps", &self.pinned_apps)?;
+        for (key, data) in self.history.iter().flatten() {
+            if HISTORY_KEYS.contains(&key.as_str()) {
+                db.save_data(key, data)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Tokens and API keys of `config`, along with the extension and name of
+/// the secret each can be stored as instead
+fn secrets(config: &mut Configuration) -> [(&mut Option<String>, &'static str); 3] {
+    [
+        (&mut config.repos.github_token, "repos github_token"),
+        (&mut config.repos.gitlab_token, "repos gitlab_token"),
+        (&mut config.weather.api_key, "weather api_key"),
+    ]
+}
+
+/// Removes the tokens and API keys of `config`, returning the extension and
+/// name of the secret each can be stored as instead
+fn strip_secrets(config: &mut Configuration) -> Vec<&'static str> {
+    secrets(config)
+        .into_iter()
+        .filter_map(|(value, secret)| value.take().map(|_| secret))
+        .collect()
+}
+
+/// Exports to the Downloads folder, returning the path of the file
+pub fn export_to_downloads() -> Result<PathBuf, Report> {
+    let path = dirs::download_dir()
+        .ok_or_else(|| report!("No Downloads folder found"))?
+        .join(EXPORT_FILE_NAME);
+    std::fs::write(&path, PortableConfig::collect(false)?.to_toml()?)?;
+
+    Ok(path)
+}
+
+/// Runs `fetch config export [path] [--with-history]`, which writes to the
+/// standard output without a path, or `fetch config import <path>`
+pub fn run_command(args: &[String]) -> Result<(), Report> {
+    match args {
+        [action, rest @ ..] if action == "export" => {
+            let with_history = rest.iter().any(|arg| arg == WITH_HISTORY_FLAG);
+            let path = rest.iter().find(|arg| *arg != WITH_HISTORY_FLAG);
+            let exported = PortableConfig::collect(with_history)?.to_toml()?;
+
+            match path {
+                Some(path) => std::fs::write(path, exported)?,
+                None => std::io::stdout().write_all(exported.as_bytes())?,
+            }
+        }
+        [action, path] if action == "import" => {
+            // Fetch would keep using what it read at launch, and save it
+            // over what was imported
+            if instance::is_running() {
+                return Err(report!(
+                    "Fetch is running: quit it before importing, or import from its search window with Cmd+Shift+I"
+                ));
+            }
+            PortableConfig::read(Path::new(path))?.apply()?;
+            println!("Configuration imported");
+        }
+        _ => {
+            return Err(report!(
+                "Usage: fetch config export [path] [--with-history] | fetch config import <path>"
+            ));
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,216 @@
+//! What the user's own searches look like, exported on demand for them to
+//! analyze: how often each app was opened from Fetch, and how long recent
+//! searches took. Nothing leaves the machine, and queries themselves are
+//! never recorded, only their length.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::Write as _,
+    io::Write,
+    path::PathBuf,
+};
+
+use rootcause::{Report, report};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app::AppUsage,
+    fs::db::{AppPersistence, FilesystemPersistence},
+};
+
+/// Subcommand of the executable, as in `fetch stats export`
+pub const STATS_COMMAND: &str = "stats";
+
+/// Asks `fetch stats export` for CSV rather than JSON
+const CSV_FLAG: &str = "--csv";
+
+/// Key of the data file holding the latest searches
+pub const SEARCH_LATENCIES_KEY: &str = "search_latencies";
+
+/// Only the latest searches are kept
+const MAX_SEARCH_LATENCIES: usize = 1000;
+
+/// How long a search took, without what was searched for
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct SearchLatency {
+    /// In seconds since the UNIX epoch
+    pub timestamp: u64,
+    /// In graphemes
+    pub query_length: usize,
+    pub duration_us: u64,
+    pub results: usize,
+}
+
+/// Adds a search to the history, forgetting the oldest ones if needed
+pub fn record_search(history: &mut VecDeque<SearchLatency>, latency: SearchLatency) {
+    history.push_back(latency);
+    while history.len() > MAX_SEARCH_LATENCIES {
+        history.pop_front();
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct AppLaunches {
+    pub name: String,
+    pub path: PathBuf,
+    pub launch_count: u32,
+    /// In seconds since the UNIX epoch
+    pub last_opened: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UsageStats {
+    /// Most opened first
+    pub apps: Vec<AppLaunches>,
+    /// Oldest first
+    pub searches: Vec<SearchLatency>,
+}
+
+impl UsageStats {
+    pub fn collect() -> Result<Self, Report> {
+        let db = FilesystemPersistence::open()?;
+        let app_usage: HashMap<PathBuf, AppUsage> = db.get_data("app_usage").unwrap_or_default();
+
+        let mut apps: Vec<AppLaunches> = app_usage
+            .into_iter()
+            .map(|(path, usage)| AppLaunches {
+                name: path
+                    .file_stem()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .into_owned(),
+                path,
+                launch_count: usage.launch_count,
+                last_opened: usage.last_opened,
+            })
+            .collect();
+        apps.sort_by(|a, b| {
+            b.launch_count
+                .cmp(&a.launch_count)
+                .then_with(|| a.name.cmp(&b.name))
+        });
+
+        Ok(Self {
+            apps,
+            searches: db.get_data(SEARCH_LATENCIES_KEY).unwrap_or_default(),
+        })
+    }
+
+    pub fn to_json(&self) -> Result<String, Report> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// A single table, with a `kind` column telling app rows from search
+    /// rows. Cells which don't apply to the kind of a row are left empty.
+    #[must_use]
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from(
+            "kind,name,path,launch_count,timestamp,query_length,duration_us,results\n",
+        );
+
+        for app in &self.apps {
+            let _ = writeln!(
+                csv,
+                "app,{},{},{},{},,,",
+                csv_field(&app.name),
+                csv_field(&app.path.to_string_lossy()),
+                app.launch_count,
+                app.last_opened,
+            );
+        }
+        for search in &self.searches {
+            let _ = writeln!(
+                csv,
+                "search,,,,{},{},{},{}",
+                search.timestamp, search.query_length, search.duration_us, search.results,
+            );
+        }
+
+        csv
+    }
+}
+
+/// Quotes a field if it contains a separator, a quote or a line break
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+/// Runs `fetch stats export [path] [--csv]`, which writes JSON (or CSV)
+/// to the standard output without a path
+pub fn run_command(args: &[String]) -> Result<(), Report> {
+    let [action, rest @ ..] = args else {
+        return Err(report!("Usage: fetch stats export [path] [--csv]"));
+    };
+    if action != "export" {
+        return Err(report!("Usage: fetch stats export [path] [--csv]"));
+    }
+
+    let is_csv = rest.iter().any(|arg| arg == CSV_FLAG);
+    let path = rest.iter().find(|arg| *arg != CSV_FLAG);
+    let stats = UsageStats::collect()?;
+    let exported = if is_csv {
+        stats.to_csv()
+    } else {
+        stats.to_json()?
+    };
+
+    match path {
+        Some(path) => std::fs::write(path, exported)?,
+        None => std::io::stdout().write_all(exported.as_bytes())?,
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_search() {
+        let mut history = VecDeque::new();
+        for timestamp in 0..=MAX_SEARCH_LATENCIES as u64 {
+            record_search(
+                &mut history,
+                SearchLatency {
+                    timestamp,
+                    query_length: 3,
+                    duration_us: 120,
+                    results: 4,
+                },
+            );
+        }
+
+        assert_eq!(history.len(), MAX_SEARCH_LATENCIES);
+        assert_eq!(history.front().map(|search| search.timestamp), Some(1));
+    }
+
+    #[test]
+    fn test_to_csv() {
+        let stats = UsageStats {
+            apps: vec![AppLaunches {
+                name: "Foo, Bar".to_owned(),
+                path: PathBuf::from("/Applications/Foo, Bar.app"),
+                launch_count: 12,
+                last_opened: 1_700_000_000,
+            }],
+            searches: vec![SearchLatency {
+                timestamp: 1_700_000_100,
+                query_length: 2,
+                duration_us: 850,
+                results: 7,
+            }],
+        };
+
+        assert_eq!(
+            stats.to_csv(),
+            "kind,name,path,launch_count,timestamp,query_length,duration_us,results\n\
+             app,\"Foo, Bar\",\"/Applications/Foo, Bar.app\",12,1700000000,,,\n\
+             search,,,,1700000100,2,850,7\n"
+        );
+    }
+}
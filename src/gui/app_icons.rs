@@ -0,0 +1,142 @@
+//! Decoded app icons, shared by every search window. Decoding takes a few
+//! milliseconds per icon, so the icons of the apps opened most are decoded
+//! ahead of time whenever the index is built, for the first keystrokes not
+//! to wait for them.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use gpui::RenderImage;
+use gpui_component::ActiveTheme;
+use image::{Frame, ImageFormat};
+
+use crate::{
+    app::ExecutableApp,
+    cache::{BYTES_PER_MB, CacheSize, LruCache},
+    fs::config::{CacheBudgetsConfig, IconStyle},
+    gui::icon_tint::Tint,
+};
+
+/// How many decoded icons to keep
+const ICONS_CACHE_CAPACITY: usize = 256;
+
+/// Icons are told apart by the path of their app, and by the size of their
+/// PNG, which changes when the app is updated with a new icon
+type IconKey = (PathBuf, usize, Option<Tint>);
+
+impl CacheSize for Arc<RenderImage> {
+    fn cache_size(&self) -> usize {
+        (0..self.frame_count())
+            .filter_map(|frame| self.as_bytes(frame))
+            .map(<[u8]>::len)
+            .sum()
+    }
+}
+
+pub struct AppIcons {
+    icons: Mutex<LruCache<IconKey, Arc<RenderImage>>>,
+    icon_style: IconStyle,
+}
+
+impl AppIcons {
+    #[must_use]
+    pub fn new(budgets: CacheBudgetsConfig, icon_style: IconStyle) -> Self {
+        Self {
+            icons: Mutex::new(LruCache::registered(
+                "App icons",
+                ICONS_CACHE_CAPACITY,
+                budgets.app_icons_mb * BYTES_PER_MB,
+            )),
+            icon_style,
+        }
+    }
+
+    /// The tint of monochrome icons, which follows the appearance of macOS
+    #[must_use]
+    pub fn tint(&self, cx: &gpui::App) -> Option<Tint> {
+        (self.icon_style == IconStyle::Monochrome)
+            .then(|| Tint::from_color(cx.theme().muted_foreground))
+    }
+
+    pub fn load(&self, app: &ExecutableApp, tint: Option<Tint>) -> Option<Arc<RenderImage>> {
+        let key = icon_key(app, tint)?;
+        if let Some(icon) = self.icons.lock().expect("no lock poisoning").get(&key) {
+            return Some(icon);
+        }
+
+        let icon = decode(app, tint)?;
+        self.icons
+            .lock()
+            .expect("no lock poisoning")
+            .insert(key, icon.clone());
+
+        Some(icon)
+    }
+
+    /// Decodes the icons of `apps` that aren't decoded yet, for as long as
+    /// they fit in the budget. Icons that were shown are never evicted to
+    /// make room for icons that might be.
+    pub fn prefetch(&self, apps: &[ExecutableApp], tint: Option<Tint>) {
+        for app in apps {
+            let Some(key) = icon_key(app, tint) else {
+                continue;
+            };
+            if self.icons.lock().expect("no lock poisoning").contains(&key) {
+                continue;
+            }
+            let Some(icon) = decode(app, tint) else {
+                continue;
+            };
+
+            let mut icons = self.icons.lock().expect("no lock poisoning");
+            if !icons.has_room_for(icon.cache_size()) {
+                return;
+            }
+            icons.insert(key, icon);
+        }
+    }
+
+    /// Forgets the icons of the app at `path`, once it was deleted or
+    /// installed again
+    pub fn forget(&self, path: &Path) {
+        self.icons
+            .lock()
+            .expect("no lock poisoning")
+            .retain(|(icon_path, _, _)| icon_path != path);
+    }
+}
+
+fn icon_key(app: &ExecutableApp, tint: Option<Tint>) -> Option<IconKey> {
+    let data = app.icon_png_data.as_ref()?;
+
+    Some((app.path.clone(), data.len(), tint))
+}
+
+/// Decodes the icon the way `gpui::Image::to_image_data` does for PNGs,
+/// without the SVG renderer of the app, which can't be used off the main
+/// thread: prefetched icons are decoded in the background.
+fn decode(app: &ExecutableApp, tint: Option<Tint>) -> Option<Arc<RenderImage>> {
+    let data = app.icon_png_data.clone()?;
+    let data = match tint.map(|tint| tint.png(&data)) {
+        Some(Ok(tinted)) => tinted,
+        Some(Err(report)) => {
+            eprintln!(
+                "{}",
+                report.context(format!("Could not tint icon of {}", app.name))
+            );
+            data
+        }
+        None => data,
+    };
+    let mut pixels = image::load_from_memory_with_format(&data, ImageFormat::Png)
+        .ok()?
+        .into_rgba8();
+    // gpui renders BGRA
+    for pixel in pixels.chunks_exact_mut(4) {
+        pixel.swap(0, 2);
+    }
+
+    Some(Arc::new(RenderImage::new([Frame::new(pixels)])))
+}
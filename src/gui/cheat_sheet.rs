@@ -0,0 +1,159 @@
+//! The cheat sheet shown over the results with `Cmd+/`, listing the key
+//! bindings of the search window. It's read from the keymap rather than
+//! written by hand, so that it can't get out of date.
+
+use gpui::{
+    App, InteractiveElement, IntoElement, ParentElement, StatefulInteractiveElement, Styled, div,
+    px,
+};
+use gpui_component::{ActiveTheme, StyledExt};
+
+/// Namespace of the actions of Fetch, as opposed to the ones of the input
+const ACTIONS_NAMESPACE: &str = "fetch_actions::";
+
+/// Width of the column of keystrokes
+const KEYSTROKES_WIDTH: f32 = 140.0;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheatSheetEntry {
+    pub description: String,
+    /// Every keystroke the action is bound to, as in `⌘-s`
+    pub keystrokes: Vec<String>,
+}
+
+/// The bindings of the actions of Fetch, in the order they were bound
+#[must_use]
+pub fn key_bindings(cx: &App) -> Vec<CheatSheetEntry> {
+    let keymap = cx.key_bindings();
+    let keymap = keymap.borrow();
+
+    group_bindings(keymap.bindings().map(|binding| {
+        let keystrokes = binding
+            .keystrokes()
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(" ");
+        (binding.action().name(), keystrokes)
+    }))
+}
+
+/// One entry per action, with every keystroke bound to it
+fn group_bindings<'a>(bindings: impl Iterator<Item = (&'a str, String)>) -> Vec<CheatSheetEntry> {
+    let mut entries: Vec<(&str, CheatSheetEntry)> = Vec::new();
+
+    for (action_name, keystrokes) in bindings {
+        let Some(action_name) = action_name.strip_prefix(ACTIONS_NAMESPACE) else {
+            continue;
+        };
+
+        match entries.iter_mut().find(|(name, _)| *name == action_name) {
+            Some((_, entry)) => entry.keystrokes.push(keystrokes),
+            None => entries.push((
+                action_name,
+                CheatSheetEntry {
+                    description: describe_action(action_name),
+                    keystrokes: vec![keystrokes],
+                },
+            )),
+        }
+    }
+
+    entries.into_iter().map(|(_, entry)| entry).collect()
+}
+
+/// Actions are named after what they do, except for a few named after
+/// their key
+fn describe_action(action_name: &str) -> String {
+    let description = match action_name {
+        "EnterPressed" => "Open the selected result",
+        "EnterKeepOpen" => "Open and keep searching",
+        "EnterAlternate" => "Open the other way, or paste text",
+        "EscPressed" => "Close the window",
+        "TabSelectApp" => "Select the next result",
+        "TabBackSelectApp" => "Select the previous result",
+        "SpacePressed" => "Quick Look the result picked with the keyboard",
+        "AcceptCompletion" => "Complete the query",
+        "OpenSettings" => "Open the configuration",
+        "RevealResult" => "Show in Finder",
+        "TogglePinned" => "Pin the selected app to the query",
+        "ToggleLayout" => "Switch the layout of the typed keyword",
+        _ => return sentence_case(action_name),
+    };
+
+    description.to_owned()
+}
+
+/// `CycleSortMode` -> `Cycle sort mode`
+fn sentence_case(name: &str) -> String {
+    let mut sentence = String::with_capacity(name.len() + 4);
+    for (i, c) in name.chars().enumerate() {
+        if i > 0 && c.is_uppercase() {
+            sentence.push(' ');
+            sentence.extend(c.to_lowercase());
+        } else {
+            sentence.push(c);
+        }
+    }
+
+    sentence
+}
+
+pub fn render(cx: &App) -> impl IntoElement {
+    div()
+        .id("cheat-sheet")
+        .v_flex()
+        .size_full()
+        .gap_1()
+        .px_2()
+        .overflow_y_scroll()
+        .text_sm()
+        .children(key_bindings(cx).into_iter().map(|entry| {
+            div()
+                .h_flex()
+                .gap_2()
+                .child(
+                    div()
+                        .w(px(KEYSTROKES_WIDTH))
+                        .flex_none()
+                        .text_color(cx.theme().muted_foreground)
+                        .child(entry.keystrokes.join("  ")),
+                )
+                .child(div().flex_1().truncate().child(entry.description))
+        }))
+        .child(
+            div()
+                .pt_1()
+                .text_color(cx.theme().muted_foreground)
+                .child("Type ? to list keywords, press ⌘/ to close"),
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_group_bindings() {
+        let bindings = [
+            ("fetch_actions::TabSelectApp", "tab".to_owned()),
+            ("input::Backspace", "backspace".to_owned()),
+            ("fetch_actions::CycleSortMode", "cmd-s".to_owned()),
+            ("fetch_actions::TabSelectApp", "down".to_owned()),
+        ];
+
+        assert_eq!(
+            group_bindings(bindings.into_iter()),
+            vec![
+                CheatSheetEntry {
+                    description: "Select the next result".to_owned(),
+                    keystrokes: vec!["tab".to_owned(), "down".to_owned()],
+                },
+                CheatSheetEntry {
+                    description: "Cycle sort mode".to_owned(),
+                    keystrokes: vec!["cmd-s".to_owned()],
+                },
+            ]
+        );
+    }
+}
@@ -1,55 +1,238 @@
-use std::{path::PathBuf, sync::Arc};
+use std::{
+    sync::{Arc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
 
-use gpui::{ImageFormat, RenderImage, SharedString};
+use gpui::{RenderImage, SharedString};
 
-use crate::extensions::SearchResult;
+use crate::{
+    app::{AppUsage, ExecutableApp},
+    cache::{BYTES_PER_MB, CacheSize, LruCache},
+    command::CommandAction,
+    extensions::SearchResult,
+    fs::config::CacheBudgetsConfig,
+    gui::{
+        app_icons::AppIcons,
+        provider_icons::{ProviderIcon, ProviderIcons},
+    },
+};
 
 /// This struct contains the elements used to render an app in the search results.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct GpuiApp {
     pub(super) name: SharedString,
-    pub(super) path: PathBuf,
+    /// Secondary text displayed next to the name, such as the menu
+    /// a menu item belongs to
+    pub(super) subtitle: Option<SharedString>,
     pub(super) is_open: bool,
     pub(super) icon: Option<Arc<RenderImage>>,
 }
 
-/// This loads apps ready for gpui to render, with
-/// an internal cache.
-pub struct GpuiAppLoader(scc::HashMap<SearchResult, GpuiApp>);
+impl GpuiApp {
+    /// Row of a result that isn't an app, which is never dimmed, and
+    /// shows the icon of its provider
+    fn provider_row(name: impl Into<SharedString>, subtitle: impl Into<SharedString>) -> Self {
+        Self {
+            name: name.into(),
+            subtitle: Some(subtitle.into()),
+            is_open: true,
+            icon: None,
+        }
+    }
+}
 
-impl Default for GpuiAppLoader {
-    fn default() -> Self {
-        Self(scc::HashMap::new())
+impl CacheSize for GpuiApp {
+    fn cache_size(&self) -> usize {
+        size_of::<Self>()
+            + self.name.len()
+            + self.subtitle.as_ref().map_or(0, |subtitle| subtitle.len())
+            + self.icon.as_ref().map_or(0, CacheSize::cache_size)
     }
 }
 
+/// How many rendered results to keep
+const APPS_CACHE_CAPACITY: usize = 512;
+
+/// This loads apps ready for gpui to render, with
+/// an internal cache. Rows are cached per window, but the icons in them
+/// are shared by every window, so that opening the window again doesn't
+/// decode them again.
+pub struct GpuiAppLoader {
+    apps: Mutex<LruCache<SearchResult, GpuiApp>>,
+    /// Shared by every window, like `app_icons`
+    provider_icons: Arc<ProviderIcons>,
+    /// Shared by every window, see [`AppIcons`]
+    app_icons: Arc<AppIcons>,
+}
+
 impl GpuiAppLoader {
+    #[must_use]
+    pub fn new(
+        budgets: CacheBudgetsConfig,
+        app_icons: Arc<AppIcons>,
+        provider_icons: Arc<ProviderIcons>,
+    ) -> Self {
+        Self {
+            apps: Mutex::new(LruCache::registered(
+                "Result rows",
+                APPS_CACHE_CAPACITY,
+                budgets.result_rows_mb * BYTES_PER_MB,
+            )),
+            provider_icons,
+            app_icons,
+        }
+    }
+
     pub fn load(&self, result: &SearchResult, cx: &gpui::App) -> GpuiApp {
-        if let Some(cached_entry) = self.0.get_sync(result) {
-            cached_entry.get().clone()
+        let cached_app = self.apps.lock().expect("no lock poisoning").get(result);
+        if let Some(cached_app) = cached_app {
+            cached_app
         } else {
-            match result.clone() {
+            // Rows are cached for as long as the window is open, during
+            // which the appearance of macOS rarely changes
+            let tint = self.app_icons.tint(cx);
+
+            let mut gpui_app = match result.clone() {
                 SearchResult::Executable(executable_app) => {
-                    let icon = executable_app
-                        .icon_png_data
-                        .clone()
-                        .and_then(|data: Vec<u8>| {
-                            let im = gpui::Image::from_bytes(ImageFormat::Png, data);
-                            im.to_image_data(cx.svg_renderer()).ok()
-                        });
-
-                    let gpui_app = GpuiApp {
-                        name: SharedString::from(executable_app.name),
-                        path: executable_app.path,
-                        is_open: executable_app.is_open,
-                        icon,
-                    };
-
-                    let _ = self.0.insert_sync(result.clone(), gpui_app.clone());
-
-                    gpui_app
+                    let icon = self.app_icons.load(&executable_app, tint);
+                    app_row(executable_app, icon)
                 }
+                SearchResult::TrashedApp { app, .. } => GpuiApp {
+                    icon: self.app_icons.load(&app, tint),
+                    name: SharedString::from(app.name),
+                    subtitle: Some("In the Trash · Restore and open".into()),
+                    // Dimmed, like closed apps
+                    is_open: false,
+                },
+                SearchResult::OpenWith { app, files } => GpuiApp {
+                    icon: self.app_icons.load(&app, tint),
+                    name: SharedString::from(app.name),
+                    subtitle: Some(match files.as_slice() {
+                        [file] => format!(
+                            "Open {}",
+                            file.file_name().unwrap_or_default().to_string_lossy()
+                        )
+                        .into(),
+                        files => format!("Open {} files", files.len()).into(),
+                    }),
+                    is_open: true,
+                },
+                SearchResult::MenuItem(menu_item) => GpuiApp::provider_row(
+                    menu_item.title.clone(),
+                    format!("{} › {}", menu_item.app.name, menu_item.breadcrumb()),
+                ),
+                SearchResult::Shortcut(shortcut) => GpuiApp::provider_row(
+                    shortcut.name,
+                    match &shortcut.input {
+                        Some(input) => format!("Run shortcut with “{input}”"),
+                        None => "Run shortcut".to_owned(),
+                    },
+                ),
+                SearchResult::SshHost(host) => GpuiApp::provider_row(host.name, "Connect with SSH"),
+                SearchResult::Project(project) => GpuiApp::provider_row(
+                    project.name,
+                    format!("Open in {}", project.target.display_name()),
+                ),
+                // Multi-line text (like pretty-printed JSON) is copied in
+                // full, but only its first line fits in a row
+                SearchResult::Text { text, label } => match text.split_once('\n') {
+                    Some((first_line, _)) => {
+                        GpuiApp::provider_row(format!("{first_line} …"), label)
+                    }
+                    None => GpuiApp::provider_row(text, label),
+                },
+                SearchResult::Command(command) => GpuiApp::provider_row(
+                    command.keyword,
+                    match &command.action {
+                        CommandAction::Url(url) => url.clone(),
+                        CommandAction::AppleScript(_) | CommandAction::AppleScriptFile(_) => {
+                            "Run script".to_owned()
+                        }
+                    },
+                ),
+                SearchResult::Keyword {
+                    keyword,
+                    description,
+                } => GpuiApp::provider_row(keyword, description),
+                SearchResult::PastQuery { query, label } => GpuiApp::provider_row(query, label),
+                SearchResult::Correction { query } => GpuiApp::provider_row(
+                    format!("Did you mean “{query}”?"),
+                    "Search again with this spelling",
+                ),
+                SearchResult::Internal(command) => {
+                    GpuiApp::provider_row(command.name(), command.description())
+                }
+                SearchResult::Progress(progress) => GpuiApp {
+                    name: SharedString::from(progress.label),
+                    // Rows without a percentage show a spinner instead
+                    subtitle: progress.percent.map(|percent| format!("{percent}%").into()),
+                    is_open: true,
+                    icon: None,
+                },
+                SearchResult::Url { name, url } => GpuiApp::provider_row(name, url.to_string()),
+            };
+
+            if gpui_app.icon.is_none() {
+                gpui_app.icon = ProviderIcon::for_result(result)
+                    .and_then(|icon| self.provider_icons.load(icon, tint, cx));
             }
+
+            self.apps
+                .lock()
+                .expect("no lock poisoning")
+                .insert(result.clone(), gpui_app.clone());
+
+            gpui_app
         }
     }
 }
+
+/// Row of an app, dimmed when the app isn't running. Its subtitle lists
+/// what sets the app apart, such as its version or being a web app.
+fn app_row(app: ExecutableApp, icon: Option<Arc<RenderImage>>) -> GpuiApp {
+    let kind = if app.is_web_app {
+        Some("Web app".to_owned())
+    } else {
+        app.category_display_name()
+    };
+    let version = app.version.clone();
+    let architecture = app
+        .architecture
+        .map(|architecture| architecture.display_name().to_owned());
+    let location = app.location.badge().map(str::to_owned);
+    let usage = app.usage.map(usage_summary);
+    let subtitle = [kind, version, architecture, location, usage]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join(" · ");
+
+    GpuiApp {
+        name: SharedString::from(app.name),
+        subtitle: (!subtitle.is_empty()).then(|| subtitle.into()),
+        is_open: app.is_open,
+        icon,
+    }
+}
+
+/// "Opened 34 times · 2h ago", so that users understand why an app ranks high
+fn usage_summary(usage: AppUsage) -> String {
+    let times = match usage.launch_count {
+        1 => "Opened once".to_owned(),
+        count => format!("Opened {count} times"),
+    };
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default();
+    let minutes_ago = now.saturating_sub(usage.last_opened) / 60;
+    let ago = match minutes_ago {
+        0 => "just now".to_owned(),
+        1..60 => format!("{minutes_ago}m ago"),
+        60..1440 => format!("{}h ago", minutes_ago / 60),
+        _ => format!("{}d ago", minutes_ago / 1440),
+    };
+
+    format!("{times} · {ago}")
+}
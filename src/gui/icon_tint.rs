@@ -0,0 +1,97 @@
+use std::io::Cursor;
+
+use gpui::Hsla;
+use image::ImageFormat;
+use rootcause::Report;
+
+/// Grey every provider icon is drawn with
+const PROVIDER_ICON_COLOR: &str = "#8e8e93";
+
+/// Brightness of the darkest parts of a tinted app icon, relative to the
+/// tint, so that the shapes of the icon remain visible
+const MIN_SHADE: f32 = 0.4;
+
+/// Color icons are drawn with when [`IconStyle::Monochrome`] is selected
+///
+/// [`IconStyle::Monochrome`]: crate::fs::config::IconStyle::Monochrome
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Tint([u8; 3]);
+
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    reason = "channels are clamped to 0..=255 before casting"
+)]
+fn to_channel(value: f32) -> u8 {
+    value.clamp(0.0, 255.0).round() as u8
+}
+
+impl Tint {
+    #[must_use]
+    pub fn from_color(color: Hsla) -> Self {
+        let rgba = color.to_rgb();
+        Self([rgba.r, rgba.g, rgba.b].map(|channel| to_channel(channel * 255.0)))
+    }
+
+    fn hex(self) -> String {
+        let [r, g, b] = self.0;
+        format!("#{r:02x}{g:02x}{b:02x}")
+    }
+
+    /// A provider icon, drawn with the tint instead of grey
+    #[must_use]
+    pub fn svg(self, svg: &[u8]) -> Vec<u8> {
+        String::from_utf8_lossy(svg)
+            .replace(PROVIDER_ICON_COLOR, &self.hex())
+            .into_bytes()
+    }
+
+    /// An app icon, turned into shades of the tint
+    pub fn png(self, png: &[u8]) -> Result<Vec<u8>, Report> {
+        let mut image = image::load_from_memory_with_format(png, ImageFormat::Png)?.to_rgba8();
+        for pixel in image.pixels_mut() {
+            pixel.0 = self.pixel(pixel.0);
+        }
+
+        let mut tinted = Vec::new();
+        image.write_to(&mut Cursor::new(&mut tinted), ImageFormat::Png)?;
+
+        Ok(tinted)
+    }
+
+    /// Light parts of the icon get the tint, darker ones a darker
+    /// shade of it. Transparency is kept as is.
+    fn pixel(self, [r, g, b, a]: [u8; 4]) -> [u8; 4] {
+        let luminance =
+            (0.2126 * f32::from(r) + 0.7152 * f32::from(g) + 0.0722 * f32::from(b)) / 255.0;
+        let shade = MIN_SHADE + (1.0 - MIN_SHADE) * luminance;
+        let [r, g, b] = self.0.map(|channel| to_channel(f32::from(channel) * shade));
+
+        [r, g, b, a]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tint_svg() {
+        let svg = br##"<svg stroke="#8e8e93"><path d="M7 9h10"/></svg>"##;
+        let tinted = Tint([255, 16, 0]).svg(svg);
+
+        assert_eq!(
+            String::from_utf8(tinted).unwrap(),
+            r##"<svg stroke="#ff1000"><path d="M7 9h10"/></svg>"##
+        );
+    }
+
+    #[test]
+    fn test_tint_pixel() {
+        let tint = Tint([200, 100, 50]);
+
+        assert_eq!(tint.pixel([255, 255, 255, 255]), [200, 100, 50, 255]);
+        assert_eq!(tint.pixel([0, 0, 0, 128]), [80, 40, 20, 128]);
+        assert_eq!(tint.pixel([0, 0, 0, 0]), [80, 40, 20, 0]);
+    }
+}
@@ -0,0 +1,70 @@
+//! The layout of the search window picked for a provider, with `Cmd+L`
+//! while its keyword is typed. Providers without one are shown in the
+//! `layout_mode` of the configuration.
+
+use std::{collections::HashMap, sync::Mutex};
+
+use rootcause::Report;
+
+use crate::fs::{
+    config::LayoutMode,
+    db::{AppPersistence, FilesystemPersistence},
+};
+
+/// Key of the data file holding the remembered layouts
+const LAYOUT_MODES_KEY: &str = "layout_modes";
+
+/// Layout by provider keyword, read from the data file the first time
+/// they are needed
+static LAYOUT_MODES: Mutex<Option<HashMap<String, LayoutMode>>> = Mutex::new(None);
+
+fn with_layout_modes<T>(f: impl FnOnce(&mut HashMap<String, LayoutMode>) -> T) -> T {
+    let mut layout_modes = LAYOUT_MODES.lock().expect("no lock poisoning");
+    let layout_modes = layout_modes.get_or_insert_with(|| {
+        FilesystemPersistence::open()
+            .and_then(|db| db.get_data(LAYOUT_MODES_KEY))
+            .unwrap_or_default()
+    });
+
+    f(layout_modes)
+}
+
+/// The keyword a query starts with, as in `weather` for `weather paris`.
+/// A word only counts once a space follows it, as it is an app name
+/// being typed otherwise.
+#[must_use]
+pub fn provider_keyword(query: &str) -> Option<&str> {
+    let (keyword, _) = query.trim_start().split_once(' ')?;
+    (!keyword.is_empty()).then_some(keyword)
+}
+
+/// The layout picked for the provider of `keyword`, if one was
+#[must_use]
+pub fn preferred(keyword: &str) -> Option<LayoutMode> {
+    with_layout_modes(|layout_modes| layout_modes.get(&keyword.to_lowercase()).copied())
+}
+
+/// Remembers the layout picked for the provider of `keyword`, and saves
+/// it to the data file right away, as it is rarely changed
+pub fn remember(keyword: &str, layout: LayoutMode) -> Result<(), Report> {
+    let layout_modes = with_layout_modes(|layout_modes| {
+        layout_modes.insert(keyword.to_lowercase(), layout);
+        layout_modes.clone()
+    });
+
+    FilesystemPersistence::open()?.save_data(LAYOUT_MODES_KEY, layout_modes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_provider_keyword() {
+        assert_eq!(provider_keyword("weather paris"), Some("weather"));
+        assert_eq!(provider_keyword("stats "), Some("stats"));
+        assert_eq!(provider_keyword("  ssh host"), Some("ssh"));
+        assert_eq!(provider_keyword("safari"), None);
+        assert_eq!(provider_keyword(""), None);
+    }
+}
@@ -1,3 +1,10 @@
+pub mod app_icons;
+pub mod cheat_sheet;
 pub mod gpui_app;
+pub mod icon_tint;
+pub mod layout_modes;
+pub mod provider_icons;
 pub mod search_bar;
 pub mod search_engine;
+pub mod text_macros;
+pub mod window_position;
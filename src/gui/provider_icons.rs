@@ -0,0 +1,90 @@
+use std::sync::Arc;
+
+use gpui::{ImageFormat, RenderImage};
+
+use crate::{app::AppLocation, extensions::SearchResult, gui::icon_tint::Tint};
+
+/// Icon displayed next to the results of a provider, since only
+/// apps come with their own icon.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ProviderIcon {
+    MenuItem,
+    Shortcut,
+    Link,
+    Text,
+    Command,
+    Terminal,
+    Folder,
+    /// Apps without an icon, as they aren't stored on this Mac
+    Cloud,
+}
+
+impl ProviderIcon {
+    #[must_use]
+    pub fn for_result(result: &SearchResult) -> Option<Self> {
+        match result {
+            SearchResult::Executable(app) if app.location != AppLocation::Local => {
+                Some(ProviderIcon::Cloud)
+            }
+            SearchResult::Executable(_)
+            | SearchResult::TrashedApp { .. }
+            | SearchResult::OpenWith { .. } => None,
+            SearchResult::MenuItem(_) => Some(ProviderIcon::MenuItem),
+            SearchResult::Shortcut(_) => Some(ProviderIcon::Shortcut),
+            SearchResult::SshHost(_) => Some(ProviderIcon::Terminal),
+            SearchResult::Project(_) => Some(ProviderIcon::Folder),
+            SearchResult::Url { .. } => Some(ProviderIcon::Link),
+            SearchResult::Text { .. }
+            | SearchResult::PastQuery { .. }
+            | SearchResult::Correction { .. } => Some(ProviderIcon::Text),
+            SearchResult::Command(_)
+            | SearchResult::Keyword { .. }
+            | SearchResult::Internal(_)
+            | SearchResult::Progress(_) => Some(ProviderIcon::Command),
+        }
+    }
+
+    /// SVGs are sized for the row icon on a Retina display,
+    /// as they are rendered at their own size.
+    fn svg_data(self) -> &'static [u8] {
+        match self {
+            ProviderIcon::MenuItem => include_bytes!("../../assets/icons/menu.svg"),
+            ProviderIcon::Shortcut => include_bytes!("../../assets/icons/shortcut.svg"),
+            ProviderIcon::Link => include_bytes!("../../assets/icons/link.svg"),
+            ProviderIcon::Text => include_bytes!("../../assets/icons/text.svg"),
+            ProviderIcon::Command => include_bytes!("../../assets/icons/command.svg"),
+            ProviderIcon::Terminal => include_bytes!("../../assets/icons/terminal.svg"),
+            ProviderIcon::Folder => include_bytes!("../../assets/icons/folder.svg"),
+            ProviderIcon::Cloud => include_bytes!("../../assets/icons/cloud.svg"),
+        }
+    }
+}
+
+/// Provider icons, rendered once and then shared by every result of every
+/// window, like app icons are. Icons are rendered once per tint, as the tint
+/// changes along with the appearance of macOS.
+#[derive(Default)]
+pub struct ProviderIcons(scc::HashMap<(ProviderIcon, Option<Tint>), Arc<RenderImage>>);
+
+impl ProviderIcons {
+    pub fn load(
+        &self,
+        icon: ProviderIcon,
+        tint: Option<Tint>,
+        cx: &gpui::App,
+    ) -> Option<Arc<RenderImage>> {
+        if let Some(cached_icon) = self.0.get_sync(&(icon, tint)) {
+            return Some(cached_icon.get().clone());
+        }
+
+        let svg_data = match tint {
+            Some(tint) => tint.svg(icon.svg_data()),
+            None => icon.svg_data().to_vec(),
+        };
+        let image = gpui::Image::from_bytes(ImageFormat::Svg, svg_data);
+        let render_image = image.to_image_data(cx.svg_renderer()).ok()?;
+        let _ = self.0.insert_sync((icon, tint), render_image.clone());
+
+        Some(render_image)
+    }
+}
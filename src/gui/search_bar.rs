@@ -1,23 +1,49 @@
 use std::cmp::min;
+use std::ops::Range;
+use std::sync::Arc;
+use std::time::Duration;
 
 use gpui::prelude::FluentBuilder;
 use gpui::{
-    AppContext, Context, Corners, ElementId, Entity, Fill, Hsla, InteractiveElement, IntoElement,
-    MouseButton, Negate, ParentElement, Pixels, Point, Render, ScrollHandle,
-    StatefulInteractiveElement, Styled, Subscription, Window, div, img, px,
+    Animation, AnimationExt, AppContext, ClipboardItem, Context, Corners, ElementId, Entity,
+    EntityInputHandler, Fill, FontWeight, HighlightStyle, Hsla, InteractiveElement, IntoElement,
+    KeyDownEvent, Modifiers, ModifiersChangedEvent, MouseButton, MouseDownEvent, MouseMoveEvent,
+    MouseUpEvent, Negate, ParentElement, PathPromptOptions, Pixels, Point, Render, ScrollHandle,
+    ScrollWheelEvent, SharedString, StatefulInteractiveElement, Styled, StyledText, Subscription,
+    Task, Window, div, img, px,
 };
-use gpui_component::input::{Input, InputEvent, InputState};
+use gpui_component::input::{Input, InputEvent, InputState, Position};
+use gpui_component::menu::{ContextMenuExt, PopupMenu};
 use gpui_component::{ActiveTheme, StyledExt};
+use rootcause::{Report, report};
 
-use crate::app::AppString;
+use crate::app::{AppString, Architecture, ExecutableApp, RunningApp};
 use crate::command::CommandTrie;
-use crate::extensions::{SearchEngine, SearchResult};
-use crate::fs::config::config_file_path;
+use crate::errors::{self, ErrorEvent};
+use crate::extensions::internal_commands::InternalCommand;
+use crate::extensions::query_operators::QueryOperators;
+use crate::extensions::{SearchContext, SearchEngine, SearchResult};
+use crate::fs::config::{Configuration, LayoutMode, SortMode, WindowConfig, config_file_path};
+use crate::fs::portable::{self, PortableConfig};
+use crate::gui::cheat_sheet;
 use crate::gui::gpui_app::{GpuiApp, GpuiAppLoader};
+use crate::gui::layout_modes;
 use crate::gui::search_engine::GpuiSearchEngine;
+use crate::gui::text_macros;
+use crate::gui::window_position;
+use crate::launch::{self, Remedy};
+use crate::notifications::{self, Notification};
+use crate::permissions::{self, Permission};
 use crate::platform::{ImplPlatform, Platform};
+use crate::updater::{self, Version};
 use crate::url::Url;
-use crate::{EnterPressed, EscPressed, OpenSettings, TabBackSelectApp, TabSelectApp};
+use crate::{
+    AcceptCompletion, CheckForUpdates, CopyResultLocation, CopyResultName, CycleSortMode,
+    EnterAlternate, EnterKeepOpen, EnterPressed, EscPressed, ExportConfig, ImportConfig,
+    MoveToTrash, OpenSettings, OpenWithRosetta, QuickLook, RestartToUpdate, RevealResult,
+    SelectFirst, SelectLast, SelectPageDown, SelectPageUp, SpacePressed, TabBackSelectApp,
+    TabSelectApp, ToggleCheatSheet, ToggleHidden, ToggleLayout, TogglePinned, TogglePrivateMode,
+};
 
 pub struct SearchBar<SE: SearchEngine> {
     search_engine: Entity<GpuiSearchEngine<SE>>,
@@ -34,36 +60,165 @@ pub struct SearchBar<SE: SearchEngine> {
     ///
     /// `scrolled_result_idx` + `hovered_offset_idx` = selected app index
     hovered_offset_idx: usize,
+    /// What last moved the selection. Unless it was the mouse, rows
+    /// moving under a still mouse don't take the selection (nor look
+    /// hovered), until the mouse moves.
+    selection_source: SelectionSource,
     scroll_handle: ScrollHandle,
+    /// Distance scrolled with the wheel or trackpad that doesn't add up to
+    /// a whole row yet, as results scroll by rows
+    scroll_remainder: f32,
     gpui_app_renderer: GpuiAppLoader,
+    config: Arc<Configuration>,
+    /// The app that was in front before the window opened, which text
+    /// results are pasted into
+    previous_app: Option<RunningApp>,
+    /// Layout currently shown, which is expanded in compact mode while
+    /// there are results to show
+    layout: LayoutMode,
+    /// Window heights (from, to) of the latest expand/collapse animation
+    height_transition: (f32, f32),
+    /// Resizes the window for the latest animation, replaced (and so
+    /// cancelled) by the next one
+    window_resize: Option<Task<()>>,
+    /// When enabled, the query is masked and nothing the user searches
+    /// for or opens is learned by the search engine.
+    private_mode: bool,
+    /// Modifier keys held. While `Cmd` is, rows show their quick-open shortcut.
+    modifiers: Modifiers,
+    /// Order of results which rank the same, once changed with `Cmd+S`
+    sort_mode: Option<SortMode>,
+    /// Version of the update installed on the next launch, if any
+    staged_update: Option<Version>,
+    /// Latest error, shown above the results until dismissed
+    error: Option<ErrorBanner>,
+    /// Latest notification that couldn't be posted to the Notification
+    /// Center, shown above the results until dismissed
+    notice: Option<Notification>,
+    /// How many results at once fit in the window, whose height is set
+    /// by `max_visible_rows` in the configuration
+    visible_rows: usize,
+    /// Whether the key bindings are listed over the results
+    cheat_sheet_shown: bool,
+    /// Set once the user starts dragging the window, by its edges or by
+    /// the input with `Cmd` held. Its position is then remembered, when
+    /// `remember_position` is set.
+    window_dragged: bool,
+    /// Row the left button was pressed on, and where. It opens once the
+    /// button is released, unless it was dragged out of the list meanwhile.
+    row_press: Option<(usize, Point<Pixels>)>,
+}
+
+/// What moves the selected result
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SelectionSource {
+    /// The mouse, which moved over a row
+    Mouse,
+    /// The keyboard, such as with `Tab`, after which Space previews the
+    /// selected result rather than typing a space
+    Keyboard,
+    /// New results, which moved the selection back to the top
+    Results,
 }
 
-/// The number of elements to render in gpui. This corresponds
-/// to how many search results at once are physically able to
-/// appear in the GUI (whose window height is a fixed size)
-const MAX_RENDERED_ELS: usize = 4;
+/// An error shown above the results
+#[derive(Clone)]
+struct ErrorBanner {
+    event: ErrorEvent,
+    /// Whether the whole error is shown, rather than only its summary
+    details_shown: bool,
+}
+
+/// Height of the window when only the input is shown (input + padding)
+const COMPACT_WINDOW_HEIGHT: u32 = 80;
+/// Height the results area takes besides its rows
+const RESULTS_AREA_PADDING: u32 = 14;
+const LAYOUT_ANIMATION_DURATION: Duration = Duration::from_millis(120);
+/// New rows fade in rather than popping into place
+const ROW_FADE_IN_DURATION: Duration = Duration::from_millis(150);
+/// Period of the bar pulsing under the input while results are loading
+const LOADING_PULSE_DURATION: Duration = Duration::from_millis(900);
+/// Period of the spinner of tasks which can't tell how far along they are
+const SPINNER_DURATION: Duration = Duration::from_millis(800);
+const SPINNER_FRAMES: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
 /// The height of the element containing a search result (icon + app name)
 const RESULT_EL_HEIGHT: usize = 44;
 /// The padding (all sides) of the element containing a search result (icon + app name)
 const RESULT_EL_PADDING: usize = 8;
 
+/// Horizontal padding of the text inside the input, used to align
+/// the inline completion with the query
+const INPUT_PADDING_X: f32 = 12.0;
+
+/// Rows which can be opened with `Cmd` and their number, as in `Cmd+1`
+const MAX_QUICK_OPEN: usize = 9;
+
+/// Distance the mouse moves with the button pressed on a row before the
+/// files of its result are dragged, so that a shaky click still opens it
+const DRAG_THRESHOLD: f32 = 4.0;
+
+const PLACEHOLDER: &str = "Search an app";
+const PRIVATE_MODE_PLACEHOLDER: &str = "Search an app (private)";
+
+/// Height of the window when the results area is shown, which fits
+/// `max_visible_rows` rows of results
+fn expanded_window_height(config: &WindowConfig) -> u32 {
+    let rows_height = config.max_visible_rows.max(1) * RESULT_EL_HEIGHT;
+
+    u32::try_from(rows_height)
+        .unwrap_or(u32::MAX)
+        .saturating_add(COMPACT_WINDOW_HEIGHT + RESULTS_AREA_PADDING)
+}
+
+/// Height of the window in `layout`, which it opens with in the layout of
+/// the configuration
+#[allow(
+    clippy::cast_precision_loss,
+    reason = "window heights are small integers"
+)]
+#[must_use]
+pub fn window_height(config: &WindowConfig, layout: LayoutMode) -> f32 {
+    if layout == LayoutMode::Expanded {
+        expanded_window_height(config) as f32
+    } else {
+        COMPACT_WINDOW_HEIGHT as f32
+    }
+}
+
+/// The rest of `name` after `query`, if `name` starts with it (ignoring
+/// case) and the cursor is at the end of the query, where typing goes on
+fn completion_suffix<'a>(query: &str, cursor: usize, name: &'a AppString) -> Option<&'a str> {
+    if query.is_empty() || cursor != query.len() {
+        return None;
+    }
+
+    name.strip_prefix_ignore_case(query)
+        .filter(|suffix| !suffix.is_empty())
+}
+
 impl<SE: SearchEngine> SearchBar<SE> {
     pub fn new(
         window: &mut Window,
         cx: &mut Context<Self>,
         search_engine: Entity<GpuiSearchEngine<SE>>,
+        context: SearchContext,
+        config: Arc<Configuration>,
     ) -> Self {
         let input_state = cx.new(|cx| {
-            let is = InputState::new(window, cx).placeholder("Search an app");
+            let is = InputState::new(window, cx).placeholder(PLACEHOLDER);
             is.focus(window, cx);
             is
         });
 
+        let previous_app = context.frontmost_app.clone();
+        // Running apps are listed before anything is typed
+        let initial_query = config.open_apps.group.then(AppString::default);
         search_engine.update(cx, |this, cx| {
-            this.preload(cx);
+            this.preload(cx, window, context, initial_query);
         });
 
-        let subscriptions = vec![cx.subscribe_in(&input_state, window, {
+        let mut subscriptions = vec![cx.subscribe_in(&input_state, window, {
             let input_state = input_state.clone();
             move |this, _, ev: &InputEvent, window, cx| {
                 if let InputEvent::Change = ev {
@@ -76,33 +231,983 @@ impl<SE: SearchEngine> SearchBar<SE> {
 
                     this.scrolled_result_idx = 0;
                     this.hovered_offset_idx = 0;
+                    this.scroll_remainder = 0.0;
+                    this.selection_source = SelectionSource::Results;
 
                     cx.notify();
                 }
             }
         })];
 
+        subscriptions.push(
+            cx.observe_in(&search_engine, window, |this, _, window, cx| {
+                this.open_top_app_if_requested(window, cx);
+            }),
+        );
+        Self::watch_errors(cx);
+        Self::watch_notices(cx);
+
+        let layout = config.layout_mode;
+        let initial_height = window_height(&config.window, layout);
+
+        if config.window.remember_position {
+            subscriptions.push(cx.observe_window_bounds(window, |this, window, cx| {
+                if this.window_dragged
+                    && let Some(display) = window.display(cx)
+                {
+                    window_position::remember(u32::from(display.id()), window.bounds().origin);
+                }
+            }));
+            subscriptions.push(cx.on_release(|_, _| {
+                if let Err(report) = window_position::save() {
+                    errors::report_error("Could not remember the window position", report);
+                }
+            }));
+        }
+
+        let gpui_app_renderer = {
+            let search_engine = search_engine.read(cx);
+            GpuiAppLoader::new(
+                config.cache_budgets,
+                search_engine.app_icons.clone(),
+                search_engine.provider_icons.clone(),
+            )
+        };
+
         Self {
             search_engine,
             input_state,
-            commands: CommandTrie::default(),
+            commands: CommandTrie::new(&config.commands),
             subscriptions,
             scrolled_result_idx: 0,
             hovered_offset_idx: 0,
+            selection_source: SelectionSource::Mouse,
             scroll_handle: ScrollHandle::new(),
-            gpui_app_renderer: GpuiAppLoader::default(),
+            scroll_remainder: 0.0,
+            gpui_app_renderer,
+            private_mode: false,
+            modifiers: Modifiers::default(),
+            sort_mode: None,
+            staged_update: updater::staged_update(),
+            error: None,
+            notice: None,
+            visible_rows: config.window.max_visible_rows.max(1),
+            cheat_sheet_shown: false,
+            window_dragged: false,
+            row_press: None,
+            layout,
+            height_transition: (initial_height, initial_height),
+            window_resize: None,
+            config,
+            previous_app,
+        }
+    }
+
+    /// Index of the first result after the running apps, when they are
+    /// listed in their own section (see `open_apps.group`)
+    fn open_apps_section_end(&self, cx: &Context<Self>) -> Option<usize> {
+        let search_engine = self.search_engine.read(cx);
+        if !self.config.open_apps.group || search_engine.results_query.grapheme_len() > 1 {
+            return None;
+        }
+
+        let section_end = search_engine
+            .results
+            .iter()
+            .position(|result| !matches!(result, SearchResult::Executable(app) if app.is_open))?;
+
+        (section_end > 0).then_some(section_end)
+    }
+
+    /// Selects the result at `idx` (or the last one, past the end), scrolling
+    /// the list as little as needed for it to be shown
+    fn select_result(&mut self, idx: usize, cx: &Context<Self>) {
+        let results_len = self.search_engine.read(cx).results.len();
+        let Some(last_idx) = results_len.checked_sub(1) else {
+            return;
+        };
+        let idx = idx.min(last_idx);
+
+        if idx < self.scrolled_result_idx {
+            self.scrolled_result_idx = idx;
+        } else if idx >= self.scrolled_result_idx + self.visible_rows {
+            self.scrolled_result_idx = idx + 1 - self.visible_rows;
+        }
+        self.hovered_offset_idx = idx - self.scrolled_result_idx;
+        self.selection_source = SelectionSource::Keyboard;
+    }
+
+    /// Scrolls the results by whole rows as the wheel or trackpad moves. The
+    /// selection stays on the row under the mouse, which takes it back from
+    /// the keyboard, so that the list stops following it.
+    fn scroll_results(
+        &mut self,
+        event: &ScrollWheelEvent,
+        window: &Window,
+        cx: &mut Context<Self>,
+    ) {
+        let results_len = self.search_engine.read(cx).results.len();
+        let max_scrolled_idx = results_len.saturating_sub(self.visible_rows);
+
+        // In pixels for trackpads, in lines for mouse wheels. Scrolling up
+        // gives a positive distance, moving the rows towards the first one.
+        self.scroll_remainder += f32::from(event.delta.pixel_delta(window.line_height()).y);
+        let is_past_end = (self.scrolled_result_idx == 0 && self.scroll_remainder > 0.0)
+            || (self.scrolled_result_idx >= max_scrolled_idx && self.scroll_remainder < 0.0);
+        if is_past_end {
+            // Would otherwise hold back scrolling the other way
+            self.scroll_remainder = 0.0;
+            return;
+        }
+
+        #[allow(
+            clippy::cast_precision_loss,
+            reason = "rows are a few dozen pixels high"
+        )]
+        let row_height = RESULT_EL_HEIGHT as f32;
+        let rows = (self.scroll_remainder / row_height).trunc();
+        if rows.abs() < 1.0 {
+            return;
+        }
+        self.scroll_remainder -= rows * row_height;
+
+        #[allow(
+            clippy::cast_possible_truncation,
+            clippy::cast_sign_loss,
+            reason = "a whole number of rows, which is positive once its sign is dropped"
+        )]
+        let row_count = rows.abs() as usize;
+        self.scrolled_result_idx = if rows > 0.0 {
+            self.scrolled_result_idx.saturating_sub(row_count)
+        } else {
+            (self.scrolled_result_idx + row_count).min(max_scrolled_idx)
+        };
+
+        self.selection_source = SelectionSource::Mouse;
+        self.scroll_handle.set_offset(Point::default());
+        cx.notify();
+    }
+
+    /// Moves the window along with the mouse until the button is released
+    fn start_window_drag(&mut self, window: &mut Window) {
+        self.window_dragged = true;
+        window.start_window_move();
+    }
+
+    /// Whether an input method is composing text (as when typing Japanese or
+    /// Chinese). Enter, Tab, Escape, Space and the arrows then commit or
+    /// change the composition, so Fetch leaves them to the input.
+    fn is_composing(&self, window: &mut Window, cx: &mut Context<Self>) -> bool {
+        self.input_state.update(cx, |input_state, cx| {
+            input_state.marked_text_range(window, cx).is_some()
+        })
+    }
+
+    /// Whether part of the query is selected, in which case copying copies it
+    fn has_selected_text(&self, window: &mut Window, cx: &mut Context<Self>) -> bool {
+        self.input_state.update(cx, |input_state, cx| {
+            input_state
+                .selected_text_range(false, window, cx)
+                .is_some_and(|selection| !selection.range.is_empty())
+        })
+    }
+
+    /// Opens the top app of a query ending with `!` (as in `firefox!`),
+    /// as soon as its results arrive
+    fn open_top_app_if_requested(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let query: AppString = self.input_state.read(cx).value().into();
+        if !QueryOperators::parse(&query).open_top_app {
+            return;
+        }
+
+        let search_engine = self.search_engine.read(cx);
+        // Other providers read the `!` as part of their input
+        let is_top_app = search_engine.results_query == query
+            && matches!(
+                search_engine.results.first(),
+                Some(SearchResult::Executable(_))
+            );
+        if is_top_app {
+            self.open_result(0, false, false, window, cx);
+        }
+    }
+
+    /// Shows errors published by the engine, providers and actions.
+    /// An error published before the window opened is shown as well,
+    /// unless it was dismissed already.
+    fn watch_errors(cx: &mut Context<Self>) {
+        let mut rx = errors::subscribe();
+
+        cx.spawn(async move |this, cx| {
+            loop {
+                let error = rx.borrow_and_update().clone();
+
+                let updated = this.update(cx, |this, cx| {
+                    this.error = error.map(|event| ErrorBanner {
+                        event,
+                        details_shown: false,
+                    });
+                    cx.notify();
+                });
+
+                if updated.is_err() || rx.changed().await.is_err() {
+                    return;
+                }
+            }
+        })
+        .detach();
+    }
+
+    /// Shows the notifications Fetch wasn't allowed to post
+    fn watch_notices(cx: &mut Context<Self>) {
+        let mut rx = notifications::subscribe();
+
+        cx.spawn(async move |this, cx| {
+            loop {
+                let notice = rx.borrow_and_update().clone();
+
+                let updated = this.update(cx, |this, cx| {
+                    this.notice = notice;
+                    cx.notify();
+                });
+
+                if updated.is_err() || rx.changed().await.is_err() {
+                    return;
+                }
+            }
+        })
+        .detach();
+    }
+
+    /// The layout picked for the provider whose keyword is typed, or the
+    /// one of the configuration
+    fn preferred_layout(&self, cx: &Context<Self>) -> LayoutMode {
+        let query = self.input_state.read(cx).value();
+        layout_modes::provider_keyword(&query)
+            .and_then(layout_modes::preferred)
+            .unwrap_or(self.config.layout_mode)
+    }
+
+    /// Switches the provider whose keyword is typed to the other layout,
+    /// which is remembered for the next time it is typed
+    fn toggle_layout(&mut self, cx: &Context<Self>) {
+        let query = self.input_state.read(cx).value();
+        let Some(keyword) = layout_modes::provider_keyword(&query) else {
+            return;
+        };
+
+        let layout = match self.preferred_layout(cx) {
+            LayoutMode::Compact => LayoutMode::Expanded,
+            LayoutMode::Expanded => LayoutMode::Compact,
+        };
+        if let Err(report) = layout_modes::remember(keyword, layout) {
+            errors::report_error("Could not remember the layout", report);
+        }
+    }
+
+    /// In compact mode, expands the window when there are results to show
+    /// and collapses it back when there are none.
+    fn update_layout(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let layout = if self.error.is_some()
+            || self.notice.is_some()
+            || self.cheat_sheet_shown
+            || !self.search_engine.read(cx).results.is_empty()
+        {
+            LayoutMode::Expanded
+        } else {
+            self.preferred_layout(cx)
+        };
+
+        if layout != self.layout {
+            let (from_height, to_height) = (
+                window_height(&self.config.window, self.layout),
+                window_height(&self.config.window, layout),
+            );
+            self.height_transition = (from_height, to_height);
+            self.layout = layout;
+
+            self.window_resize = Some(cx.spawn_in(window, async move |_, cx| {
+                // Grows before the results slide in, and shrinks once they
+                // slid out, so that the animation is never cut off
+                if to_height < from_height {
+                    cx.background_executor()
+                        .timer(LAYOUT_ANIMATION_DURATION)
+                        .await;
+                }
+                let _ = cx.update(|window, _| {
+                    if let Err(report) = ImplPlatform::set_window_height(window, to_height) {
+                        errors::report_error("Could not resize the window", report);
+                    }
+                });
+            }));
+        }
+    }
+
+    /// The query typed so far, and the remaining characters of the top result's
+    /// name if it starts with the query.
+    fn completion(&self, cx: &Context<Self>) -> Option<(SharedString, SharedString)> {
+        if self.private_mode || self.scrolled_result_idx + self.hovered_offset_idx != 0 {
+            return None;
+        }
+
+        let input_state = self.input_state.read(cx);
+        let query = input_state.value();
+        let top_result_name = self.search_engine.read(cx).results.first()?.name();
+        let suffix = completion_suffix(&query, input_state.cursor(), &top_result_name)?;
+
+        Some((query, suffix.to_owned().into()))
+    }
+
+    /// Replaces the query, as when the hotkey is pressed twice in a row
+    pub fn set_query(&mut self, query: &str, window: &mut Window, cx: &mut Context<Self>) {
+        self.input_state.update(cx, |input_state, cx| {
+            input_state.set_value(SharedString::from(query.to_owned()), window, cx);
+        });
+        self.search_engine.update(cx, |this, cx| {
+            this.deferred_search(cx, window, query.into());
+        });
+
+        self.scrolled_result_idx = 0;
+        self.hovered_offset_idx = 0;
+        self.selection_source = SelectionSource::Results;
+        cx.notify();
+    }
+
+    /// Expands the abbreviation right before the cursor, if any. Returns
+    /// `false` if there was none.
+    fn expand_text_macro(&mut self, window: &mut Window, cx: &mut Context<Self>) -> bool {
+        let input_state = self.input_state.read(cx);
+        let query = input_state.value();
+        if input_state.cursor() != query.len() {
+            return false;
+        }
+        let Some(expansion) = text_macros::expand(&query, &self.config.text_macros) else {
+            return false;
+        };
+
+        self.set_query(&expansion.query, window, cx);
+        let cursor = u32::try_from(expansion.cursor).unwrap_or(u32::MAX);
+        self.input_state.update(cx, |input_state, cx| {
+            input_state.set_cursor_position(Position::new(0, cursor), window, cx);
+        });
+
+        true
+    }
+
+    /// Replaces the query with the name of the top result. Returns `false` if
+    /// there was nothing to complete.
+    fn accept_completion(&mut self, window: &mut Window, cx: &mut Context<Self>) -> bool {
+        if self.completion(cx).is_none() {
+            return false;
+        }
+
+        let Some(top_result_name) = self
+            .search_engine
+            .read(cx)
+            .results
+            .first()
+            .map(SearchResult::name)
+        else {
+            return false;
+        };
+
+        self.input_state.update(cx, |input_state, cx| {
+            input_state.set_value(SharedString::from(top_result_name.clone()), window, cx);
+        });
+        self.search_engine.update(cx, |this, cx| {
+            this.deferred_search(cx, window, top_result_name);
+        });
+
+        true
+    }
+
+    /// Hides the selected result from searches (or unhides it, when
+    /// reviewing hidden apps), then refreshes the results.
+    fn selected_result(&self, cx: &Context<Self>) -> Option<SearchResult> {
+        let selected_app_idx = self.scrolled_result_idx + self.hovered_offset_idx;
+
+        self.search_engine
+            .read(cx)
+            .results
+            .get(selected_app_idx)
+            .cloned()
+    }
+
+    /// Previews the files of the selected result with Quick Look.
+    /// Returns false if the selected result has no files.
+    fn quick_look_selected(&self, window: &Window, cx: &Context<Self>) -> bool {
+        let Some(paths) = self
+            .selected_result(cx)
+            .map(|result| result.file_paths())
+            .filter(|paths| !paths.is_empty())
+        else {
+            return false;
+        };
+
+        if let Err(report) = ImplPlatform::quick_look(window, &paths) {
+            errors::report_error("Could not preview with Quick Look", report);
+        }
+
+        true
+    }
+
+    /// Drags the files of the result of the row pressed at `offset_idx` out
+    /// of the list, into Finder, the Dock or another app, once the mouse
+    /// moved far enough from where it was pressed
+    fn drag_pressed_row(
+        &mut self,
+        offset_idx: usize,
+        position: Point<Pixels>,
+        window: &Window,
+        cx: &Context<Self>,
+    ) {
+        let Some((pressed_idx, pressed_at)) = self.row_press else {
+            return;
+        };
+        let moved = position - pressed_at;
+        if pressed_idx != offset_idx
+            || f32::from(moved.x).hypot(f32::from(moved.y)) < DRAG_THRESHOLD
+        {
+            return;
+        }
+        // Released outside of the list once dragged, so it doesn't open
+        self.row_press = None;
+
+        let paths = self
+            .search_engine
+            .read(cx)
+            .results
+            .get(self.scrolled_result_idx + offset_idx)
+            .map(SearchResult::file_paths)
+            .unwrap_or_default();
+        if paths.is_empty() {
+            return;
+        }
+
+        if let Err(report) = ImplPlatform::drag_files(window, &paths) {
+            errors::report_error("Could not drag the result", report);
+        }
+    }
+
+    /// Shows the file of the selected result in Finder. Returns false if
+    /// the selected result has no file.
+    fn reveal_selected(&self, cx: &Context<Self>) -> bool {
+        let Some(path) = self
+            .selected_result(cx)
+            .and_then(|result| result.file_paths().into_iter().next())
+        else {
+            return false;
+        };
+
+        if let Err(report) = ImplPlatform::reveal(&path) {
+            errors::report_error("Could not show in Finder", report);
+        }
+
+        true
+    }
+
+    /// Opens the selected app with Rosetta, if it can be, then closes the
+    /// window as opening it normally does
+    fn open_selected_with_rosetta(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(SearchResult::Executable(app)) = self.selected_result(cx) else {
+            return;
+        };
+        if !app.architecture.is_some_and(Architecture::can_use_rosetta) {
+            return;
+        }
+
+        if let Err(report) = ImplPlatform::open_with_rosetta(&app.path) {
+            errors::report_error(format!("Could not open {} with Rosetta", app.name), report);
+            return;
+        }
+
+        self.end_search(Some(SearchResult::Executable(app)), cx);
+        window.remove_window();
+    }
+
+    /// Opens where the selected app gets its updates from: the updates page
+    /// of the Mac App Store, or its Sparkle feed
+    fn check_selected_for_updates(&self, cx: &Context<Self>) {
+        let Some(SearchResult::Executable(app)) = self.selected_result(cx) else {
+            return;
+        };
+        let Some(updates_url) = &app.updates_url else {
+            errors::report_error(
+                format!("{} doesn't say where it gets its updates from", app.name),
+                report!("Neither from the Mac App Store, nor with a Sparkle feed"),
+            );
+            return;
+        };
+
+        if let Err(report) = ImplPlatform::open_url(updates_url) {
+            errors::report_error(format!("Could not check {} for updates", app.name), report);
+        }
+    }
+
+    /// Moves the selected app to the Trash, then refreshes the results,
+    /// in which it is listed as trashed until the index is updated
+    fn move_selected_to_trash(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(SearchResult::Executable(app)) = self.selected_result(cx) else {
+            return;
+        };
+
+        if let Err(report) = ImplPlatform::move_to_trash(&app.path) {
+            permissions::guide_if_denied(&report);
+            errors::report_error(format!("Could not move {} to the Trash", app.name), report);
+            return;
+        }
+
+        let query: AppString = self.input_state.read(cx).value().into();
+        self.search_engine.update(cx, |search_engine, cx| {
+            search_engine.deferred_search(cx, window, query);
+        });
+    }
+
+    /// Copies the path or URL of the selected result. Returns false if
+    /// there is nothing to copy.
+    fn copy_selected_location(&self, cx: &mut Context<Self>) -> bool {
+        let Some(location) = self
+            .selected_result(cx)
+            .as_ref()
+            .and_then(SearchResult::location)
+        else {
+            return false;
+        };
+
+        cx.write_to_clipboard(ClipboardItem::new_string(location));
+        true
+    }
+
+    /// Copies the name of the selected result. Returns false if
+    /// there is nothing to copy.
+    fn copy_selected_name(&self, cx: &mut Context<Self>) -> bool {
+        let Some(result) = self.selected_result(cx) else {
+            return false;
+        };
+
+        cx.write_to_clipboard(ClipboardItem::new_string(result.name().to_string()));
+        true
+    }
+
+    fn toggle_selected_hidden(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(result) = self.selected_result(cx) else {
+            return;
+        };
+
+        let query: AppString = self.input_state.read(cx).value().into();
+        self.search_engine.update(cx, |search_engine, cx| {
+            search_engine.toggle_hidden(&result);
+            search_engine.deferred_search(cx, window, query);
+        });
+
+        self.scrolled_result_idx = 0;
+        self.hovered_offset_idx = 0;
+    }
+
+    /// Pins the selected app to the query, or unpins it if it was, then
+    /// refreshes the results to show where it ranks now
+    fn toggle_selected_pinned(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(result) = self.selected_result(cx) else {
+            return;
+        };
+
+        let query: AppString = self.input_state.read(cx).value().into();
+        self.search_engine.update(cx, |search_engine, cx| {
+            search_engine.toggle_pinned(&query, &result);
+            search_engine.deferred_search(cx, window, query);
+        });
+
+        self.scrolled_result_idx = 0;
+        self.hovered_offset_idx = 0;
+    }
+
+    /// Exports the configuration to the Downloads folder, and shows it in Finder
+    fn export_config(window: &mut Window) {
+        match portable::export_to_downloads() {
+            Ok(path) => {
+                window.remove_window();
+                if let Some(dir) = path.parent()
+                    && let Err(report) = ImplPlatform::open_url(&Url::File(dir.to_path_buf()))
+                {
+                    errors::report_error("Could not show exported configuration", report);
+                }
+            }
+            Err(report) => errors::report_error("Could not export configuration", report),
+        }
+    }
+
+    /// Imports a configuration picked by the user, then restarts Fetch
+    /// so that it is applied
+    fn import_config(window: &mut Window, cx: &mut Context<Self>) {
+        let paths = cx.prompt_for_paths(PathPromptOptions {
+            files: true,
+            directories: false,
+            multiple: false,
+            prompt: Some("Import".into()),
+        });
+
+        cx.spawn_in(window, async move |_, cx| {
+            let Ok(Ok(Some(paths))) = paths.await else {
+                return;
+            };
+            let Some(path) = paths.first() else {
+                return;
+            };
+
+            match PortableConfig::read(path).and_then(|config| config.apply()) {
+                Ok(()) => {
+                    let _ = cx.update(|_, cx| cx.restart());
+                }
+                Err(report) => errors::report_error("Could not import configuration", report),
+            }
+        })
+        .detach();
+    }
+
+    /// Tells the search engine the search is over, with the result opened
+    /// if one was. Searches in private mode are neither learned from nor
+    /// kept in the history of queries.
+    fn end_search(&self, opened_result: Option<SearchResult>, cx: &mut Context<Self>) {
+        let private_mode = self.private_mode;
+        self.search_engine.update(cx, |search_engine, cx| {
+            search_engine.after_search(cx, opened_result.filter(|_| !private_mode));
+        });
+    }
+
+    fn toggle_private_mode(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.private_mode = !self.private_mode;
+
+        let private_mode = self.private_mode;
+        self.search_engine.update(cx, |search_engine, _| {
+            search_engine.set_private_mode(private_mode);
+        });
+        self.input_state.update(cx, |input_state, cx| {
+            input_state.set_masked(private_mode, window, cx);
+            input_state.set_placeholder(
+                if private_mode {
+                    PRIVATE_MODE_PLACEHOLDER
+                } else {
+                    PLACEHOLDER
+                },
+                window,
+                cx,
+            );
+        });
+    }
+
+    /// Switches to the next sort mode, then refreshes the results
+    fn cycle_sort_mode(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let sort_mode = self.sort_mode.unwrap_or(self.config.sort_mode).next();
+        self.sort_mode = Some(sort_mode);
+
+        let query: AppString = self.input_state.read(cx).value().into();
+        self.search_engine.update(cx, |search_engine, cx| {
+            search_engine.set_sort_mode(sort_mode);
+            search_engine.deferred_search(cx, window, query);
+        });
+
+        self.scrolled_result_idx = 0;
+        self.hovered_offset_idx = 0;
+    }
+
+    /// Opens the search result at index `idx`, then closes the window.
+    /// Replaces the results with the output of a command, which
+    /// can then be copied by selecting it.
+    fn show_output(&mut self, command: &str, output: String, cx: &mut Context<Self>) {
+        self.search_engine.update(cx, |search_engine, cx| {
+            search_engine.set_results(vec![SearchResult::Text {
+                text: output.into(),
+                label: format!("Output of “{command}”").into(),
+            }]);
+            cx.notify();
+        });
+        self.scrolled_result_idx = 0;
+        self.hovered_offset_idx = 0;
+    }
+
+    /// Runs a command in the background, as scripts can take a while, then
+    /// shows its output if it has one. Otherwise, the search ends with
+    /// `opened_result` if there is one, and the window closes unless
+    /// `keep_open` is set.
+    fn run_command(
+        keyword: String,
+        run: impl FnOnce() -> Result<Option<String>, Report> + Send + 'static,
+        opened_result: Option<SearchResult>,
+        keep_open: bool,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let run = cx.background_spawn(async move { run() });
+
+        cx.spawn_in(window, async move |this, cx| {
+            let output = run.await.unwrap_or_else(|report| {
+                permissions::guide_if_denied(&report);
+                errors::report_error("Could not run command", report);
+                None
+            });
+
+            let _ = this.update_in(cx, |this, window, cx| {
+                if let Some(output) = output {
+                    this.show_output(&keyword, output, cx);
+                    cx.notify();
+                    return;
+                }
+
+                if opened_result.is_some() {
+                    this.end_search(opened_result, cx);
+                }
+                if !keep_open {
+                    window.remove_window();
+                }
+            });
+        })
+        .detach();
+    }
+
+    /// Forgets an app that is gone since it was indexed, tells the user,
+    /// then searches again, keeping the window open to pick another result
+    fn forget_stale_app(
+        &mut self,
+        app: &ExecutableApp,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        errors::publish(ErrorEvent {
+            summary: format!("{} was moved or deleted", app.name),
+            details: format!(
+                "It is no longer at {}, so it was removed from the results. It is listed \
+                 again once Fetch finds it, wherever it is now.",
+                app.path.display()
+            ),
+            remedy: None,
+        });
+
+        let query: AppString = self.input_state.read(cx).value().into();
+        self.search_engine.update(cx, |search_engine, cx| {
+            search_engine.forget_app(&app.path);
+            search_engine.deferred_search(cx, window, query);
+        });
+
+        self.scrolled_result_idx = 0;
+        self.hovered_offset_idx = 0;
+    }
+
+    /// Pastes the clipboard into the app that was in front before the
+    /// window opened, once the window is closed. Without the Accessibility
+    /// permission, the text stays copied and the user is offered to grant it.
+    fn paste_into_previous_app(&self, cx: &mut Context<Self>) {
+        let Some(app) = self.previous_app.clone() else {
+            return;
+        };
+
+        if !ImplPlatform::has_accessibility_permission() {
+            errors::publish(ErrorEvent {
+                summary: format!("Copied, allow Fetch to paste into {}?", app.name),
+                details: "Pasting presses Cmd+V in the app, which takes the Accessibility \
+                          permission. The text was copied instead."
+                    .to_owned(),
+                remedy: Some(Remedy::GrantPermission(Permission::Accessibility)),
+            });
+            return;
+        }
+
+        cx.background_spawn(async move {
+            if let Err(report) = ImplPlatform::paste_into(&app) {
+                errors::report_error(format!("Could not paste into {}", app.name), report);
+            }
+        })
+        .detach();
+    }
+
+    /// Runs a command acting on Fetch itself, see [`InternalCommand`]
+    fn run_internal_command(&self, command: InternalCommand, cx: &mut Context<Self>) {
+        match command {
+            InternalCommand::Quit => cx.quit(),
+            InternalCommand::Restart => cx.restart(),
+            // Fetch doesn't start with a configuration it can't read, so
+            // it is only restarted once the configuration is known to work
+            InternalCommand::ReloadConfig => match Configuration::read_from_fs() {
+                Ok(_) => cx.restart(),
+                Err(report) => errors::report_error("Could not reload configuration", report),
+            },
+            InternalCommand::RebuildIndex => self.search_engine.read(cx).rebuild_index(),
+        }
+    }
+
+    /// Opens, runs or copies `result`, reporting what went wrong. Links and
+    /// files open the other way their type can when `alternate` is set,
+    /// and text is pasted rather than only copied.
+    fn launch(&self, result: &SearchResult, alternate: bool, cx: &mut Context<Self>) {
+        match result {
+            SearchResult::Executable(app) => {
+                // `open` is waited for to tell why it failed, if it does
+                let app = app.clone();
+                cx.background_spawn(async move { launch::open_app(&app) })
+                    .detach();
+            }
+            SearchResult::TrashedApp { app, trash_path } => {
+                match std::fs::rename(trash_path, &app.path) {
+                    Ok(()) => {
+                        let app = app.clone();
+                        cx.background_spawn(async move { launch::open_app(&app) })
+                            .detach();
+                    }
+                    Err(err) => errors::report_error(
+                        format!("Could not restore {} from the Trash", app.name),
+                        err.into(),
+                    ),
+                }
+            }
+            SearchResult::OpenWith { app, files } => {
+                if let Err(report) = ImplPlatform::open_with(&app.path, files) {
+                    errors::report_error(format!("Could not open with {}", app.name), report);
+                }
+            }
+            SearchResult::MenuItem(menu_item) => {
+                let menu_item = menu_item.clone();
+                // Scripting the menu bar takes a moment, and the app needs
+                // to be brought back to the front after Fetch closes anyway.
+                cx.background_spawn(async move {
+                    if let Err(report) = ImplPlatform::click_menu_item(&menu_item) {
+                        permissions::guide_if_denied(&report);
+                        errors::report_error("Could not click menu item", report);
+                    }
+                })
+                .detach();
+            }
+            SearchResult::Shortcut(shortcut) => {
+                if let Err(report) = ImplPlatform::run_shortcut(shortcut) {
+                    errors::report_error("Could not run shortcut", report);
+                }
+            }
+            SearchResult::SshHost(host) => {
+                if let Err(report) = ImplPlatform::open_ssh(host, self.config.terminal) {
+                    permissions::guide_if_denied(&report);
+                    errors::report_error("Could not open SSH connection", report);
+                }
+            }
+            SearchResult::Project(project) => {
+                if let Err(report) = ImplPlatform::open_project(project, self.config.terminal) {
+                    permissions::guide_if_denied(&report);
+                    errors::report_error("Could not open project", report);
+                }
+            }
+            SearchResult::Url { url, .. } => {
+                launch::open_url(
+                    url,
+                    &self.config.open_behavior,
+                    self.config.terminal,
+                    alternate,
+                );
+            }
+            SearchResult::Text { text, .. } => {
+                cx.write_to_clipboard(ClipboardItem::new_string(text.to_string()));
+                if alternate {
+                    self.paste_into_previous_app(cx);
+                }
+            }
+            SearchResult::Internal(command) => self.run_internal_command(*command, cx),
+            // Ran above, since its output may have to be shown, and
+            // keywords and other queries were typed above
+            SearchResult::Command(_)
+            | SearchResult::Keyword { .. }
+            | SearchResult::PastQuery { .. }
+            | SearchResult::Correction { .. }
+            | SearchResult::Progress(_) => {}
+        }
+    }
+
+    /// Opens the result at `idx`, then closes the window unless `keep_open`
+    /// is set, so that several results can be opened in a row.
+    fn open_result(
+        &mut self,
+        idx: usize,
+        keep_open: bool,
+        alternate: bool,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let result_opt = self
+            .search_engine
+            .read(cx)
+            .results
+            .get(idx)
+            // Cloning removes `cx` lifetime
+            .cloned();
+
+        let Some(result) = result_opt else {
+            // tmp hack: execute command that might exist
+            let query = self.input_state.read(cx).value();
+            let browser = self.config.open_behavior.browser.as_deref();
+            if let Some(run) = self.commands.find(query.as_str(), browser) {
+                Self::run_command(query.to_string(), run, None, keep_open, window, cx);
+            }
+
+            return;
+        };
+
+        if matches!(result, SearchResult::Progress(_)) {
+            // Tasks can only be cancelled, with the button of their row
+            return;
+        }
+        if let SearchResult::Keyword { keyword, .. } = &result {
+            // Filters take what follows their `:` right away
+            let query = if keyword.ends_with(':') {
+                keyword.to_string()
+            } else {
+                format!("{keyword} ")
+            };
+            self.set_query(&query, window, cx);
+            return;
+        }
+        if let SearchResult::PastQuery { query, .. } | SearchResult::Correction { query } = &result
+        {
+            self.set_query(query, window, cx);
+            return;
+        }
+        if let SearchResult::Executable(app) = &result
+            && launch::is_stale(&app.path)
+        {
+            self.forget_stale_app(app, window, cx);
+            return;
+        }
+
+        if let SearchResult::Command(command) = &result {
+            let keyword = command.keyword.clone();
+            let command = command.clone();
+            let browser = self.config.open_behavior.browser.clone();
+            let run = move || command.run(browser.as_deref());
+            Self::run_command(keyword, run, Some(result), keep_open, window, cx);
+            return;
+        }
+
+        self.launch(&result, alternate, cx);
+
+        let opened_result = match result {
+            // Restored, so it's an app like any other now
+            SearchResult::TrashedApp { app, .. } => SearchResult::Executable(app),
+            result => result,
+        };
+        self.end_search(Some(opened_result), cx);
+
+        if !keep_open {
+            window.remove_window();
         }
     }
 }
 
 impl<SE: SearchEngine> Render for SearchBar<SE> {
     #[allow(clippy::too_many_lines, reason = "Results entity needs refactor")]
-    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        self.update_layout(window, cx);
+        let (from_height, to_height) = self.height_transition;
+        let open_apps_section_end = self.open_apps_section_end(cx);
+
         div()
             .v_flex()
             .p_2()
             .gap_2()
-            .size_full()
+            .w_full()
             .items_center()
             .justify_center()
             // Display a red border when the app in running in debug mode
@@ -113,7 +1218,62 @@ impl<SE: SearchEngine> Render for SearchBar<SE> {
                 this
             })
             .bg(cx.theme().secondary)
-            .on_action(cx.listener(|this, &TabSelectApp, _, cx| {
+            // Clicks on the input and the rows don't get here
+            .on_mouse_down(MouseButton::Left, cx.listener(|this, _: &MouseDownEvent, window, _| {
+                this.start_window_drag(window);
+            }))
+            .on_modifiers_changed(cx.listener(|this, event: &ModifiersChangedEvent, _, cx| {
+                let cmd_toggled = this.modifiers.platform != event.modifiers.platform;
+                this.modifiers = event.modifiers;
+                if cmd_toggled {
+                    cx.notify();
+                }
+            }))
+            // Captured before the input gets to handle the keystroke
+            .capture_key_down(cx.listener(|this, event: &KeyDownEvent, window, cx| {
+                let modifiers = event.keystroke.modifiers;
+                if !modifiers.platform || modifiers.shift || modifiers.alt || modifiers.control {
+                    return;
+                }
+                if this.is_composing(window, cx) {
+                    return;
+                }
+
+                let Ok(number @ 1..=MAX_QUICK_OPEN) = event.keystroke.key.parse::<usize>() else {
+                    return;
+                };
+                // Only the rendered rows show their number
+                let result_idx = this.scrolled_result_idx + number - 1;
+                let results_len = this.search_engine.read(cx).results.len();
+                if number <= this.visible_rows + 1 && result_idx < results_len {
+                    cx.stop_propagation();
+                    this.open_result(result_idx, false, false, window, cx);
+                    cx.notify();
+                }
+            }))
+            .on_action(cx.listener(|this, &AcceptCompletion, window, cx| {
+                if this.is_composing(window, cx) {
+                    cx.propagate();
+                    return;
+                }
+                if this.accept_completion(window, cx) {
+                    cx.notify();
+                } else {
+                    // Let the input move the cursor instead
+                    cx.propagate();
+                }
+            }))
+            .on_action(cx.listener(|this, &TabSelectApp, window, cx| {
+                if this.is_composing(window, cx) {
+                    cx.propagate();
+                    return;
+                }
+                if this.accept_completion(window, cx) {
+                    cx.notify();
+                    return;
+                }
+
+                this.selection_source = SelectionSource::Keyboard;
                 let results_len = this.search_engine.read(cx).results.len();
                 if results_len > 0 {
                     let selected_app_idx = this.scrolled_result_idx + this.hovered_offset_idx;
@@ -122,7 +1282,7 @@ impl<SE: SearchEngine> Render for SearchBar<SE> {
                     if wrap_around_needed {
                         this.scrolled_result_idx = 0;
                         this.hovered_offset_idx = 0;
-                    } else if this.hovered_offset_idx < (MAX_RENDERED_ELS - 1) {
+                    } else if this.hovered_offset_idx < (this.visible_rows - 1) {
                         this.hovered_offset_idx += 1;
                     } else {
                         this.scrolled_result_idx += 1;
@@ -130,14 +1290,19 @@ impl<SE: SearchEngine> Render for SearchBar<SE> {
                 }
                 cx.notify();
             }))
-            .on_action(cx.listener(|this, &TabBackSelectApp, _, cx| {
+            .on_action(cx.listener(|this, &TabBackSelectApp, window, cx| {
+                if this.is_composing(window, cx) {
+                    cx.propagate();
+                    return;
+                }
+                this.selection_source = SelectionSource::Keyboard;
                 let results_len = this.search_engine.read(cx).results.len();
                 if results_len > 0 {
                     let selected_app_idx = this.scrolled_result_idx + this.hovered_offset_idx;
                     // User scrolled down at the first index, so we need to loop back down
                     let wrap_around_needed = selected_app_idx == 0;
                     if wrap_around_needed {
-                        this.hovered_offset_idx = min(results_len, MAX_RENDERED_ELS) - 1;
+                        this.hovered_offset_idx = min(results_len, this.visible_rows) - 1;
                         this.scrolled_result_idx = (results_len - 1).saturating_sub(this.hovered_offset_idx);
                     } else if this.hovered_offset_idx > 0 {
                         if this.scrolled_result_idx > 0 && this.hovered_offset_idx == 1  {
@@ -158,57 +1323,346 @@ impl<SE: SearchEngine> Render for SearchBar<SE> {
                 }
                 cx.notify();
             }))
+            .on_action(cx.listener(|this, &SelectPageDown, _, cx| {
+                let selected_app_idx = this.scrolled_result_idx + this.hovered_offset_idx;
+                this.select_result(selected_app_idx + this.visible_rows, cx);
+                cx.notify();
+            }))
+            .on_action(cx.listener(|this, &SelectPageUp, _, cx| {
+                let selected_app_idx = this.scrolled_result_idx + this.hovered_offset_idx;
+                this.select_result(selected_app_idx.saturating_sub(this.visible_rows), cx);
+                cx.notify();
+            }))
+            .on_action(cx.listener(|this, &SelectFirst, _, cx| {
+                this.select_result(0, cx);
+                cx.notify();
+            }))
+            .on_action(cx.listener(|this, &SelectLast, _, cx| {
+                this.select_result(usize::MAX, cx);
+                cx.notify();
+            }))
             .on_action(cx.listener(|this, &EscPressed, window, cx| {
+                if this.is_composing(window, cx) {
+                    cx.propagate();
+                    return;
+                }
+                if this.cheat_sheet_shown {
+                    this.cheat_sheet_shown = false;
+                    cx.notify();
+                    return;
+                }
                 window.remove_window();
-                this.search_engine.update(cx, |search_engine, cx| {
-                    search_engine.after_search(cx, None);
-                });
+                this.end_search(None, cx);
+                cx.notify();
+            }))
+            .on_action(cx.listener(|this, &CopyResultLocation, window, cx| {
+                // Copy the selected part of the query instead
+                if this.has_selected_text(window, cx) || !this.copy_selected_location(cx) {
+                    cx.propagate();
+                }
+            }))
+            .on_action(cx.listener(|this, &QuickLook, window, cx| {
+                this.quick_look_selected(window, cx);
+            }))
+            .on_action(cx.listener(|this, &SpacePressed, window, cx| {
+                if this.is_composing(window, cx) {
+                    cx.propagate();
+                    return;
+                }
+                // Spaces are part of queries, until a result is picked with the keyboard
+                if this.selection_source == SelectionSource::Keyboard && this.quick_look_selected(window, cx) {
+                    return;
+                }
+                if this.expand_text_macro(window, cx) {
+                    cx.notify();
+                } else {
+                    cx.propagate();
+                }
+            }))
+            .on_action(cx.listener(|this, &CopyResultName, _, cx| {
+                if !this.copy_selected_name(cx) {
+                    cx.propagate();
+                }
+            }))
+            .on_action(cx.listener(|this, &RevealResult, _, cx| {
+                this.reveal_selected(cx);
+            }))
+            .on_action(cx.listener(|this, &OpenWithRosetta, window, cx| {
+                this.open_selected_with_rosetta(window, cx);
+            }))
+            .on_action(cx.listener(|this, &CheckForUpdates, _, cx| {
+                this.check_selected_for_updates(cx);
+            }))
+            .on_action(cx.listener(|this, &MoveToTrash, window, cx| {
+                this.move_selected_to_trash(window, cx);
+                cx.notify();
+            }))
+            .on_action(cx.listener(|this, &ToggleHidden, window, cx| {
+                this.toggle_selected_hidden(window, cx);
+                cx.notify();
+            }))
+            .on_action(cx.listener(|this, &TogglePinned, window, cx| {
+                this.toggle_selected_pinned(window, cx);
+                cx.notify();
+            }))
+            .on_action(cx.listener(|this, &ToggleCheatSheet, _, cx| {
+                this.cheat_sheet_shown = !this.cheat_sheet_shown;
+                cx.notify();
+            }))
+            .on_action(cx.listener(|this, &ToggleLayout, _, cx| {
+                this.toggle_layout(cx);
+                cx.notify();
+            }))
+            .on_action(cx.listener(|this, &CycleSortMode, window, cx| {
+                this.cycle_sort_mode(window, cx);
+                cx.notify();
+            }))
+            .on_action(cx.listener(|this, &TogglePrivateMode, window, cx| {
+                this.toggle_private_mode(window, cx);
                 cx.notify();
             }))
             .on_action(cx.listener(|_, &OpenSettings, window, cx| {
                 window.remove_window();
-                if let Ok(cfg_path) = config_file_path() {
-                    ImplPlatform::open_url(&Url::File(cfg_path)).ok();
+                if let Ok(cfg_path) = config_file_path()
+                    && let Err(report) = ImplPlatform::open_url(&Url::File(cfg_path))
+                {
+                    errors::report_error("Could not open settings", report);
                 }
                 cx.notify();
             }))
+            .on_action(cx.listener(|_, &ExportConfig, window, cx| {
+                Self::export_config(window);
+                cx.notify();
+            }))
+            .on_action(cx.listener(|_, &ImportConfig, window, cx| {
+                Self::import_config(window, cx);
+            }))
+            .on_action(cx.listener(|this, &RestartToUpdate, _, cx| {
+                // Installed on launch, see `updater::apply_staged_update`
+                if this.staged_update.is_some() {
+                    cx.restart();
+                }
+            }))
             .on_action(cx.listener(|this, &EnterPressed, window, cx| {
+                if this.is_composing(window, cx) {
+                    cx.propagate();
+                    return;
+                }
                 let selected_app_idx = this.scrolled_result_idx + this.hovered_offset_idx;
-                let app_opt = this
-                    .search_engine
-                    .read(cx)
-                    .results.get(selected_app_idx)
-                    // Cloning removes `cx` lifetime
-                    .cloned();
-
-                if let Some(SearchResult::Executable(app)) = app_opt {
-                    ImplPlatform::open_url(&Url::File(app.path.clone())).ok();
-                    this.search_engine.update(cx, |search_engine, cx| {
-                        search_engine.after_search(cx, Some(app));
-                    });
-                    window.remove_window();
-                } else if this.commands.execute(this.input_state.read(cx).value().as_str()).is_ok() {
-                    // tmp hack: execute command that might exist
-                    window.remove_window();
+                this.open_result(selected_app_idx, false, false, window, cx);
+
+                cx.notify();
+            }))
+            .on_action(cx.listener(|this, &EnterAlternate, window, cx| {
+                if this.is_composing(window, cx) {
+                    cx.propagate();
+                    return;
                 }
+                let selected_app_idx = this.scrolled_result_idx + this.hovered_offset_idx;
+                this.open_result(selected_app_idx, false, true, window, cx);
+
+                cx.notify();
+            }))
+            .on_action(cx.listener(|this, &EnterKeepOpen, window, cx| {
+                if this.is_composing(window, cx) {
+                    cx.propagate();
+                    return;
+                }
+                let selected_app_idx = this.scrolled_result_idx + this.hovered_offset_idx;
+                this.open_result(selected_app_idx, true, false, window, cx);
 
                 cx.notify();
             }))
             .child(
-                Input::new(&self.input_state)
-                    .bg(cx.theme().sidebar_border)
-                    .corner_radii(Corners::all(10.0f64.into()))
-                    .border_color(cx.theme().window_border)
-                    .m_auto()
-                    .h_16()
-                    .text_xl(),
+                div()
+                    .relative()
+                    .w_full()
+                    // The input can't be clicked into while it's a drag region
+                    .capture_any_mouse_down(cx.listener(|this, event: &MouseDownEvent, window, cx| {
+                        if event.button == MouseButton::Left && event.modifiers.platform {
+                            cx.stop_propagation();
+                            this.start_window_drag(window);
+                        }
+                    }))
+                    .on_mouse_down(MouseButton::Left, |_, _, cx| cx.stop_propagation())
+                    .child(
+                        Input::new(&self.input_state)
+                            .bg(cx.theme().sidebar_border)
+                            .corner_radii(Corners::all(10.0f64.into()))
+                            .border_color(cx.theme().window_border)
+                            .m_auto()
+                            .h_16()
+                            .text_xl(),
+                    )
+                    .when_some(self.completion(cx), |this, (query, suffix)| {
+                        // Greyed-out completion drawn over the input, right after the query
+                        this.child(
+                            div()
+                                .absolute()
+                                .top_0()
+                                .left_0()
+                                .size_full()
+                                .flex()
+                                .items_center()
+                                .px(px(INPUT_PADDING_X))
+                                .text_xl()
+                                .whitespace_nowrap()
+                                .child(div().invisible().child(query))
+                                .child(div().text_color(cx.theme().muted_foreground).child(suffix)),
+                        )
+                    })
+                    .when(self.search_engine.read(cx).is_searching, |this| {
+                        this.child(
+                            div()
+                                .absolute()
+                                .bottom_0()
+                                .left_0()
+                                .w_full()
+                                .h(px(2.0))
+                                .rounded_full()
+                                .bg(cx.theme().primary)
+                                .with_animation(
+                                    "search-loading",
+                                    Animation::new(LOADING_PULSE_DURATION)
+                                        .repeat()
+                                        .with_easing(gpui::pulsating_between(0.1, 0.6)),
+                                    Styled::opacity,
+                                ),
+                        )
+                    }),
+            )
+            .when_some(
+                self.search_engine.read(cx).indexing_progress,
+                |this, progress| {
+                    this.child(
+                        div()
+                            .w_full()
+                            .text_sm()
+                            .text_color(cx.theme().muted_foreground)
+                            .child(match progress.total {
+                                Some(total) => format!("Indexing… {}/{total}", progress.indexed),
+                                None => "Indexing…".to_owned(),
+                            }),
+                    )
+                },
             )
+            .when_some(self.sort_mode, |this, sort_mode| {
+                this.child(
+                    div()
+                        .w_full()
+                        .text_sm()
+                        .text_color(cx.theme().muted_foreground)
+                        .child(format!("Sorted {}, press ⌘S to change", sort_mode.display_name())),
+                )
+            })
+            .when_some(self.staged_update.clone(), |this, version| {
+                this.child(
+                    div()
+                        .w_full()
+                        .text_sm()
+                        .text_color(cx.theme().muted_foreground)
+                        .child(format!("Fetch {version} is ready, press ⌘⇧U to restart and update")),
+                )
+            })
+            .when_some(self.notice.clone(), |this, notice| {
+                this.child(
+                    div()
+                        .h_flex()
+                        .w_full()
+                        .gap_2()
+                        .p_2()
+                        .rounded_md()
+                        .border_1()
+                        .border_color(cx.theme().border)
+                        .text_sm()
+                        .child(
+                            div()
+                                .v_flex()
+                                .flex_1()
+                                .child(notice.title)
+                                .child(div().text_color(cx.theme().muted_foreground).child(notice.body)),
+                        )
+                        .child(
+                            div()
+                                .text_color(cx.theme().muted_foreground)
+                                .child("×")
+                                .on_mouse_down(MouseButton::Left, cx.listener(|_, _: &MouseDownEvent, _, _| {
+                                    notifications::dismiss();
+                                })),
+                        ),
+                )
+            })
+            .when_some(self.error.clone(), |this, error| {
+                this.child(
+                    div()
+                        .v_flex()
+                        .w_full()
+                        .gap_1()
+                        .p_2()
+                        .rounded_md()
+                        .border_1()
+                        .border_color(cx.theme().danger)
+                        .text_sm()
+                        .child(
+                            div()
+                                .h_flex()
+                                .gap_2()
+                                .child(div().flex_1().truncate().child(error.event.summary))
+                                .when_some(error.event.remedy, |this, remedy| {
+                                    this.child(
+                                        div()
+                                            .text_color(cx.theme().primary)
+                                            .child(remedy.label())
+                                            .on_mouse_down(MouseButton::Left, cx.listener(move |_, _: &MouseDownEvent, _, cx| {
+                                                errors::dismiss();
+                                                let remedy = remedy.clone();
+                                                cx.background_spawn(async move {
+                                                    if let Err(report) = remedy.apply() {
+                                                        errors::report_error("Could not fix the error", report);
+                                                    }
+                                                })
+                                                .detach();
+                                            })),
+                                    )
+                                })
+                                .child(
+                                    div()
+                                        .text_color(cx.theme().muted_foreground)
+                                        .child(if error.details_shown { "Hide details" } else { "Details" })
+                                        .on_mouse_down(MouseButton::Left, cx.listener(|this, _: &MouseDownEvent, _, cx| {
+                                            if let Some(error) = &mut this.error {
+                                                error.details_shown = !error.details_shown;
+                                            }
+                                            cx.notify();
+                                        })),
+                                )
+                                .child(
+                                    div()
+                                        .text_color(cx.theme().muted_foreground)
+                                        .child("×")
+                                        .on_mouse_down(MouseButton::Left, cx.listener(|_, _: &MouseDownEvent, _, _| {
+                                            errors::dismiss();
+                                        })),
+                                ),
+                        )
+                        .when(error.details_shown, |this| {
+                            this.child(
+                                div()
+                                    .text_xs()
+                                    .text_color(cx.theme().muted_foreground)
+                                    .child(error.event.details),
+                            )
+                        }),
+                )
+            })
+            .when(self.cheat_sheet_shown, |this| this.child(cheat_sheet::render(cx)))
             .child(
                 div()
                     .v_flex()
                     .gap_2()
                     .size_full()
                     .overflow_y_hidden()
+                    .when(self.cheat_sheet_shown, Styled::hidden)
                     .child(
                         div()
                             .id("apps-list")
@@ -216,38 +1670,55 @@ impl<SE: SearchEngine> Render for SearchBar<SE> {
                             .flex()
                             .flex_col()
                             .track_scroll(&self.scroll_handle)
+                            .on_scroll_wheel(cx.listener(|this, event: &ScrollWheelEvent, window, cx| {
+                                this.scroll_results(event, window, cx);
+                            }))
                             .children(self
                                 .search_engine
                                 .read(cx)
                                 .results
                                 .iter()
+                                .enumerate()
                                 .skip(self.scrolled_result_idx)
-                                .take(MAX_RENDERED_ELS + 1)
-                                .map(|app| self.gpui_app_renderer.load(app, cx)).enumerate().map(|(i, GpuiApp { name, path, is_open, icon })| {
+                                .take(self.visible_rows + 1)
+                                .map(|(idx, result)| {
+                                    // Identifies the result wherever it's listed, so
+                                    // that only rows which weren't shown yet fade in
+                                    let row_key = result.stable_id();
+                                    let match_range = self.search_engine.read(cx).match_ranges.get(idx).cloned().flatten();
+                                    (row_key, result.clone(), match_range, self.gpui_app_renderer.load(result, cx))
+                                })
+                                .enumerate().map(|(i, (row_key, result, match_range, GpuiApp { name, subtitle, is_open, icon }))| {
+                                    let task = match &result {
+                                        SearchResult::Progress(progress) => Some(progress.clone()),
+                                        _ => None,
+                                    };
                                     #[allow(
                                         clippy::cast_precision_loss,
                                         reason = "we don't need high precision, div el height is tiny"
                                     )]
-                                    div()
-                                        .id(ElementId::named_usize(name.clone(), i))
+                                    let row = div()
+                                        .id(ElementId::Name(format!("result:{row_key}").into()))
                                         .flex()
                                         .items_center()
                                         .p(Pixels::from(RESULT_EL_PADDING))
                                         .min_h(Pixels::from(RESULT_EL_HEIGHT))
                                         .h(Pixels::from(RESULT_EL_HEIGHT))
                                         .pl(Pixels::from(40.0 / ((self.hovered_offset_idx.abs_diff(i) + 1) as f64).powf(1.67)))
+                                        .when(open_apps_section_end == Some(self.scrolled_result_idx + i), |this| {
+                                            this.border_t_1().border_color(cx.theme().border)
+                                        })
                                         .when(i == self.hovered_offset_idx, |mut this| {
                                             this.style().background =
                                                 Some(Fill::Color(cx.theme().secondary_hover.into()));
 
-                                            self.scroll_handle.set_offset(Point::new(
-                                                0f64.into(),
-                                                // RESULT_EL_HEIGHT: height of el
-                                                // RESULT_EL_PADDING: padding top
-                                                // RESULT_EL_PADDING: padding bottom
-                                                Pixels::from((i * (RESULT_EL_HEIGHT + 2 * RESULT_EL_PADDING))
-                                                    as f64).negate(),
-                                            ));
+                                            // Scrolled by the measured bounds of the row, which
+                                            // stay right whatever the height of rows. Only while
+                                            // the keyboard has the selection, so that rows don't
+                                            // move under the mouse.
+                                            if self.selection_source != SelectionSource::Mouse {
+                                                self.scroll_handle.scroll_to_item(i);
+                                            }
 
                                             this.pl_3().child(
                                                 div()
@@ -267,13 +1738,49 @@ impl<SE: SearchEngine> Render for SearchBar<SE> {
                                                     .child("↵"),
                                             )
                                         })
-                                        .hover(|style| style.bg(cx.theme().secondary_hover))
-                                        .on_mouse_down(MouseButton::Left, move |_, window, _cx| {
-                                            ImplPlatform::open_url(&Url::File(path.clone())).ok();
-                                            window.remove_window();
+                                        .when(self.modifiers.platform && i < MAX_QUICK_OPEN, |this| {
+                                            this.child(
+                                                div()
+                                                    .ml_auto()
+                                                    .pr_2()
+                                                    .text_sm()
+                                                    .text_color(cx.theme().muted_foreground)
+                                                    .child(format!("⌘{}", i + 1)),
+                                            )
+                                        })
+                                        .when(self.selection_source == SelectionSource::Mouse, |this| {
+                                            this.hover(|style| style.bg(cx.theme().secondary_hover))
                                         })
+                                        .on_mouse_down(MouseButton::Left, cx.listener(move |this, event: &MouseDownEvent, _window, cx| {
+                                            cx.stop_propagation();
+                                            this.row_press = Some((i, event.position));
+                                        }))
+                                        .on_mouse_up(MouseButton::Left, cx.listener(move |this, event: &MouseUpEvent, window, cx| {
+                                            cx.stop_propagation();
+                                            if this.row_press.take().is_some_and(|(pressed_idx, _)| pressed_idx == i) {
+                                                let clicked_idx = this.scrolled_result_idx + i;
+                                                this.open_result(clicked_idx, event.modifiers.platform, event.modifiers.alt, window, cx);
+                                            }
+                                        }))
+                                        // The context menu acts on the selected result
+                                        .on_mouse_down(MouseButton::Right, cx.listener(move |this, _: &MouseDownEvent, _window, cx| {
+                                            this.selection_source = SelectionSource::Mouse;
+                                            this.hovered_offset_idx = i;
+                                            cx.notify();
+                                        }))
                                         .on_hover(cx.listener(move |this, hovered, _window, cx| {
-                                            if *hovered {
+                                            if *hovered && this.selection_source == SelectionSource::Mouse {
+                                                this.hovered_offset_idx = i;
+                                                cx.notify();
+                                            }
+                                        }))
+                                        // The mouse takes the selection back once it moves
+                                        .on_mouse_move(cx.listener(move |this, event: &MouseMoveEvent, window, cx| {
+                                            if event.pressed_button == Some(MouseButton::Left) {
+                                                this.drag_pressed_row(i, event.position, window, cx);
+                                            }
+                                            if this.selection_source != SelectionSource::Mouse || this.hovered_offset_idx != i {
+                                                this.selection_source = SelectionSource::Mouse;
                                                 this.hovered_offset_idx = i;
                                                 cx.notify();
                                             }
@@ -295,12 +1802,158 @@ impl<SE: SearchEngine> Render for SearchBar<SE> {
                                                             .p(Pixels::from(RESULT_EL_PADDING)),
                                                     )
                                                 })
-                                                .child(div().child(name).text_xl().when(!is_open, |this| {
+                                                .child(div().child(highlighted_name(name, match_range)).text_xl().when(!is_open, |this| {
                                                     this.opacity(0.5f32)
-                                                })),
+                                                }))
+                                                .when_some(subtitle, |this, subtitle| {
+                                                    this.child(
+                                                        div()
+                                                            .child(subtitle)
+                                                            .text_sm()
+                                                            .ml_2()
+                                                            .text_color(cx.theme().muted_foreground),
+                                                    )
+                                                })
+                                                .when_some(task, |this, task| {
+                                                    this.when(task.percent.is_none(), |this| {
+                                                        this.child(
+                                                            div()
+                                                                .ml_2()
+                                                                .text_color(cx.theme().muted_foreground)
+                                                                .with_animation(
+                                                                    ElementId::Name(format!("spinner:{row_key}").into()),
+                                                                    Animation::new(SPINNER_DURATION).repeat(),
+                                                                    |this, delta| this.child(spinner_frame(delta)),
+                                                                ),
+                                                        )
+                                                    })
+                                                    .child(
+                                                        div()
+                                                            .ml_2()
+                                                            .text_color(cx.theme().muted_foreground)
+                                                            .child("×")
+                                                            .on_mouse_down(MouseButton::Left, cx.listener(move |this, _: &MouseDownEvent, _, cx| {
+                                                                // Rather than selecting the row
+                                                                cx.stop_propagation();
+                                                                this.search_engine.read(cx).cancel_task(task.id);
+                                                            })),
+                                                    )
+                                                }),
                                         )
+                                        .with_animation(
+                                            ElementId::Name(row_key.clone().into()),
+                                            Animation::new(ROW_FADE_IN_DURATION).with_easing(gpui::ease_in_out),
+                                            Styled::opacity,
+                                        );
+
+                                    div()
+                                        .id(ElementId::Name(format!("result-menu:{row_key}").into()))
+                                        .context_menu(move |menu, _window, _cx| result_context_menu(menu, &result))
+                                        .child(row)
                                 })),
                     ),
             )
+            .with_animation(
+                ElementId::Name(
+                    match self.layout {
+                        LayoutMode::Expanded => "expanded",
+                        LayoutMode::Compact => "compact",
+                    }
+                    .into(),
+                ),
+                Animation::new(LAYOUT_ANIMATION_DURATION).with_easing(gpui::ease_in_out),
+                move |this, delta| this.h(px(from_height + (to_height - from_height) * delta)),
+            )
+    }
+}
+
+/// The name of a result, with the part the query matched in bold
+fn highlighted_name(name: SharedString, match_range: Option<Range<usize>>) -> StyledText {
+    let highlight = match_range
+        .filter(|range| name.get(range.clone()).is_some())
+        .map(|range| {
+            let style = HighlightStyle {
+                font_weight: Some(FontWeight::BOLD),
+                ..HighlightStyle::default()
+            };
+            (range, style)
+        });
+
+    StyledText::new(name).with_highlights(highlight)
+}
+
+/// Frame of the spinner `delta` into its period
+fn spinner_frame(delta: f32) -> &'static str {
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::cast_precision_loss,
+        reason = "delta is between 0 and 1, so the index is a small positive number"
+    )]
+    let idx = (delta * SPINNER_FRAMES.len() as f32) as usize;
+
+    SPINNER_FRAMES[idx.min(SPINNER_FRAMES.len() - 1)]
+}
+
+/// Actions of the context menu of a result, which are the ones its
+/// shortcuts trigger (the menu lists them)
+fn result_context_menu(menu: PopupMenu, result: &SearchResult) -> PopupMenu {
+    let mut menu = menu
+        .menu("Open", Box::new(EnterPressed))
+        .menu("Open and Keep Searching", Box::new(EnterKeepOpen));
+    if let SearchResult::Url {
+        url: Url::File(_), ..
+    } = result
+    {
+        menu = menu.menu("Open the Other Way", Box::new(EnterAlternate));
+    }
+    if matches!(result, SearchResult::Text { .. }) {
+        menu = menu.menu("Paste", Box::new(EnterAlternate));
+    }
+    if let SearchResult::Executable(app) = result
+        && app.architecture.is_some_and(Architecture::can_use_rosetta)
+    {
+        menu = menu.menu("Open with Rosetta", Box::new(OpenWithRosetta));
+    }
+    if !result.file_paths().is_empty() {
+        menu = menu
+            .menu("Show in Finder", Box::new(RevealResult))
+            .menu("Quick Look", Box::new(QuickLook));
+    }
+
+    menu = menu.separator();
+    if result.location().is_some() {
+        menu = menu.menu("Copy Location", Box::new(CopyResultLocation));
+    }
+    menu = menu.menu("Copy Name", Box::new(CopyResultName));
+
+    if let SearchResult::Executable(app) = result {
+        if app.updates_url.is_some() {
+            menu = menu.menu("Check for Updates", Box::new(CheckForUpdates));
+        }
+        menu = menu
+            .separator()
+            .menu("Pin to This Query", Box::new(TogglePinned))
+            .menu("Hide", Box::new(ToggleHidden))
+            .menu("Move to Trash", Box::new(MoveToTrash));
+    }
+
+    menu
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_completion_suffix() {
+        let name = AppString::from("Firefox");
+
+        assert_eq!(completion_suffix("fire", 4, &name), Some("fox"));
+        // Not while editing the middle of the query
+        assert_eq!(completion_suffix("fire", 2, &name), None);
+        assert_eq!(completion_suffix("", 0, &name), None);
+        assert_eq!(completion_suffix("firefox", 7, &name), None);
+        assert_eq!(completion_suffix("fox", 3, &name), None);
     }
 }
@@ -1,32 +1,227 @@
-use std::sync::Arc;
+use std::{
+    ops::Range,
+    path::Path,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use gpui::{AppContext, Entity};
+use tokio::sync::broadcast::error::RecvError;
 
 use crate::{
-    app::{AppString, ExecutableApp},
-    extensions::{DeferredReceiver, DeferredToken, SearchEngine, SearchResult},
+    app::AppString,
+    extensions::{
+        DeferredReceiver, DeferredToken, SearchContext, SearchEngine, SearchResult,
+        query_operators::QueryOperators, tasks::TaskId,
+    },
+    fs::config::{DebounceConfig, SortMode},
+    gui::{app_icons::AppIcons, provider_icons::ProviderIcons},
+    normalize::match_range,
+    url::{IndexEvent, IndexingProgress},
 };
 
 pub struct GpuiSearchEngine<SE: SearchEngine> {
     pub(super) results: Vec<SearchResult>,
+    /// Query `results` were found for
+    pub(super) results_query: AppString,
+    /// Byte range of the name of each result the query matched, to
+    /// highlight it. See [`GpuiSearchEngine::set_results`].
+    pub(super) match_ranges: Vec<Option<Range<usize>>>,
+    /// Set while the engine is still building its index
+    pub(super) indexing_progress: Option<IndexingProgress>,
+    /// Set from the moment a query is typed until its results arrive
+    pub(super) is_searching: bool,
     engine: Arc<SE>,
+    debounce: DebounceConfig,
+    /// Moving average of how long the engine takes to search
+    search_latency: Duration,
+    /// Incremented for every query, so that debounced searches
+    /// can tell whether a newer query was typed in the meantime
+    latest_query_id: usize,
+    /// Decoded icons, kept from one search window to the next
+    pub(super) app_icons: Arc<AppIcons>,
+    /// Rendered like app icons are decoded, and kept along with them
+    pub(super) provider_icons: Arc<ProviderIcons>,
 }
 
 pub type SearchEngineEntity<SE> = Entity<Arc<SE>>;
 
 impl<SE: SearchEngine> GpuiSearchEngine<SE> {
-    pub fn new(search_engine: SE) -> GpuiSearchEngine<SE> {
+    pub fn new(
+        search_engine: SE,
+        debounce: DebounceConfig,
+        app_icons: AppIcons,
+    ) -> GpuiSearchEngine<SE> {
         GpuiSearchEngine::<SE> {
             results: Vec::new(),
+            results_query: AppString::default(),
+            match_ranges: Vec::new(),
+            indexing_progress: None,
+            is_searching: false,
             engine: Arc::new(search_engine),
+            debounce,
+            search_latency: Duration::ZERO,
+            latest_query_id: 0,
+            app_icons: Arc::new(app_icons),
+            provider_icons: Arc::new(ProviderIcons::default()),
         }
     }
 
-    pub fn preload(&self, cx: &mut gpui::Context<'_, Self>) {
-        let engine = self.engine.clone();
+    /// Decodes the icons of the apps opened most, whenever the engine
+    /// lists them after building its index
+    pub fn prefetch_icons(&self, cx: &mut gpui::Context<'_, Self>) {
+        let Some(mut rx) = self.engine.top_apps() else {
+            return;
+        };
+        let app_icons = self.app_icons.clone();
+
+        cx.spawn(async move |_, cx| {
+            loop {
+                let apps = rx.borrow_and_update().clone();
+                let Ok(tint) = cx.update(|cx| app_icons.tint(cx)) else {
+                    return;
+                };
+
+                let app_icons = app_icons.clone();
+                cx.background_spawn(async move {
+                    app_icons.prefetch(&apps, tint);
+                })
+                .await;
+
+                if rx.changed().await.is_err() {
+                    return;
+                }
+            }
+        })
+        .detach();
+    }
+
+    /// Forgets the icons of apps deleted or installed again, for as long as
+    /// the app runs
+    pub fn forget_changed_icons(&self, cx: &mut gpui::Context<'_, Self>) {
+        let Some(mut rx) = self.engine.index_events() else {
+            return;
+        };
+        let app_icons = self.app_icons.clone();
 
         cx.background_spawn(async move {
-            engine.preload();
+            loop {
+                match rx.recv().await {
+                    Ok(IndexEvent::AppAdded(path) | IndexEvent::AppRemoved(path)) => {
+                        app_icons.forget(&path);
+                    }
+                    Ok(IndexEvent::IndexUpdated) | Err(RecvError::Lagged(_)) => {}
+                    Err(RecvError::Closed) => return,
+                }
+            }
+        })
+        .detach();
+    }
+
+    /// Preloads the engine, then searches for `initial_query` unless the
+    /// user started typing in the meantime
+    pub fn preload(
+        &self,
+        cx: &mut gpui::Context<'_, Self>,
+        window: &gpui::Window,
+        context: SearchContext,
+        initial_query: Option<AppString>,
+    ) {
+        let engine = self.engine.clone();
+
+        cx.spawn_in(window, async move |w, cx| {
+            cx.background_spawn(async move {
+                engine.preload(context);
+            })
+            .await;
+
+            if let Some(query) = initial_query {
+                let _ = w.update_in(cx, |this, window, cx| {
+                    if this.latest_query_id == 0 {
+                        this.deferred_search(cx, window, query);
+                    }
+                });
+            }
+        })
+        .detach();
+
+        self.watch_indexing_progress(cx);
+        self.watch_results_changed(cx, window);
+        self.watch_index_events(cx, window);
+    }
+
+    /// Searches the current query again whenever a provider got new results
+    /// for it, for as long as `window` is open
+    fn watch_results_changed(&self, cx: &mut gpui::Context<'_, Self>, window: &gpui::Window) {
+        let Some(mut rx) = self.engine.results_changed() else {
+            return;
+        };
+
+        cx.spawn_in(window, async move |w, cx| {
+            while rx.changed().await.is_ok() {
+                let updated = w.update_in(cx, |this, window, cx| {
+                    // Results of the query being typed are on their way
+                    if !this.is_searching {
+                        this.deferred_search(cx, window, this.results_query.clone());
+                    }
+                });
+                if updated.is_err() {
+                    return;
+                }
+            }
+        })
+        .detach();
+    }
+
+    /// Searches the current query again once apps were added to or removed
+    /// from the index, for as long as `window` is open
+    fn watch_index_events(&self, cx: &mut gpui::Context<'_, Self>, window: &gpui::Window) {
+        let Some(mut rx) = self.engine.index_events() else {
+            return;
+        };
+
+        cx.spawn_in(window, async move |w, cx| {
+            loop {
+                match rx.recv().await {
+                    // Searched again once the update is over
+                    Ok(IndexEvent::AppAdded(_) | IndexEvent::AppRemoved(_)) => continue,
+                    Ok(IndexEvent::IndexUpdated) | Err(RecvError::Lagged(_)) => {}
+                    Err(RecvError::Closed) => return,
+                }
+
+                let updated = w.update_in(cx, |this, window, cx| {
+                    // Results of the query being typed are on their way
+                    if !this.is_searching {
+                        this.deferred_search(cx, window, this.results_query.clone());
+                    }
+                });
+                if updated.is_err() {
+                    return;
+                }
+            }
+        })
+        .detach();
+    }
+
+    fn watch_indexing_progress(&self, cx: &mut gpui::Context<'_, Self>) {
+        let Some(mut rx) = self.engine.indexing_progress() else {
+            return;
+        };
+
+        cx.spawn(async move |this, cx| {
+            loop {
+                let progress = *rx.borrow_and_update();
+                let is_done = progress.is_done();
+
+                let updated = this.update(cx, |this, cx| {
+                    this.indexing_progress = (!is_done).then_some(progress);
+                    cx.notify();
+                });
+
+                if is_done || updated.is_err() || rx.changed().await.is_err() {
+                    return;
+                }
+            }
         })
         .detach();
     }
@@ -35,15 +230,54 @@ impl<SE: SearchEngine> GpuiSearchEngine<SE> {
         self.engine.blocking_search(query);
     }
 
+    /// How long to wait for another keypress before searching. Short queries
+    /// and slow searches are debounced, since their results are likely to be
+    /// replaced by the next keypress anyway.
+    fn debounce_delay(&self, query: &AppString) -> Duration {
+        let is_short_query = query.grapheme_len() < self.debounce.short_query_len;
+        let is_fast_search =
+            self.search_latency < Duration::from_millis(self.debounce.fast_search_ms);
+
+        if is_fast_search && !is_short_query {
+            Duration::ZERO
+        } else {
+            self.search_latency
+                .min(Duration::from_millis(self.debounce.max_delay_ms))
+        }
+    }
+
     pub fn deferred_search(
         &mut self,
         cx: &mut gpui::Context<'_, Self>,
         window: &gpui::Window,
         query: AppString,
     ) {
+        self.latest_query_id += 1;
+        let query_id = self.latest_query_id;
+        let delay = self.debounce_delay(&query);
+        let results_query = query.clone();
+        self.is_searching = true;
+        cx.notify();
+
         cx.spawn_in(window, async move |w, cx| {
+            if !delay.is_zero() {
+                cx.background_executor().timer(delay).await;
+
+                let is_latest_query = w
+                    .read_with(cx, |this, _cx| this.latest_query_id == query_id)
+                    .unwrap_or(false);
+                if !is_latest_query {
+                    // User kept typing, skip this search
+                    return;
+                }
+            }
+
+            // Timed until results arrive, as engines may only send the
+            // search on, like the one of the daemon does
+            let search_start = Instant::now();
+            let mut latency_measured = false;
             let (token, mut rx): (DeferredToken, DeferredReceiver) = w
-                .read_with(cx, |this, _cx| this.engine.deferred_search(query))
+                .update(cx, |this, _cx| this.engine.deferred_search(query))
                 .expect("entity has not been released");
 
             loop {
@@ -52,11 +286,27 @@ impl<SE: SearchEngine> GpuiSearchEngine<SE> {
                     // New search executed on a different task,
                     // abort this one
                     return;
+                } else if search_token < token {
+                    // Results of this search are still on their way, unless
+                    // the engine started counting again after the search
+                    let is_latest_query = w
+                        .read_with(cx, |this, _cx| this.latest_query_id == query_id)
+                        .unwrap_or(false);
+                    if !is_latest_query {
+                        return;
+                    }
                 } else if let Some(view) = w.upgrade() {
                     // Update search results and notify UI
                     let _ = view.update(cx, |this, cx| {
+                        if !latency_measured {
+                            latency_measured = true;
+                            this.search_latency =
+                                (this.search_latency + search_start.elapsed()) / 2;
+                        }
                         let search_results = rx.borrow().1.clone();
-                        this.results = search_results;
+                        this.results_query = results_query.clone();
+                        this.set_results(search_results);
+                        this.is_searching = this.latest_query_id != query_id;
                         cx.notify();
                     });
                 }
@@ -70,16 +320,66 @@ impl<SE: SearchEngine> GpuiSearchEngine<SE> {
         .detach();
     }
 
+    /// Replaces the results, which are matched against `results_query` to
+    /// highlight what matched in their names
+    pub(super) fn set_results(&mut self, results: Vec<SearchResult>) {
+        let apps_query = QueryOperators::parse(&self.results_query).apps_query;
+        self.match_ranges = results
+            .iter()
+            .map(|result| name_match_range(result, &apps_query))
+            .collect();
+        self.results = results;
+    }
+
+    pub fn toggle_hidden(&self, result: &SearchResult) {
+        self.engine.toggle_hidden(result);
+    }
+
+    pub fn toggle_pinned(&self, query: &AppString, result: &SearchResult) {
+        self.engine.toggle_pinned(query, result);
+    }
+
+    pub fn set_sort_mode(&self, mode: SortMode) {
+        self.engine.set_sort_mode(mode);
+    }
+
+    pub fn rebuild_index(&self) {
+        self.engine.rebuild_index();
+    }
+
+    pub fn forget_app(&self, path: &Path) {
+        self.engine.forget_app(path);
+    }
+
+    pub fn set_private_mode(&self, private: bool) {
+        self.engine.set_private_mode(private);
+    }
+
+    pub fn cancel_task(&self, id: TaskId) {
+        self.engine.cancel_task(id);
+    }
+
     pub fn after_search(
         &self,
         cx: &mut gpui::Context<'_, Self>,
-        opened_app: Option<ExecutableApp>,
+        opened_result: Option<SearchResult>,
     ) {
         let engine = self.engine.clone();
 
         cx.background_spawn(async move {
-            engine.after_search(opened_app.map(SearchResult::Executable));
+            engine.after_search(opened_result);
         })
         .detach();
     }
 }
+
+/// Range of the name of `result` matched by `query`, for the results found
+/// by their name
+fn name_match_range(result: &SearchResult, query: &str) -> Option<Range<usize>> {
+    match result {
+        SearchResult::Executable(app) | SearchResult::TrashedApp { app, .. } => {
+            match_range(&app.name, query)
+        }
+        _ => None,
+    }
+}
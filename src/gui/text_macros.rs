@@ -0,0 +1,77 @@
+//! Abbreviations typed in the search bar, expanded in place when Space is
+//! pressed right after them, such as `@@gh` expanding to `repo `. They save
+//! typing keywords and operators that are often combined.
+
+use crate::fs::config::TextMacroEntry;
+
+/// Where the cursor is placed in an expansion
+pub const CURSOR_MARKER: &str = "{cursor}";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Expansion {
+    pub query: String,
+    /// In characters from the start of the query
+    pub cursor: usize,
+}
+
+/// Expands the abbreviation `query` ends with, if any. Only whole words are
+/// expanded, so that `a@@gh` is left as is.
+#[must_use]
+pub fn expand(query: &str, macros: &[TextMacroEntry]) -> Option<Expansion> {
+    let word_start = query
+        .char_indices()
+        .rfind(|(_, c)| c.is_whitespace())
+        .map_or(0, |(idx, c)| idx + c.len_utf8());
+    let (prefix, word) = query.split_at(word_start);
+    if word.is_empty() {
+        return None;
+    }
+
+    let entry = macros.iter().find(|entry| entry.abbreviation == word)?;
+    let (before_cursor, after_cursor) = entry
+        .expansion
+        .split_once(CURSOR_MARKER)
+        .unwrap_or((&entry.expansion, ""));
+
+    Some(Expansion {
+        query: format!("{prefix}{before_cursor}{after_cursor}"),
+        cursor: prefix.chars().count() + before_cursor.chars().count(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand() {
+        let macros = [
+            TextMacroEntry {
+                abbreviation: "@@gh".to_owned(),
+                expansion: "repo ".to_owned(),
+            },
+            TextMacroEntry {
+                abbreviation: ";w".to_owned(),
+                expansion: "weather {cursor} tomorrow".to_owned(),
+            },
+        ];
+
+        assert_eq!(
+            expand("@@gh", &macros),
+            Some(Expansion {
+                query: "repo ".to_owned(),
+                cursor: 5,
+            })
+        );
+        assert_eq!(
+            expand("été ;w", &macros),
+            Some(Expansion {
+                query: "été weather  tomorrow".to_owned(),
+                cursor: 12,
+            })
+        );
+        assert_eq!(expand("a@@gh", &macros), None);
+        assert_eq!(expand("@@gh ", &macros), None);
+        assert_eq!(expand("", &macros), None);
+    }
+}
@@ -0,0 +1,86 @@
+//! Where the search window shows up: centered horizontally, at the height
+//! picked in the configuration, or where the user last dragged it to on
+//! the display when `remember_position` is set.
+
+use std::{
+    collections::HashMap,
+    sync::{
+        Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use gpui::{Bounds, Pixels, PlatformDisplay, Point, Size, point, px};
+use rootcause::Report;
+
+use crate::fs::{
+    config::WindowConfig,
+    db::{AppPersistence, FilesystemPersistence},
+};
+
+/// Key of the data file holding the remembered positions
+const WINDOW_POSITIONS_KEY: &str = "window_positions";
+
+/// Top-left corner of the window by display id, read from the data file
+/// the first time they are needed
+static POSITIONS: Mutex<Option<HashMap<u32, (f32, f32)>>> = Mutex::new(None);
+
+/// Set when a position changed since they were last saved
+static POSITIONS_CHANGED: AtomicBool = AtomicBool::new(false);
+
+fn with_positions<T>(f: impl FnOnce(&mut HashMap<u32, (f32, f32)>) -> T) -> T {
+    let mut positions = POSITIONS.lock().expect("no lock poisoning");
+    let positions = positions.get_or_insert_with(|| {
+        FilesystemPersistence::open()
+            .and_then(|db| db.get_data(WINDOW_POSITIONS_KEY))
+            .unwrap_or_default()
+    });
+
+    f(positions)
+}
+
+/// Bounds of a window of `size` shown on `display`
+#[must_use]
+pub fn window_bounds(
+    display: &dyn PlatformDisplay,
+    config: &WindowConfig,
+    size: Size<Pixels>,
+) -> Bounds<Pixels> {
+    let display_bounds = display.bounds();
+
+    let remembered_origin = config
+        .remember_position
+        .then(|| with_positions(|positions| positions.get(&u32::from(display.id())).copied()))
+        .flatten()
+        .map(|(x, y)| point(px(x), px(y)))
+        // Displays may have been rearranged since
+        .filter(|origin| display_bounds.contains(origin));
+
+    let origin = remembered_origin.unwrap_or_else(|| {
+        let free_height = display_bounds.size.height - size.height;
+        point(
+            display_bounds.center().x - size.width / 2.0,
+            display_bounds.origin.y + free_height * config.vertical_position.clamp(0.0, 1.0),
+        )
+    });
+
+    Bounds { origin, size }
+}
+
+/// Remembers where the window was dragged to on a display
+pub fn remember(display_id: u32, origin: Point<Pixels>) {
+    with_positions(|positions| {
+        positions.insert(display_id, (f32::from(origin.x), f32::from(origin.y)));
+    });
+    POSITIONS_CHANGED.store(true, Ordering::Release);
+}
+
+/// Saves the remembered positions to the data file, if any changed
+pub fn save() -> Result<(), Report> {
+    if !POSITIONS_CHANGED.swap(false, Ordering::AcqRel) {
+        return Ok(());
+    }
+
+    let positions = with_positions(|positions| positions.clone());
+    FilesystemPersistence::open()?.save_data(WINDOW_POSITIONS_KEY, positions)
+}
@@ -0,0 +1,150 @@
+//! Presses of the global hotkey. The search window opens on the first
+//! press, without waiting to see whether a second one follows: a second
+//! press shortly after switches the window it opened to an alternate mode.
+//!
+//! Presses are ignored in front of the apps excluded in the configuration,
+//! and while the frontmost app is full screen, as games and presentations are.
+
+use std::{
+    sync::mpsc::Sender,
+    time::{Duration, Instant},
+};
+
+use global_hotkey::{GlobalHotKeyEvent, HotKeyState};
+
+use crate::{
+    fs::config::{DoublePressConfig, HotkeyExclusionsConfig},
+    platform::{ImplPlatform, Platform},
+};
+
+/// Why the search window should be shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShowWindow {
+    /// The hotkey was pressed, or Fetch was launched again
+    Search,
+    /// The hotkey was pressed twice in a row, which types the `query` of
+    /// [`DoublePressConfig`] into the window
+    Alternate,
+}
+
+/// Tells double presses from single ones
+#[derive(Debug)]
+pub struct PressTracker {
+    interval: Duration,
+    /// When the last single press happened. Cleared by a double press, so
+    /// that a third press starts over.
+    last_press: Option<Instant>,
+}
+
+impl PressTracker {
+    #[must_use]
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            last_press: None,
+        }
+    }
+
+    pub fn press(&mut self, now: Instant) -> ShowWindow {
+        let is_double = self
+            .last_press
+            .take()
+            .is_some_and(|last_press| now.duration_since(last_press) <= self.interval);
+
+        if is_double {
+            ShowWindow::Alternate
+        } else {
+            self.last_press = Some(now);
+            ShowWindow::Search
+        }
+    }
+}
+
+/// Whether `bundle_id` is one of the excluded apps. Bundle identifiers
+/// are compared ignoring case, as macOS does.
+fn is_excluded(exclusions: &HotkeyExclusionsConfig, bundle_id: &str) -> bool {
+    exclusions
+        .bundle_ids
+        .iter()
+        .any(|excluded| excluded.trim().eq_ignore_ascii_case(bundle_id))
+}
+
+/// Whether the hotkey should do nothing right now. The frontmost app is
+/// only asked for when some are excluded, as that takes a moment.
+fn is_suppressed(exclusions: &HotkeyExclusionsConfig) -> bool {
+    if exclusions.presentation_mode && ImplPlatform::is_frontmost_app_full_screen() {
+        return true;
+    }
+
+    !exclusions.bundle_ids.is_empty()
+        && ImplPlatform::frontmost_app().is_some_and(|app| is_excluded(exclusions, &app.bundle_id))
+}
+
+/// Forwards presses of the hotkey to `show_window`, until the receiver is
+/// dropped. Double presses are only told apart when they do something.
+pub fn listen(
+    config: &DoublePressConfig,
+    exclusions: &HotkeyExclusionsConfig,
+    show_window: &Sender<ShowWindow>,
+) {
+    let mut tracker = config
+        .query
+        .is_some()
+        .then(|| PressTracker::new(Duration::from_millis(config.interval_ms)));
+
+    while let Ok(ev) = GlobalHotKeyEvent::receiver().recv() {
+        if ev.state != HotKeyState::Pressed || is_suppressed(exclusions) {
+            continue;
+        }
+
+        let show = tracker
+            .as_mut()
+            .map_or(ShowWindow::Search, |tracker| tracker.press(Instant::now()));
+        if show_window.send(show).is_err() {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_double_press() {
+        let start = Instant::now();
+        let mut tracker = PressTracker::new(Duration::from_millis(300));
+
+        assert_eq!(tracker.press(start), ShowWindow::Search);
+        assert_eq!(
+            tracker.press(start + Duration::from_millis(200)),
+            ShowWindow::Alternate
+        );
+        // A third press starts over
+        assert_eq!(
+            tracker.press(start + Duration::from_millis(400)),
+            ShowWindow::Search
+        );
+        // Too late to be a double press
+        assert_eq!(
+            tracker.press(start + Duration::from_millis(800)),
+            ShowWindow::Search
+        );
+    }
+
+    #[test]
+    fn test_is_excluded() {
+        let exclusions = HotkeyExclusionsConfig {
+            bundle_ids: vec!["com.apple.ScreenSharing".to_owned()],
+            presentation_mode: false,
+        };
+
+        assert!(is_excluded(&exclusions, "com.apple.ScreenSharing"));
+        assert!(is_excluded(&exclusions, "com.apple.screensharing"));
+        assert!(!is_excluded(&exclusions, "com.apple.Safari"));
+        assert!(!is_excluded(
+            &HotkeyExclusionsConfig::default(),
+            "com.apple.ScreenSharing"
+        ));
+    }
+}
@@ -0,0 +1,64 @@
+//! Only one instance of Fetch may run at once, otherwise every
+//! instance would race to register the global hotkey.
+//!
+//! The first instance listens on a Unix socket. Instances launched
+//! afterwards connect to it, ask it to show its search window, and exit.
+
+use std::{
+    io::{BufRead, BufReader, ErrorKind, Write},
+    os::unix::net::{UnixListener, UnixStream},
+    path::PathBuf,
+    sync::mpsc::Sender,
+};
+
+use rootcause::Report;
+
+use crate::{fs::config::data_dir_path, hotkey::ShowWindow};
+
+const SOCKET_FILE_NAME: &str = "fetch.sock";
+
+/// Sent by other instances to show the search window
+const SHOW_COMMAND: &str = "show";
+
+fn socket_path() -> Result<PathBuf, Report> {
+    Ok(data_dir_path()?.join(SOCKET_FILE_NAME))
+}
+
+/// Returns `None` if another instance is already running, in which case
+/// its search window was shown. Otherwise, returns the socket to listen on.
+pub fn acquire() -> Result<Option<UnixListener>, Report> {
+    let socket_path = socket_path()?;
+
+    match UnixStream::connect(&socket_path) {
+        Ok(mut stream) => {
+            writeln!(stream, "{SHOW_COMMAND}")?;
+            return Ok(None);
+        }
+        // Left behind by an instance that didn't exit cleanly
+        Err(err) if err.kind() == ErrorKind::ConnectionRefused => {
+            std::fs::remove_file(&socket_path)?;
+        }
+        Err(_) => {}
+    }
+
+    Ok(Some(UnixListener::bind(&socket_path)?))
+}
+
+/// Whether an instance is running, which is left alone
+#[must_use]
+pub fn is_running() -> bool {
+    socket_path().is_ok_and(|socket_path| UnixStream::connect(socket_path).is_ok())
+}
+
+/// Forwards the commands of other instances to `show_window`, forever.
+pub fn listen(listener: &UnixListener, show_window: &Sender<ShowWindow>) {
+    for stream in listener.incoming().flatten() {
+        for line in BufReader::new(stream).lines().map_while(Result::ok) {
+            if line == SHOW_COMMAND {
+                let _ = show_window.send(ShowWindow::Search);
+            } else {
+                eprintln!("Unknown command from another instance: {line}");
+            }
+        }
+    }
+}
@@ -0,0 +1,396 @@
+//! Why an app failed to open, and what can be done about it. `open` only
+//! says that the app can't be opened, so the app is inspected to tell the
+//! usual causes apart, and the user is told how to fix it rather than only
+//! that it failed. When Fetch can fix it, the error offers to.
+//!
+//! Also decides how links and files open, following the `[open_behavior]`
+//! table of the configuration.
+
+use std::{
+    fs::File,
+    io::{ErrorKind, Read},
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
+};
+
+use rootcause::Report;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app::{ExecutableApp, MACH_O_HEADER_LEN, is_32_bit_mach_o},
+    errors::{self, ErrorEvent},
+    fs::config::{FileBehavior, FolderBehavior, OpenBehaviorConfig, Terminal},
+    permissions::Permission,
+    platform::{ImplPlatform, Platform},
+    url::Url,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LaunchError {
+    /// Downloaded, and blocked by Gatekeeper, which calls apps that aren't
+    /// notarized "damaged"
+    Quarantined,
+    /// Only built for 32-bit processors, which macOS stopped running in Catalina
+    Requires32Bit,
+    /// A symlink to an app that was moved or deleted
+    BrokenSymlink { target: PathBuf },
+    /// The app, or its executable, can't be read or run by the user
+    PermissionDenied,
+}
+
+/// A fix offered along with an error, applied once the user confirms it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Remedy {
+    /// Removes the quarantine of the app, then opens it
+    OpenAnyway(PathBuf),
+    /// Shows the file in Finder, to fix it there
+    Reveal(PathBuf),
+    /// Opens the System Settings pane in which the permission is granted
+    GrantPermission(Permission),
+}
+
+impl LaunchError {
+    /// The cause of `app` failing to open, if it's one of the known ones.
+    /// Checked from the one most to the one least certain, as a 32-bit app
+    /// can be quarantined too, but opening it anyway wouldn't help.
+    #[must_use]
+    pub fn diagnose(app: &Path) -> Option<Self> {
+        if app.is_symlink() && !app.exists() {
+            let target = std::fs::read_link(app).unwrap_or_default();
+            return Some(LaunchError::BrokenSymlink { target });
+        }
+
+        if std::fs::read_dir(app).is_err_and(|err| err.kind() == ErrorKind::PermissionDenied) {
+            return Some(LaunchError::PermissionDenied);
+        }
+
+        if let Some(executable) = ImplPlatform::app_executable(app) {
+            match File::open(&executable) {
+                Ok(file) => {
+                    let runnable = file
+                        .metadata()
+                        .is_ok_and(|metadata| metadata.permissions().mode() & 0o111 != 0);
+                    if !runnable {
+                        return Some(LaunchError::PermissionDenied);
+                    }
+
+                    let mut header = Vec::new();
+                    if file
+                        .take(MACH_O_HEADER_LEN)
+                        .read_to_end(&mut header)
+                        .is_ok()
+                        && is_32_bit_mach_o(&header)
+                    {
+                        return Some(LaunchError::Requires32Bit);
+                    }
+                }
+                Err(err) if err.kind() == ErrorKind::PermissionDenied => {
+                    return Some(LaunchError::PermissionDenied);
+                }
+                Err(_) => {}
+            }
+        }
+
+        ImplPlatform::is_quarantined(app).then_some(LaunchError::Quarantined)
+    }
+
+    /// Shown in the error banner, phrased as a question when there's a remedy
+    #[must_use]
+    pub fn summary(&self, app_name: &str) -> String {
+        match self {
+            LaunchError::Quarantined => {
+                format!("{app_name} is damaged or quarantined, open it anyway?")
+            }
+            LaunchError::Requires32Bit => {
+                format!("{app_name} is a 32-bit app, which this macOS can't open")
+            }
+            LaunchError::BrokenSymlink { .. } => {
+                format!("{app_name} links to an app that no longer exists")
+            }
+            LaunchError::PermissionDenied => {
+                format!("You aren't allowed to open {app_name}")
+            }
+        }
+    }
+
+    /// How to fix it
+    #[must_use]
+    pub fn remediation(&self) -> String {
+        match self {
+            LaunchError::Quarantined => "It was downloaded and isn't notarized by Apple, so \
+                Gatekeeper blocks it. Opening it anyway removes its quarantine \
+                (xattr -d com.apple.quarantine), only do it if you trust where it came from."
+                .to_owned(),
+            LaunchError::Requires32Bit => "Look for a 64-bit version on the website of its \
+                developer, or run it in a virtual machine with macOS Mojave or earlier."
+                .to_owned(),
+            LaunchError::BrokenSymlink { target } => format!(
+                "It points to {}, which was moved or deleted. Delete the link, or make it \
+                 point to where the app is now.",
+                target.display()
+            ),
+            LaunchError::PermissionDenied => "The app, or its executable, can't be read or run \
+                by your user. Check its permissions in the Get Info window of Finder."
+                .to_owned(),
+        }
+    }
+
+    #[must_use]
+    pub fn remedy(&self, app: &Path) -> Option<Remedy> {
+        match self {
+            LaunchError::Quarantined => Some(Remedy::OpenAnyway(app.to_path_buf())),
+            LaunchError::BrokenSymlink { .. } | LaunchError::PermissionDenied => {
+                Some(Remedy::Reveal(app.to_path_buf()))
+            }
+            LaunchError::Requires32Bit => None,
+        }
+    }
+}
+
+impl Remedy {
+    /// Label of the button applying it
+    #[must_use]
+    pub fn label(&self) -> &'static str {
+        match self {
+            Remedy::OpenAnyway(_) => "Open Anyway",
+            Remedy::Reveal(_) => "Show in Finder",
+            Remedy::GrantPermission(_) => "Open System Settings",
+        }
+    }
+
+    pub fn apply(&self) -> Result<(), Report> {
+        match self {
+            Remedy::OpenAnyway(app) => {
+                ImplPlatform::remove_quarantine(app)?;
+                ImplPlatform::launch_app(app)
+            }
+            Remedy::Reveal(path) => ImplPlatform::reveal(path),
+            Remedy::GrantPermission(permission) => {
+                ImplPlatform::open_url(&permission.settings_url())
+            }
+        }
+    }
+}
+
+/// Whether `app` was deleted or renamed since it was indexed, or lost its
+/// executable, which is checked right before opening it. Links to a missing
+/// app aren't stale, as they are diagnosed along with how to fix them.
+#[must_use]
+pub fn is_stale(app: &Path) -> bool {
+    if app.is_symlink() {
+        return false;
+    }
+
+    !app.exists()
+        || ImplPlatform::app_executable(app).is_some_and(|executable| !executable.exists())
+}
+
+/// Opens an app, telling the user why it failed if it did
+pub fn open_app(app: &ExecutableApp) {
+    let Err(report) = ImplPlatform::launch_app(&app.path) else {
+        return;
+    };
+
+    let Some(error) = LaunchError::diagnose(&app.path) else {
+        errors::report_error(format!("Could not open {}", app.name), report);
+        return;
+    };
+
+    let summary = error.summary(&app.name);
+    eprintln!("{}", report.context(summary.clone()));
+    errors::publish(ErrorEvent {
+        summary,
+        details: error.remediation(),
+        remedy: error.remedy(&app.path),
+    });
+}
+
+/// How a link or file opens
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OpenAction {
+    /// With the app macOS picks for it
+    Default,
+    /// In the browser of the given bundle identifier
+    InBrowser(String),
+    /// Selected in its folder in Finder
+    Reveal,
+    /// In a new window of the terminal app of the configuration
+    InTerminal,
+}
+
+impl OpenAction {
+    /// What opening `url` does under `config`, `is_dir` telling folders
+    /// apart from files. `alternate` is set when `Option` is held, and does
+    /// the other thing for the type, for this time only.
+    #[must_use]
+    pub fn for_url(url: &Url, is_dir: bool, config: &OpenBehaviorConfig, alternate: bool) -> Self {
+        match url {
+            Url::File(_) if is_dir => match (config.folders, alternate) {
+                (FolderBehavior::Finder, false) | (FolderBehavior::Terminal, true) => {
+                    OpenAction::Default
+                }
+                (FolderBehavior::Terminal, false) | (FolderBehavior::Finder, true) => {
+                    OpenAction::InTerminal
+                }
+            },
+            Url::File(_) => match (config.files, alternate) {
+                (FileBehavior::Open, false) | (FileBehavior::Reveal, true) => OpenAction::Default,
+                (FileBehavior::Reveal, false) | (FileBehavior::Open, true) => OpenAction::Reveal,
+            },
+            Url::Https(_) => match &config.browser {
+                Some(browser) if !alternate => OpenAction::InBrowser(browser.clone()),
+                _ => OpenAction::Default,
+            },
+            Url::SystemSettings(_) | Url::AppStore(_) | Url::Other(_) => OpenAction::Default,
+        }
+    }
+}
+
+/// Opens a link or file as the configuration says, telling the user if it failed
+pub fn open_url(url: &Url, config: &OpenBehaviorConfig, terminal: Terminal, alternate: bool) {
+    let is_dir = matches!(url, Url::File(path) if path.is_dir());
+    let opened = match (OpenAction::for_url(url, is_dir, config, alternate), url) {
+        (OpenAction::InBrowser(browser), url) => open_in_browser(url, Some(&browser)),
+        (OpenAction::Reveal, Url::File(path)) => ImplPlatform::reveal(path),
+        (OpenAction::InTerminal, Url::File(path)) => ImplPlatform::open_in_terminal(path, terminal),
+        (_, url) => ImplPlatform::open_url(url),
+    };
+
+    if let Err(report) = opened {
+        errors::report_error("Could not open link", report);
+    }
+}
+
+/// Opens a web link in the browser of bundle identifier `browser`, or in
+/// the default browser. A browser that isn't installed is reported, and
+/// the link opens in the default browser instead.
+pub fn open_in_browser(url: &Url, browser: Option<&str>) -> Result<(), Report> {
+    let Some(browser) = browser else {
+        return ImplPlatform::open_url(url);
+    };
+
+    if !is_bundle_id(browser) || ImplPlatform::app_with_bundle_id(browser).is_none() {
+        let summary = format!("No browser of bundle identifier `{browser}` is installed");
+        eprintln!("{summary}");
+        errors::publish(ErrorEvent {
+            summary,
+            details: "The link was opened in the default browser. Set `browser` to the \
+                bundle identifier of an installed browser, such as \"com.google.Chrome\", \
+                which `osascript -e 'id of app \"Google Chrome\"'` prints."
+                .to_owned(),
+            remedy: None,
+        });
+        return ImplPlatform::open_url(url);
+    }
+
+    ImplPlatform::open_url_in(url, browser)
+}
+
+/// Whether `id` looks like a bundle identifier, as in `com.apple.Safari`
+fn is_bundle_id(id: &str) -> bool {
+    id.contains('.')
+        && id.split('.').all(|part| {
+            !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diagnose_broken_symlink() {
+        let dir = std::env::temp_dir().join("fetch-test-launch");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let link = dir.join("Moved.app");
+        std::os::unix::fs::symlink(dir.join("Nowhere.app"), &link).unwrap();
+
+        let error = LaunchError::diagnose(&link);
+
+        assert_eq!(
+            error,
+            Some(LaunchError::BrokenSymlink {
+                target: dir.join("Nowhere.app")
+            })
+        );
+        assert_eq!(
+            error.and_then(|error| error.remedy(&link)),
+            Some(Remedy::Reveal(link))
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_is_stale() {
+        let dir = std::env::temp_dir().join("fetch-test-stale");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let link = dir.join("Moved.app");
+        std::os::unix::fs::symlink(dir.join("Nowhere.app"), &link).unwrap();
+
+        assert!(is_stale(&dir.join("Deleted.app")));
+        // Diagnosed when opened instead
+        assert!(!is_stale(&link));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_is_bundle_id() {
+        assert!(is_bundle_id("com.apple.Safari"));
+        assert!(is_bundle_id("org.mozilla.firefox"));
+        assert!(is_bundle_id("company.thebrowser.Browser"));
+        assert!(is_bundle_id("com.brave.Browser.beta"));
+        assert!(is_bundle_id("com.google.Chrome-canary"));
+
+        assert!(!is_bundle_id("Firefox"));
+        assert!(!is_bundle_id("com..Safari"));
+        assert!(!is_bundle_id("com.apple.Safari'"));
+        assert!(!is_bundle_id("com.apple Safari"));
+    }
+
+    #[test]
+    fn test_open_action() {
+        let file = Url::File(PathBuf::from("/Users/me/notes.txt"));
+        let web = Url::Https("example.com".into());
+        let settings = Url::SystemSettings("com.apple.preference.displays".into());
+        let config = OpenBehaviorConfig {
+            folders: FolderBehavior::Terminal,
+            files: FileBehavior::Open,
+            browser: Some(String::from("org.mozilla.firefox")),
+        };
+
+        assert_eq!(
+            OpenAction::for_url(&file, true, &config, false),
+            OpenAction::InTerminal
+        );
+        assert_eq!(
+            OpenAction::for_url(&file, true, &config, true),
+            OpenAction::Default
+        );
+        assert_eq!(
+            OpenAction::for_url(&file, false, &config, false),
+            OpenAction::Default
+        );
+        assert_eq!(
+            OpenAction::for_url(&file, false, &config, true),
+            OpenAction::Reveal
+        );
+        assert_eq!(
+            OpenAction::for_url(&web, false, &config, false),
+            OpenAction::InBrowser(String::from("org.mozilla.firefox"))
+        );
+        assert_eq!(
+            OpenAction::for_url(&web, false, &config, true),
+            OpenAction::Default
+        );
+        assert_eq!(
+            OpenAction::for_url(&settings, false, &config, false),
+            OpenAction::Default
+        );
+        assert_eq!(
+            OpenAction::for_url(&web, false, &OpenBehaviorConfig::default(), false),
+            OpenAction::Default
+        );
+    }
+}
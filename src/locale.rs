@@ -0,0 +1,529 @@
+//! Numbers and dates typed in queries are read the way the user's locale
+//! writes them: `1.234,56` is a thousand-something in German, and
+//! `03/04/2025` is the 3rd of April in France but March 4th in the US.
+//!
+//! The locale is the one of macOS, unless `locale` is set in the
+//! configuration. This is shared by providers reading numbers and dates,
+//! and by the search engine for the keywords of providers in the user's
+//! language, such as `datei` for `file`.
+
+use std::fmt::Display;
+
+use crate::{
+    fs::config::Configuration,
+    platform::{ImplPlatform, Platform},
+};
+
+/// Used when the locale of macOS can't be read
+const DEFAULT_LOCALE: &str = "en_US";
+
+/// Languages writing `1.234,56`, or `1 234,56` for those in
+/// [`SPACE_GROUPING_LANGUAGES`]
+const COMMA_DECIMAL_LANGUAGES: &[&str] = &[
+    "de", "fr", "es", "it", "pt", "nl", "ru", "pl", "sv", "da", "nb", "no", "fi", "cs", "sk", "tr",
+    "uk", "el", "hu", "ro", "id", "vi",
+];
+const SPACE_GROUPING_LANGUAGES: &[&str] = &[
+    "fr", "ru", "pl", "sv", "nb", "no", "fi", "cs", "sk", "uk", "hu",
+];
+
+/// Languages separating the numbers of a date with dots, as in `3.4.2025`
+const DOT_DATE_LANGUAGES: &[&str] = &[
+    "de", "ru", "pl", "cs", "sk", "fi", "nb", "no", "da", "tr", "uk", "hu", "ro",
+];
+
+/// Languages and regions writing the year first
+const YEAR_FIRST_LANGUAGES: &[&str] = &["ja", "zh", "ko", "hu", "lt"];
+const YEAR_FIRST_REGIONS: &[&str] = &["CN", "JP", "KR", "TW", "HU", "LT"];
+/// Regions writing the month before the day
+const MONTH_FIRST_REGIONS: &[&str] = &["US", "PH", "BZ", "FM", "MH", "PW"];
+
+/// Keywords of providers in other languages than English, by language,
+/// as pairs of the alias and the keyword it is typed instead of
+const KEYWORD_ALIASES: &[(&str, &[(&str, &str)])] = &[
+    (
+        "de",
+        &[
+            ("datei", "file"),
+            ("datum", "date"),
+            ("wetter", "weather"),
+            ("verlauf", "history"),
+            ("projekt", "proj"),
+        ],
+    ),
+    (
+        "fr",
+        &[
+            ("fichier", "file"),
+            ("météo", "weather"),
+            ("meteo", "weather"),
+            ("historique", "history"),
+            ("projet", "proj"),
+            ("courriel", "mail"),
+        ],
+    ),
+    (
+        "es",
+        &[
+            ("archivo", "file"),
+            ("fecha", "date"),
+            ("tiempo", "weather"),
+            ("historial", "history"),
+            ("proyecto", "proj"),
+            ("correo", "mail"),
+        ],
+    ),
+    (
+        "it",
+        &[
+            ("data", "date"),
+            ("meteo", "weather"),
+            ("cronologia", "history"),
+            ("progetto", "proj"),
+            ("posta", "mail"),
+        ],
+    ),
+];
+
+/// Order of the day, month and year in a date written with numbers only
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DateOrder {
+    DayMonthYear,
+    MonthDayYear,
+    YearMonthDay,
+}
+
+/// How the user writes numbers and dates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Locale {
+    pub decimal_separator: char,
+    /// Separates groups of thousands. When it is a space, non-breaking
+    /// spaces are accepted too, as that's what macOS writes.
+    pub grouping_separator: char,
+    pub date_order: DateOrder,
+    /// Separates the numbers of a date written by Fetch
+    pub date_separator: char,
+}
+
+/// A calendar date, without time zone
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Date {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl Date {
+    /// A date, if it exists in the calendar
+    #[must_use]
+    pub fn new(year: i32, month: u32, day: u32) -> Option<Self> {
+        ((1..=12).contains(&month) && (1..=days_in_month(year, month)).contains(&day))
+            .then_some(Self { year, month, day })
+    }
+
+    /// Days since 1970-01-01, negative before it
+    #[must_use]
+    pub fn days_since_epoch(self) -> i64 {
+        // From Howard Hinnant's `days_from_civil`, with years starting in March
+        let year = i64::from(self.year) - i64::from(self.month <= 2);
+        let era = year.div_euclid(400);
+        let year_of_era = year.rem_euclid(400);
+        let month = i64::from(self.month);
+        let day_of_year =
+            (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + i64::from(self.day) - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+        era * 146_097 + day_of_era - 719_468
+    }
+
+    #[must_use]
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        reason = "months and days are in range by construction, and years are read from i32"
+    )]
+    pub fn from_days_since_epoch(days: i64) -> Self {
+        let days = days + 719_468;
+        let era = days.div_euclid(146_097);
+        let day_of_era = days.rem_euclid(146_097);
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let shifted_month = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+        let month = if shifted_month < 10 {
+            shifted_month + 3
+        } else {
+            shifted_month - 9
+        };
+        let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+        Self {
+            year: year as i32,
+            month: month as u32,
+            day: day as u32,
+        }
+    }
+
+    #[must_use]
+    pub fn add_days(self, days: i64) -> Self {
+        Self::from_days_since_epoch(self.days_since_epoch() + days)
+    }
+
+    /// The same day, `months` later. Days past the end of the target month
+    /// are moved to its last day: January 31st + 1 month is February 28th.
+    #[must_use]
+    #[allow(
+        clippy::cast_possible_truncation,
+        reason = "dates this far away can't be typed in a query"
+    )]
+    pub fn add_months(self, months: i64) -> Self {
+        let month_index = i64::from(self.year) * 12 + i64::from(self.month) - 1 + months;
+        let year = month_index.div_euclid(12) as i32;
+        let month = u32::try_from(month_index.rem_euclid(12)).unwrap_or_default() + 1;
+
+        Self {
+            year,
+            month,
+            day: self.day.min(days_in_month(year, month)),
+        }
+    }
+
+    /// 0 for Monday, through 6 for Sunday
+    #[must_use]
+    pub fn weekday(self) -> u32 {
+        // 1970-01-01 was a Thursday
+        u32::try_from((self.days_since_epoch() + 3).rem_euclid(7)).unwrap_or_default()
+    }
+}
+
+impl Display for Date {
+    /// ISO 8601, as in `2025-04-03`
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+#[must_use]
+pub fn is_leap_year(year: i32) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+#[must_use]
+pub fn days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Identifier of the locale of the configuration, or else of the one of macOS
+#[must_use]
+pub fn current_identifier(config: &Configuration) -> String {
+    config
+        .locale
+        .clone()
+        .or_else(ImplPlatform::locale)
+        .unwrap_or_else(|| DEFAULT_LOCALE.to_owned())
+}
+
+/// Lowercase language and uppercase region of identifiers such as `de_DE`,
+/// `fr-CA` or `en_GB@rg=dezzzz`. The region is empty if there is none.
+fn language_and_region(identifier: &str) -> (String, String) {
+    let identifier = identifier.split('@').next().unwrap_or_default();
+    let mut parts = identifier.split(['_', '-']);
+    let language = parts.next().unwrap_or_default().to_ascii_lowercase();
+    // Skips the script, as in `zh_Hant_TW`
+    let region = parts
+        .find(|part| part.len() == 2)
+        .unwrap_or_default()
+        .to_ascii_uppercase();
+
+    (language, region)
+}
+
+/// Aliases of provider keywords in the language of a locale, as pairs of
+/// the alias and the keyword. None for English and unknown languages.
+#[must_use]
+pub fn keyword_aliases(identifier: &str) -> &'static [(&'static str, &'static str)] {
+    let (language, _) = language_and_region(identifier);
+
+    KEYWORD_ALIASES
+        .iter()
+        .find(|(alias_language, _)| *alias_language == language)
+        .map(|(_, aliases)| *aliases)
+        .unwrap_or_default()
+}
+
+impl Locale {
+    /// The locale of the configuration, or else the one of macOS
+    #[must_use]
+    pub fn current(config: &Configuration) -> Self {
+        Self::from_identifier(&current_identifier(config))
+    }
+
+    /// Reads identifiers such as `de_DE`, `fr-CA` or `en_GB@rg=dezzzz`
+    #[must_use]
+    pub fn from_identifier(identifier: &str) -> Self {
+        let (language, region) = language_and_region(identifier);
+
+        let (decimal_separator, grouping_separator) =
+            if SPACE_GROUPING_LANGUAGES.contains(&language.as_str()) {
+                (',', ' ')
+            } else if COMMA_DECIMAL_LANGUAGES.contains(&language.as_str()) {
+                (',', '.')
+            } else {
+                ('.', ',')
+            };
+
+        let date_separator = if DOT_DATE_LANGUAGES.contains(&language.as_str()) {
+            '.'
+        } else {
+            '/'
+        };
+
+        let date_order = if YEAR_FIRST_LANGUAGES.contains(&language.as_str())
+            || YEAR_FIRST_REGIONS.contains(&region.as_str())
+        {
+            DateOrder::YearMonthDay
+        } else if MONTH_FIRST_REGIONS.contains(&region.as_str()) {
+            DateOrder::MonthDayYear
+        } else {
+            DateOrder::DayMonthYear
+        };
+
+        Self {
+            decimal_separator,
+            grouping_separator,
+            date_order,
+            date_separator,
+        }
+    }
+
+    /// Writes a date with numbers, the way this locale does
+    #[must_use]
+    pub fn format_date(&self, date: Date) -> String {
+        let Date { year, month, day } = date;
+        let separator = self.date_separator;
+
+        match self.date_order {
+            DateOrder::DayMonthYear => format!("{day:02}{separator}{month:02}{separator}{year}"),
+            DateOrder::MonthDayYear => format!("{month:02}{separator}{day:02}{separator}{year}"),
+            DateOrder::YearMonthDay => format!("{year}{separator}{month:02}{separator}{day:02}"),
+        }
+    }
+
+    fn is_grouping_separator(&self, c: char) -> bool {
+        c == self.grouping_separator
+            || (self.grouping_separator == ' ' && matches!(c, '\u{a0}' | '\u{202f}'))
+    }
+
+    /// Reads a number written in this locale. Numbers written with a
+    /// decimal point are understood too, as long as they can't be read as
+    /// grouped thousands: in German, `1.5` is 1.5 but `1.500` is 1500.
+    #[must_use]
+    pub fn parse_number(&self, text: &str) -> Option<f64> {
+        let text = text.trim();
+        let (sign, unsigned) = match text.strip_prefix(['-', '−']) {
+            Some(rest) => (-1.0, rest),
+            None => (1.0, text.strip_prefix('+').unwrap_or(text)),
+        };
+
+        let number = self.parse_localized(unsigned).or_else(|| {
+            if unsigned.chars().all(|c| c.is_ascii_digit() || c == '.') {
+                unsigned.parse().ok()
+            } else {
+                None
+            }
+        })?;
+
+        Some(sign * number)
+    }
+
+    fn parse_localized(&self, text: &str) -> Option<f64> {
+        let (integer, fraction) = match text.split_once(self.decimal_separator) {
+            Some((integer, fraction)) => (integer, Some(fraction)),
+            None => (text, None),
+        };
+
+        let mut groups = integer.split(|c| self.is_grouping_separator(c));
+        let first_group = groups.next()?;
+        let mut digits = first_group.to_owned();
+        let mut is_grouped = false;
+        for group in groups {
+            if group.len() != 3 {
+                return None;
+            }
+            digits.push_str(group);
+            is_grouped = true;
+        }
+
+        // ",5" is fine, but not "" or ".500"
+        if (first_group.is_empty() && (is_grouped || fraction.is_none()))
+            || (is_grouped && first_group.len() > 3)
+        {
+            return None;
+        }
+
+        if let Some(fraction) = fraction {
+            if fraction.is_empty() {
+                return None;
+            }
+            digits.push('.');
+            digits.push_str(fraction);
+        }
+
+        if !digits.chars().all(|c| c.is_ascii_digit() || c == '.') {
+            return None;
+        }
+
+        digits.parse().ok()
+    }
+
+    /// Reads a date written with numbers, in the order of this locale.
+    /// ISO 8601 dates (`2025-04-03`) are understood in every locale, and
+    /// two-digit years are read as years of this century.
+    #[must_use]
+    pub fn parse_date(&self, text: &str) -> Option<Date> {
+        let text = text.trim().trim_end_matches('.');
+        let is_iso = text.len() == 10 && text.as_bytes()[4] == b'-' && text.as_bytes()[7] == b'-';
+        let order = if is_iso {
+            DateOrder::YearMonthDay
+        } else {
+            self.date_order
+        };
+
+        let parts: Vec<&str> = text.split(['/', '.', '-']).map(str::trim).collect();
+        let [first, second, third] = parts.as_slice() else {
+            return None;
+        };
+        if !parts
+            .iter()
+            .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()))
+        {
+            return None;
+        }
+
+        let (year, month, day) = match order {
+            DateOrder::DayMonthYear => (third, second, first),
+            DateOrder::MonthDayYear => (third, first, second),
+            DateOrder::YearMonthDay => (first, second, third),
+        };
+        let year = match year.len() {
+            2 => 2000 + year.parse::<i32>().ok()?,
+            4 => year.parse().ok()?,
+            _ => return None,
+        };
+
+        Date::new(year, month.parse().ok()?, day.parse().ok()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_identifier() {
+        let german = Locale::from_identifier("de_DE");
+        assert_eq!(german.decimal_separator, ',');
+        assert_eq!(german.grouping_separator, '.');
+        assert_eq!(german.date_order, DateOrder::DayMonthYear);
+
+        let american = Locale::from_identifier("en_US");
+        assert_eq!(american.decimal_separator, '.');
+        assert_eq!(american.date_order, DateOrder::MonthDayYear);
+
+        assert_eq!(Locale::from_identifier("fr-CA").grouping_separator, ' ');
+        assert_eq!(
+            Locale::from_identifier("zh_Hant_TW").date_order,
+            DateOrder::YearMonthDay
+        );
+        assert_eq!(
+            Locale::from_identifier("en_GB@rg=dezzzz").date_order,
+            DateOrder::DayMonthYear
+        );
+    }
+
+    #[test]
+    fn test_keyword_aliases() {
+        assert!(keyword_aliases("de_AT").contains(&("datei", "file")));
+        assert!(keyword_aliases("fr-CA").contains(&("fichier", "file")));
+        assert!(keyword_aliases("en_US").is_empty());
+        assert!(keyword_aliases("").is_empty());
+    }
+
+    #[test]
+    fn test_parse_number() {
+        let german = Locale::from_identifier("de_DE");
+        assert_eq!(german.parse_number("1.234,56"), Some(1234.56));
+        assert_eq!(german.parse_number("-0,5"), Some(-0.5));
+        assert_eq!(german.parse_number(",5"), Some(0.5));
+        assert_eq!(german.parse_number("1.500"), Some(1500.0));
+        assert_eq!(german.parse_number("1.5"), Some(1.5));
+        assert_eq!(german.parse_number("1.2345,6"), None);
+
+        let american = Locale::from_identifier("en_US");
+        assert_eq!(american.parse_number("1,234.56"), Some(1234.56));
+        assert_eq!(american.parse_number("1.5"), Some(1.5));
+        assert_eq!(american.parse_number("1,5"), None);
+        assert_eq!(american.parse_number("abc"), None);
+
+        let french = Locale::from_identifier("fr_FR");
+        assert_eq!(french.parse_number("1\u{202f}234,5"), Some(1234.5));
+    }
+
+    #[test]
+    fn test_date_arithmetic() {
+        let date = Date::new(2024, 2, 29).unwrap();
+        assert_eq!(Date::new(1970, 1, 1).unwrap().days_since_epoch(), 0);
+        assert_eq!(Date::from_days_since_epoch(date.days_since_epoch()), date);
+        assert_eq!(date.add_days(1), Date::new(2024, 3, 1).unwrap());
+        assert_eq!(date.add_days(-366), Date::new(2023, 2, 28).unwrap());
+        assert_eq!(date.add_months(12), Date::new(2025, 2, 28).unwrap());
+        assert_eq!(
+            Date::new(2025, 1, 31).unwrap().add_months(-2),
+            Date::new(2024, 11, 30).unwrap()
+        );
+        // A Thursday
+        assert_eq!(date.weekday(), 3);
+    }
+
+    #[test]
+    fn test_format_date() {
+        let date = Date::new(2025, 4, 3).unwrap();
+        assert_eq!(
+            Locale::from_identifier("de_DE").format_date(date),
+            "03.04.2025"
+        );
+        assert_eq!(
+            Locale::from_identifier("en_US").format_date(date),
+            "04/03/2025"
+        );
+        assert_eq!(
+            Locale::from_identifier("ja_JP").format_date(date),
+            "2025/04/03"
+        );
+        assert_eq!(date.to_string(), "2025-04-03");
+    }
+
+    #[test]
+    fn test_parse_date() {
+        let german = Locale::from_identifier("de_DE");
+        assert_eq!(german.parse_date("3.4.2025"), Date::new(2025, 4, 3));
+        assert_eq!(german.parse_date("31.12.25"), Date::new(2025, 12, 31));
+        assert_eq!(german.parse_date("2025-04-03"), Date::new(2025, 4, 3));
+        assert_eq!(german.parse_date("30.2.2025"), None);
+
+        let american = Locale::from_identifier("en_US");
+        assert_eq!(american.parse_date("03/04/2025"), Date::new(2025, 3, 4));
+        assert_eq!(american.parse_date("2/29/2024"), Date::new(2024, 2, 29));
+        assert_eq!(american.parse_date("2/29/2023"), None);
+        assert_eq!(american.parse_date("03/04"), None);
+
+        let japanese = Locale::from_identifier("ja_JP");
+        assert_eq!(japanese.parse_date("2025/4/3"), Date::new(2025, 4, 3));
+    }
+}
@@ -6,27 +6,49 @@
     clippy::missing_panics_doc,
     reason = "Not a library + Usage of `except` over `unwrap` is enforced, facilitating panic auditing"
 )]
-use std::sync::Arc;
+use std::os::unix::{net::UnixListener, process::CommandExt};
+use std::sync::{Arc, Mutex, mpsc};
 
-use crate::extensions::deterministic_search::DeterministicSearchEngine;
-use crate::fs::config::Configuration;
-use crate::gui::search_bar::SearchBar;
+use crate::daemon::DaemonSearchEngine;
+use crate::extensions::{SearchContext, secrets};
+use crate::fs::config::{Configuration, DoublePressConfig, HotkeyExclusionsConfig};
+use crate::fs::{portable, stats};
+use crate::gui::app_icons::AppIcons;
+use crate::gui::search_bar::{SearchBar, window_height};
 use crate::gui::search_engine::GpuiSearchEngine;
+use crate::gui::window_position;
+use crate::hotkey::ShowWindow;
+use crate::platform::{ImplPlatform, Platform};
 use global_hotkey::GlobalHotKeyManager;
-use global_hotkey::{GlobalHotKeyEvent, HotKeyState};
 use gpui::{
-    AppContext, Application, Bounds, Pixels, WindowBackgroundAppearance, WindowBounds, WindowKind,
-    WindowOptions, actions,
+    App, AppContext, Application, AsyncApp, Bounds, Entity, Pixels, WindowBackgroundAppearance,
+    WindowBounds, WindowHandle, WindowKind, WindowOptions, actions,
 };
 use gpui_component::Root;
-use rootcause::Report;
+use rootcause::{Report, report};
 
 pub mod app;
+pub mod cache;
 pub mod command;
+pub mod daemon;
+pub mod doctor;
+pub mod errors;
 pub mod extensions;
 pub mod fs;
 pub mod gui;
+pub mod hotkey;
+pub mod instance;
+pub mod launch;
+pub mod locale;
+pub mod normalize;
+pub mod notifications;
+pub mod permissions;
 pub mod platform;
+#[cfg(feature = "extension-protocol")]
+pub mod protocol;
+pub mod scheduler;
+pub mod settings_anchors;
+pub mod updater;
 pub mod url;
 
 const APP_NAME: &str = "Fetch";
@@ -35,115 +57,146 @@ actions!(
     fetch_actions,
     [
         EnterPressed,
+        EnterKeepOpen,
+        EnterAlternate,
         EscPressed,
         TabSelectApp,
         TabBackSelectApp,
         OpenSettings,
+        TogglePrivateMode,
+        AcceptCompletion,
+        ToggleHidden,
+        CopyResultLocation,
+        CopyResultName,
+        ExportConfig,
+        ImportConfig,
+        RestartToUpdate,
+        QuickLook,
+        SpacePressed,
+        CycleSortMode,
+        RevealResult,
+        MoveToTrash,
+        SelectPageDown,
+        SelectPageUp,
+        SelectFirst,
+        SelectLast,
+        ToggleCheatSheet,
+        CheckForUpdates,
+        OpenWithRosetta,
+        TogglePinned,
+        ToggleLayout,
     ]
 );
 
 fn main() -> Result<(), Report> {
+    if let Some(result) = run_command_line() {
+        return result;
+    }
+
+    let Some(instance_listener) = instance::acquire()? else {
+        eprintln!("Fetch is already running, showing its search window instead");
+        return Ok(());
+    };
+
+    // Installed before anything else runs, so that the daemon is updated too
+    match updater::apply_staged_update() {
+        Ok(true) => {
+            drop(instance_listener);
+            // Only returns if the updated app couldn't be started
+            let err = std::process::Command::new(std::env::current_exe()?).exec();
+            return Err(report!(err)
+                .context("Could not start the updated app")
+                .into());
+        }
+        Ok(false) => {}
+        Err(report) => errors::report_error("Could not install update", report),
+    }
+
     let manager = GlobalHotKeyManager::new()?;
     let config = Arc::new(Configuration::read_from_fs()?);
+    if config.updates.enabled {
+        let updates_config = config.updates.clone();
+        std::thread::spawn(move || updater::run_checker(&updates_config));
+    }
     let hotkey = config.hotkey_config()?;
 
     manager.register(hotkey)?;
 
     // Attempt to register app to auto-start on login
     if cfg!(target_os = "macos") && config.launch_on_boot {
-        use smappservice_rs::{AppService, ServiceStatus, ServiceType};
-
-        let app_service = AppService::new(ServiceType::MainApp);
-
-        match app_service.status() {
-            // Either it's already enabled, or user/macOS did not allow
-            // Fetch to start, so, leave it as-is.
-            ServiceStatus::Enabled | ServiceStatus::RequiresApproval => {}
-            ServiceStatus::NotRegistered | ServiceStatus::NotFound => {
-                if app_service.register().is_err() {
-                    eprintln!("Registering app for auto-start failed");
-                }
-            }
-        }
+        register_launch_on_boot();
     }
 
+    let show_window_rx = Arc::new(Mutex::new(listen_for_show_requests(
+        instance_listener,
+        config.hotkey_double_press.clone(),
+        config.hotkey_exclusions.clone(),
+    )));
+
     let app = Application::new();
 
     app.run(move |cx| {
-        cx.bind_keys([
-            gpui::KeyBinding::new("enter", EnterPressed, None),
-            gpui::KeyBinding::new("escape", EscPressed, None),
-            gpui::KeyBinding::new("tab", TabSelectApp, None),
-            gpui::KeyBinding::new("down", TabSelectApp, None),
-            gpui::KeyBinding::new("shift-tab", TabBackSelectApp, None),
-            gpui::KeyBinding::new("up", TabBackSelectApp, None),
-            gpui::KeyBinding::new("cmd-t", OpenSettings, None),
-        ]);
-
-        // This must be called before using any GPUI Component features.
-        gpui_component::init(cx);
+        bind_keys(cx);
 
         cx.spawn(async move |cx| {
-            let search_engine = match DeterministicSearchEngine::build(config) {
+            let search_engine = match DaemonSearchEngine::spawn() {
                 Ok(engine) => engine,
                 Err(report) => {
-                    panic!("{}", report.context("Could not build search engine"))
+                    panic!("{}", report.context("Could not start search daemon"))
                 }
             };
 
             let search_engine_entity = cx
-                .new(|_cx| GpuiSearchEngine::new(search_engine))
+                .new(|cx| {
+                    let app_icons = AppIcons::new(config.cache_budgets, config.theme.icon_style);
+                    let search_engine =
+                        GpuiSearchEngine::new(search_engine, config.search_debounce, app_icons);
+                    search_engine.prefetch_icons(cx);
+                    search_engine.forget_changed_icons(cx);
+                    search_engine
+                })
                 .expect("Search engine building is infallible");
 
+            // The window opened last, which a double press of the hotkey
+            // switches to the alternate mode
+            let mut last_window = None;
+
             loop {
-                // Await hotkey
-                if cx
+                // Await hotkey, or another instance being launched. The
+                // context is captured there too, as it spawns processes,
+                // which would hold up the main thread on every press.
+                let show_window_rx = show_window_rx.clone();
+                if let Some((show, context)) = cx
                     .background_executor()
                     .spawn(async move {
-                        if let Ok(ev) = GlobalHotKeyEvent::receiver().recv() {
-                            return ev.state == HotKeyState::Pressed;
-                        }
-
-                        false
+                        let show = show_window_rx
+                            .lock()
+                            .expect("no lock poisoning")
+                            .recv()
+                            .ok()?;
+                        Some((show, SearchContext::capture()))
                     })
                     .await
                 {
-                    // Hotkey pressed -> open window
-                    let display_center = cx
-                        .update(|app| {
-                            app.primary_display()
-                                .expect("A GUI app requires a display, so there should always be a primary display")
-                                .bounds()
-                                .center()
-                        }).expect("global read lock");
-
-                    let window_options = WindowOptions {
-                        window_bounds: Some(WindowBounds::Windowed(Bounds::centered_at(
-                            display_center,
-                            gpui::Size {
-                                width: Pixels::from(520u32),
-                                height: Pixels::from(270u32),
-                            },
-                        ))),
-                        focus: true,
-                        show: true,
-                        kind: WindowKind::PopUp,
-                        is_resizable: false,
-                        window_decorations: None,
-                        titlebar: None,
-                        window_background: WindowBackgroundAppearance::Transparent,
-                        app_id: Some(APP_NAME.to_string()),
-                        tabbing_identifier: None,
-                        ..Default::default()
-                    };
-
-                    cx.open_window(window_options, |window, cx| {
-                        let view =
-                            cx.new(|cx| SearchBar::new(window, cx, search_engine_entity.clone()));
-
-                        cx.new(|cx| Root::new(view, window, cx))
-                    })
-                    .expect("If window can't be opened, there is nothing to be doing");
+                    let alternate_query = (show == ShowWindow::Alternate)
+                        .then_some(config.hotkey_double_press.query.as_deref())
+                        .flatten();
+                    if let Some(query) = alternate_query
+                        && let Some((window_handle, search_bar)) = &last_window
+                        && set_search_window_query(cx, window_handle, search_bar, query)
+                    {
+                        continue;
+                    }
+
+                    // Show window requested -> open window
+                    let (window_handle, search_bar) =
+                        open_search_window(cx, &config, &search_engine_entity, context);
+
+                    // Double pressed after the first window was closed
+                    if let Some(query) = alternate_query {
+                        set_search_window_query(cx, &window_handle, &search_bar, query);
+                    }
+                    last_window = Some((window_handle, search_bar));
                 }
             }
         })
@@ -152,3 +205,187 @@ fn main() -> Result<(), Report> {
 
     Ok(())
 }
+
+/// Runs the command passed on the command line instead of the app, if any
+fn run_command_line() -> Option<Result<(), Report>> {
+    if std::env::args().any(|arg| arg == daemon::DAEMON_ARG) {
+        return Some(daemon::run());
+    }
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == doctor::DOCTOR_ARG) {
+        return Some(doctor::run_command());
+    }
+    if let [command, rest @ ..] = args.as_slice()
+        && command == portable::CONFIG_COMMAND
+    {
+        return Some(portable::run_command(rest));
+    }
+    if let [command, rest @ ..] = args.as_slice()
+        && command == stats::STATS_COMMAND
+    {
+        return Some(stats::run_command(rest));
+    }
+    if let [command, rest @ ..] = args.as_slice()
+        && command == secrets::SECRET_COMMAND
+    {
+        return Some(secrets::run_command(rest));
+    }
+
+    None
+}
+
+/// Opens a search window on the primary display, with the `context`
+/// captured when it was requested
+fn open_search_window(
+    cx: &mut AsyncApp,
+    config: &Arc<Configuration>,
+    search_engine: &Entity<GpuiSearchEngine<DaemonSearchEngine>>,
+    context: SearchContext,
+) -> (WindowHandle<Root>, Entity<SearchBar<DaemonSearchEngine>>) {
+    let window_size = gpui::Size {
+        width: Pixels::from(config.window.width),
+        height: Pixels::from(window_height(&config.window, config.layout_mode)),
+    };
+    let window_bounds = cx
+        .update(|app| {
+            let display = app.primary_display().expect(
+                "A GUI app requires a display, so there should always be a primary display",
+            );
+            window_position::window_bounds(&*display, &config.window, window_size)
+        })
+        .expect("global read lock");
+
+    let mut search_bar = None;
+    let window_handle = cx
+        .open_window(search_window_options(window_bounds), |window, cx| {
+            if config.window.show_over_full_screen_apps
+                && let Err(report) = ImplPlatform::show_over_full_screen_apps(window)
+            {
+                errors::report_error("Could not show the window over full-screen apps", report);
+            }
+
+            let view = cx.new(|cx| {
+                SearchBar::new(window, cx, search_engine.clone(), context, config.clone())
+            });
+            search_bar = Some(view.clone());
+
+            cx.new(|cx| Root::new(view, window, cx))
+        })
+        .expect("If window can't be opened, there is nothing to be doing");
+
+    (
+        window_handle,
+        search_bar.expect("the search bar is built with the window"),
+    )
+}
+
+/// Types `query` into a search window. Returns `false` if it was closed.
+fn set_search_window_query(
+    cx: &mut AsyncApp,
+    window_handle: &WindowHandle<Root>,
+    search_bar: &Entity<SearchBar<DaemonSearchEngine>>,
+    query: &str,
+) -> bool {
+    window_handle
+        .update(cx, |_, window, cx| {
+            search_bar.update(cx, |search_bar, cx| search_bar.set_query(query, window, cx));
+        })
+        .is_ok()
+}
+
+/// The search window is shown when the hotkey is pressed,
+/// or when Fetch is launched again
+fn listen_for_show_requests(
+    instance_listener: UnixListener,
+    double_press_config: DoublePressConfig,
+    hotkey_exclusions: HotkeyExclusionsConfig,
+) -> mpsc::Receiver<ShowWindow> {
+    let (show_window_tx, show_window_rx) = mpsc::channel();
+    let hotkey_tx = show_window_tx.clone();
+    std::thread::spawn(move || {
+        hotkey::listen(&double_press_config, &hotkey_exclusions, &hotkey_tx);
+    });
+    std::thread::spawn(move || instance::listen(&instance_listener, &show_window_tx));
+
+    show_window_rx
+}
+
+fn search_window_options(window_bounds: Bounds<Pixels>) -> WindowOptions {
+    WindowOptions {
+        window_bounds: Some(WindowBounds::Windowed(window_bounds)),
+        focus: true,
+        show: true,
+        kind: WindowKind::PopUp,
+        is_resizable: false,
+        window_decorations: None,
+        titlebar: None,
+        window_background: WindowBackgroundAppearance::Transparent,
+        app_id: Some(APP_NAME.to_string()),
+        tabbing_identifier: None,
+        ..Default::default()
+    }
+}
+
+/// Registers the app to start when the user logs in, unless they chose not to
+fn register_launch_on_boot() {
+    use smappservice_rs::{AppService, ServiceStatus, ServiceType};
+
+    let app_service = AppService::new(ServiceType::MainApp);
+
+    match app_service.status() {
+        // Either it's already enabled, or user/macOS did not allow
+        // Fetch to start, so, leave it as-is.
+        ServiceStatus::Enabled | ServiceStatus::RequiresApproval => {}
+        ServiceStatus::NotRegistered | ServiceStatus::NotFound => {
+            if app_service.register().is_err() {
+                eprintln!("Registering app for auto-start failed");
+            }
+        }
+    }
+}
+
+fn bind_keys(cx: &mut App) {
+    cx.bind_keys([
+        gpui::KeyBinding::new("enter", EnterPressed, None),
+        gpui::KeyBinding::new("escape", EscPressed, None),
+        gpui::KeyBinding::new("tab", TabSelectApp, None),
+        gpui::KeyBinding::new("down", TabSelectApp, None),
+        gpui::KeyBinding::new("shift-tab", TabBackSelectApp, None),
+        gpui::KeyBinding::new("up", TabBackSelectApp, None),
+        gpui::KeyBinding::new("cmd-t", OpenSettings, None),
+        gpui::KeyBinding::new("cmd-shift-h", TogglePrivateMode, None),
+        gpui::KeyBinding::new("cmd-shift-backspace", ToggleHidden, None),
+        gpui::KeyBinding::new("cmd-shift-e", ExportConfig, None),
+        gpui::KeyBinding::new("cmd-shift-i", ImportConfig, None),
+        gpui::KeyBinding::new("cmd-shift-u", RestartToUpdate, None),
+        gpui::KeyBinding::new("cmd-s", CycleSortMode, None),
+        gpui::KeyBinding::new("cmd-l", ToggleLayout, None),
+        gpui::KeyBinding::new("cmd-r", RevealResult, None),
+        gpui::KeyBinding::new("cmd-alt-backspace", MoveToTrash, None),
+        gpui::KeyBinding::new("cmd-/", ToggleCheatSheet, None),
+        gpui::KeyBinding::new("cmd-u", CheckForUpdates, None),
+        gpui::KeyBinding::new("cmd-alt-enter", OpenWithRosetta, None),
+        gpui::KeyBinding::new("cmd-p", TogglePinned, None),
+    ]);
+
+    // This must be called before using any GPUI Component features.
+    gpui_component::init(cx);
+
+    // Bound after GPUI Component so that they take precedence over the
+    // input's own bindings. Completing and copying propagate back to the
+    // input when there is nothing to complete, copy or preview.
+    cx.bind_keys([
+        gpui::KeyBinding::new("right", AcceptCompletion, Some("Input")),
+        gpui::KeyBinding::new("cmd-enter", EnterKeepOpen, Some("Input")),
+        gpui::KeyBinding::new("alt-enter", EnterAlternate, Some("Input")),
+        gpui::KeyBinding::new("cmd-c", CopyResultLocation, Some("Input")),
+        gpui::KeyBinding::new("cmd-shift-c", CopyResultName, Some("Input")),
+        gpui::KeyBinding::new("space", SpacePressed, Some("Input")),
+        gpui::KeyBinding::new("cmd-y", QuickLook, Some("Input")),
+        gpui::KeyBinding::new("pagedown", SelectPageDown, Some("Input")),
+        gpui::KeyBinding::new("pageup", SelectPageUp, Some("Input")),
+        gpui::KeyBinding::new("home", SelectFirst, Some("Input")),
+        gpui::KeyBinding::new("end", SelectLast, Some("Input")),
+    ]);
+}
@@ -0,0 +1,244 @@
+//! Folding of app names and queries into a form in which they are
+//! compared, so that what the user types doesn't need to be exact.
+//! Only ever used for matching: names are still displayed as-is.
+
+use std::ops::Range;
+
+/// Strips diacritics, turns full-width characters into their ASCII
+/// counterparts, and drops punctuation. For instance, "Café – Ｍｅｎｕ"
+/// becomes "Cafe Menu".
+#[must_use]
+pub fn normalize(string: &str) -> String {
+    let mut normalized = String::with_capacity(string.len());
+    for char in string.chars().map(unwiden) {
+        if is_combining_mark(char) || is_punctuation(char) {
+            continue;
+        }
+
+        match fold_diacritic(char) {
+            Some(folded) => normalized.push_str(folded),
+            None => normalized.push(char),
+        }
+    }
+
+    // Dropping punctuation can leave spaces next to each other
+    normalized.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Byte range of `name` that `query` matches once both are normalized, to
+/// highlight it. Matches at the start of a word are preferred, as that's
+/// where users start typing from.
+#[must_use]
+pub fn match_range(name: &str, query: &str) -> Option<Range<usize>> {
+    let query: Vec<char> = normalize(query).chars().collect();
+    if query.is_empty() {
+        return None;
+    }
+
+    let spans = normalized_spans(name);
+    let matches_at = |start: usize| {
+        spans.get(start..start + query.len()).is_some_and(|window| {
+            window
+                .iter()
+                .zip(&query)
+                .all(|((char, _), query_char)| char.to_lowercase().eq(query_char.to_lowercase()))
+        })
+    };
+    let starts: Vec<usize> = (0..spans.len())
+        .filter(|&start| matches_at(start))
+        .collect();
+    let start = starts
+        .iter()
+        .copied()
+        .find(|&start| start == 0 || spans[start - 1].0 == ' ')
+        .or_else(|| starts.first().copied())?;
+
+    let (_, first) = &spans[start];
+    let (_, last) = &spans[start + query.len() - 1];
+    Some(first.start..last.end)
+}
+
+/// The characters of `normalize(string)`, each along with the byte range of
+/// `string` it comes from
+fn normalized_spans(string: &str) -> Vec<(char, Range<usize>)> {
+    let mut spans: Vec<(char, Range<usize>)> = Vec::new();
+    for (idx, char) in string.char_indices() {
+        let range = idx..idx + char.len_utf8();
+        let char = unwiden(char);
+
+        if is_combining_mark(char) {
+            // Highlighted along with the letter it accents
+            if let Some((_, previous)) = spans.last_mut()
+                && previous.end == idx
+            {
+                previous.end = range.end;
+            }
+            continue;
+        }
+        if is_punctuation(char) {
+            continue;
+        }
+        if char.is_whitespace() {
+            if spans.last().is_some_and(|(last, _)| *last != ' ') {
+                spans.push((' ', range));
+            }
+            continue;
+        }
+
+        match fold_diacritic(char) {
+            Some(folded) => spans.extend(folded.chars().map(|folded| (folded, range.clone()))),
+            None => spans.push((char, range)),
+        }
+    }
+
+    if spans.last().is_some_and(|(last, _)| *last == ' ') {
+        spans.pop();
+    }
+    spans
+}
+
+/// Full-width forms (as typed with CJK input methods) are
+/// offset from ASCII by a fixed amount
+fn unwiden(char: char) -> char {
+    match char {
+        '\u{3000}' => ' ',
+        '\u{FF01}'..='\u{FF5E}' => char::from_u32(u32::from(char) - 0xFEE0).unwrap_or(char),
+        _ => char,
+    }
+}
+
+/// Decomposed accents, as found in file names on macOS
+fn is_combining_mark(char: char) -> bool {
+    matches!(char, '\u{0300}'..='\u{036F}')
+}
+
+fn is_punctuation(char: char) -> bool {
+    char.is_ascii_punctuation()
+        || matches!(
+            char,
+            '\u{00A1}' | '\u{00AB}' | '\u{00B7}' | '\u{00BB}' | '\u{00BF}'
+                | '\u{2010}'..='\u{2027}'
+                | '\u{2030}'..='\u{205E}'
+                | '\u{3001}'..='\u{3003}'
+                | '\u{3008}'..='\u{3011}'
+        )
+}
+
+/// Base letters of the precomposed Latin letters
+fn fold_diacritic(char: char) -> Option<&'static str> {
+    let folded = match char {
+        'À'..='Å' | 'Ā' | 'Ă' | 'Ą' => "A",
+        'à'..='å' | 'ā' | 'ă' | 'ą' => "a",
+        'Æ' => "AE",
+        'æ' => "ae",
+        'Ç' | 'Ć' | 'Ĉ' | 'Ċ' | 'Č' => "C",
+        'ç' | 'ć' | 'ĉ' | 'ċ' | 'č' => "c",
+        'Ď' | 'Đ' | 'Ð' => "D",
+        'ď' | 'đ' | 'ð' => "d",
+        'È'..='Ë' | 'Ē' | 'Ĕ' | 'Ė' | 'Ę' | 'Ě' => "E",
+        'è'..='ë' | 'ē' | 'ĕ' | 'ė' | 'ę' | 'ě' => "e",
+        'Ĝ' | 'Ğ' | 'Ġ' | 'Ģ' => "G",
+        'ĝ' | 'ğ' | 'ġ' | 'ģ' => "g",
+        'Ĥ' | 'Ħ' => "H",
+        'ĥ' | 'ħ' => "h",
+        'Ì'..='Ï' | 'Ĩ' | 'Ī' | 'Ĭ' | 'Į' | 'İ' => "I",
+        'ì'..='ï' | 'ĩ' | 'ī' | 'ĭ' | 'į' | 'ı' => "i",
+        'Ĵ' => "J",
+        'ĵ' => "j",
+        'Ķ' => "K",
+        'ķ' => "k",
+        'Ĺ' | 'Ļ' | 'Ľ' | 'Ŀ' | 'Ł' => "L",
+        'ĺ' | 'ļ' | 'ľ' | 'ŀ' | 'ł' => "l",
+        'Ñ' | 'Ń' | 'Ņ' | 'Ň' => "N",
+        'ñ' | 'ń' | 'ņ' | 'ň' => "n",
+        'Ò'..='Ö' | 'Ø' | 'Ō' | 'Ŏ' | 'Ő' => "O",
+        'ò'..='ö' | 'ø' | 'ō' | 'ŏ' | 'ő' => "o",
+        'Œ' => "OE",
+        'œ' => "oe",
+        'Ŕ' | 'Ŗ' | 'Ř' => "R",
+        'ŕ' | 'ŗ' | 'ř' => "r",
+        'Ś' | 'Ŝ' | 'Ş' | 'Š' => "S",
+        'ś' | 'ŝ' | 'ş' | 'š' => "s",
+        'ß' => "ss",
+        'Ţ' | 'Ť' | 'Ŧ' => "T",
+        'ţ' | 'ť' | 'ŧ' => "t",
+        'Þ' => "TH",
+        'þ' => "th",
+        'Ù'..='Ü' | 'Ũ' | 'Ū' | 'Ŭ' | 'Ů' | 'Ű' | 'Ų' => "U",
+        'ù'..='ü' | 'ũ' | 'ū' | 'ŭ' | 'ů' | 'ű' | 'ų' => "u",
+        'Ŵ' => "W",
+        'ŵ' => "w",
+        'Ý' | 'Ŷ' | 'Ÿ' => "Y",
+        'ý' | 'ÿ' | 'ŷ' => "y",
+        'Ź' | 'Ż' | 'Ž' => "Z",
+        'ź' | 'ż' | 'ž' => "z",
+        _ => return None,
+    };
+
+    Some(folded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_diacritics() {
+        assert_eq!(normalize("Café"), "Cafe");
+        assert_eq!(normalize("Cafe\u{0301}"), "Cafe");
+        assert_eq!(normalize("Straße"), "Strasse");
+    }
+
+    #[test]
+    fn test_normalize_full_width() {
+        assert_eq!(normalize("ｔｅｌｅｇｒａｍ"), "telegram");
+        assert_eq!(
+            normalize("Ｆｉｒｅｆｏｘ\u{3000}Ｎｉｇｈｔｌｙ"),
+            "Firefox Nightly"
+        );
+    }
+
+    #[test]
+    fn test_normalize_punctuation() {
+        assert_eq!(normalize("Spotify™"), "Spotify™");
+        assert_eq!(
+            normalize("Ableton Live 11 – Suite"),
+            "Ableton Live 11 Suite"
+        );
+        assert_eq!(normalize("Node.js"), "Nodejs");
+        assert_eq!(normalize("..."), "");
+    }
+
+    #[test]
+    fn test_normalized_spans() {
+        for name in [
+            "Café – Ｍｅｎｕ",
+            "Cafe\u{0301}",
+            "Straße",
+            " Ableton Live 11 – Suite ",
+            "微信",
+        ] {
+            let normalized: String = normalized_spans(name)
+                .iter()
+                .map(|(char, _)| char)
+                .collect();
+            assert_eq!(normalized, normalize(name));
+        }
+    }
+
+    #[test]
+    fn test_match_range() {
+        assert_eq!(match_range("Firefox", "fire"), Some(0..4));
+        assert_eq!(match_range("Firefox", "fox"), Some(4..7));
+        // At the start of a word rather than within one
+        assert_eq!(match_range("Photo Booth", "oo"), Some(7..9));
+        assert_eq!(match_range("Adobe Photoshop", "pho"), Some(6..9));
+        assert_eq!(match_range("Café", "cafe"), Some(0..5));
+        assert_eq!(match_range("Cafe\u{0301} Noir", "cafe"), Some(0..6));
+        assert_eq!(match_range("zoom.us", "mus"), Some(3..7));
+        assert_eq!(match_range("Ｍｅｎｕ", "menu"), Some(0..12));
+        assert_eq!(match_range("QQ音乐", "音乐"), Some(2..8));
+        assert_eq!(match_range("Safari", "chrome"), None);
+        assert_eq!(match_range("Safari", ""), None);
+    }
+}
@@ -0,0 +1,70 @@
+//! Notifications Fetch posts on its own, such as when an update is ready.
+//! They go through the Notification Center, which holds them back while a
+//! Focus is on since they are never time-sensitive.
+//!
+//! The permission to post them is asked for with the first one. When it is
+//! denied, the latest notification is shown in the search window instead,
+//! until it is dismissed.
+
+use std::sync::LazyLock;
+
+use tokio::sync::watch::{Receiver, Sender};
+
+use crate::platform::{ImplPlatform, Platform};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notification {
+    /// A notification replaces the previous one of the same identifier
+    pub id: String,
+    pub title: String,
+    pub body: String,
+}
+
+/// Whether the user allowed Fetch to post notifications
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotificationPermission {
+    Granted,
+    Denied,
+    /// Asked for with the first notification
+    NotDetermined,
+}
+
+/// Notifications that couldn't be posted, of which only the latest is shown
+static LATEST_NOTICE: LazyLock<Sender<Option<Notification>>> = LazyLock::new(|| Sender::new(None));
+
+/// Posts a notification, or shows it in the search window if that isn't
+/// allowed. Blocks while the user is asked for the permission.
+pub fn post(notification: Notification) {
+    let permission = match ImplPlatform::notification_permission() {
+        NotificationPermission::NotDetermined => {
+            match ImplPlatform::request_notification_permission() {
+                Ok(true) => NotificationPermission::Granted,
+                Ok(false) => NotificationPermission::Denied,
+                Err(report) => {
+                    eprintln!("{}", report.context("Could not ask to post notifications"));
+                    NotificationPermission::Denied
+                }
+            }
+        }
+        permission => permission,
+    };
+
+    if permission == NotificationPermission::Granted {
+        match ImplPlatform::post_notification(&notification) {
+            Ok(()) => return,
+            Err(report) => eprintln!("{}", report.context("Could not post notification")),
+        }
+    }
+
+    LATEST_NOTICE.send_replace(Some(notification));
+}
+
+/// Hides the notification shown in the search window
+pub fn dismiss() {
+    LATEST_NOTICE.send_replace(None);
+}
+
+#[must_use]
+pub fn subscribe() -> Receiver<Option<Notification>> {
+    LATEST_NOTICE.subscribe()
+}
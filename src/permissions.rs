@@ -0,0 +1,166 @@
+//! Some features rely on macOS privacy permissions, which the user grants
+//! in System Settings. This lists which ones the enabled features need, and
+//! recognizes the errors of scripts that were denied one, so that the user
+//! is guided to System Settings instead of nothing happening.
+
+use std::{borrow::Cow, fmt::Display};
+
+use rootcause::Report;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    command::CommandAction,
+    errors,
+    fs::config::{Configuration, Terminal},
+    notifications::NotificationPermission,
+    platform::{ImplPlatform, Platform},
+    url::Url,
+};
+
+/// A privacy permission, granted in System Settings › Privacy & Security
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Permission {
+    /// Reading and clicking the menus of other apps, and pressing keys in them
+    Accessibility,
+    /// Sending Apple events to other apps, as AppleScript does.
+    /// Granted per app, when a script first talks to it.
+    Automation,
+    /// Posting notifications, granted in System Settings › Notifications
+    /// rather than with the privacy permissions
+    Notifications,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PermissionStatus {
+    Granted,
+    Denied,
+    /// Can't be told without asking, which macOS does on first use
+    AskedOnFirstUse,
+}
+
+impl Display for PermissionStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PermissionStatus::Granted => write!(f, "Granted"),
+            PermissionStatus::Denied => write!(f, "Not granted"),
+            PermissionStatus::AskedOnFirstUse => write!(f, "Asked on first use"),
+        }
+    }
+}
+
+/// A permission needed by an enabled feature
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RequiredPermission {
+    pub permission: Permission,
+    /// What the permission is needed for (for instance: "Searching menu items")
+    pub feature: &'static str,
+}
+
+impl Permission {
+    #[must_use]
+    pub fn display_name(self) -> &'static str {
+        match self {
+            Permission::Accessibility => "Accessibility",
+            Permission::Automation => "Automation",
+            Permission::Notifications => "Notifications",
+        }
+    }
+
+    #[must_use]
+    pub fn status(self) -> PermissionStatus {
+        match self {
+            Permission::Accessibility if ImplPlatform::has_accessibility_permission() => {
+                PermissionStatus::Granted
+            }
+            Permission::Accessibility => PermissionStatus::Denied,
+            Permission::Automation => PermissionStatus::AskedOnFirstUse,
+            Permission::Notifications => match ImplPlatform::notification_permission() {
+                NotificationPermission::Granted => PermissionStatus::Granted,
+                NotificationPermission::Denied => PermissionStatus::Denied,
+                NotificationPermission::NotDetermined => PermissionStatus::AskedOnFirstUse,
+            },
+        }
+    }
+
+    /// The System Settings pane in which the permission is granted
+    #[must_use]
+    pub fn settings_url(self) -> Url {
+        Url::SystemSettings(Cow::Borrowed(match self {
+            Permission::Accessibility => "com.apple.preference.security?Privacy_Accessibility",
+            Permission::Automation => "com.apple.preference.security?Privacy_Automation",
+            Permission::Notifications => "com.apple.preference.notifications",
+        }))
+    }
+
+    /// The permission a script was denied, recognized by the error it failed with
+    #[must_use]
+    pub fn denied_by(report: &Report) -> Option<Permission> {
+        let message = report.to_string();
+
+        let permission = if message.contains("-1743") {
+            // errAEEventNotPermitted: "Not authorized to send Apple events"
+            Permission::Automation
+        } else if message.contains("-1719") || message.contains("-25211") {
+            // "osascript is not allowed assistive access"
+            Permission::Accessibility
+        } else {
+            return None;
+        };
+
+        Some(permission)
+    }
+}
+
+/// Permissions needed by the features enabled in `config`
+#[must_use]
+pub fn required_permissions(config: &Configuration) -> Vec<RequiredPermission> {
+    let mut required = Vec::new();
+    let mut require = |permission, feature| {
+        required.push(RequiredPermission {
+            permission,
+            feature,
+        });
+    };
+
+    if config.search_menu_items {
+        require(Permission::Accessibility, "Searching menu items");
+        require(Permission::Automation, "Searching menu items");
+    }
+
+    if config.finder_selection.enabled {
+        require(Permission::Automation, "Reading the Finder selection");
+    }
+
+    // Kitty is opened with arguments rather than scripted
+    if config.ssh.enabled && config.terminal != Terminal::Kitty {
+        require(Permission::Automation, "Connecting to SSH hosts");
+    }
+
+    let runs_applescript = config
+        .commands
+        .iter()
+        .map(|command| &command.action)
+        .chain(config.matchers.iter().map(|matcher| &matcher.action))
+        .any(|action| !matches!(action, CommandAction::Url(_)));
+    if runs_applescript {
+        require(Permission::Automation, "Running AppleScript commands");
+    }
+
+    if config.updates.enabled {
+        require(Permission::Notifications, "Notifying of updates");
+    }
+
+    required
+}
+
+/// Opens the System Settings pane of the permission `report` was caused
+/// by the lack of, if any
+pub fn guide_if_denied(report: &Report) {
+    let Some(permission) = Permission::denied_by(report) else {
+        return;
+    };
+
+    if let Err(report) = ImplPlatform::open_url(&permission.settings_url()) {
+        errors::report_error("Could not open System Settings", report);
+    }
+}
@@ -1,10 +1,20 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
+use raw_window_handle::HasWindowHandle;
 use rootcause::Report;
 use scc::HashSet;
 
 use crate::{
-    fs::config::Configuration,
+    app::{AppString, RunningApp},
+    command::{AppleScript, ProcessEnvironment},
+    extensions::{
+        files::FileMetadata, menu_items::MenuItem, projects::Project, shortcuts::Shortcut,
+        ssh::SshHost,
+    },
+    fs::config::{Configuration, Terminal},
+    locale::Date,
+    notifications::{Notification, NotificationPermission},
+    scheduler::PowerState,
     url::{Url, UrlEntry},
 };
 
@@ -14,6 +24,42 @@ pub mod mac;
 #[cfg(target_os = "macos")]
 pub type ImplPlatform = mac::MacPlatform;
 
+/// What the index reads about the apps installed: which there are, as
+/// Spotlight and the app folders list them, and what their bundles say.
+///
+/// Split from [`Platform`] as an object, so that the index can be built
+/// from fixtures in tests, on any OS.
+pub trait SystemApps: std::fmt::Debug + Send + Sync + 'static {
+    /// See [`Platform::list_binary_paths`]
+    fn list_app_paths(&self, config: &Configuration, quick: bool) -> HashSet<PathBuf>;
+
+    /// See [`Platform::to_url_entry`]
+    fn read_entry(&self, url: &Url) -> Option<UrlEntry>;
+
+    /// See [`Platform::fallback_app_icon`]
+    fn decode_icon(&self, app: &Path) -> Result<Vec<u8>, Report>;
+}
+
+/// The apps installed on this machine
+#[cfg(target_os = "macos")]
+#[derive(Debug, Default)]
+pub struct PlatformApps;
+
+#[cfg(target_os = "macos")]
+impl SystemApps for PlatformApps {
+    fn list_app_paths(&self, config: &Configuration, quick: bool) -> HashSet<PathBuf> {
+        ImplPlatform::list_binary_paths(config, quick)
+    }
+
+    fn read_entry(&self, url: &Url) -> Option<UrlEntry> {
+        ImplPlatform::to_url_entry(url)
+    }
+
+    fn decode_icon(&self, app: &Path) -> Result<Vec<u8>, Report> {
+        ImplPlatform::fallback_app_icon(app)
+    }
+}
+
 /// A collection of utility functions that are platform-dependant.
 pub trait Platform {
     /// List of the paths of apps included by default.
@@ -25,6 +71,9 @@ pub trait Platform {
     /// List of binaries to display in search results.
     fn list_binary_paths(config: &Configuration, quick: bool) -> HashSet<PathBuf>;
 
+    /// Counts the apps Spotlight knows of, to check that it responds.
+    fn count_spotlight_apps() -> Result<usize, Report>;
+
     /// List of the path of the binaries that are currently running
     /// on the system.
     fn list_open_binaries() -> Vec<PathBuf>;
@@ -35,4 +84,169 @@ pub trait Platform {
     fn to_url_entry(url: &Url) -> Option<UrlEntry>;
 
     fn open_url(url: &Url) -> Result<(), Report>;
+
+    /// Opens a URL with the app of the given bundle identifier (for
+    /// instance: "org.mozilla.firefox"), rather than with its default app.
+    fn open_url_in(url: &Url, bundle_id: &str) -> Result<(), Report>;
+
+    /// Files in the home folder whose name contains `name`, with how
+    /// recently and how often they were opened, as Spotlight finds them.
+    /// At most `limit` of them, in no particular order.
+    fn find_files(name: &str, limit: usize) -> Result<Vec<FileMetadata>, Report>;
+
+    /// Where the app of the given bundle identifier is installed, if it is.
+    fn app_with_bundle_id(bundle_id: &str) -> Option<PathBuf>;
+
+    /// Opens an app, waiting to know whether it could be opened, which
+    /// [`Platform::open_url`] doesn't.
+    fn launch_app(app: &Path) -> Result<(), Report>;
+
+    /// Path of the executable inside an app bundle, as declared by its Info.plist.
+    fn app_executable(app: &Path) -> Option<PathBuf>;
+
+    /// Version of an app bundle shown to users, as declared by its Info.plist.
+    fn app_version(app: &Path) -> Option<String>;
+
+    /// Whether Gatekeeper quarantined `path`, as it does with downloaded files.
+    fn is_quarantined(path: &Path) -> bool;
+
+    /// Removes the quarantine of an app and of everything it contains, so
+    /// that Gatekeeper no longer blocks it.
+    fn remove_quarantine(app: &Path) -> Result<(), Report>;
+
+    /// Opens files with the given app, rather than with their default app.
+    fn open_with(app: &Path, files: &[PathBuf]) -> Result<(), Report>;
+
+    /// Previews files in a Quick Look panel controlled from `window`, as
+    /// pressing Space does in Finder.
+    fn quick_look(window: &impl HasWindowHandle, paths: &[PathBuf]) -> Result<(), Report>;
+
+    /// Opens an app translated by Rosetta, rather than natively, which
+    /// universal apps need to load plugins only built for Intel.
+    fn open_with_rosetta(app: &Path) -> Result<(), Report>;
+
+    /// Shows a file in Finder, selected in its folder.
+    fn reveal(path: &Path) -> Result<(), Report>;
+
+    /// Moves a file to the Trash, the way Finder does so that it can be put back.
+    fn move_to_trash(path: &Path) -> Result<(), Report>;
+
+    /// Checks that `signature` is the signature of `file` by the holder of
+    /// `public_key` (PEM), as made by `openssl dgst -sha256 -sign`.
+    fn verify_signature(file: &Path, signature: &Path, public_key: &str) -> Result<(), Report>;
+
+    /// Extracts a zip archive into `dir`, keeping app bundles intact.
+    fn extract_archive(archive: &Path, dir: &Path) -> Result<(), Report>;
+
+    /// UTIs a file conforms to, most specific first
+    /// (for instance: `public.plain-text`, `public.text`, `public.data`).
+    fn content_types(path: &Path) -> Vec<String>;
+
+    /// Decodes the icon of an app with the system's own tools, for icons
+    /// the indexer couldn't read (such as JPEG 2000 elements).
+    fn fallback_app_icon(app: &Path) -> Result<Vec<u8>, Report>;
+
+    /// The app currently in the foreground, if any.
+    fn frontmost_app() -> Option<RunningApp>;
+
+    /// Whether the frontmost app covers a whole display with a window, as
+    /// full-screen games and presentations do.
+    fn is_frontmost_app_full_screen() -> bool;
+
+    /// Whether Fetch is allowed to read and control the UI of other apps.
+    fn has_accessibility_permission() -> bool;
+
+    /// Brings `app` back to the front and presses `Cmd+V` in it, which
+    /// takes the Accessibility permission.
+    fn paste_into(app: &RunningApp) -> Result<(), Report>;
+
+    /// Lists every item in the menu bar of the given (running) app.
+    fn list_menu_items(app: &RunningApp) -> Result<Vec<MenuItem>, Report>;
+
+    /// Brings the app owning the menu item to the foreground, and clicks the item.
+    fn click_menu_item(item: &MenuItem) -> Result<(), Report>;
+
+    /// Runs an AppleScript in `environment`, which the shell scripts it
+    /// runs inherit, and returns what it printed.
+    fn run_applescript(
+        script: &AppleScript,
+        environment: &ProcessEnvironment,
+    ) -> Result<String, Report>;
+
+    /// Paths of the files and folders selected in the frontmost Finder window.
+    fn finder_selection() -> Result<Vec<PathBuf>, Report>;
+
+    /// Names of the shortcuts the user created in the Shortcuts app.
+    fn list_shortcuts() -> Result<Vec<AppString>, Report>;
+
+    /// Runs a shortcut, passing `input` as text if provided.
+    fn run_shortcut(shortcut: &Shortcut) -> Result<(), Report>;
+
+    /// Text currently in the clipboard, if any.
+    fn read_clipboard() -> Option<String>;
+
+    /// Opens a new window of `terminal` connected to `host`.
+    fn open_ssh(host: &SshHost, terminal: Terminal) -> Result<(), Report>;
+
+    /// Opens a new window of `terminal` in `dir`.
+    fn open_in_terminal(dir: &Path, terminal: Terminal) -> Result<(), Report>;
+
+    /// Opens the directory of a project with its target app.
+    fn open_project(project: &Project, terminal: Terminal) -> Result<(), Report>;
+
+    /// Lets `window` show up on every Space, including the ones of
+    /// full-screen apps, rather than only on the one it was opened on.
+    fn show_over_full_screen_apps(window: &impl HasWindowHandle) -> Result<(), Report>;
+
+    /// Resizes `window` to `height`, keeping its top edge where it is, so
+    /// that the search window grows and shrinks below its input.
+    fn set_window_height(window: &impl HasWindowHandle, height: f32) -> Result<(), Report>;
+
+    /// Starts dragging `paths` out of `window`, as file URLs, from the mouse
+    /// event being handled. Must be called while handling a mouse drag.
+    fn drag_files(window: &impl HasWindowHandle, paths: &[PathBuf]) -> Result<(), Report>;
+
+    /// Whether `path` is inside a Trash folder.
+    fn is_in_trash(path: &Path) -> bool;
+
+    /// Where the app that was at `path` is, if it was moved to the Trash.
+    fn trash_location(path: &Path) -> Option<PathBuf>;
+
+    /// Mount point of the external volume `path` is on, which can be
+    /// unmounted while apps on it are indexed.
+    fn external_volume(path: &Path) -> Option<PathBuf>;
+
+    /// Password of the generic keychain item of `service` and `account`, if there is one.
+    fn keychain_password(service: &str, account: &str) -> Option<String>;
+
+    /// Stores a password in the keychain, replacing the one of the same
+    /// `service` and `account`.
+    fn set_keychain_password(service: &str, account: &str, password: &str) -> Result<(), Report>;
+
+    /// Removes a password from the keychain.
+    fn delete_keychain_password(service: &str, account: &str) -> Result<(), Report>;
+
+    /// Sends a GET request and returns the body of the response.
+    fn http_get(url: &str, headers: &[(&str, &str)]) -> Result<Vec<u8>, Report>;
+
+    /// Whether the computer runs on battery or in Low Power Mode.
+    /// Assumes it is plugged in if that can't be told.
+    fn power_state() -> PowerState;
+
+    /// Whether Fetch may post notifications, as set in System Settings.
+    fn notification_permission() -> NotificationPermission;
+
+    /// Asks the user to let Fetch post notifications, waiting for the
+    /// answer, which is whether they did.
+    fn request_notification_permission() -> Result<bool, Report>;
+
+    /// Posts a notification to the Notification Center, which holds it
+    /// back while a Focus is on.
+    fn post_notification(notification: &Notification) -> Result<(), Report>;
+
+    /// Identifier of the locale the user picked, such as `"de_DE"`
+    fn locale() -> Option<String>;
+
+    /// The current date in the time zone of the computer
+    fn today() -> Option<Date>;
 }
@@ -1,12 +1,31 @@
 use std::{
+    borrow::Cow,
+    ffi::c_void,
     fs::File,
-    io::BufReader,
+    io::{BufReader, Read, Write},
+    os::macos::fs::MetadataExt,
     path::{Path, PathBuf},
-    process::Command,
-    str::FromStr,
+    process::{Command, Stdio},
+    ptr::NonNull,
+    sync::{
+        Mutex,
+        atomic::{AtomicUsize, Ordering},
+        mpsc,
+    },
+    time::{Duration, Instant},
 };
 
+use block2::RcBlock;
 use icns::IconFamily;
+use objc2::{rc::Retained, runtime::Bool};
+use objc2_app_kit::{NSView, NSWindow, NSWindowCollectionBehavior, NSWorkspace};
+use objc2_foundation::{NSBundle, NSError, NSPoint, NSRect, NSSize, NSString};
+use objc2_user_notifications::{
+    UNAuthorizationOptions, UNAuthorizationStatus, UNMutableNotificationContent,
+    UNNotificationInterruptionLevel, UNNotificationRequest, UNNotificationSettings,
+    UNUserNotificationCenter,
+};
+use raw_window_handle::{HasWindowHandle, RawWindowHandle};
 use rayon::{
     iter::{IntoParallelRefIterator, ParallelIterator},
     str::ParallelString,
@@ -15,58 +34,344 @@ use rootcause::{Report, report};
 use scc::HashSet;
 
 use crate::{
-    app::ExecutableApp,
-    fs::config::Configuration,
+    app::{AppLocation, AppString, Architecture, ExecutableApp, MACH_O_HEADER_LEN, RunningApp},
+    command::{AppleScript, ProcessEnvironment, applescript_string},
+    extensions::{
+        files::FileMetadata, menu_items::MenuItem, projects::Project, shortcuts::Shortcut,
+        ssh::SshHost,
+    },
+    fs::config::{Configuration, ExternalVolumesConfig, ProjectTarget, Terminal, expand_home},
+    locale::Date,
+    notifications::{Notification, NotificationPermission},
     platform::Platform,
+    scheduler::PowerState,
     url::{Url, UrlEntry},
 };
 
+#[link(name = "ApplicationServices", kind = "framework")]
+unsafe extern "C" {
+    fn AXIsProcessTrusted() -> bool;
+}
+
+#[link(name = "CoreGraphics", kind = "framework")]
+unsafe extern "C" {
+    fn CGEventCreateKeyboardEvent(
+        source: *const c_void,
+        key_code: u16,
+        key_down: bool,
+    ) -> *mut c_void;
+    fn CGEventSetFlags(event: *mut c_void, flags: u64);
+    fn CGEventPost(tap: u32, event: *mut c_void);
+    fn CGGetActiveDisplayList(max_displays: u32, displays: *mut u32, count: *mut u32) -> i32;
+    fn CGDisplayBounds(display: u32) -> NSRect;
+    fn CGWindowListCopyWindowInfo(option: u32, relative_to_window: u32) -> *const c_void;
+    fn CGRectMakeWithDictionaryRepresentation(dict: *const c_void, rect: *mut NSRect) -> bool;
+    #[allow(non_upper_case_globals, reason = "named as in CoreGraphics")]
+    static kCGWindowOwnerPID: *const c_void;
+    #[allow(non_upper_case_globals, reason = "named as in CoreGraphics")]
+    static kCGWindowBounds: *const c_void;
+}
+
+#[link(name = "CoreFoundation", kind = "framework")]
+unsafe extern "C" {
+    fn CFRelease(object: *const c_void);
+    fn CFArrayGetCount(array: *const c_void) -> isize;
+    fn CFArrayGetValueAtIndex(array: *const c_void, index: isize) -> *const c_void;
+    fn CFDictionaryGetValue(dict: *const c_void, key: *const c_void) -> *const c_void;
+    fn CFNumberGetValue(number: *const c_void, number_type: isize, value: *mut c_void) -> bool;
+}
+
+/// Virtual key code of `V` (`kVK_ANSI_V`)
+const KEY_CODE_V: u16 = 0x09;
+
+/// `kCGEventFlagMaskCommand`
+const COMMAND_FLAG: u64 = 0x0010_0000;
+
+/// `kCGHIDEventTap`, where events are posted as if typed on the keyboard
+const HID_EVENT_TAP: u32 = 0;
+
+/// Displays windows are compared with, more than are ever connected
+const MAX_DISPLAYS: u32 = 16;
+
+/// `kCGWindowListOptionOnScreenOnly | kCGWindowListExcludeDesktopElements`
+const ON_SCREEN_WINDOWS: u32 = (1 << 0) | (1 << 4);
+
+/// `kCGNullWindowID`, to list windows regardless of any other
+const NULL_WINDOW_ID: u32 = 0;
+
+/// `kCFNumberSInt32Type`, the type of process identifiers
+const CF_NUMBER_SINT32_TYPE: isize = 3;
+
+/// Time given to an app to come to the front before `Cmd+V` is pressed,
+/// or it would be pressed in the app that is leaving the front
+const PASTE_DELAY: Duration = Duration::from_millis(150);
+
+/// Bundle identifier prefix of the web apps created with Safari's "Add to Dock"
+const SAFARI_WEB_APP_BUNDLE_ID_PREFIX: &str = "com.apple.Safari.WebApp";
+
+/// Prefix of the UTIs used as `LSApplicationCategoryType`
+const APP_CATEGORY_PREFIX: &str = "public.app-category.";
+
+/// Receipt of apps installed from the Mac App Store, relative to their bundle
+const APP_STORE_RECEIPT_PATH: &str = "Contents/_MASReceipt/receipt";
+
+/// Page of the Mac App Store listing available updates
+const APP_STORE_UPDATES_PAGE: &str = "showUpdatesPage";
+
+/// Extended attribute Gatekeeper sets on downloaded files
+const QUARANTINE_ATTRIBUTE: &str = "com.apple.quarantine";
+
+/// `st_flags` bit of files whose contents aren't on disk, such as
+/// iCloud Drive placeholders (`SF_DATALESS` in `sys/stat.h`)
+const SF_DATALESS: u32 = 0x4000_0000;
+
+/// Where external drives and network volumes are mounted
+const VOLUMES_DIR: &str = "/Volumes";
+
+/// File systems of volumes mounted over the network, as listed by `mount`
+const NETWORK_FILE_SYSTEMS: &[&str] = &["smbfs", "afpfs", "nfs", "webdav", "ftp"];
+
+/// Volumes are listed once per indexing rather than once per app
+const NETWORK_VOLUMES_TTL: Duration = Duration::from_secs(60);
+
+/// Mount points of the network volumes, with when they were listed
+static NETWORK_VOLUMES: Mutex<Option<(Vec<PathBuf>, Instant)>> = Mutex::new(None);
+
+/// Largest side of the icons decoded with `sips`, in pixels
+const FALLBACK_ICON_SIZE: &str = "128";
+
+/// Names the temporary files of icons decoded with `sips`
+static FALLBACK_ICONS_DECODED: AtomicUsize = AtomicUsize::new(0);
+
+mod dragging;
+mod quick_look;
+
 pub struct MacPlatform;
 
+/// The AppKit window of `window`
+fn ns_window(window: &impl HasWindowHandle) -> Result<Retained<NSWindow>, Report> {
+    let RawWindowHandle::AppKit(handle) = window.window_handle()?.as_raw() else {
+        return Err(report!("Not an AppKit window"));
+    };
+    // SAFETY: the view belongs to `window`, which is borrowed for the
+    // whole call, and gpui only hands out windows on the main thread
+    let ns_view = unsafe { handle.ns_view.cast::<NSView>().as_ref() };
+
+    ns_view
+        .window()
+        .ok_or_else(|| report!("The view isn't in a window"))
+}
+
 impl MacPlatform {
+    fn run_osascript(script: &str) -> Result<String, Report> {
+        Self::osascript_output(Command::new("osascript").arg("-e").arg(script))
+    }
+
+    fn osascript_output(cmd: &mut Command) -> Result<String, Report> {
+        let output = cmd.output()?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(report!("osascript exited with an error: {stderr}"));
+        }
+
+        Ok(String::from_utf8(output.stdout)?)
+    }
+
+    /// Description of the error a completion handler was called with, if any
+    fn ns_error_description(error: *mut NSError) -> Option<String> {
+        // SAFETY: the error is either null or alive for the whole handler
+        unsafe { error.as_ref() }.map(|error| error.localizedDescription().to_string())
+    }
+
+    /// Reads what `mdls -name kMDItemLastUsedDate -name kMDItemUseCount`
+    /// prints for several files, one pair of lines per file in order:
+    ///
+    /// ```text
+    /// kMDItemLastUsedDate = 2025-06-30 08:12:44 +0000
+    /// kMDItemUseCount     = 12
+    /// ```
+    ///
+    /// Files never opened have `(null)` values. Attributes are listed by
+    /// name, so the date starts the lines of each file.
+    fn parse_file_usage(mdls: &str) -> Vec<(Option<Date>, u32)> {
+        // "2025-06-30 08:12:44 +0000", of which the day is enough
+        fn parse_day(value: &str) -> Option<Date> {
+            let mut parts = value.split([' ', '-']).map(str::parse::<i32>);
+            let year = parts.next()?.ok()?;
+            let month = u32::try_from(parts.next()?.ok()?).ok()?;
+            let day = u32::try_from(parts.next()?.ok()?).ok()?;
+            Date::new(year, month, day)
+        }
+
+        let mut usage = Vec::new();
+
+        for line in mdls.lines() {
+            let Some((name, value)) = line.split_once('=') else {
+                continue;
+            };
+            let value = value.trim();
+            match name.trim() {
+                "kMDItemLastUsedDate" => usage.push((parse_day(value), 0)),
+                "kMDItemUseCount" => {
+                    if let Some((_, use_count)) = usage.last_mut() {
+                        *use_count = value.parse().unwrap_or(0);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        usage
+    }
+
+    /// Mount points of the volumes mounted over the network
+    fn network_volumes() -> Vec<PathBuf> {
+        let mut cached_volumes = NETWORK_VOLUMES.lock().expect("no lock poisoning");
+        if let Some((volumes, listed_at)) = cached_volumes.as_ref()
+            && listed_at.elapsed() < NETWORK_VOLUMES_TTL
+        {
+            return volumes.clone();
+        }
+
+        let mount = Command::new("mount")
+            .output()
+            .map(|output| String::from_utf8_lossy(&output.stdout).into_owned())
+            .unwrap_or_default();
+        // "//me@nas/apps on /Volumes/apps (smbfs, nodev, nosuid, mounted by me)"
+        let volumes: Vec<PathBuf> = mount
+            .lines()
+            .filter_map(|line| {
+                let (_, mounted) = line.split_once(" on ")?;
+                let (mount_point, options) = mounted.rsplit_once(" (")?;
+                let file_system = options.split(',').next()?;
+
+                NETWORK_FILE_SYSTEMS
+                    .contains(&file_system)
+                    .then(|| PathBuf::from(mount_point))
+            })
+            .collect();
+
+        *cached_volumes = Some((volumes.clone(), Instant::now()));
+        volumes
+    }
+
+    /// `Applications` folders of the mounted volumes whose apps are indexed
+    fn external_app_dirs(config: &ExternalVolumesConfig) -> Vec<PathBuf> {
+        let Ok(volumes) = std::fs::read_dir(VOLUMES_DIR) else {
+            return Vec::new();
+        };
+
+        volumes
+            .filter_map(Result::ok)
+            // The startup disk is linked to `/`, whose apps are indexed already
+            .filter(|volume| {
+                volume
+                    .file_type()
+                    .is_ok_and(|file_type| !file_type.is_symlink())
+            })
+            .filter(|volume| config.indexes(&volume.file_name().to_string_lossy()))
+            .map(|volume| volume.path().join("Applications"))
+            .filter(|app_dir| app_dir.is_dir())
+            .collect()
+    }
+
+    /// Reads file flags only, which doesn't download iCloud placeholders
+    fn is_dataless(path: &Path) -> bool {
+        std::fs::symlink_metadata(path).is_ok_and(|metadata| metadata.st_flags() & SF_DATALESS != 0)
+    }
+
+    fn app_location(path: &Path) -> AppLocation {
+        if Self::network_volumes()
+            .iter()
+            .any(|volume| path.starts_with(volume))
+        {
+            AppLocation::NetworkVolume
+        } else if Self::is_dataless(path) || Self::is_dataless(&path.join("Contents/Info.plist")) {
+            AppLocation::ICloud
+        } else {
+            AppLocation::Local
+        }
+    }
+
+    fn read_info_plist(name: &str, path: &Path) -> Result<plist::Dictionary, Report> {
+        let info_plist_path = path.join("Contents/Info.plist");
+        let mut info_plist_res = plist::Value::from_file(&info_plist_path);
+
+        if info_plist_res.is_err() {
+            // Low-effort attempt at loading iPad apps downloaded from Mac App Store.
+            let info_plist_path = path.join(format!("Wrapper/{name}.app/Info.plist"));
+            info_plist_res = plist::Value::from_file(info_plist_path);
+        }
+
+        info_plist_res?
+            .into_dictionary()
+            .ok_or_else(|| report!("macOS plist is a dict"))
+    }
+
+    /// Path of the `.icns` file of an app, as declared in its Info.plist
+    fn icon_file_path(path: &Path, info_plist: &plist::Dictionary) -> Result<PathBuf, Report> {
+        let icon_name = match info_plist.get("CFBundleIconFile") {
+            Some(icon_file) => icon_file.as_string().ok_or_else(|| {
+                report!(
+                    "Could not convert CFBundleIconFile value into String (it wasn't a String?)"
+                )
+            })?,
+            // Safari web apps (and some others) don't declare their icon file,
+            // but still ship one under the conventional name.
+            None => "AppIcon",
+        };
+
+        #[allow(
+            clippy::case_sensitive_file_extension_comparisons,
+            reason = "APFS is case-insensitive"
+        )]
+        let icns_suffix = if icon_name.ends_with(".icns") {
+            ""
+        } else {
+            ".icns"
+        };
+
+        Ok(path.join(format!("Contents/Resources/{icon_name}{icns_suffix}")))
+    }
+
+    /// Path of the executable of an app, as declared by its Info.plist
+    fn executable_path(path: &Path, info_plist: &plist::Dictionary) -> Option<PathBuf> {
+        let executable_name = info_plist
+            .get("CFBundleExecutable")
+            .and_then(plist::Value::as_string)?;
+
+        Some(path.join("Contents/MacOS").join(executable_name))
+    }
+
+    /// Processors the executable of an app was built for, as declared
+    /// by its Info.plist
+    fn executable_architecture(
+        path: &Path,
+        info_plist: &plist::Dictionary,
+    ) -> Option<Architecture> {
+        let executable = File::open(Self::executable_path(path, info_plist)?).ok()?;
+
+        let mut header = Vec::new();
+        executable
+            .take(MACH_O_HEADER_LEN)
+            .read_to_end(&mut header)
+            .ok()?;
+
+        Architecture::from_mach_o_header(&header)
+    }
+
     fn read_app_file(path: PathBuf) -> Result<ExecutableApp, Report> {
         // Because try blocks aren't stabilized, make this a function
         // so that error propagation stops at the function scope if icon
         // fails to load.
-        fn try_get_icon_data(name: &String, path: &Path) -> Result<Vec<u8>, Report> {
-            let info_plist_path = path.join("Contents/Info.plist");
-            let mut info_plist_res = plist::Value::from_file(&info_plist_path);
-
-            if info_plist_res.is_err() {
-                // Low-effort attempt at loading iPad apps downloaded from Mac App Store.
-                let info_plist_path = path.join(format!("Wrapper/{name}.app/Info.plist"));
-                info_plist_res = plist::Value::from_file(info_plist_path);
-            }
 
-            let info_plist = info_plist_res?;
-
-            // Extract an icon from Info.plist.
-
-            // iPad apps downloaded from Mac App Store
-
-            let icon_name = info_plist
-                .as_dictionary()
-                .expect("macOS plist is a dict")
-                .get("CFBundleIconFile")
-                .ok_or_else(|| report!("CFBundleIconFile not present in Info.plist"))?
-                .as_string()
-                .ok_or_else(|| {
-                    report!(
-                        "Could not convert CFBundleIconFile value into String (it wasn't a String?)"
-                    )
-                })?;
-
-            #[allow(
-                clippy::case_sensitive_file_extension_comparisons,
-                reason = "APFS is case-insensitive"
-            )]
-            let icns_suffix = if icon_name.ends_with(".icns") {
-                ""
-            } else {
-                ".icns"
-            };
-
-            let icon_path = path.join(format!("Contents/Resources/{icon_name}{icns_suffix}"));
+        fn try_get_icon_data(
+            name: &String,
+            path: &Path,
+            info_plist: &plist::Dictionary,
+        ) -> Result<Vec<u8>, Report> {
+            let icon_path = MacPlatform::icon_file_path(path, info_plist)?;
             let icns_file = BufReader::new(File::open(icon_path)?);
             let icon_family = IconFamily::read(icns_file)?;
 
@@ -81,7 +386,7 @@ impl MacPlatform {
 
             let im = icon_family.get_icon_with_type(*smallest_available_icon_type)?;
             let mut png_data = Vec::new();
-            let _ = im.write_png(&mut png_data);
+            im.write_png(&mut png_data)?;
 
             Ok(png_data)
         }
@@ -106,13 +411,93 @@ impl MacPlatform {
                     .attach("This file path isn't UTF-8 compatible (are you using a supported OS?)")
             })?;
 
-        let icon_png_data = try_get_icon_data(&name, &path).ok();
+        let location = Self::app_location(&path);
+        // Apps stored elsewhere are indexed by name only, see `AppLocation`
+        let info_plist = if location == AppLocation::Local {
+            Self::read_info_plist(&name, &path).ok()
+        } else {
+            None
+        };
+        let icon_png_data = info_plist
+            .as_ref()
+            .and_then(|info_plist| try_get_icon_data(&name, &path, info_plist).ok());
+        let is_web_app = info_plist
+            .as_ref()
+            .and_then(|info_plist| info_plist.get("CFBundleIdentifier"))
+            .and_then(plist::Value::as_string)
+            .is_some_and(|bundle_id| bundle_id.starts_with(SAFARI_WEB_APP_BUNDLE_ID_PREFIX));
+
+        let declared = |key: &str| -> Vec<String> {
+            info_plist
+                .as_ref()
+                .and_then(|info_plist| info_plist.get("CFBundleDocumentTypes"))
+                .and_then(plist::Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(plist::Value::as_dictionary)
+                .filter_map(|document_type| document_type.get(key))
+                .filter_map(plist::Value::as_array)
+                .flatten()
+                .filter_map(plist::Value::as_string)
+                .map(str::to_owned)
+                .collect()
+        };
+        let document_types = declared("LSItemContentTypes");
+        // `*` declares every extension, which says nothing about the app
+        let document_extensions = declared("CFBundleTypeExtensions")
+            .into_iter()
+            .filter(|extension| extension != "*")
+            .map(|extension| extension.to_lowercase())
+            .collect();
+
+        let version = info_plist
+            .as_ref()
+            .and_then(|info_plist| info_plist.get("CFBundleShortVersionString"))
+            .and_then(plist::Value::as_string)
+            .map(|version| version.trim().to_owned())
+            .filter(|version| !version.is_empty());
+        // Apps from the Mac App Store come with a receipt
+        let updates_url =
+            if location == AppLocation::Local && path.join(APP_STORE_RECEIPT_PATH).exists() {
+                Some(Url::AppStore(Cow::Borrowed(APP_STORE_UPDATES_PAGE)))
+            } else {
+                info_plist
+                    .as_ref()
+                    .and_then(|info_plist| info_plist.get("SUFeedURL"))
+                    .and_then(plist::Value::as_string)
+                    .and_then(|feed_url| feed_url.strip_prefix("https://"))
+                    .map(|feed_url| Url::Https(Cow::Owned(feed_url.to_owned())))
+            };
+
+        let architecture = info_plist
+            .as_ref()
+            .and_then(|info_plist| Self::executable_architecture(&path, info_plist));
+
+        let category = info_plist
+            .as_ref()
+            .and_then(|info_plist| info_plist.get("LSApplicationCategoryType"))
+            .and_then(plist::Value::as_string)
+            .map(|category| {
+                category
+                    .strip_prefix(APP_CATEGORY_PREFIX)
+                    .unwrap_or(category)
+                    .to_owned()
+            });
 
         Ok(ExecutableApp {
             name: name.into(),
             is_open: <Self as Platform>::list_open_binaries().contains(&path),
             path,
             icon_png_data,
+            is_web_app,
+            category,
+            document_types,
+            document_extensions,
+            location,
+            version,
+            updates_url,
+            architecture,
+            usage: None,
         })
     }
 
@@ -120,11 +505,13 @@ impl MacPlatform {
         let default_app_paths = config
             .applications
             .iter()
-            .filter_map(|app_path| PathBuf::from_str(app_path).ok());
+            .map(|app_path| expand_home(app_path));
 
         config
             .application_dirs
             .iter()
+            .map(|app_dir| expand_home(app_dir))
+            .chain(Self::external_app_dirs(&config.external_volumes))
             .filter_map(|app_dir| std::fs::read_dir(app_dir).ok())
             .flat_map(IntoIterator::into_iter)
             .filter_map(Result::ok)
@@ -146,7 +533,11 @@ impl MacPlatform {
 
         for path in &config.application_dirs {
             cmd.arg("-onlyin");
-            cmd.arg(path);
+            cmd.arg(expand_home(path));
+        }
+        for app_dir in Self::external_app_dirs(&config.external_volumes) {
+            cmd.arg("-onlyin");
+            cmd.arg(app_dir);
         }
 
         let mdfind_bytes = cmd.output().unwrap().stdout;
@@ -155,12 +546,15 @@ impl MacPlatform {
 
         let set = HashSet::new();
 
-        apps.par_split('\n').map(PathBuf::from).for_each(|p| {
-            let _ = set.insert_sync(p);
-        });
+        apps.par_split('\n')
+            .map(PathBuf::from)
+            .filter(|p| !Self::is_in_trash(p))
+            .for_each(|p| {
+                let _ = set.insert_sync(p);
+            });
 
         config.applications.par_iter().for_each(|app_path| {
-            let _ = set.insert_sync(app_path.to_owned().into());
+            let _ = set.insert_sync(expand_home(app_path));
         });
 
         set
@@ -183,6 +577,23 @@ impl super::Platform for MacPlatform {
         ]
     }
 
+    fn count_spotlight_apps() -> Result<usize, Report> {
+        let output = Command::new("mdfind")
+            .args([
+                "-count",
+                "-onlyin",
+                "/Applications",
+                "kMDItemKind == 'Application'",
+            ])
+            .output()?;
+        if !output.status.success() {
+            return Err(report!("mdfind exited with an error")
+                .attach(String::from_utf8_lossy(&output.stderr).into_owned()));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).trim().parse()?)
+    }
+
     fn list_open_binaries() -> Vec<PathBuf> {
         let lsappinfo_bytes = Command::new("lsappinfo")
             .arg("list")
@@ -224,6 +635,871 @@ impl super::Platform for MacPlatform {
         Ok(())
     }
 
+    fn open_url_in(url: &Url, bundle_id: &str) -> Result<(), Report> {
+        Command::new("open")
+            .args(["-b", bundle_id, "-u"])
+            .arg(url.to_string())
+            .spawn()?;
+
+        Ok(())
+    }
+
+    fn find_files(name: &str, limit: usize) -> Result<Vec<FileMetadata>, Report> {
+        let home = dirs::home_dir().ok_or_else(|| report!("No home folder"))?;
+        let output = Command::new("mdfind")
+            .arg("-onlyin")
+            .arg(home)
+            .args(["-name", name])
+            .output()?;
+        if !output.status.success() {
+            return Err(report!("mdfind exited with an error")
+                .attach(String::from_utf8_lossy(&output.stderr).into_owned()));
+        }
+        let paths: Vec<PathBuf> = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter(|line| !line.is_empty())
+            .take(limit)
+            .map(PathBuf::from)
+            .collect();
+        if paths.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let output = Command::new("mdls")
+            .args(["-name", "kMDItemLastUsedDate", "-name", "kMDItemUseCount"])
+            .args(&paths)
+            .output()?;
+        if !output.status.success() {
+            return Err(report!("mdls exited with an error")
+                .attach(String::from_utf8_lossy(&output.stderr).into_owned()));
+        }
+        let usage = Self::parse_file_usage(&String::from_utf8_lossy(&output.stdout));
+
+        Ok(paths
+            .into_iter()
+            .zip(usage.into_iter().chain(std::iter::repeat((None, 0))))
+            .map(|(path, (last_used, use_count))| FileMetadata {
+                path,
+                last_used,
+                use_count,
+            })
+            .collect())
+    }
+
+    fn app_with_bundle_id(bundle_id: &str) -> Option<PathBuf> {
+        let output = Command::new("mdfind")
+            .arg(format!("kMDItemCFBundleIdentifier == '{bundle_id}'"))
+            .output()
+            .ok()?;
+
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .find(|line| line.ends_with(".app"))
+            .map(PathBuf::from)
+    }
+
+    fn launch_app(app: &Path) -> Result<(), Report> {
+        let output = Command::new("open").arg(app).output()?;
+        if !output.status.success() {
+            return Err(report!("open exited with an error")
+                .attach(String::from_utf8_lossy(&output.stderr).into_owned()));
+        }
+
+        Ok(())
+    }
+
+    fn app_executable(app: &Path) -> Option<PathBuf> {
+        let name = app.file_stem()?.to_string_lossy();
+        let info_plist = Self::read_info_plist(&name, app).ok()?;
+
+        Self::executable_path(app, &info_plist)
+    }
+
+    fn app_version(app: &Path) -> Option<String> {
+        let name = app.file_stem()?.to_string_lossy();
+        let info_plist = Self::read_info_plist(&name, app).ok()?;
+
+        info_plist
+            .get("CFBundleShortVersionString")
+            .and_then(plist::Value::as_string)
+            .map(|version| version.trim().to_owned())
+    }
+
+    fn is_quarantined(path: &Path) -> bool {
+        Command::new("xattr")
+            .args(["-p", QUARANTINE_ATTRIBUTE])
+            .arg(path)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .is_ok_and(|status| status.success())
+    }
+
+    fn remove_quarantine(app: &Path) -> Result<(), Report> {
+        let output = Command::new("xattr")
+            .args(["-r", "-d", QUARANTINE_ATTRIBUTE])
+            .arg(app)
+            .output()?;
+        if !output.status.success() {
+            return Err(report!("xattr could not remove the quarantine")
+                .attach(String::from_utf8_lossy(&output.stderr).into_owned()));
+        }
+
+        Ok(())
+    }
+
+    fn open_with(app: &Path, files: &[PathBuf]) -> Result<(), Report> {
+        Command::new("open")
+            .arg("-a")
+            .arg(app)
+            .args(files)
+            .spawn()?;
+
+        Ok(())
+    }
+
+    fn quick_look(window: &impl HasWindowHandle, paths: &[PathBuf]) -> Result<(), Report> {
+        let ns_window = ns_window(window)?;
+
+        quick_look::quick_look(&ns_window, paths)
+    }
+
+    fn open_with_rosetta(app: &Path) -> Result<(), Report> {
+        Command::new("open")
+            .args(["--arch", "x86_64"])
+            .arg(app)
+            .spawn()?;
+
+        Ok(())
+    }
+
+    fn reveal(path: &Path) -> Result<(), Report> {
+        Command::new("open").arg("-R").arg(path).spawn()?;
+
+        Ok(())
+    }
+
+    fn move_to_trash(path: &Path) -> Result<(), Report> {
+        Self::run_osascript(&format!(
+            "tell application \"Finder\" to delete POSIX file {}",
+            applescript_string(&path.to_string_lossy())
+        ))?;
+
+        Ok(())
+    }
+
+    fn verify_signature(file: &Path, signature: &Path, public_key: &str) -> Result<(), Report> {
+        // `openssl` only reads keys from files
+        let key_path = signature.with_extension("pem");
+        std::fs::write(&key_path, public_key)?;
+        let output = Command::new("openssl")
+            .args(["dgst", "-sha256", "-verify"])
+            .arg(&key_path)
+            .arg("-signature")
+            .arg(signature)
+            .arg(file)
+            .output();
+        let _ = std::fs::remove_file(&key_path);
+
+        let output = output?;
+        if !output.status.success() {
+            return Err(report!("The signature doesn't match")
+                .attach(String::from_utf8_lossy(&output.stderr).into_owned()));
+        }
+
+        Ok(())
+    }
+
+    fn extract_archive(archive: &Path, dir: &Path) -> Result<(), Report> {
+        // Unlike `unzip`, `ditto` keeps the symlinks and attributes of bundles
+        let output = Command::new("ditto")
+            .args(["-x", "-k"])
+            .arg(archive)
+            .arg(dir)
+            .output()?;
+        if !output.status.success() {
+            return Err(report!("ditto failed to extract the archive")
+                .attach(String::from_utf8_lossy(&output.stderr).into_owned()));
+        }
+
+        Ok(())
+    }
+
+    fn content_types(path: &Path) -> Vec<String> {
+        let Ok(output) = Command::new("mdls")
+            .args(["-raw", "-name", "kMDItemContentTypeTree"])
+            .arg(path)
+            .output()
+        else {
+            return Vec::new();
+        };
+
+        // One quoted UTI per line between parentheses, or `(null)`
+        // for files Spotlight doesn't know about
+        let mut content_types: Vec<String> = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| {
+                let content_type = line.trim().trim_end_matches(',');
+                content_type
+                    .strip_prefix('"')
+                    .and_then(|content_type| content_type.strip_suffix('"'))
+                    .map(str::to_owned)
+            })
+            .collect();
+
+        if content_types.is_empty() && path.is_dir() {
+            content_types.push(String::from("public.folder"));
+        }
+
+        content_types
+    }
+
+    fn fallback_app_icon(app: &Path) -> Result<Vec<u8>, Report> {
+        let name = app
+            .file_stem()
+            .ok_or_else(|| report!("App path has no name"))?
+            .to_string_lossy();
+        let icon_path = Self::icon_file_path(app, &Self::read_info_plist(&name, app)?)?;
+
+        // `sips` only writes to files
+        let png_path = std::env::temp_dir().join(format!(
+            "fetch-icon-{}-{}.png",
+            std::process::id(),
+            FALLBACK_ICONS_DECODED.fetch_add(1, Ordering::Relaxed)
+        ));
+        let output = Command::new("sips")
+            .args(["-s", "format", "png", "-Z", FALLBACK_ICON_SIZE])
+            .arg(&icon_path)
+            .arg("--out")
+            .arg(&png_path)
+            .output()?;
+        let png_data = std::fs::read(&png_path);
+        let _ = std::fs::remove_file(&png_path);
+
+        if !output.status.success() {
+            return Err(report!("sips failed to convert the icon")
+                .attach(String::from_utf8_lossy(&output.stderr).into_owned()));
+        }
+
+        Ok(png_data?)
+    }
+
+    fn frontmost_app() -> Option<RunningApp> {
+        let front_bytes = Command::new("lsappinfo").arg("front").output().ok()?.stdout;
+        let asn = String::from_utf8(front_bytes).ok()?;
+
+        let info_bytes = Command::new("lsappinfo")
+            .arg("info")
+            .arg(asn.trim())
+            .output()
+            .ok()?
+            .stdout;
+        let info = String::from_utf8(info_bytes).ok()?;
+
+        // First line is formatted as `"Finder" ASN:0x0-0x2d02d:`
+        let name = info.lines().next()?.split('"').nth(1)?;
+        let bundle_id = info.lines().find_map(|line| {
+            line.trim_start()
+                .strip_prefix("bundleID=\"")
+                .and_then(|id| id.strip_suffix('"'))
+        })?;
+
+        Some(RunningApp {
+            name: name.into(),
+            bundle_id: bundle_id.to_owned(),
+        })
+    }
+
+    fn has_accessibility_permission() -> bool {
+        // SAFETY: Takes no arguments and only reads the TCC database
+        unsafe { AXIsProcessTrusted() }
+    }
+
+    fn is_frontmost_app_full_screen() -> bool {
+        let Some(pid) = NSWorkspace::sharedWorkspace()
+            .frontmostApplication()
+            .map(|app| app.processIdentifier())
+        else {
+            return false;
+        };
+
+        let mut displays = [0; MAX_DISPLAYS as usize];
+        let mut count = 0;
+        // SAFETY: `displays` holds the `MAX_DISPLAYS` ids written at most
+        let error =
+            unsafe { CGGetActiveDisplayList(MAX_DISPLAYS, displays.as_mut_ptr(), &raw mut count) };
+        if error != 0 {
+            return false;
+        }
+        let display_bounds: Vec<NSRect> = displays
+            .iter()
+            .take(usize::try_from(count).unwrap_or_default())
+            // SAFETY: Takes a display id, which isn't dereferenced
+            .map(|display| unsafe { CGDisplayBounds(*display) })
+            .collect();
+
+        // SAFETY: the list is checked for null and released once read, and
+        // the values read from it are owned by it, checked for null before
+        // being read, and read as the types CoreGraphics documents
+        unsafe {
+            let windows = CGWindowListCopyWindowInfo(ON_SCREEN_WINDOWS, NULL_WINDOW_ID);
+            if windows.is_null() {
+                return false;
+            }
+
+            // Windows of full-screen apps cover a whole display, where
+            // other windows leave room for the menu bar
+            let is_full_screen = (0..CFArrayGetCount(windows)).any(|i| {
+                let window = CFArrayGetValueAtIndex(windows, i);
+                let owner = CFDictionaryGetValue(window, kCGWindowOwnerPID);
+                let bounds = CFDictionaryGetValue(window, kCGWindowBounds);
+                if owner.is_null() || bounds.is_null() {
+                    return false;
+                }
+
+                let mut owner_pid: i32 = 0;
+                let mut window_bounds = NSRect::ZERO;
+                CFNumberGetValue(owner, CF_NUMBER_SINT32_TYPE, (&raw mut owner_pid).cast())
+                    && owner_pid == pid
+                    && CGRectMakeWithDictionaryRepresentation(bounds, &raw mut window_bounds)
+                    && display_bounds.contains(&window_bounds)
+            });
+            CFRelease(windows);
+
+            is_full_screen
+        }
+    }
+
+    fn paste_into(app: &RunningApp) -> Result<(), Report> {
+        // `activate` isn't an Apple event, so it takes no Automation permission
+        Self::run_osascript(&format!(
+            "tell application id {} to activate",
+            applescript_string(&app.bundle_id)
+        ))?;
+        std::thread::sleep(PASTE_DELAY);
+
+        for key_down in [true, false] {
+            // SAFETY: a null source is allowed, and the event is released
+            // once posted, which copies it
+            unsafe {
+                let event = CGEventCreateKeyboardEvent(std::ptr::null(), KEY_CODE_V, key_down);
+                if event.is_null() {
+                    return Err(report!("Could not create a keyboard event"));
+                }
+                CGEventSetFlags(event, COMMAND_FLAG);
+                CGEventPost(HID_EVENT_TAP, event);
+                CFRelease(event);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn list_menu_items(app: &RunningApp) -> Result<Vec<MenuItem>, Report> {
+        // Outputs one menu item per line, with the titles of its menus and
+        // its own title separated by tabs. Goes one submenu deep at most.
+        let script = format!(
+            r#"
+            tell application "System Events"
+                tell (first application process whose bundle identifier is {bundle_id})
+                    set output to ""
+                    repeat with barItem in menu bar items of menu bar 1
+                        set barName to name of barItem
+                        try
+                            repeat with menuItem in menu items of menu 1 of barItem
+                                set itemName to name of menuItem
+                                if itemName is not missing value then
+                                    if exists menu 1 of menuItem then
+                                        repeat with subItem in menu items of menu 1 of menuItem
+                                            set subName to name of subItem
+                                            if subName is not missing value then
+                                                set output to output & barName & tab & itemName & tab & subName & linefeed
+                                            end if
+                                        end repeat
+                                    else
+                                        set output to output & barName & tab & itemName & linefeed
+                                    end if
+                                end if
+                            end repeat
+                        end try
+                    end repeat
+                    return output
+                end tell
+            end tell
+            "#,
+            bundle_id = applescript_string(&app.bundle_id)
+        );
+
+        let output = Self::run_osascript(&script)?;
+
+        Ok(output
+            .lines()
+            .filter_map(|line| {
+                let mut titles: Vec<AppString> = line.split('\t').map(AppString::from).collect();
+                let title = titles.pop()?;
+
+                (!titles.is_empty()).then(|| MenuItem {
+                    app: app.clone(),
+                    menu_path: titles,
+                    title,
+                })
+            })
+            .collect())
+    }
+
+    fn click_menu_item(item: &MenuItem) -> Result<(), Report> {
+        let mut menu_path = item.menu_path.iter();
+        let bar_item = menu_path
+            .next()
+            .ok_or_else(|| report!("Menu items always belong to a menu bar item"))?;
+
+        let mut reference = format!(
+            "menu bar item {} of menu bar 1",
+            applescript_string(bar_item)
+        );
+        for menu in menu_path.chain(std::iter::once(&item.title)) {
+            reference = format!(
+                "menu item {} of menu 1 of {reference}",
+                applescript_string(menu)
+            );
+        }
+
+        let script = format!(
+            r#"
+            tell application "System Events"
+                tell (first application process whose bundle identifier is {bundle_id})
+                    set frontmost to true
+                    click {reference}
+                end tell
+            end tell
+            "#,
+            bundle_id = applescript_string(&item.app.bundle_id)
+        );
+
+        Self::run_osascript(&script)?;
+
+        Ok(())
+    }
+
+    fn run_applescript(
+        script: &AppleScript,
+        environment: &ProcessEnvironment,
+    ) -> Result<String, Report> {
+        let mut osascript = Command::new("osascript");
+        match script {
+            AppleScript::Source(source) => osascript.arg("-e").arg(source),
+            AppleScript::File(path) => osascript.arg(path),
+        };
+        environment.apply(&mut osascript);
+
+        Self::osascript_output(&mut osascript)
+    }
+
+    fn finder_selection() -> Result<Vec<PathBuf>, Report> {
+        let output = Self::run_osascript(
+            r#"tell application "Finder" to set selectedItems to selection as alias list
+set paths to ""
+repeat with selectedItem in selectedItems
+    set paths to paths & POSIX path of selectedItem & linefeed
+end repeat
+return paths"#,
+        )?;
+
+        Ok(output
+            .lines()
+            .filter(|path| !path.is_empty())
+            .map(PathBuf::from)
+            .collect())
+    }
+
+    fn list_shortcuts() -> Result<Vec<AppString>, Report> {
+        let output = Command::new("shortcuts").arg("list").output()?;
+
+        if !output.status.success() {
+            return Err(report!("`shortcuts list` exited with an error"));
+        }
+
+        Ok(String::from_utf8(output.stdout)?
+            .lines()
+            .filter(|name| !name.is_empty())
+            .map(AppString::from)
+            .collect())
+    }
+
+    fn run_shortcut(shortcut: &Shortcut) -> Result<(), Report> {
+        let mut cmd = Command::new("shortcuts");
+        cmd.arg("run").arg(&*shortcut.name);
+
+        if let Some(input) = &shortcut.input {
+            // The CLI only takes input from files
+            let input_path = std::env::temp_dir().join("fetch-shortcut-input.txt");
+            std::fs::write(&input_path, input.as_bytes())?;
+            cmd.arg("--input-path").arg(input_path);
+        }
+
+        cmd.spawn()?;
+
+        Ok(())
+    }
+
+    fn read_clipboard() -> Option<String> {
+        let output = Command::new("pbpaste").output().ok()?;
+
+        if !output.status.success() {
+            return None;
+        }
+
+        String::from_utf8(output.stdout).ok()
+    }
+
+    fn open_ssh(host: &SshHost, terminal: Terminal) -> Result<(), Report> {
+        let command = format!("ssh {}", host.ssh_args().join(" "));
+
+        match terminal {
+            Terminal::Terminal => {
+                Self::run_osascript(&format!(
+                    r#"
+                    tell application "Terminal"
+                        activate
+                        do script {command}
+                    end tell
+                    "#,
+                    command = applescript_string(&command)
+                ))?;
+            }
+            Terminal::Iterm => {
+                Self::run_osascript(&format!(
+                    r#"
+                    tell application "iTerm"
+                        activate
+                        create window with default profile command {command}
+                    end tell
+                    "#,
+                    command = applescript_string(&command)
+                ))?;
+            }
+            Terminal::Kitty => {
+                Command::new("open")
+                    .args(["-n", "-a", "kitty", "--args", "ssh"])
+                    .args(host.ssh_args())
+                    .spawn()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn open_in_terminal(dir: &Path, terminal: Terminal) -> Result<(), Report> {
+        let app = match terminal {
+            Terminal::Terminal => "Terminal",
+            Terminal::Iterm => "iTerm",
+            Terminal::Kitty => {
+                // kitty ignores paths given to `open`
+                Command::new("open")
+                    .args(["-n", "-a", "kitty", "--args", "--directory"])
+                    .arg(dir)
+                    .spawn()?;
+                return Ok(());
+            }
+        };
+
+        Command::new("open").arg("-a").arg(app).arg(dir).spawn()?;
+
+        Ok(())
+    }
+
+    fn open_project(project: &Project, terminal: Terminal) -> Result<(), Report> {
+        let app = match project.target {
+            ProjectTarget::VsCode => "Visual Studio Code",
+            ProjectTarget::Zed => "Zed",
+            ProjectTarget::Intellij => "IntelliJ IDEA",
+            ProjectTarget::Terminal => return Self::open_in_terminal(&project.path, terminal),
+            ProjectTarget::Finder => "Finder",
+        };
+
+        Command::new("open")
+            .arg("-a")
+            .arg(app)
+            .arg(&project.path)
+            .spawn()?;
+
+        Ok(())
+    }
+
+    fn show_over_full_screen_apps(window: &impl HasWindowHandle) -> Result<(), Report> {
+        let ns_window = ns_window(window)?;
+
+        // Joining every Space isn't enough on its own: full-screen Spaces
+        // only accept windows that are auxiliary to the full-screen app
+        ns_window.setCollectionBehavior(
+            ns_window.collectionBehavior()
+                | NSWindowCollectionBehavior::CanJoinAllSpaces
+                | NSWindowCollectionBehavior::FullScreenAuxiliary,
+        );
+
+        Ok(())
+    }
+
+    fn set_window_height(window: &impl HasWindowHandle, height: f32) -> Result<(), Report> {
+        let ns_window = ns_window(window)?;
+
+        // AppKit keeps the bottom edge of windows where it is, as its
+        // coordinates go up from the bottom of the screen
+        let content = ns_window.contentRectForFrameRect(ns_window.frame());
+        let height = f64::from(height);
+        let resized = NSRect::new(
+            NSPoint::new(
+                content.origin.x,
+                content.origin.y + content.size.height - height,
+            ),
+            NSSize::new(content.size.width, height),
+        );
+        ns_window.setFrame_display(ns_window.frameRectForContentRect(resized), true);
+
+        Ok(())
+    }
+
+    fn drag_files(window: &impl HasWindowHandle, paths: &[PathBuf]) -> Result<(), Report> {
+        let RawWindowHandle::AppKit(handle) = window.window_handle()?.as_raw() else {
+            return Err(report!("Not an AppKit window"));
+        };
+        // SAFETY: the view belongs to `window`, which is borrowed for the
+        // whole call, and gpui only hands out windows on the main thread
+        let ns_view = unsafe { handle.ns_view.cast::<NSView>().as_ref() };
+
+        dragging::drag_files(ns_view, paths)
+    }
+
+    fn is_in_trash(path: &Path) -> bool {
+        let in_user_trash =
+            dirs::home_dir().is_some_and(|home| path.starts_with(home.join(".Trash")));
+        // Other volumes have their own Trash
+        let in_volume_trash = path
+            .components()
+            .any(|component| component.as_os_str() == ".Trashes");
+
+        in_user_trash || in_volume_trash
+    }
+
+    fn trash_location(path: &Path) -> Option<PathBuf> {
+        let trash_path = dirs::home_dir()?.join(".Trash").join(path.file_name()?);
+
+        trash_path.exists().then_some(trash_path)
+    }
+
+    fn external_volume(path: &Path) -> Option<PathBuf> {
+        let volume_name = path.strip_prefix(VOLUMES_DIR).ok()?.components().next()?;
+
+        Some(Path::new(VOLUMES_DIR).join(volume_name))
+    }
+
+    fn keychain_password(service: &str, account: &str) -> Option<String> {
+        let output = Command::new("security")
+            .args(["find-generic-password", "-s", service, "-a", account, "-w"])
+            .stderr(Stdio::null())
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+
+        let password = String::from_utf8(output.stdout).ok()?;
+        Some(password.trim_end().to_owned())
+    }
+
+    fn set_keychain_password(service: &str, account: &str, password: &str) -> Result<(), Report> {
+        // The command is written to stdin rather than passed as arguments,
+        // which other processes can read
+        let mut security = Command::new("security")
+            .arg("-i")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        if let Some(mut stdin) = security.stdin.take() {
+            let quote =
+                |text: &str| format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""));
+            writeln!(
+                stdin,
+                "add-generic-password -U -s {} -a {} -w {}",
+                quote(service),
+                quote(account),
+                quote(password)
+            )?;
+        }
+
+        let output = security.wait_with_output()?;
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if !output.status.success() || !stderr.trim().is_empty() {
+            return Err(report!("security could not store the password: {stderr}"));
+        }
+
+        Ok(())
+    }
+
+    fn delete_keychain_password(service: &str, account: &str) -> Result<(), Report> {
+        let output = Command::new("security")
+            .args(["delete-generic-password", "-s", service, "-a", account])
+            .stdout(Stdio::null())
+            .output()?;
+        if !output.status.success() {
+            return Err(report!("security could not delete the password")
+                .attach(String::from_utf8_lossy(&output.stderr).into_owned()));
+        }
+
+        Ok(())
+    }
+
+    fn http_get(url: &str, headers: &[(&str, &str)]) -> Result<Vec<u8>, Report> {
+        // Headers (which may contain tokens) are written to stdin
+        // rather than passed as arguments, which other processes can read
+        let mut curl = Command::new("curl")
+            .args(["--silent", "--show-error", "--fail", "--location"])
+            .args(["--header", "@-"])
+            .arg(url)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        if let Some(mut stdin) = curl.stdin.take() {
+            for (name, value) in headers {
+                writeln!(stdin, "{name}: {value}")?;
+            }
+        }
+
+        let output = curl.wait_with_output()?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(report!("curl exited with an error: {stderr}"));
+        }
+
+        Ok(output.stdout)
+    }
+
+    fn power_state() -> PowerState {
+        // `pmset` reports the power source and settings known to IOKit
+        let pmset = |args: &[&str]| {
+            Command::new("pmset")
+                .args(args)
+                .output()
+                .ok()
+                .filter(|output| output.status.success())
+                .map(|output| String::from_utf8_lossy(&output.stdout).into_owned())
+                .unwrap_or_default()
+        };
+
+        // "Now drawing from 'Battery Power'"
+        let on_battery = pmset(&["-g", "batt"]).contains("'Battery Power'");
+        // " lowpowermode         1"
+        let low_power_mode = pmset(&["-g"]).lines().any(|line| {
+            let mut words = line.split_whitespace();
+            words.next() == Some("lowpowermode") && words.next() == Some("1")
+        });
+
+        PowerState {
+            on_battery,
+            low_power_mode,
+        }
+    }
+
+    fn notification_permission() -> NotificationPermission {
+        // The notification center throws for processes that aren't in an
+        // app bundle, like the ones `cargo run` starts
+        if NSBundle::mainBundle().bundleIdentifier().is_none() {
+            return NotificationPermission::Denied;
+        }
+
+        let (sender, receiver) = mpsc::channel();
+        let handler = RcBlock::new(move |settings: NonNull<UNNotificationSettings>| {
+            // SAFETY: the settings are alive for the whole handler
+            let status = unsafe { settings.as_ref() }.authorizationStatus();
+            let _ = sender.send(status);
+        });
+        UNUserNotificationCenter::currentNotificationCenter()
+            .getNotificationSettingsWithCompletionHandler(&handler);
+
+        match receiver.recv_timeout(Duration::from_secs(5)) {
+            Ok(UNAuthorizationStatus::NotDetermined) => NotificationPermission::NotDetermined,
+            Ok(
+                UNAuthorizationStatus::Authorized
+                | UNAuthorizationStatus::Provisional
+                | UNAuthorizationStatus::Ephemeral,
+            ) => NotificationPermission::Granted,
+            _ => NotificationPermission::Denied,
+        }
+    }
+
+    fn request_notification_permission() -> Result<bool, Report> {
+        let (sender, receiver) = mpsc::channel();
+        let handler = RcBlock::new(move |granted: Bool, error: *mut NSError| {
+            let _ =
+                sender.send(Self::ns_error_description(error).map_or(Ok(granted.as_bool()), Err));
+        });
+        UNUserNotificationCenter::currentNotificationCenter()
+            .requestAuthorizationWithOptions_completionHandler(
+                UNAuthorizationOptions::Alert | UNAuthorizationOptions::Sound,
+                &handler,
+            );
+
+        // No timeout, the user can take their time to answer
+        receiver
+            .recv()?
+            .map_err(|description| report!("{description}"))
+    }
+
+    fn post_notification(notification: &Notification) -> Result<(), Report> {
+        let content = UNMutableNotificationContent::new();
+        content.setTitle(&NSString::from_str(&notification.title));
+        content.setBody(&NSString::from_str(&notification.body));
+        // Only time-sensitive notifications break through a Focus
+        content.setInterruptionLevel(UNNotificationInterruptionLevel::Active);
+        let request = UNNotificationRequest::requestWithIdentifier_content_trigger(
+            &NSString::from_str(&notification.id),
+            &content,
+            None,
+        );
+
+        let (sender, receiver) = mpsc::channel();
+        let handler = RcBlock::new(move |error: *mut NSError| {
+            let _ = sender.send(Self::ns_error_description(error));
+        });
+        UNUserNotificationCenter::currentNotificationCenter()
+            .addNotificationRequest_withCompletionHandler(&request, Some(&handler));
+
+        match receiver.recv_timeout(Duration::from_secs(5))? {
+            Some(description) => Err(report!("{description}")),
+            None => Ok(()),
+        }
+    }
+
+    fn locale() -> Option<String> {
+        let output = Command::new("defaults")
+            .args(["read", "-g", "AppleLocale"])
+            .output()
+            .ok()
+            .filter(|output| output.status.success())?;
+        let locale = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+
+        (!locale.is_empty()).then_some(locale)
+    }
+
+    fn today() -> Option<Date> {
+        let output = Command::new("date")
+            .arg("+%Y %m %d")
+            .output()
+            .ok()
+            .filter(|output| output.status.success())?;
+        let output = String::from_utf8_lossy(&output.stdout);
+        let mut parts = output.split_whitespace().map(str::parse::<i32>);
+
+        let year = parts.next()?.ok()?;
+        let month = u32::try_from(parts.next()?.ok()?).ok()?;
+        let day = u32::try_from(parts.next()?.ok()?).ok()?;
+        Date::new(year, month, day)
+    }
+
     /// Lists the paths of every application to list.
     ///
     /// If `quick` is set to true, this function will only rely on Spotlight indexing,
@@ -233,7 +1509,9 @@ impl super::Platform for MacPlatform {
 
         if !quick {
             Self::read_apps_from_dir_path(config).iter_sync(|e| {
-                let _ = set.insert_sync(e.clone());
+                if !Self::is_in_trash(e) {
+                    let _ = set.insert_sync(e.clone());
+                }
                 true
             });
         }
@@ -251,7 +1529,7 @@ impl super::Platform for MacPlatform {
                     None
                 }
             }
-            Url::Https(_cow) => None,
+            Url::Https(_) | Url::SystemSettings(_) | Url::AppStore(_) | Url::Other(_) => None,
         }
     }
 }
@@ -0,0 +1,114 @@
+//! Dragging the files of results out of the search window, into Finder,
+//! the Dock or another app. gpui only handles drags within a window, so
+//! they are started with AppKit.
+
+use std::{cell::OnceCell, path::PathBuf};
+
+use objc2::{
+    ClassType, MainThreadMarker, MainThreadOnly, define_class, msg_send,
+    rc::{Allocated, Retained},
+    runtime::{AnyObject, NSObject, NSObjectProtocol, ProtocolObject},
+};
+use objc2_app_kit::{
+    NSApplication, NSDragOperation, NSDraggingContext, NSDraggingItem, NSDraggingSession,
+    NSDraggingSource, NSView, NSWorkspace,
+};
+use objc2_foundation::{NSArray, NSPoint, NSRect, NSSize, NSString, NSURL};
+use rootcause::{Report, report};
+
+/// Side of the icons dragged along with the files, in points
+const ICON_SIZE: f64 = 32.0;
+
+/// Distance between the icons of the files dragged at once, in points
+const ICON_SPACING: f64 = 6.0;
+
+define_class!(
+    /// Source of the drags out of the search window. Files can be copied
+    /// or linked (as the Dock does), but not moved, so that dropping an app
+    /// in a folder doesn't take it out of `/Applications`.
+    // SAFETY:
+    // - NSObject has no subclassing requirements
+    // - `FileDragSource` doesn't implement `Drop`
+    #[unsafe(super(NSObject))]
+    #[thread_kind = MainThreadOnly]
+    #[name = "FetchFileDragSource"]
+    struct FileDragSource;
+
+    unsafe impl NSObjectProtocol for FileDragSource {}
+
+    unsafe impl NSDraggingSource for FileDragSource {
+        #[unsafe(method(draggingSession:sourceOperationMaskForDraggingContext:))]
+        fn source_operation_mask(
+            &self,
+            _session: &NSDraggingSession,
+            _context: NSDraggingContext,
+        ) -> NSDragOperation {
+            NSDragOperation::Copy | NSDragOperation::Link | NSDragOperation::Generic
+        }
+    }
+);
+
+impl FileDragSource {
+    fn new(mtm: MainThreadMarker) -> Retained<Self> {
+        let this = Self::alloc(mtm).set_ivars(());
+        // SAFETY: `init` of NSObject, which takes no arguments
+        unsafe { msg_send![super(this), init] }
+    }
+}
+
+thread_local! {
+    /// Kept for as long as Fetch runs, as drags outlive the call starting them
+    static FILE_DRAG_SOURCE: OnceCell<Retained<FileDragSource>> = const { OnceCell::new() };
+}
+
+/// Starts dragging `paths` out of `ns_view`, from the mouse event AppKit
+/// is dispatching
+pub(super) fn drag_files(ns_view: &NSView, paths: &[PathBuf]) -> Result<(), Report> {
+    let mtm = MainThreadMarker::from(ns_view);
+    let event = NSApplication::sharedApplication(mtm)
+        .currentEvent()
+        .ok_or_else(|| report!("No mouse event to start dragging from"))?;
+    let mouse = ns_view.convertPoint_fromView(event.locationInWindow(), None);
+
+    let workspace = NSWorkspace::sharedWorkspace();
+    let mut offset = 0.0;
+    let items: Vec<Retained<NSDraggingItem>> = paths
+        .iter()
+        .map(|path| {
+            let path = NSString::from_str(&path.to_string_lossy());
+            let url = NSURL::fileURLWithPath(&path);
+            // SAFETY: `alloc` of NSObject subclasses, initialized right after
+            let item: Allocated<NSDraggingItem> =
+                unsafe { msg_send![NSDraggingItem::class(), alloc] };
+            let item =
+                NSDraggingItem::initWithPasteboardWriter(item, ProtocolObject::from_ref(&*url));
+
+            // Stacked under the mouse, a little apart from each other
+            let frame = NSRect::new(
+                NSPoint::new(
+                    mouse.x - ICON_SIZE / 2.0 + offset,
+                    mouse.y - ICON_SIZE / 2.0 - offset,
+                ),
+                NSSize::new(ICON_SIZE, ICON_SIZE),
+            );
+            offset += ICON_SPACING;
+            let icon = workspace.iconForFile(&path);
+            let contents: &AnyObject = &icon;
+            // SAFETY: images are what dragging items expect as contents
+            unsafe { item.setDraggingFrame_contents(frame, Some(contents)) };
+
+            item
+        })
+        .collect();
+
+    FILE_DRAG_SOURCE.with(|source| {
+        let source = source.get_or_init(|| FileDragSource::new(mtm));
+        ns_view.beginDraggingSessionWithItems_event_source(
+            &NSArray::from_retained_slice(&items),
+            &event,
+            ProtocolObject::from_ref(&**source),
+        );
+    });
+
+    Ok(())
+}
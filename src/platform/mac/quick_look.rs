@@ -0,0 +1,153 @@
+//! Previewing the files of results in the Quick Look panel of Fetch itself,
+//! as Finder does. The panel looks for whatever controls it in the responder
+//! chain of the key window, where the search window lists the files.
+
+use std::{
+    cell::{OnceCell, RefCell},
+    path::PathBuf,
+};
+
+use objc2::{
+    DefinedClass, MainThreadMarker, MainThreadOnly, define_class, msg_send,
+    rc::Retained,
+    runtime::{AnyClass, AnyObject, NSObjectProtocol},
+};
+use objc2_app_kit::{NSPanel, NSResponder, NSWindow};
+use objc2_foundation::{NSInteger, NSString, NSURL};
+use rootcause::{Report, report};
+
+// The panel is part of Quick Look UI, which isn't among the frameworks Fetch
+// has objc2 bindings for, so it is looked up and messaged through the runtime
+#[link(name = "Quartz", kind = "framework")]
+unsafe extern "C" {}
+
+/// What the panel previews
+struct Previews {
+    urls: RefCell<Vec<Retained<NSURL>>>,
+}
+
+define_class!(
+    /// Controls the Quick Look panel from right after the search window in
+    /// its responder chain, and is the data source listing the files the
+    /// panel previews (`QLPreviewPanelDataSource`)
+    // SAFETY:
+    // - NSResponder has no subclassing requirements
+    // - `PreviewController` doesn't implement `Drop`
+    #[unsafe(super(NSResponder))]
+    #[thread_kind = MainThreadOnly]
+    #[name = "FetchPreviewController"]
+    #[ivars = Previews]
+    struct PreviewController;
+
+    impl PreviewController {
+        #[unsafe(method(acceptsPreviewPanelControl:))]
+        fn accepts_preview_panel_control(&self, _panel: Option<&NSPanel>) -> bool {
+            true
+        }
+
+        #[unsafe(method(beginPreviewPanelControl:))]
+        fn begin_preview_panel_control(&self, panel: Option<&NSPanel>) {
+            let Some(panel) = panel else {
+                return;
+            };
+            // SAFETY: the controller answers the data source methods, and
+            // is kept for as long as Fetch runs, so it outlives the panel
+            // pointing to it
+            unsafe {
+                let _: () = msg_send![panel, setDataSource: Some(self)];
+                let _: () = msg_send![panel, reloadData];
+            }
+        }
+
+        #[unsafe(method(endPreviewPanelControl:))]
+        fn end_preview_panel_control(&self, panel: Option<&NSPanel>) {
+            if let Some(panel) = panel {
+                // SAFETY: the panel takes no data source as previewing nothing
+                let _: () = unsafe { msg_send![panel, setDataSource: None::<&AnyObject>] };
+            }
+        }
+
+        #[unsafe(method(numberOfPreviewItemsInPreviewPanel:))]
+        fn number_of_preview_items(&self, _panel: Option<&NSPanel>) -> NSInteger {
+            NSInteger::try_from(self.ivars().urls.borrow().len()).unwrap_or(NSInteger::MAX)
+        }
+
+        // File URLs are preview items (`QLPreviewItem`) as they are
+        #[unsafe(method(previewPanel:previewItemAtIndex:))]
+        fn preview_item(
+            &self,
+            _panel: Option<&NSPanel>,
+            index: NSInteger,
+        ) -> Option<Retained<NSURL>> {
+            self.ivars()
+                .urls
+                .borrow()
+                .get(usize::try_from(index).ok()?)
+                .cloned()
+        }
+    }
+
+    unsafe impl NSObjectProtocol for PreviewController {}
+);
+
+impl PreviewController {
+    fn new(mtm: MainThreadMarker) -> Retained<Self> {
+        let this = Self::alloc(mtm).set_ivars(Previews {
+            urls: RefCell::new(Vec::new()),
+        });
+        // SAFETY: `init` of NSResponder, which takes no arguments
+        unsafe { msg_send![super(this), init] }
+    }
+}
+
+thread_local! {
+    /// Kept for as long as Fetch runs, as the panel doesn't retain its
+    /// data source
+    static PREVIEW_CONTROLLER: OnceCell<Retained<PreviewController>> = const { OnceCell::new() };
+}
+
+/// Shows `paths` in the Quick Look panel, controlled from `ns_window`
+pub(super) fn quick_look(ns_window: &NSWindow, paths: &[PathBuf]) -> Result<(), Report> {
+    let mtm = MainThreadMarker::from(ns_window);
+    let controller = PREVIEW_CONTROLLER.with(|controller| {
+        controller
+            .get_or_init(|| PreviewController::new(mtm))
+            .clone()
+    });
+
+    // Search windows are created anew, so the controller follows the
+    // latest one in its responder chain
+    let next_responder = ns_window.nextResponder();
+    let is_in_chain = next_responder.as_ref().is_some_and(|responder| {
+        std::ptr::eq(
+            Retained::as_ptr(responder).cast::<AnyObject>(),
+            Retained::as_ptr(&controller).cast::<AnyObject>(),
+        )
+    });
+    if !is_in_chain {
+        controller.setNextResponder(next_responder.as_deref());
+        ns_window.setNextResponder(Some(&controller));
+    }
+
+    *controller.ivars().urls.borrow_mut() = paths
+        .iter()
+        .map(|path| NSURL::fileURLWithPath(&NSString::from_str(&path.to_string_lossy())))
+        .collect();
+
+    let panel_class =
+        AnyClass::get(c"QLPreviewPanel").ok_or_else(|| report!("Quick Look isn't available"))?;
+    // SAFETY: `sharedPreviewPanel` takes no arguments and returns the
+    // panel, a subclass of NSPanel, and is called on the main thread
+    let panel: Option<Retained<NSPanel>> = unsafe { msg_send![panel_class, sharedPreviewPanel] };
+    let panel = panel.ok_or_else(|| report!("No Quick Look panel"))?;
+
+    if panel.isVisible() {
+        // Already controlled by the search window, showing other files
+        // SAFETY: `reloadData` takes no arguments
+        let _: () = unsafe { msg_send![&*panel, reloadData] };
+    } else {
+        panel.makeKeyAndOrderFront(None);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,274 @@
+//! The protocol extensions talk to Fetch with, whichever runtime they run
+//! in (scripts or WASM modules). Like the daemon, they exchange JSON
+//! messages, one per line, each carrying the version of the protocol it
+//! was written for.
+//!
+//! An extension starts by sending [`ExtensionMessage::Hello`], with the
+//! protocol version it was written for and the capabilities it has.
+//! [`negotiate`] then settles on the capabilities both sides have, and
+//! lists the deprecation warnings to show its author. Extensions written
+//! for an older version keep working for as long as that version is at
+//! least [`MIN_PROTOCOL_VERSION`]: newer fields are optional, and unknown
+//! capabilities or messages are ignored rather than rejected.
+
+use std::borrow::Cow;
+
+use rootcause::{Report, report};
+use serde::{Deserialize, Serialize};
+
+use crate::{app::AppString, extensions::SearchResult, url::Url};
+
+/// Version of the messages Fetch sends
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Oldest version of the messages Fetch still reads
+pub const MIN_PROTOCOL_VERSION: u32 = 1;
+
+/// Versions still read, but going away, with what to do about it
+const DEPRECATED_VERSIONS: &[(u32, &str)] = &[];
+
+/// What an extension can do, declared in its hello message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Capability {
+    /// Answers [`HostMessage::Search`]
+    Search,
+    /// Wants [`HostMessage::Preload`] whenever the search window opens
+    Preload,
+    /// Only searched for queries starting with one of its keywords
+    Keywords,
+    /// Sent by extensions written for a later version of the protocol
+    #[serde(other)]
+    Unknown,
+}
+
+/// Capabilities Fetch supports, for [`PROTOCOL_VERSION`]
+const HOST_CAPABILITIES: &[Capability] = &[
+    Capability::Search,
+    Capability::Preload,
+    Capability::Keywords,
+];
+
+/// A message along with the version of the protocol it was written for
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Envelope<T> {
+    pub version: u32,
+    #[serde(flatten)]
+    pub message: T,
+}
+
+impl<T> Envelope<T> {
+    #[must_use]
+    pub fn new(message: T) -> Self {
+        Self {
+            version: PROTOCOL_VERSION,
+            message,
+        }
+    }
+}
+
+/// Sent by Fetch to an extension
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HostMessage {
+    /// Answers the hello of the extension, with the capabilities both have
+    Welcome {
+        capabilities: Vec<Capability>,
+    },
+    Preload,
+    Search {
+        id: usize,
+        query: String,
+    },
+}
+
+/// Sent by an extension to Fetch
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ExtensionMessage {
+    Hello {
+        name: String,
+        capabilities: Vec<Capability>,
+        #[serde(default)]
+        keywords: Vec<String>,
+    },
+    Results {
+        id: usize,
+        results: Vec<ExtensionResult>,
+    },
+    /// Sent by extensions written for a later version of the protocol
+    #[serde(other)]
+    Unknown,
+}
+
+/// A result, in a form that doesn't change along with [`SearchResult`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExtensionResult {
+    pub title: String,
+    #[serde(default)]
+    pub subtitle: Option<String>,
+    pub action: ResultAction,
+}
+
+/// What selecting a result does
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResultAction {
+    /// Opens an `https://` URL
+    OpenUrl(String),
+    CopyText(String),
+}
+
+impl ExtensionResult {
+    pub fn into_search_result(self) -> Result<SearchResult, Report> {
+        let result = match self.action {
+            ResultAction::OpenUrl(url) => {
+                let Some(url) = url.strip_prefix("https://") else {
+                    return Err(report!("Only https:// URLs can be opened, not {url}"));
+                };
+                SearchResult::Url {
+                    name: self.title.into(),
+                    url: Url::Https(Cow::Owned(url.to_owned())),
+                }
+            }
+            ResultAction::CopyText(text) => SearchResult::Text {
+                label: self.subtitle.unwrap_or(self.title).into(),
+                text: AppString::from(text),
+            },
+        };
+
+        Ok(result)
+    }
+}
+
+/// What was agreed on with an extension
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Session {
+    pub name: String,
+    /// Version of the messages the extension sends
+    pub version: u32,
+    /// Capabilities both Fetch and the extension have
+    pub capabilities: Vec<Capability>,
+    pub keywords: Vec<String>,
+    /// To show the author of the extension
+    pub warnings: Vec<String>,
+}
+
+impl Session {
+    #[must_use]
+    pub fn has(&self, capability: Capability) -> bool {
+        self.capabilities.contains(&capability)
+    }
+
+    /// The answer to the hello of the extension
+    #[must_use]
+    pub fn welcome(&self) -> Envelope<HostMessage> {
+        Envelope::new(HostMessage::Welcome {
+            capabilities: self.capabilities.clone(),
+        })
+    }
+}
+
+/// Settles on what an extension and Fetch can both do, from the hello
+/// message of the extension
+pub fn negotiate(hello: Envelope<ExtensionMessage>) -> Result<Session, Report> {
+    let ExtensionMessage::Hello {
+        name,
+        capabilities,
+        keywords,
+    } = hello.message
+    else {
+        return Err(report!("Extensions must start by saying hello"));
+    };
+
+    if hello.version > PROTOCOL_VERSION {
+        return Err(report!(
+            "{name} needs version {} of the extension protocol, this version of Fetch only supports up to {PROTOCOL_VERSION}",
+            hello.version
+        ));
+    }
+    if hello.version < MIN_PROTOCOL_VERSION {
+        return Err(report!(
+            "{name} was written for version {} of the extension protocol, which is no longer supported (oldest: {MIN_PROTOCOL_VERSION})",
+            hello.version
+        ));
+    }
+
+    let mut warnings: Vec<String> = DEPRECATED_VERSIONS
+        .iter()
+        .filter(|(version, _)| *version == hello.version)
+        .map(|(version, advice)| {
+            format!("Version {version} of the extension protocol is deprecated: {advice}")
+        })
+        .collect();
+    if capabilities.contains(&Capability::Unknown) {
+        warnings.push(format!(
+            "{name} declares capabilities this version of Fetch doesn't know of, which are ignored"
+        ));
+    }
+
+    let mut capabilities: Vec<Capability> = HOST_CAPABILITIES
+        .iter()
+        .copied()
+        .filter(|capability| capabilities.contains(capability))
+        .collect();
+    if capabilities.contains(&Capability::Keywords) && keywords.is_empty() {
+        warnings.push(format!(
+            "{name} has the keywords capability, but no keyword"
+        ));
+        capabilities.retain(|capability| *capability != Capability::Keywords);
+    }
+
+    Ok(Session {
+        name,
+        version: hello.version,
+        capabilities,
+        keywords,
+        warnings,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hello(line: &str) -> Envelope<ExtensionMessage> {
+        serde_json::from_str(line).expect("a valid message")
+    }
+
+    #[test]
+    fn test_negotiate() {
+        let session = negotiate(hello(
+            r#"{"version":1,"type":"hello","name":"Emoji","capabilities":["search","keywords","telepathy"],"keywords":["emoji"]}"#,
+        ))
+        .expect("a supported version");
+
+        assert_eq!(
+            session.capabilities,
+            vec![Capability::Search, Capability::Keywords]
+        );
+        assert_eq!(session.warnings.len(), 1);
+        assert_eq!(
+            serde_json::to_string(&session.welcome()).expect("serializable"),
+            r#"{"version":1,"type":"welcome","capabilities":["search","keywords"]}"#
+        );
+    }
+
+    #[test]
+    fn test_negotiate_newer_version() {
+        let result = negotiate(hello(
+            r#"{"version":2,"type":"hello","name":"Emoji","capabilities":["search"]}"#,
+        ));
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_unknown_message() {
+        let message: Envelope<ExtensionMessage> =
+            serde_json::from_str(r#"{"version":1,"type":"progress","done":3}"#)
+                .expect("unknown messages are read");
+
+        assert_eq!(message.message, ExtensionMessage::Unknown);
+    }
+}
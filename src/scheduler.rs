@@ -0,0 +1,100 @@
+//! Background work (refreshing the app index and the lists of providers)
+//! runs whenever the search window opens. On battery or in Low Power Mode,
+//! it is deferred instead, and coalesced into batches that run at most once
+//! per [`PowerConfig::battery_batch_interval_mins`].
+
+use std::{
+    sync::{
+        Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use crate::{
+    fs::config::PowerConfig,
+    platform::{ImplPlatform, Platform},
+};
+
+/// Power source and mode of the computer
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PowerState {
+    pub on_battery: bool,
+    pub low_power_mode: bool,
+}
+
+/// Reading the power state spawns a process, so it is only read
+/// once in a while
+const POWER_STATE_TTL: Duration = Duration::from_mins(1);
+
+/// Decides when background jobs run. Jobs ask [`BackgroundScheduler::is_due`]
+/// before running, after the search engine started a batch with
+/// [`BackgroundScheduler::begin_batch`].
+#[derive(Debug)]
+pub struct BackgroundScheduler {
+    config: PowerConfig,
+    /// Latest power state, with when it was read
+    power_state: Mutex<Option<(PowerState, Instant)>>,
+    /// When the latest batch ran, while saving power
+    last_batch: Mutex<Option<Instant>>,
+    /// Whether jobs may run during the current batch
+    batch_open: AtomicBool,
+}
+
+impl BackgroundScheduler {
+    #[must_use]
+    pub fn new(config: PowerConfig) -> Self {
+        Self {
+            config,
+            power_state: Mutex::new(None),
+            last_batch: Mutex::new(None),
+            batch_open: AtomicBool::new(true),
+        }
+    }
+
+    fn is_saving_power(&self) -> bool {
+        if !self.config.save_power_on_battery {
+            return false;
+        }
+
+        let mut cached_state = self.power_state.lock().expect("no lock poisoning");
+        let state = match *cached_state {
+            Some((state, read_at)) if read_at.elapsed() < POWER_STATE_TTL => state,
+            _ => {
+                let state = ImplPlatform::power_state();
+                *cached_state = Some((state, Instant::now()));
+                state
+            }
+        };
+
+        state.on_battery || state.low_power_mode
+    }
+
+    /// Called before background jobs get a chance to run. While saving power,
+    /// jobs only run if the previous batch is old enough.
+    pub fn begin_batch(&self) {
+        let batch_open = if self.is_saving_power() {
+            let mut last_batch = self.last_batch.lock().expect("no lock poisoning");
+            let batch_interval = Duration::from_mins(self.config.battery_batch_interval_mins);
+            let is_open = last_batch.is_none_or(|instant| instant.elapsed() >= batch_interval);
+            if is_open {
+                *last_batch = Some(Instant::now());
+            }
+
+            is_open
+        } else {
+            true
+        };
+
+        self.batch_open.store(batch_open, Ordering::Relaxed);
+    }
+
+    /// Whether a job should run now, given when it is next meant to run.
+    /// Jobs that never ran always run, as there is nothing to show until then.
+    pub fn is_due(&self, next_run: Option<Instant>) -> bool {
+        match next_run {
+            None => true,
+            Some(next_run) => Instant::now() >= next_run && self.batch_open.load(Ordering::Relaxed),
+        }
+    }
+}
@@ -0,0 +1,287 @@
+//! Sections of System Settings panes that can be opened directly, such as
+//! Night Shift in Displays. macOS lists the anchors of each pane, but under
+//! ids rather than the names shown in System Settings, so this maps the
+//! useful ones to their names. Entries of the configuration replace the
+//! built-in ones of the same name, for anchors that moved in a later macOS.
+
+use std::borrow::Cow;
+
+use crate::{fs::config::SettingsSectionEntry, url::Url};
+
+/// A section of a pane, opened with `x-apple.systempreferences:<pane>?<anchor>`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SettingsSection {
+    /// Name of the pane, as in "Displays"
+    pub pane_name: String,
+    /// Name of the section, as in "Night Shift"
+    pub name: String,
+    /// Other words it's known by, as in "blue light"
+    pub aliases: Vec<String>,
+    /// Identifier of the pane, as in `com.apple.preference.displays`
+    pub pane: String,
+    /// Without one, the pane opens on its first section
+    pub anchor: Option<String>,
+}
+
+impl SettingsSection {
+    #[must_use]
+    pub fn url(&self) -> Url {
+        Url::SystemSettings(Cow::Owned(match &self.anchor {
+            Some(anchor) => format!("{}?{anchor}", self.pane),
+            None => self.pane.clone(),
+        }))
+    }
+
+    /// "Displays › Night Shift"
+    #[must_use]
+    pub fn breadcrumb(&self) -> String {
+        format!("{} › {}", self.pane_name, self.name)
+    }
+}
+
+struct BuiltInSection {
+    pane_name: &'static str,
+    name: &'static str,
+    aliases: &'static [&'static str],
+    pane: &'static str,
+    anchor: &'static str,
+}
+
+const DISPLAYS: &str = "com.apple.preference.displays";
+const SECURITY: &str = "com.apple.preference.security";
+const UNIVERSAL_ACCESS: &str = "com.apple.preference.universalaccess";
+const KEYBOARD: &str = "com.apple.preference.keyboard";
+const SHARING: &str = "com.apple.preferences.sharing";
+
+const BUILT_IN_SECTIONS: &[BuiltInSection] = &[
+    BuiltInSection {
+        pane_name: "Displays",
+        name: "Night Shift",
+        aliases: &["blue light", "warmer colors"],
+        pane: DISPLAYS,
+        anchor: "displaysNightShiftTab",
+    },
+    BuiltInSection {
+        pane_name: "Displays",
+        name: "Arrangement",
+        aliases: &["mirror displays"],
+        pane: DISPLAYS,
+        anchor: "displaysArrangementTab",
+    },
+    BuiltInSection {
+        pane_name: "Displays",
+        name: "Color Profile",
+        aliases: &["calibrate"],
+        pane: DISPLAYS,
+        anchor: "displaysColorTab",
+    },
+    BuiltInSection {
+        pane_name: "Accessibility",
+        name: "Pointer Control",
+        aliases: &["mouse keys", "double-click speed"],
+        pane: UNIVERSAL_ACCESS,
+        anchor: "Mouse",
+    },
+    BuiltInSection {
+        pane_name: "Accessibility",
+        name: "Zoom",
+        aliases: &["magnify"],
+        pane: UNIVERSAL_ACCESS,
+        anchor: "Seeing_Zoom",
+    },
+    BuiltInSection {
+        pane_name: "Accessibility",
+        name: "VoiceOver",
+        aliases: &["screen reader"],
+        pane: UNIVERSAL_ACCESS,
+        anchor: "Seeing_VoiceOver",
+    },
+    BuiltInSection {
+        pane_name: "Accessibility",
+        name: "Display",
+        aliases: &["reduce motion", "reduce transparency", "increase contrast"],
+        pane: UNIVERSAL_ACCESS,
+        anchor: "Seeing_Display",
+    },
+    BuiltInSection {
+        pane_name: "Keyboard",
+        name: "Keyboard Shortcuts",
+        aliases: &["hotkeys"],
+        pane: KEYBOARD,
+        anchor: "Shortcuts",
+    },
+    BuiltInSection {
+        pane_name: "Keyboard",
+        name: "Dictation",
+        aliases: &["voice typing"],
+        pane: KEYBOARD,
+        anchor: "Dictation",
+    },
+    BuiltInSection {
+        pane_name: "Keyboard",
+        name: "Text Replacements",
+        aliases: &["autocorrect"],
+        pane: KEYBOARD,
+        anchor: "Text",
+    },
+    BuiltInSection {
+        pane_name: "Privacy & Security",
+        name: "Accessibility",
+        aliases: &["assistive access"],
+        pane: SECURITY,
+        anchor: "Privacy_Accessibility",
+    },
+    BuiltInSection {
+        pane_name: "Privacy & Security",
+        name: "Automation",
+        aliases: &["apple events"],
+        pane: SECURITY,
+        anchor: "Privacy_Automation",
+    },
+    BuiltInSection {
+        pane_name: "Privacy & Security",
+        name: "Full Disk Access",
+        aliases: &[],
+        pane: SECURITY,
+        anchor: "Privacy_AllFiles",
+    },
+    BuiltInSection {
+        pane_name: "Privacy & Security",
+        name: "Screen Recording",
+        aliases: &["screen capture", "screen sharing permission"],
+        pane: SECURITY,
+        anchor: "Privacy_ScreenCapture",
+    },
+    BuiltInSection {
+        pane_name: "Privacy & Security",
+        name: "Camera",
+        aliases: &["webcam"],
+        pane: SECURITY,
+        anchor: "Privacy_Camera",
+    },
+    BuiltInSection {
+        pane_name: "Privacy & Security",
+        name: "Microphone",
+        aliases: &[],
+        pane: SECURITY,
+        anchor: "Privacy_Microphone",
+    },
+    BuiltInSection {
+        pane_name: "Privacy & Security",
+        name: "Location Services",
+        aliases: &["gps"],
+        pane: SECURITY,
+        anchor: "Privacy_LocationServices",
+    },
+    BuiltInSection {
+        pane_name: "Privacy & Security",
+        name: "Input Monitoring",
+        aliases: &["keylogging"],
+        pane: SECURITY,
+        anchor: "Privacy_ListenEvent",
+    },
+    BuiltInSection {
+        pane_name: "Privacy & Security",
+        name: "FileVault",
+        aliases: &["disk encryption"],
+        pane: SECURITY,
+        anchor: "FDE",
+    },
+    BuiltInSection {
+        pane_name: "Sharing",
+        name: "Remote Login",
+        aliases: &["ssh server"],
+        pane: SHARING,
+        anchor: "Services_RemoteLogin",
+    },
+    BuiltInSection {
+        pane_name: "Sharing",
+        name: "Screen Sharing",
+        aliases: &["vnc"],
+        pane: SHARING,
+        anchor: "Services_ScreenSharing",
+    },
+    BuiltInSection {
+        pane_name: "Sharing",
+        name: "File Sharing",
+        aliases: &["smb"],
+        pane: SHARING,
+        anchor: "Services_PersonalFileSharing",
+    },
+];
+
+/// The built-in sections, with the ones of the configuration replacing those
+/// of the same name. An entry without a pane only removes the built-in one.
+#[must_use]
+pub fn sections(overrides: &[SettingsSectionEntry]) -> Vec<SettingsSection> {
+    let mut sections: Vec<SettingsSection> = BUILT_IN_SECTIONS
+        .iter()
+        .filter(|section| {
+            !overrides
+                .iter()
+                .any(|entry| entry.name.eq_ignore_ascii_case(section.name))
+        })
+        .map(|section| SettingsSection {
+            pane_name: section.pane_name.to_owned(),
+            name: section.name.to_owned(),
+            aliases: section
+                .aliases
+                .iter()
+                .map(|&alias| alias.to_owned())
+                .collect(),
+            pane: section.pane.to_owned(),
+            anchor: Some(section.anchor.to_owned()),
+        })
+        .collect();
+
+    sections.extend(overrides.iter().filter_map(|entry| {
+        Some(SettingsSection {
+            pane_name: entry.pane_name.clone(),
+            name: entry.name.clone(),
+            aliases: entry.aliases.clone(),
+            pane: entry.pane.clone()?,
+            anchor: entry.anchor.clone(),
+        })
+    }));
+
+    sections
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sections_overrides() {
+        let overrides = [
+            SettingsSectionEntry {
+                name: "night shift".to_owned(),
+                pane_name: "Displays".to_owned(),
+                aliases: Vec::new(),
+                pane: Some("com.apple.Displays-Settings.extension".to_owned()),
+                anchor: Some("nightShiftSection".to_owned()),
+            },
+            SettingsSectionEntry {
+                name: "Zoom".to_owned(),
+                pane_name: String::new(),
+                aliases: Vec::new(),
+                pane: None,
+                anchor: None,
+            },
+        ];
+
+        let sections = sections(&overrides);
+
+        assert_eq!(sections.len(), BUILT_IN_SECTIONS.len() - 1);
+        assert!(!sections.iter().any(|section| section.name == "Zoom"));
+        let night_shift: Vec<&SettingsSection> = sections
+            .iter()
+            .filter(|section| section.name.eq_ignore_ascii_case("night shift"))
+            .collect();
+        assert_eq!(night_shift.len(), 1);
+        assert_eq!(
+            night_shift[0].url().to_string(),
+            "x-apple.systempreferences:com.apple.Displays-Settings.extension?nightShiftSection"
+        );
+    }
+}
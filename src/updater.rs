@@ -0,0 +1,367 @@
+//! New versions of Fetch are looked for in a feed of releases (the GitHub
+//! releases of the repository by default), in the background.
+//!
+//! A new release is downloaded, verified against the public key Fetch
+//! was built with, and staged in the data directory. It replaces the
+//! running app the next time Fetch is launched, which the search window
+//! offers to do right away. Builds made without a public key only look
+//! for new versions, as they have no way to trust a download.
+
+use std::{
+    cmp::Ordering,
+    fmt::Display,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use rootcause::{Report, report};
+use serde::Deserialize;
+
+use crate::{
+    errors,
+    fs::config::{UpdateChannel, UpdatesConfig, data_dir_path},
+    notifications::{self, Notification},
+    platform::{ImplPlatform, Platform},
+};
+
+/// PEM public key releases are signed with, set when building releases
+const RELEASE_PUBLIC_KEY: Option<&str> = option_env!("FETCH_RELEASE_PUBLIC_KEY");
+
+/// Zipped app bundle attached to releases
+const ARCHIVE_ASSET_NAME: &str = "Fetch.app.zip";
+/// Signature of the archive, made with `openssl dgst -sha256 -sign`
+const SIGNATURE_ASSET_NAME: &str = "Fetch.app.zip.sig";
+
+const UPDATES_DIR_NAME: &str = "updates";
+const STAGED_APP_NAME: &str = "Fetch.app";
+/// Holds the version of the staged app
+const STAGED_VERSION_FILE_NAME: &str = "version";
+/// The app replaced by the latest update, kept until the next one
+const PREVIOUS_APP_NAME: &str = "Fetch-previous.app";
+
+/// A version number, such as `1.2.0` or `1.3.0-beta.2`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Version {
+    numbers: [u64; 3],
+    pre_release: Option<String>,
+}
+
+impl Version {
+    /// Reads versions with or without a leading `v`, as in `v1.2.0`
+    #[must_use]
+    pub fn parse(version: &str) -> Option<Self> {
+        let version = version.trim().trim_start_matches('v');
+        let (numbers, pre_release) = match version.split_once('-') {
+            Some((numbers, pre_release)) => (numbers, Some(pre_release.to_owned())),
+            None => (version, None),
+        };
+
+        let mut parts = numbers.split('.').map(str::parse);
+        let numbers = [
+            parts.next()?.ok()?,
+            parts.next().unwrap_or(Ok(0)).ok()?,
+            parts.next().unwrap_or(Ok(0)).ok()?,
+        ];
+        if parts.next().is_some() {
+            return None;
+        }
+
+        Some(Self {
+            numbers,
+            pre_release,
+        })
+    }
+
+    #[must_use]
+    pub fn current() -> Self {
+        Self::parse(env!("CARGO_PKG_VERSION")).expect("the package version is valid")
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.numbers.cmp(&other.numbers).then_with(|| {
+            match (&self.pre_release, &other.pre_release) {
+                (None, None) => Ordering::Equal,
+                // `1.0.0-beta` comes before `1.0.0`
+                (None, Some(_)) => Ordering::Greater,
+                (Some(_), None) => Ordering::Less,
+                (Some(pre_release), Some(other)) => compare_pre_releases(pre_release, other),
+            }
+        })
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Display for Version {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let [major, minor, patch] = self.numbers;
+        write!(f, "{major}.{minor}.{patch}")?;
+        if let Some(pre_release) = &self.pre_release {
+            write!(f, "-{pre_release}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Compares dot-separated identifiers, numbers by value: `beta.2` < `beta.10`
+fn compare_pre_releases(a: &str, b: &str) -> Ordering {
+    let mut a_identifiers = a.split('.');
+    let mut b_identifiers = b.split('.');
+
+    loop {
+        let ordering = match (a_identifiers.next(), b_identifiers.next()) {
+            (None, None) => return Ordering::Equal,
+            // `beta` comes before `beta.1`
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(a), Some(b)) => match (a.parse::<u64>(), b.parse::<u64>()) {
+                (Ok(a), Ok(b)) => a.cmp(&b),
+                (Ok(_), Err(_)) => Ordering::Less,
+                (Err(_), Ok(_)) => Ordering::Greater,
+                (Err(_), Err(_)) => a.cmp(b),
+            },
+        };
+
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct GitHubRelease {
+    tag_name: String,
+    prerelease: bool,
+    draft: bool,
+    assets: Vec<GitHubAsset>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GitHubAsset {
+    name: String,
+    browser_download_url: String,
+}
+
+/// A release newer than the running version
+#[derive(Debug, Clone)]
+pub struct Release {
+    pub version: Version,
+    archive_url: String,
+    signature_url: String,
+}
+
+fn updates_dir_path() -> Result<PathBuf, Report> {
+    let path = data_dir_path()?.join(UPDATES_DIR_NAME);
+    std::fs::create_dir_all(&path)?;
+
+    Ok(path)
+}
+
+/// The newest release of the channel, if it is newer than the running version
+pub fn latest_release(config: &UpdatesConfig) -> Result<Option<Release>, Report> {
+    let headers = [
+        ("Accept", "application/vnd.github+json"),
+        ("User-Agent", "Fetch"),
+    ];
+    let body = ImplPlatform::http_get(&config.feed_url, &headers)?;
+    let releases: Vec<GitHubRelease> = serde_json::from_slice(&body)?;
+
+    let asset_url = |release: &GitHubRelease, name: &str| {
+        release
+            .assets
+            .iter()
+            .find(|asset| asset.name == name)
+            .map(|asset| asset.browser_download_url.clone())
+    };
+
+    let latest = releases
+        .iter()
+        .filter(|release| !release.draft)
+        .filter(|release| config.channel == UpdateChannel::Beta || !release.prerelease)
+        .filter_map(|release| {
+            Some(Release {
+                version: Version::parse(&release.tag_name)?,
+                archive_url: asset_url(release, ARCHIVE_ASSET_NAME)?,
+                signature_url: asset_url(release, SIGNATURE_ASSET_NAME)?,
+            })
+        })
+        .max_by(|a, b| a.version.cmp(&b.version));
+
+    Ok(latest.filter(|release| release.version > Version::current()))
+}
+
+/// Downloads and verifies a release, then stages it to replace the
+/// running app on the next launch
+pub fn stage(release: &Release) -> Result<(), Report> {
+    let public_key =
+        RELEASE_PUBLIC_KEY.ok_or_else(|| report!("This build of Fetch can't verify updates"))?;
+
+    let updates_dir = updates_dir_path()?;
+    let archive_path = updates_dir.join(ARCHIVE_ASSET_NAME);
+    let signature_path = updates_dir.join(SIGNATURE_ASSET_NAME);
+    std::fs::write(
+        &archive_path,
+        ImplPlatform::http_get(&release.archive_url, &[])?,
+    )?;
+    std::fs::write(
+        &signature_path,
+        ImplPlatform::http_get(&release.signature_url, &[])?,
+    )?;
+
+    let verified = ImplPlatform::verify_signature(&archive_path, &signature_path, public_key);
+    let staged = verified.and_then(|()| {
+        let staged_app_path = updates_dir.join(STAGED_APP_NAME);
+        if staged_app_path.exists() {
+            std::fs::remove_dir_all(&staged_app_path)?;
+        }
+        ImplPlatform::extract_archive(&archive_path, &updates_dir)?;
+
+        // The signature only covers the archive, so an older signed release
+        // could be served in place of the latest one
+        let bundle_version = ImplPlatform::app_version(&staged_app_path)
+            .and_then(|version| Version::parse(&version));
+        if bundle_version.as_ref() != Some(&release.version) {
+            std::fs::remove_dir_all(&staged_app_path)?;
+            return Err(report!(
+                "The downloaded app isn't Fetch {}, but {}",
+                release.version,
+                bundle_version.map_or_else(
+                    || "an unknown version".to_owned(),
+                    |version| version.to_string()
+                )
+            ));
+        }
+
+        std::fs::write(
+            updates_dir.join(STAGED_VERSION_FILE_NAME),
+            release.version.to_string(),
+        )?;
+
+        Ok(())
+    });
+
+    let _ = std::fs::remove_file(&archive_path);
+    let _ = std::fs::remove_file(&signature_path);
+
+    staged
+}
+
+/// The version of the staged update, if it is newer than the running version
+#[must_use]
+pub fn staged_update() -> Option<Version> {
+    let updates_dir = updates_dir_path().ok()?;
+    if !updates_dir.join(STAGED_APP_NAME).exists() {
+        return None;
+    }
+
+    let version = std::fs::read_to_string(updates_dir.join(STAGED_VERSION_FILE_NAME)).ok()?;
+    Version::parse(&version).filter(|version| *version > Version::current())
+}
+
+/// The `.app` bundle the running executable is part of
+fn current_app_path() -> Result<PathBuf, Report> {
+    let executable = std::env::current_exe()?;
+
+    executable
+        .ancestors()
+        .find(|path| path.extension().is_some_and(|extension| extension == "app"))
+        .map(Path::to_path_buf)
+        .ok_or_else(|| report!("Fetch isn't running from an app bundle"))
+}
+
+/// Replaces the running app with the staged update, if any. Returns
+/// `true` if it was replaced, in which case Fetch must be relaunched.
+pub fn apply_staged_update() -> Result<bool, Report> {
+    let Some(version) = staged_update() else {
+        return Ok(false);
+    };
+
+    let updates_dir = updates_dir_path()?;
+    let app_path = current_app_path()?;
+    let previous_app_path = updates_dir.join(PREVIOUS_APP_NAME);
+    if previous_app_path.exists() {
+        std::fs::remove_dir_all(&previous_app_path)?;
+    }
+
+    std::fs::rename(&app_path, &previous_app_path)?;
+    if let Err(err) = std::fs::rename(updates_dir.join(STAGED_APP_NAME), &app_path) {
+        // Put the running app back, rather than leaving no app at all
+        std::fs::rename(&previous_app_path, &app_path)?;
+        return Err(report!(err)
+            .context(format!("Could not install Fetch {version}"))
+            .into());
+    }
+    std::fs::remove_file(updates_dir.join(STAGED_VERSION_FILE_NAME))?;
+
+    Ok(true)
+}
+
+/// Looks for new releases every `check_interval_hours`, staging them
+/// when this build can verify them, forever
+pub fn run_checker(config: &UpdatesConfig) {
+    let interval = Duration::from_secs(config.check_interval_hours.max(1) * 60 * 60);
+
+    loop {
+        match latest_release(config) {
+            Ok(Some(release)) if staged_update().as_ref() != Some(&release.version) => {
+                if RELEASE_PUBLIC_KEY.is_none() {
+                    eprintln!("Fetch {} is available", release.version);
+                } else {
+                    match stage(&release) {
+                        Ok(()) => notifications::post(Notification {
+                            id: "update".to_owned(),
+                            title: format!("Fetch {} is ready", release.version),
+                            body: "It will be installed the next time Fetch starts, \
+                                   or right away by pressing ⌘⇧U in the search window."
+                                .to_owned(),
+                        }),
+                        Err(report) => errors::report_error(
+                            format!("Could not download Fetch {}", release.version),
+                            report,
+                        ),
+                    }
+                }
+            }
+            Ok(_) => {}
+            // Likely offline, which isn't worth a banner
+            Err(report) => eprintln!("{}", report.context("Could not check for updates")),
+        }
+
+        std::thread::sleep(interval);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_version() {
+        let version = Version::parse("v1.2.3-beta.1").unwrap();
+        assert_eq!(version.numbers, [1, 2, 3]);
+        assert_eq!(version.pre_release.as_deref(), Some("beta.1"));
+        assert_eq!(version.to_string(), "1.2.3-beta.1");
+
+        assert_eq!(Version::parse("0.10").unwrap().numbers, [0, 10, 0]);
+        assert_eq!(Version::parse("1.2.3.4"), None);
+        assert_eq!(Version::parse("latest"), None);
+    }
+
+    #[test]
+    fn test_compare_versions() {
+        let version = |version| Version::parse(version).unwrap();
+
+        assert!(version("0.10.0") > version("0.9.1"));
+        assert!(version("1.0.0") > version("1.0.0-beta.2"));
+        assert!(version("1.0.0-beta.10") > version("1.0.0-beta.2"));
+        assert!(version("1.0.0-rc.1") > version("1.0.0-beta.3"));
+        assert!(version("1.0.0-beta.1") > version("1.0.0-beta"));
+    }
+}
@@ -1,13 +1,22 @@
-use std::{borrow::Cow, fmt::Display, path::PathBuf};
+use std::{
+    borrow::Cow,
+    fmt::Display,
+    path::{Path, PathBuf},
+};
 
 use serde::{Deserialize, Serialize};
 
-use scc::{Guard, HashIndex};
+use rayon::{
+    ThreadPoolBuilder,
+    iter::{IntoParallelRefIterator, ParallelIterator},
+};
+use scc::Guard;
+use tokio::sync::watch::Sender;
 
 use crate::{
-    app::ExecutableApp,
+    app::{AppLocation, ExecutableApp},
     fs::config::Configuration,
-    platform::{ImplPlatform, Platform},
+    platform::SystemApps,
 };
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
@@ -16,6 +25,12 @@ pub enum Url {
     File(PathBuf),
     /// A URL to handle opening web URLs (`https://`)
     Https(Cow<'static, str>),
+    /// A URL to a System Settings pane (`x-apple.systempreferences:`)
+    SystemSettings(Cow<'static, str>),
+    /// A URL to a page of the Mac App Store (`macappstore://`)
+    AppStore(Cow<'static, str>),
+    /// A URL of any other scheme, written out in full (as in `mailto:`)
+    Other(Cow<'static, str>),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -33,6 +48,13 @@ impl Display for Url {
             Url::Https(domain) => {
                 write!(f, "https://{domain}")
             }
+            Url::SystemSettings(pane) => {
+                write!(f, "x-apple.systempreferences:{pane}")
+            }
+            Url::AppStore(page) => {
+                write!(f, "macappstore://{page}")
+            }
+            Url::Other(url) => write!(f, "{url}"),
         }
     }
 }
@@ -43,54 +65,386 @@ impl From<PathBuf> for Url {
     }
 }
 
+/// How far along building the index is.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexingProgress {
+    pub indexed: usize,
+    /// How many apps are being read, `None` until they are all listed
+    pub total: Option<usize>,
+}
+
+impl IndexingProgress {
+    #[must_use]
+    pub fn is_done(&self) -> bool {
+        self.total.is_some_and(|total| self.indexed >= total)
+    }
+}
+
+/// How many index events are kept for subscribers lagging behind, past
+/// which they are told to refresh everything instead
+pub const INDEX_EVENTS_CAPACITY: usize = 256;
+
+/// A change of the index, after an app was installed or deleted
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum IndexEvent {
+    AppAdded(PathBuf),
+    AppRemoved(PathBuf),
+    /// Sent once the index is built, and after the apps added or removed
+    /// by an update. Subscribers that missed events refresh on it too.
+    IndexUpdated,
+}
+
 /// An index map of all known apps, optimized for fast reads.
-#[derive(Debug, Clone)]
-pub struct UrlIndex(scc::HashIndex<Url, UrlEntry>);
+#[derive(Debug)]
+pub struct UrlIndex {
+    entries: scc::HashIndex<Url, UrlEntry>,
+    /// Where apps are listed and read from
+    system_apps: Box<dyn SystemApps>,
+}
+
+#[cfg(target_os = "macos")]
+impl Default for UrlIndex {
+    fn default() -> Self {
+        Self::new(crate::platform::PlatformApps)
+    }
+}
 
 impl UrlIndex {
     #[must_use]
-    pub fn build(config: &Configuration) -> Self {
-        let apps = ImplPlatform::list_binary_paths(config, false);
-        let map = HashIndex::with_capacity(apps.len());
-
-        apps.iter_sync(|p| {
-            let url = Url::File(p.clone());
-            if let Some(url_entry) = ImplPlatform::to_url_entry(&url) {
-                let _ = map.insert_sync(url, url_entry);
-            }
+    pub fn new(system_apps: impl SystemApps) -> Self {
+        Self {
+            entries: scc::HashIndex::new(),
+            system_apps: Box::new(system_apps),
+        }
+    }
 
-            true
+    /// Reads every app, reporting progress along the way. At most
+    /// `config.indexing_threads` apps are read at once, to avoid saturating
+    /// the disk (which matters at login, when everything else starts too).
+    pub fn build(&self, config: &Configuration, progress: &Sender<IndexingProgress>) {
+        let mut paths = Vec::new();
+        self.system_apps
+            .list_app_paths(config, false)
+            .iter_sync(|p| {
+                paths.push(p.clone());
+                true
+            });
+
+        progress.send_replace(IndexingProgress {
+            indexed: 0,
+            total: Some(paths.len()),
         });
 
-        Self(map)
+        let index_app = |path: &PathBuf| {
+            let url = Url::File(path.clone());
+            if let Some(url_entry) = self.system_apps.read_entry(&url) {
+                let _ = self.entries.insert_sync(url, url_entry);
+            }
+
+            progress.send_modify(|progress| progress.indexed += 1);
+        };
+
+        match ThreadPoolBuilder::new()
+            .num_threads(config.indexing_threads.max(1))
+            .build()
+        {
+            Ok(pool) => pool.install(|| paths.par_iter().for_each(index_app)),
+            Err(_) => paths.iter().for_each(index_app),
+        }
+    }
+
+    /// Forgets a single app, returning whether it was indexed
+    pub fn remove(&self, path: &Path) -> bool {
+        self.entries.remove_sync(&Url::File(path.to_path_buf()))
+    }
+
+    /// Forgets every app, before the index is built again from scratch
+    pub fn clear(&self) {
+        self.entries.clear_sync();
+    }
+
+    /// Decodes the icons the indexer couldn't read with the platform's own
+    /// tools, which is slower, so it's done once the index is built rather
+    /// than while apps are read. Returns `true` if any icon was added.
+    pub fn fill_missing_icons(&self, config: &Configuration) -> bool {
+        let guard = Guard::new();
+        let iconless_apps: Vec<ExecutableApp> = self
+            .entries
+            .iter(&guard)
+            .filter_map(|(_, entry)| match entry {
+                UrlEntry::App { app }
+                    if app.icon_png_data.is_none() && app.location == AppLocation::Local =>
+                {
+                    Some(app.clone())
+                }
+                _ => None,
+            })
+            .collect();
+        drop(guard);
+
+        let decode_icon = |app: &ExecutableApp| {
+            let Ok(icon_png_data) = self.system_apps.decode_icon(&app.path) else {
+                return false;
+            };
+            let url = Url::File(app.path.clone());
+            let app = ExecutableApp {
+                icon_png_data: Some(icon_png_data),
+                ..app.clone()
+            };
+
+            match self.entries.entry_sync(url) {
+                scc::hash_index::Entry::Occupied(mut entry) => {
+                    entry.update(UrlEntry::App { app });
+                    true
+                }
+                // Removed by an update in the meantime
+                scc::hash_index::Entry::Vacant(_) => false,
+            }
+        };
+
+        match ThreadPoolBuilder::new()
+            .num_threads(config.indexing_threads.max(1))
+            .build()
+        {
+            Ok(pool) => pool.install(|| {
+                iconless_apps
+                    .par_iter()
+                    .filter(|app| decode_icon(app))
+                    .count()
+                    > 0
+            }),
+            Err(_) => iconless_apps.iter().filter(|app| decode_icon(app)).count() > 0,
+        }
     }
 
-    pub fn update(&self, config: &Configuration) {
-        let apps = ImplPlatform::list_binary_paths(config, true);
-        self.0.retain_sync(|k, _v| {
-            if let Url::File(path) = k {
+    /// Adds the apps installed since the index was built and removes the
+    /// deleted ones. Returns the apps added or removed, followed by
+    /// [`IndexEvent::IndexUpdated`], or nothing if the index didn't change.
+    pub fn update(&self, config: &Configuration) -> Vec<IndexEvent> {
+        let apps = self.system_apps.list_app_paths(config, true);
+        let mut events = Vec::new();
+        let mut changed = false;
+        self.entries.retain_sync(|k, v| {
+            let keep = if let Url::File(path) = k {
                 apps.contains_sync(path)
             } else {
                 false
+            };
+            if !keep && let (Url::File(path), UrlEntry::App { .. }) = (k, v) {
+                events.push(IndexEvent::AppRemoved(path.clone()));
             }
+            changed |= !keep;
+            keep
         });
         apps.iter_sync(|app| {
             let url = Url::File(app.clone());
-            if let Some(url_entry) = ImplPlatform::to_url_entry(&url) {
-                // If the key already exists (kept from the retain call)
-                // then this doesn't update, so it stays efficient
-                let _ = self.0.insert_sync(url, url_entry);
+            // If the key already exists (kept from the retain call)
+            // then this doesn't update, so it stays efficient
+            if self.entries.peek_with(&url, |_, _| ()).is_none()
+                && let Some(url_entry) = self.system_apps.read_entry(&url)
+                && self.entries.insert_sync(url, url_entry).is_ok()
+            {
+                events.push(IndexEvent::AppAdded(app.clone()));
+                changed = true;
             }
 
             true
         });
+
+        if changed {
+            events.push(IndexEvent::IndexUpdated);
+        }
+        events
     }
 
-    pub fn get<'a>(&'a self, url: &'a Url, guard: &'a Guard) -> Option<&'a UrlEntry> {
-        self.0.peek(url, guard)
+    pub fn get<'a>(&'a self, url: &Url, guard: &'a Guard) -> Option<&'a UrlEntry> {
+        self.entries.peek(url, guard)
     }
 
     pub fn iter<'a>(&'a self, guard: &'a Guard) -> impl Iterator<Item = (&'a Url, &'a UrlEntry)> {
-        self.0.iter(guard)
+        self.entries.iter(guard)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::BTreeMap,
+        sync::{Arc, Mutex},
+    };
+
+    use rootcause::{Report, report};
+
+    use super::*;
+
+    /// Apps installed in a fake filesystem, listed as Spotlight would
+    #[derive(Debug, Clone, Default)]
+    struct FakeApps {
+        /// Bundles by path, of which a `None` can't be read
+        bundles: Arc<Mutex<BTreeMap<PathBuf, Option<ExecutableApp>>>>,
+    }
+
+    impl FakeApps {
+        fn install(&self, name: &str) -> PathBuf {
+            let app = fixture_app(name);
+            let path = app.path.clone();
+            self.bundles.lock().unwrap().insert(path.clone(), Some(app));
+
+            path
+        }
+
+        fn delete(&self, path: &Path) {
+            self.bundles.lock().unwrap().remove(path);
+        }
+    }
+
+    impl SystemApps for FakeApps {
+        fn list_app_paths(&self, _config: &Configuration, _quick: bool) -> scc::HashSet<PathBuf> {
+            let paths = scc::HashSet::new();
+            for path in self.bundles.lock().unwrap().keys() {
+                let _ = paths.insert_sync(path.clone());
+            }
+
+            paths
+        }
+
+        fn read_entry(&self, url: &Url) -> Option<UrlEntry> {
+            let Url::File(path) = url else {
+                return None;
+            };
+            let app = self.bundles.lock().unwrap().get(path).cloned()??;
+
+            Some(UrlEntry::App { app })
+        }
+
+        fn decode_icon(&self, app: &Path) -> Result<Vec<u8>, Report> {
+            if self.bundles.lock().unwrap().contains_key(app) {
+                Ok(b"png".to_vec())
+            } else {
+                Err(report!("No such app"))
+            }
+        }
+    }
+
+    /// The default configuration, without asking the platform for its apps,
+    /// which only the fake filesystem lists
+    fn test_config() -> Configuration {
+        Configuration::with_app_paths(Vec::new(), Vec::new())
+    }
+
+    /// An app in `/Applications`, whose icon can't be read by the indexer
+    fn fixture_app(name: &str) -> ExecutableApp {
+        ExecutableApp {
+            name: name.into(),
+            path: PathBuf::from(format!("/Applications/{name}.app")),
+            is_open: false,
+            icon_png_data: None,
+            is_web_app: false,
+            category: None,
+            document_types: Vec::new(),
+            document_extensions: Vec::new(),
+            location: AppLocation::Local,
+            version: None,
+            updates_url: None,
+            architecture: None,
+            usage: None,
+        }
+    }
+
+    fn indexed_names(index: &UrlIndex) -> Vec<String> {
+        let guard = Guard::new();
+        let mut names: Vec<String> = index
+            .iter(&guard)
+            .filter_map(|(_, entry)| match entry {
+                UrlEntry::App { app } => Some(app.name.to_string()),
+                UrlEntry::Url { .. } => None,
+            })
+            .collect();
+        names.sort();
+
+        names
+    }
+
+    #[test]
+    fn test_build() {
+        let apps = FakeApps::default();
+        apps.install("Safari");
+        apps.install("Notes");
+        apps.bundles
+            .lock()
+            .unwrap()
+            .insert(PathBuf::from("/Applications/Broken.app"), None);
+
+        let index = UrlIndex::new(apps);
+        let progress = Sender::new(IndexingProgress::default());
+        // Not done before apps are even listed
+        assert!(!progress.borrow().is_done());
+        index.build(&test_config(), &progress);
+
+        assert_eq!(indexed_names(&index), ["Notes", "Safari"]);
+        // Bundles that can't be read count as indexed all the same
+        assert_eq!(
+            *progress.borrow(),
+            IndexingProgress {
+                indexed: 3,
+                total: Some(3)
+            }
+        );
+    }
+
+    #[test]
+    fn test_update() {
+        let config = test_config();
+        let apps = FakeApps::default();
+        let safari = apps.install("Safari");
+        apps.install("Notes");
+
+        let index = UrlIndex::new(apps.clone());
+        index.build(&config, &Sender::new(IndexingProgress::default()));
+        assert!(index.update(&config).is_empty());
+
+        apps.delete(&safari);
+        let firefox = apps.install("Firefox");
+        assert_eq!(
+            index.update(&config),
+            [
+                IndexEvent::AppRemoved(safari),
+                IndexEvent::AppAdded(firefox),
+                IndexEvent::IndexUpdated
+            ]
+        );
+        assert_eq!(indexed_names(&index), ["Firefox", "Notes"]);
+    }
+
+    #[test]
+    fn test_fill_missing_icons() {
+        let config = test_config();
+        let apps = FakeApps::default();
+        let notes = apps.install("Notes");
+
+        let index = UrlIndex::new(apps);
+        index.build(&config, &Sender::new(IndexingProgress::default()));
+
+        assert!(index.fill_missing_icons(&config));
+        let guard = Guard::new();
+        assert!(matches!(
+            index.get(&Url::File(notes), &guard),
+            Some(UrlEntry::App { app }) if app.icon_png_data.is_some()
+        ));
+        // Nothing left to decode
+        assert!(!index.fill_missing_icons(&config));
+    }
+
+    #[test]
+    fn test_remove() {
+        let apps = FakeApps::default();
+        let notes = apps.install("Notes");
+
+        let index = UrlIndex::new(apps);
+        index.build(&test_config(), &Sender::new(IndexingProgress::default()));
+
+        assert!(index.remove(&notes));
+        assert!(!index.remove(&notes));
+        assert!(indexed_names(&index).is_empty());
     }
 }